@@ -1,18 +1,145 @@
-use anyhow::{Context, Result};
-use std::process::Command;
+use crate::error::{Land2PortError, Result};
+use crate::orientation::Rotation;
+use crate::transcript::CaptionSegment;
+use crate::video_processor_utils;
+use crate::video_sink;
+use anyhow::Context;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+/// Vertical placement of captions in the frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaptionPosition {
+    Top,
+    Center,
+    #[default]
+    Bottom,
+}
+
+impl FromStr for CaptionPosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "top" => Ok(Self::Top),
+            "center" => Ok(Self::Center),
+            "bottom" => Ok(Self::Bottom),
+            _ => Err(format!(
+                "invalid caption position '{s}': expected top, center, or bottom"
+            )),
+        }
+    }
+}
+
+/// Container and codecs of the delivered video, from `--output-format`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    /// H.264 video with AAC (or stream-copied) audio in an MP4
+    #[default]
+    Mp4,
+    /// VP9 video with Opus audio in a WebM
+    Webm,
+}
+
+impl OutputFormat {
+    /// File extension for videos in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+        }
+    }
+
+    /// ffmpeg encoders needed to write this format: the video codec, plus
+    /// Opus for WebM audio (AAC is built into every ffmpeg)
+    pub fn encoders(self) -> &'static [&'static str] {
+        match self {
+            Self::Mp4 => &["libx264"],
+            Self::Webm => &["libvpx-vp9", "libopus"],
+        }
+    }
+
+    /// ffmpeg arguments to encode video for this container. MP4 is encoded
+    /// with x264 at `encode`'s preset and rate control, matching the processed
+    /// video; WebM uses fixed VP9 settings.
+    fn video_codec_args(self, encode: &video_sink::EncodeSettings) -> Vec<String> {
+        match self {
+            Self::Mp4 => encode.ffmpeg_args(),
+            Self::Webm => ["-c:v", "libvpx-vp9", "-crf", "32", "-b:v", "0"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect(),
+        }
+    }
+
+    /// ffmpeg arguments to write an audio stream of `codec` into this
+    /// container, copying it when the container supports it
+    fn audio_codec_args(self, codec: Option<&str>) -> Vec<&'static str> {
+        match self {
+            Self::Mp4 => mp4_audio_codec_args(codec),
+            Self::Webm if codec == Some("opus") => vec!["-c:a", "copy"],
+            Self::Webm => vec!["-c:a", "libopus", "-b:a", "128k"],
+        }
+    }
+
+    /// ffmpeg arguments to re-encode audio for this container, for steps that
+    /// filter the audio and so can't copy it
+    fn audio_encode_args(self) -> Vec<&'static str> {
+        match self {
+            Self::Mp4 => vec!["-c:a", "aac", "-b:a", "192k"],
+            Self::Webm => vec!["-c:a", "libopus", "-b:a", "128k"],
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mp4" => Ok(Self::Mp4),
+            "webm" => Ok(Self::Webm),
+            _ => Err(format!("invalid output format '{s}': expected mp4 or webm")),
+        }
+    }
+}
+
+/// Validates a caption color given as `RRGGBB` hex (optionally prefixed with
+/// `#`), returning it normalized to uppercase without the prefix
+pub fn parse_hex_color(color: &str) -> Result<String> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Land2PortError::InvalidOption(format!(
+            "invalid color '{color}': expected RRGGBB hex, e.g. FFFFFF"
+        )));
+    }
+    Ok(hex.to_ascii_uppercase())
+}
+
+/// Converts an `RRGGBB` color to the `BBGGRR` byte order ASS styles expect
+fn ass_color(rgb: &str) -> String {
+    if rgb.len() != 6 {
+        return rgb.to_string();
+    }
+    format!("{}{}{}", &rgb[4..6], &rgb[2..4], &rgb[0..2])
+}
 
 /// Configuration options for caption styling and positioning
 #[derive(Debug, Clone)]
 pub struct CaptionStyle {
     /// Font size in pixels
     pub font_size: u32,
-    /// Font color in hex format (e.g., "FFFFFF" for white)
+    /// Font color in RRGGBB hex format (e.g., "FFFFFF" for white)
     pub font_color: String,
+    /// Color of the word being spoken in karaoke captions, RRGGBB hex
+    pub highlight_color: String,
     /// Font name (e.g., "Arial", "Helvetica")
     pub font_name: String,
     /// Horizontal alignment: "left", "center", or "right"
     pub h_align: String,
-    /// Margin from bottom in pixels
+    /// Vertical position: top, center, or bottom
+    pub position: CaptionPosition,
+    /// Vertical margin in pixels (from the top edge for top captions)
     pub margin_bottom: u32,
     /// Background color in hex format (e.g., "000000" for black)
     pub bg_color: Option<String>,
@@ -33,8 +160,10 @@ impl Default for CaptionStyle {
         Self {
             font_size: 8,
             font_color: "FFFFFF".to_string(),
+            highlight_color: "FFFF00".to_string(),
             font_name: "Arial".to_string(),
             h_align: "center".to_string(),
+            position: CaptionPosition::Bottom,
             margin_bottom: 20, // 20 pixels from bottom
             bg_color: None,
             bg_opacity: None,
@@ -46,84 +175,334 @@ impl Default for CaptionStyle {
     }
 }
 
-/// Extracts audio from a video file using ffmpeg
-pub fn extract_audio(video_path: &str, output_path: &str) -> Result<()> {
-    let status = Command::new("ffmpeg")
+/// Height of the canvas caption margins are measured on: libass lays out SRT
+/// input, like our karaoke ASS, on a 384x288 script scaled to the video
+const CAPTION_CANVAS_HEIGHT: f32 = 288.0;
+
+/// Largest caption margin, as a percentage of the video height; past the
+/// middle it would push captions into the other half of the frame
+const MAX_CAPTION_MARGIN_PERCENT: f32 = 50.0;
+
+/// Converts a caption margin given as a percentage of the video height to
+/// the `MarginV` units of the caption canvas
+pub fn caption_margin(percent: f32) -> u32 {
+    (CAPTION_CANVAS_HEIGHT * percent / 100.0).round() as u32
+}
+
+/// Validates a caption margin percentage passed as `flag`
+pub fn check_caption_margin(flag: &str, percent: f32) -> Result<()> {
+    if !(0.0..=MAX_CAPTION_MARGIN_PERCENT).contains(&percent) {
+        return Err(Land2PortError::InvalidOption(format!(
+            "invalid {flag} {percent}: expected a percentage of the video height between 0 and {MAX_CAPTION_MARGIN_PERCENT}"
+        )));
+    }
+    Ok(())
+}
+
+/// ASS numpad alignment for a horizontal alignment and vertical position
+/// (1-3 bottom, 4-6 middle, 7-9 top; left/center/right within each row)
+fn caption_alignment(h_align: &str, position: CaptionPosition) -> u32 {
+    let column = match h_align {
+        "left" => 1,
+        "center" => 2,
+        "right" => 3,
+        _ => 1,
+    };
+    let row = match position {
+        CaptionPosition::Bottom => 0,
+        CaptionPosition::Center => 3,
+        CaptionPosition::Top => 6,
+    };
+    column + row
+}
+
+/// Formats seconds as an ASS timestamp (`H:MM:SS.cc`)
+fn ass_timestamp(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        centis / 6000 % 60,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+/// Strips characters that ASS would interpret as override blocks or line breaks
+fn ass_text(text: &str) -> String {
+    text.replace(['{', '}'], "")
+        .replace('\\', "")
+        .replace('\n', "\\N")
+}
+
+/// Builds an ASS subtitle document that highlights the word being spoken.
+/// Each timed word gets its own event showing the full line with that word in
+/// `style.highlight_color`; segments without word timing are shown as a
+/// single line-level event. The remaining styling is applied at burn time via
+/// `burn_captions`, so the script uses the same 384x288 canvas as SRT input.
+/// `font_sizes`, one per segment, override the style's size for
+/// `--caption-dynamic-size`.
+pub fn build_karaoke_ass(
+    segments: &[CaptionSegment],
+    style: &CaptionStyle,
+    font_sizes: Option<&[u32]>,
+) -> String {
+    let mut ass = String::from(
+        "[Script Info]\n\
+         ScriptType: v4.00+\n\
+         PlayResX: 384\n\
+         PlayResY: 288\n\
+         \n\
+         [V4+ Styles]\n\
+         Format: Name, Fontname, Fontsize, PrimaryColour, Alignment, MarginV\n",
+    );
+    ass.push_str(&format!(
+        "Style: Default,{},{},&H00{},{},{}\n\n",
+        style.font_name,
+        style.font_size,
+        ass_color(&style.font_color),
+        caption_alignment(&style.h_align, style.position),
+        style.margin_bottom
+    ));
+    ass.push_str("[Events]\nFormat: Layer, Start, End, Style, Text\n");
+
+    let highlight = format!("{{\\c&H{}&}}", ass_color(&style.highlight_color));
+    for (index, segment) in segments.iter().enumerate() {
+        let size = font_sizes
+            .and_then(|sizes| sizes.get(index))
+            .map(|size| format!("{{\\fs{size}}}"))
+            .unwrap_or_default();
+        if segment.words.is_empty() {
+            ass.push_str(&format!(
+                "Dialogue: 0,{},{},Default,{}{}\n",
+                ass_timestamp(segment.start),
+                ass_timestamp(segment.end),
+                size,
+                ass_text(&segment.text)
+            ));
+            continue;
+        }
+
+        let last = segment.words.len() - 1;
+        for (i, word) in segment.words.iter().enumerate() {
+            // Hold each highlight until the next word starts so the line never blinks
+            let start = if i == 0 {
+                segment.start.min(word.start)
+            } else {
+                word.start
+            };
+            let end = if i == last {
+                segment.end.max(word.end)
+            } else {
+                segment.words[i + 1].start
+            };
+            if end <= start {
+                continue;
+            }
+
+            let line = segment
+                .words
+                .iter()
+                .enumerate()
+                .map(|(j, w)| {
+                    // `{\r}` resets the size along with the highlight
+                    if j == i {
+                        format!("{}{}{{\\r}}{}", highlight, ass_text(&w.text), size)
+                    } else {
+                        ass_text(&w.text)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            ass.push_str(&format!(
+                "Dialogue: 0,{},{},Default,{}{}\n",
+                ass_timestamp(start),
+                ass_timestamp(end),
+                size,
+                line
+            ));
+        }
+    }
+
+    ass
+}
+
+/// Audio codecs that can be stream-copied into the MP4 containers this tool
+/// writes; anything else has to be re-encoded
+const MP4_AUDIO_CODECS: &[&str] = &["aac", "mp3", "alac", "ac3", "eac3", "opus", "flac"];
+
+/// Reads the codec name of the first audio stream in `path` via `ffprobe`
+pub fn probe_audio_codec(path: &str) -> Option<String> {
+    let output = Command::new("ffprobe")
         .args([
-            "-i",
-            video_path,
-            "-vn", // Disable video
-            "-acodec",
-            "copy", // Copy audio stream without re-encoding
-            output_path,
+            "-v",
+            "0",
+            "-select_streams",
+            "a:0",
+            "-show_entries",
+            "stream=codec_name",
+            "-of",
+            "csv=p=0",
         ])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let codec = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!codec.is_empty()).then_some(codec)
+}
+
+/// ffmpeg audio codec arguments for writing a stream of `codec` into an MP4:
+/// a lossless stream copy when the container supports it, otherwise AAC.
+/// An unknown codec is copied, leaving ffmpeg to report any mismatch.
+fn mp4_audio_codec_args(codec: Option<&str>) -> Vec<&'static str> {
+    match codec {
+        Some(codec) if !MP4_AUDIO_CODECS.contains(&codec) => {
+            vec!["-c:a", "aac", "-b:a", "192k"]
+        }
+        _ => vec!["-c:a", "copy"],
+    }
+}
+
+/// Extracts audio from a video file using ffmpeg, copying the stream as-is
+/// unless its codec can't be stored in the MP4 output
+pub fn extract_audio(video_path: &str, output_path: &str) -> Result<()> {
+    let codec = probe_audio_codec(video_path);
+    let status = ffmpeg_command()
+        .args(["-i", video_path, "-vn"]) // Disable video
+        .args(mp4_audio_codec_args(codec.as_deref()))
+        .arg(output_path)
         .status()
-        .context("Failed to execute ffmpeg command")?;
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command",
+        ))?;
 
     if !status.success() {
-        anyhow::bail!("ffmpeg command failed with status: {}", status);
+        return Err(Land2PortError::FfmpegFailed(status));
     }
 
     Ok(())
 }
 
-/// Checks if ffmpeg is installed and available in the system
-pub fn check_ffmpeg_installed() -> Result<()> {
-    let status = Command::new("ffmpeg")
-        .arg("-version")
-        .status()
-        .context("Failed to execute ffmpeg command. Is ffmpeg installed?")?;
+/// Names in an `ffmpeg -encoders` or `ffmpeg -muxers` listing: the second
+/// column of each line after the dashed separator that ends the legend
+fn parse_listing(listing: &str) -> Vec<&str> {
+    listing
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("--"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .collect()
+}
+
+/// ffmpeg arguments that silence its banner and progress output under
+/// `--quiet`, leaving only errors
+fn ffmpeg_log_args(quiet: bool) -> &'static [&'static str] {
+    if quiet {
+        &["-hide_banner", "-loglevel", "error"]
+    } else {
+        &[]
+    }
+}
+
+/// An ffmpeg command for a pipeline step, logging as `--quiet` asks
+fn ffmpeg_command() -> Command {
+    let mut command = Command::new("ffmpeg");
+    command.args(ffmpeg_log_args(video_processor_utils::is_quiet()));
+    command
+}
+
+/// Checks if ffmpeg is installed and available in the system, and that it
+/// was built with each of `encoders`, so a minimal build fails here with a
+/// clear message instead of deep in the pipeline
+pub fn check_ffmpeg_installed(encoders: &[&str]) -> Result<()> {
+    let mut version = Command::new("ffmpeg");
+    version.arg("-version");
+    if video_processor_utils::is_quiet() {
+        version.stdout(Stdio::null());
+    }
+    let status = version.status().map_err(Land2PortError::ffmpeg_not_found(
+        "Failed to execute ffmpeg command. Is ffmpeg installed?",
+    ))?;
 
     if !status.success() {
-        anyhow::bail!("ffmpeg is not properly installed");
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+    if encoders.is_empty() {
+        return Ok(());
+    }
+
+    let missing = missing_from_listing("-encoders", encoders)?;
+    if !missing.is_empty() {
+        return Err(Land2PortError::FfmpegEncodersMissing(missing));
     }
 
     Ok(())
 }
 
-/// Burns SRT captions into a video file using ffmpeg with customizable styling
-pub fn burn_captions(
-    video_path: &str,
-    srt_path: &str,
-    output_path: &str,
-    style: Option<CaptionStyle>,
-) -> Result<()> {
-    let style = style.unwrap_or_default();
+/// Checks that ffmpeg was built with each of `muxers`, e.g. `hls` for
+/// `--output-hls`, the same way `check_ffmpeg_installed` checks encoders
+pub fn check_ffmpeg_muxers(muxers: &[&str]) -> Result<()> {
+    if muxers.is_empty() {
+        return Ok(());
+    }
+    let missing = missing_from_listing("-muxers", muxers)?;
+    if !missing.is_empty() {
+        return Err(Land2PortError::FfmpegMuxersMissing(missing));
+    }
+
+    Ok(())
+}
 
+/// Which of `names` are absent from ffmpeg's `flag` listing (`-encoders` or
+/// `-muxers`)
+fn missing_from_listing(flag: &str, names: &[&str]) -> Result<Vec<String>> {
+    let context = format!("Failed to list the ffmpeg {}", flag.trim_start_matches('-'));
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", flag])
+        .output()
+        .map_err(Land2PortError::ffmpeg_not_found(&context))?;
+    let listing = String::from_utf8_lossy(&output.stdout);
+    let available = parse_listing(&listing);
+    Ok(names
+        .iter()
+        .filter(|name| !available.contains(name))
+        .map(|name| name.to_string())
+        .collect())
+}
+
+/// Builds the `subtitles` filter that burns `subtitles_path` with `style`
+fn caption_filter(subtitles_path: &str, style: &CaptionStyle) -> String {
     // Build the subtitle filter string with styling options
     let mut filter_str = format!(
         "subtitles={}:force_style='FontName={},FontSize={},PrimaryColour=&H{},Alignment={},MarginV={}",
-        srt_path,
+        subtitles_path,
         style.font_name,
         style.font_size,
-        style.font_color,
-        match style.h_align.as_str() {
-            "left" => "1",
-            "center" => "2",
-            "right" => "3",
-            _ => "1",
-        },
+        ass_color(&style.font_color),
+        caption_alignment(&style.h_align, style.position),
         style.margin_bottom
     );
 
-    // Determine BorderStyle based on what's specified (check before moving values)
+    // Determine BorderStyle based on what's specified
     let has_background = style.bg_color.is_some() || style.bg_opacity.is_some();
     let has_outline = style.outline_color.is_some() || style.outline_thickness.is_some();
     let has_shadow = style.shadow_color.is_some() || style.shadow_distance.is_some();
 
     // Add background color and opacity if specified
-    if let (Some(bg_color), Some(opacity)) = (style.bg_color, style.bg_opacity) {
+    if let (Some(bg_color), Some(opacity)) = (&style.bg_color, style.bg_opacity) {
         // Convert opacity to hex (0-255)
         let opacity_hex = format!("{:02X}", (opacity * 255.0) as u8);
         // Format background color with opacity
-        let bg_color_with_opacity = format!("{}{}", opacity_hex, bg_color);
+        let bg_color_with_opacity = format!("{}{}", opacity_hex, ass_color(bg_color));
 
         filter_str.push_str(&format!(",BackColour=&H{}", bg_color_with_opacity));
     }
 
     // Add outline color and thickness if specified
-    if let Some(outline_color) = style.outline_color {
-        filter_str.push_str(&format!(",OutlineColour=&H{}", outline_color));
+    if let Some(outline_color) = &style.outline_color {
+        filter_str.push_str(&format!(",OutlineColour=&H{}", ass_color(outline_color)));
     }
 
     if let Some(outline_thickness) = style.outline_thickness {
@@ -131,8 +510,8 @@ pub fn burn_captions(
     }
 
     // Add shadow color and distance if specified
-    if let Some(shadow_color) = style.shadow_color {
-        filter_str.push_str(&format!(",ShadowColour=&H{}", shadow_color));
+    if let Some(shadow_color) = &style.shadow_color {
+        filter_str.push_str(&format!(",ShadowColour=&H{}", ass_color(shadow_color)));
     }
 
     if let Some(shadow_distance) = style.shadow_distance {
@@ -149,77 +528,1705 @@ pub fn burn_captions(
 
     filter_str.push_str(&format!(",BorderStyle={}", border_style));
     filter_str.push('\'');
+    filter_str
+}
 
-    println!("filter_str: {}", filter_str);
+/// Builds the ffmpeg arguments to run `video_path` through the caption
+/// `filter`, encoding the video with `format`'s codec (at `encode`'s settings
+/// for MP4) and copying the audio
+fn burn_captions_args(
+    video_path: &str,
+    filter: &str,
+    output_path: &str,
+    format: OutputFormat,
+    encode: &video_sink::EncodeSettings,
+) -> Vec<String> {
+    let mut args: Vec<String> = ["-y", "-i", video_path, "-vf", filter]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    args.extend(format.video_codec_args(encode));
+    args.extend(
+        [
+            "-c:a",
+            "copy", // Copy audio stream without re-encoding
+            output_path,
+        ]
+        .iter()
+        .map(|arg| arg.to_string()),
+    );
+    args
+}
 
-    let status = Command::new("ffmpeg")
-        .args([
-            "-i",
+/// Burns SRT or ASS captions into a video file using ffmpeg with customizable
+/// styling, encoding the video with `format`'s codec at `encode`'s settings
+pub fn burn_captions(
+    video_path: &str,
+    subtitles_path: &str,
+    output_path: &str,
+    style: Option<CaptionStyle>,
+    format: OutputFormat,
+    encode: &video_sink::EncodeSettings,
+) -> Result<()> {
+    let style = style.unwrap_or_default();
+    let filter_str = caption_filter(subtitles_path, &style);
+
+    video_processor_utils::debug_println(format_args!("filter_str: {}", filter_str));
+
+    let status = ffmpeg_command()
+        .args(burn_captions_args(
             video_path,
-            "-vf",
             &filter_str,
-            "-c:a",
-            "copy", // Copy audio stream without re-encoding
             output_path,
-        ])
+            format,
+            encode,
+        ))
         .status()
-        .context("Failed to execute ffmpeg command to burn captions")?;
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to burn captions",
+        ))?;
 
     if !status.success() {
-        anyhow::bail!("ffmpeg command failed with status: {}", status);
+        return Err(Land2PortError::FfmpegFailed(status));
     }
 
     Ok(())
 }
 
-/// Combines a video file with an audio file into a new video file
-pub fn combine_video_audio(video_path: &str, audio_path: &str, output_path: &str) -> Result<()> {
-    let status = Command::new("ffmpeg")
-        .args([
-            "-i",
-            video_path, // Input video
-            "-i",
-            audio_path, // Input audio
-            "-c:v",
-            "copy", // Copy video stream without re-encoding
-            "-c:a",
-            "copy", // Copy audio stream without re-encoding
+/// Builds the ffmpeg arguments to mux `video_path` with `audio_path`: the
+/// video is copied, and the audio (of codec `audio_codec`) is copied when
+/// `format`'s container supports it, otherwise re-encoded
+fn combine_args(
+    video_path: &str,
+    audio_path: &str,
+    output_path: &str,
+    format: OutputFormat,
+    audio_codec: Option<&str>,
+) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-y", // Overwrite output file if it exists
+        "-i", video_path, // Input video
+        "-i", audio_path, // Input audio
+        "-c:v", "copy", // Copy video stream without re-encoding
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    args.extend(
+        format
+            .audio_codec_args(audio_codec)
+            .into_iter()
+            .map(String::from),
+    );
+    args.extend(
+        [
             "-map",
             "0:v:0", // Use video from first input
             "-map",
             "1:a:0",     // Use audio from second input
             "-shortest", // End when shortest input ends
             output_path,
+        ]
+        .iter()
+        .map(|arg| arg.to_string()),
+    );
+    args
+}
+
+/// Combines a video file, already encoded for `format`, with an audio file
+/// into a new video file. The video is copied without re-encoding; the audio
+/// is only re-encoded when its codec can't be stored in the container.
+pub fn combine_video_audio(
+    video_path: &str,
+    audio_path: &str,
+    output_path: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let codec = probe_audio_codec(audio_path);
+    let status = ffmpeg_command()
+        .args(combine_args(
+            video_path,
+            audio_path,
+            output_path,
+            format,
+            codec.as_deref(),
+        ))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to combine video and audio",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments to re-encode a silent video into `format`
+fn convert_args(
+    input_path: &str,
+    output_path: &str,
+    format: OutputFormat,
+    encode: &video_sink::EncodeSettings,
+) -> Vec<String> {
+    let mut args = vec!["-y".to_string(), "-i".to_string(), input_path.to_string()];
+    args.extend(format.video_codec_args(encode));
+    args.extend(["-an".to_string(), output_path.to_string()]);
+    args
+}
+
+/// Re-encodes the processed (H.264 MP4) video into `format`, for runs without
+/// captions whose output isn't MP4
+pub fn convert_video(
+    input_path: &str,
+    output_path: &str,
+    format: OutputFormat,
+    encode: &video_sink::EncodeSettings,
+) -> Result<()> {
+    let status = ffmpeg_command()
+        .args(convert_args(input_path, output_path, format, encode))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to convert the video",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// How far past `--pad-to-duration`, in seconds, a video may run and still be
+/// cut to length, absorbing a frame of rounding in the encode
+pub const PAD_DURATION_TOLERANCE: f64 = 0.1;
+
+/// Validates a `--pad-to-duration` target in seconds
+pub fn check_pad_duration(target: f64) -> Result<()> {
+    if !target.is_finite() || target <= 0.0 {
+        return Err(Land2PortError::InvalidOption(format!(
+            "invalid pad duration {target}: expected more than 0 seconds"
+        )));
+    }
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments to extend `video_path` to exactly `target`
+/// seconds by holding its last frame, padding the audio (when `has_audio`)
+/// with silence, encoded for `format` (at `encode`'s settings for MP4)
+fn pad_args(
+    video_path: &str,
+    output_path: &str,
+    target: f64,
+    has_audio: bool,
+    format: OutputFormat,
+    encode: &video_sink::EncodeSettings,
+) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-y",
+        "-i",
+        video_path,
+        "-vf",
+        "tpad=stop=-1:stop_mode=clone",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    args.extend(format.video_codec_args(encode));
+    if has_audio {
+        args.extend(["-af".to_string(), "apad".to_string()]);
+        args.extend(format.audio_encode_args().into_iter().map(String::from));
+    }
+    args.extend([
+        "-t".to_string(),
+        format!("{target:.3}"),
+        output_path.to_string(),
+    ]);
+    args
+}
+
+/// Pads `video_path` to `target` seconds for `--pad-to-duration`, holding
+/// the last frame over silence, re-encoding at `encode`'s settings
+pub fn pad_video(
+    video_path: &str,
+    output_path: &str,
+    target: f64,
+    format: OutputFormat,
+    encode: &video_sink::EncodeSettings,
+) -> Result<()> {
+    let has_audio = probe_audio_codec(video_path).is_some();
+    let status = ffmpeg_command()
+        .args(pad_args(
+            video_path,
+            output_path,
+            target,
+            has_audio,
+            format,
+            encode,
+        ))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to pad the video",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// A stretch of silence reported by ffmpeg's `silencedetect`, in seconds.
+/// `end` is `None` when the silence runs to the end of the input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SilenceInterval {
+    start: f64,
+    end: Option<f64>,
+}
+
+/// Slack, in seconds, when deciding whether silence touches an edge of the input
+const SILENCE_EDGE_TOLERANCE: f64 = 0.05;
+
+/// Parses the `silence_start`/`silence_end` lines `silencedetect` logs to stderr
+fn parse_silence_intervals(log: &str) -> Vec<SilenceInterval> {
+    let value_after = |line: &str, key: &str| -> Option<f64> {
+        let rest = &line[line.find(key)? + key.len()..];
+        rest.split_whitespace().next()?.parse().ok()
+    };
+
+    let mut intervals: Vec<SilenceInterval> = Vec::new();
+    for line in log.lines() {
+        if let Some(start) = value_after(line, "silence_start:") {
+            intervals.push(SilenceInterval {
+                start: start.max(0.0),
+                end: None,
+            });
+        } else if let Some(end) = value_after(line, "silence_end:")
+            && let Some(last) = intervals.last_mut()
+        {
+            last.end = Some(end);
+        }
+    }
+    intervals
+}
+
+/// The `(start, end)` span left after dropping silence that touches the start
+/// or end of an input of `duration` seconds, or `None` when there is nothing
+/// to trim (or the input is silent throughout)
+fn speech_bounds(intervals: &[SilenceInterval], duration: f64) -> Option<(f64, f64)> {
+    let mut start = 0.0;
+    let mut end = duration;
+
+    if let Some(first) = intervals.first()
+        && first.start <= SILENCE_EDGE_TOLERANCE
+    {
+        start = first.end.unwrap_or(duration);
+    }
+    if let Some(last) = intervals.last()
+        && last
+            .end
+            .is_none_or(|silence_end| silence_end >= duration - SILENCE_EDGE_TOLERANCE)
+    {
+        end = end.min(last.start);
+    }
+
+    let trimmed = start > 0.0 || end < duration;
+    (trimmed && end > start).then_some((start, end))
+}
+
+/// Trims leading and trailing silence from a video using ffmpeg's
+/// `silencedetect` (silence is audio below `threshold_db` for at least
+/// `min_duration` seconds). Returns the path to use from here on: `output_path`
+/// when something was trimmed, otherwise `input_path` unchanged.
+pub fn trim_silence(
+    input_path: &str,
+    output_path: &str,
+    threshold_db: f32,
+    min_duration: f64,
+) -> Result<String> {
+    let output = Command::new("ffmpeg")
+        .args([
+            "-i",
+            input_path,
+            "-vn", // Only the audio matters for detection
+            "-af",
+            &format!("silencedetect=noise={threshold_db}dB:d={min_duration}"),
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to detect silence",
+        ))?;
+
+    if !output.status.success() {
+        return Err(Land2PortError::FfmpegFailed(output.status));
+    }
+
+    let duration = video_sink::probe_duration(input_path)
+        .with_context(|| format!("Could not determine the duration of {input_path}"))?;
+    let intervals = parse_silence_intervals(&String::from_utf8_lossy(&output.stderr));
+    let Some((start, end)) = speech_bounds(&intervals, duration) else {
+        return Ok(input_path.to_string());
+    };
+
+    cut_segment(input_path, output_path, start, end)?;
+    Ok(output_path.to_string())
+}
+
+/// Smallest `--caption-dynamic-size` font size, as a share of the caption
+/// font size, given to the quietest cue
+const DYNAMIC_SIZE_MIN_SCALE: f32 = 0.75;
+
+/// Largest `--caption-dynamic-size` font size, as a share of the caption
+/// font size, given to the loudest cue
+const DYNAMIC_SIZE_MAX_SCALE: f32 = 1.5;
+
+/// Builds the ffmpeg arguments to measure the RMS level of every audio frame
+/// of `audio_path` in one pass, printing each frame's `pts_time` and level
+/// to stdout
+fn rms_args(audio_path: &str) -> Vec<String> {
+    [
+        "-i",
+        audio_path,
+        "-vn",
+        "-af",
+        "astats=metadata=1:reset=1,ametadata=mode=print:key=lavfi.astats.Overall.RMS_level:file=-",
+        "-f",
+        "null",
+        "-",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect()
+}
+
+/// Parses the per-frame `(pts_time, RMS dB)` pairs `ametadata` prints.
+/// Silent frames have a level of negative infinity.
+fn parse_rms_levels(log: &str) -> Vec<(f64, f32)> {
+    let mut levels = Vec::new();
+    let mut frame_time = None;
+    for line in log.lines() {
+        if let Some((_, time)) = line.split_once("pts_time:") {
+            frame_time = time.split_whitespace().next().and_then(|t| t.parse().ok());
+        } else if let Some(level) = line.strip_prefix("lavfi.astats.Overall.RMS_level=")
+            && let (Some(time), Ok(level)) = (frame_time.take(), level.trim().parse())
+        {
+            levels.push((time, level));
+        }
+    }
+    levels
+}
+
+/// RMS level in dB of the frames in `levels` that start within each cue,
+/// averaged as power; `None` for a cue with no frames or only digital silence
+fn cue_levels(levels: &[(f64, f32)], cues: &[(f64, f64)]) -> Vec<Option<f32>> {
+    cues.iter()
+        .map(|&(start, end)| {
+            let powers: Vec<f64> = levels
+                .iter()
+                .filter(|(time, _)| (start..end).contains(time))
+                .map(|&(_, level)| 10f64.powf(level as f64 / 10.0))
+                .collect();
+            let power = powers.iter().sum::<f64>() / powers.len() as f64;
+            (power > 0.0).then(|| (10.0 * power.log10()) as f32)
+        })
+        .collect()
+}
+
+/// Measures the RMS level of every audio frame of `audio_path`, as
+/// `(pts_time, dB)` pairs
+fn measure_rms_levels(audio_path: &str) -> Result<Vec<(f64, f32)>> {
+    // Run ffmpeg directly: the levels are read from its stdout
+    let output = Command::new("ffmpeg")
+        .args(rms_args(audio_path))
+        .output()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to measure caption loudness",
+        ))?;
+
+    if !output.status.success() {
+        return Err(Land2PortError::FfmpegFailed(output.status));
+    }
+
+    Ok(parse_rms_levels(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Scales each cue's font size with its RMS level for
+/// `--caption-dynamic-size`: the quietest measured cue gets
+/// `DYNAMIC_SIZE_MIN_SCALE` of `base_size`, the loudest
+/// `DYNAMIC_SIZE_MAX_SCALE`, and the rest fall linearly in dB between them.
+/// Cues without a level, and every cue when all levels are equal, keep
+/// `base_size`.
+fn dynamic_font_sizes(levels: &[Option<f32>], base_size: u32) -> Vec<u32> {
+    let measured = levels.iter().flatten().copied();
+    let quietest = measured.clone().fold(f32::INFINITY, f32::min);
+    let loudest = measured.fold(f32::NEG_INFINITY, f32::max);
+    let min_size = base_size as f32 * DYNAMIC_SIZE_MIN_SCALE;
+    let max_size = base_size as f32 * DYNAMIC_SIZE_MAX_SCALE;
+
+    levels
+        .iter()
+        .map(|level| match level {
+            Some(level) if loudest > quietest => {
+                let loudness = (level - quietest) / (loudest - quietest);
+                (min_size + loudness * (max_size - min_size))
+                    .round()
+                    .max(1.0) as u32
+            }
+            _ => base_size,
+        })
+        .collect()
+}
+
+/// Font sizes for caption cues spanning `cues` (start and end in seconds),
+/// scaled by how loud `audio_path` is under each for `--caption-dynamic-size`
+pub fn cue_font_sizes(audio_path: &str, cues: &[(f64, f64)], base_size: u32) -> Result<Vec<u32>> {
+    let levels = cue_levels(&measure_rms_levels(audio_path)?, cues);
+    Ok(dynamic_font_sizes(&levels, base_size))
+}
+
+/// Parses a `--start-time`/`--end-time` value: plain seconds (`90`, `12.5`)
+/// or a `[HH:]MM:SS[.fff]` timestamp
+pub fn parse_timestamp(text: &str) -> Result<f64> {
+    let invalid = || {
+        Land2PortError::InvalidOption(format!(
+            "invalid time '{text}': expected seconds or HH:MM:SS"
+        ))
+    };
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    let (seconds, larger_units) = parts.split_last().ok_or_else(invalid)?;
+    let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+    if !seconds.is_finite() || seconds < 0.0 || (!larger_units.is_empty() && seconds >= 60.0) {
+        return Err(invalid());
+    }
+
+    let mut total = 0.0;
+    for (i, unit) in larger_units.iter().enumerate() {
+        let value: u32 = unit.parse().map_err(|_| invalid())?;
+        // The leading unit may run over (90:00 is 90 minutes), but minutes
+        // that follow hours may not
+        let is_minutes_after_hours = larger_units.len() == 2 && i == 1;
+        if is_minutes_after_hours && value >= 60 {
+            return Err(invalid());
+        }
+        total = total * 60.0 + value as f64;
+    }
+    Ok(total * 60.0 + seconds)
+}
+
+/// Resolves the `(start, end)` clip to cut from a source of `duration`
+/// seconds, defaulting to its start and end, and checks the clip is non-empty
+/// and inside the source
+pub fn clip_bounds(start: Option<f64>, end: Option<f64>, duration: f64) -> Result<(f64, f64)> {
+    let start = start.unwrap_or(0.0);
+    let end = end.unwrap_or(duration);
+    if start >= end {
+        return Err(Land2PortError::InvalidOption(format!(
+            "--start-time ({start}s) must be before --end-time ({end}s)"
+        )));
+    }
+    if start >= duration {
+        return Err(Land2PortError::InvalidOption(format!(
+            "--start-time ({start}s) is past the end of the source ({duration:.3}s)"
+        )));
+    }
+    if end > duration {
+        return Err(Land2PortError::InvalidOption(format!(
+            "--end-time ({end}s) is past the end of the source ({duration:.3}s)"
+        )));
+    }
+    Ok((start, end))
+}
+
+/// Cuts the `start`..`end` seconds of a video into `output_path`, so the
+/// output's timestamps start at zero.
+pub fn cut_segment(input_path: &str, output_path: &str, start: f64, end: f64) -> Result<()> {
+    // Re-encode the video rather than stream copy so the cut lands on the
+    // exact frame instead of the nearest keyframe, keeping audio and video in
+    // sync. Audio packets are short enough to copy without drift.
+    let codec = probe_audio_codec(input_path);
+    let status = ffmpeg_command()
+        .args([
+            "-y",
+            "-ss",
+            &format!("{start:.3}"),
+            "-i",
+            input_path,
+            "-t",
+            &format!("{:.3}", end - start),
+            "-c:v",
+            "libx264",
+            "-crf",
+            "18",
         ])
+        .args(mp4_audio_codec_args(codec.as_deref()))
+        .arg(output_path)
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to cut the video",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments to re-encode `input_path` at a constant `fps`,
+/// duplicating or dropping frames to follow the source timestamps. The audio
+/// stream is kept as-is, so it stays aligned with the resampled video.
+fn constant_frame_rate_args(
+    input_path: &str,
+    output_path: &str,
+    fps: f64,
+    codec: Option<&str>,
+) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-y",
+        "-i",
+        input_path,
+        "-vf",
+        &format!("fps={fps}"),
+        "-fps_mode",
+        "cfr",
+        "-c:v",
+        "libx264",
+        "-crf",
+        "18",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    args.extend(
+        mp4_audio_codec_args(codec)
+            .iter()
+            .map(|arg| arg.to_string()),
+    );
+    args.push(output_path.to_string());
+    args
+}
+
+/// Re-encodes a variable-frame-rate video into `output_path` at a constant
+/// `fps`, so frame `n` plays at `n / fps` seconds as the cropping, smoothing
+/// and caption timing all assume.
+pub fn normalize_frame_rate(input_path: &str, output_path: &str, fps: f64) -> Result<()> {
+    let codec = probe_audio_codec(input_path);
+    let status = ffmpeg_command()
+        .args(constant_frame_rate_args(
+            input_path,
+            output_path,
+            fps,
+            codec.as_deref(),
+        ))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to normalize the frame rate",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments to re-encode `input_path` turned by `rotation`,
+/// keeping the audio stream as-is
+fn rotate_args(
+    input_path: &str,
+    output_path: &str,
+    rotation: Rotation,
+    codec: Option<&str>,
+) -> Vec<String> {
+    let mut args: Vec<String> = ["-y", "-i", input_path]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    if let Some(filter) = rotation.ffmpeg_filter() {
+        args.extend(["-vf".to_string(), filter.to_string()]);
+    }
+    args.extend(
+        ["-c:v", "libx264", "-crf", "18"]
+            .iter()
+            .map(|arg| arg.to_string()),
+    );
+    args.extend(
+        mp4_audio_codec_args(codec)
+            .iter()
+            .map(|arg| arg.to_string()),
+    );
+    args.push(output_path.to_string());
+    args
+}
+
+/// Re-encodes a sideways or upside-down video into `output_path` turned
+/// upright by `rotation`
+pub fn rotate_video(input_path: &str, output_path: &str, rotation: Rotation) -> Result<()> {
+    let codec = probe_audio_codec(input_path);
+    let status = ffmpeg_command()
+        .args(rotate_args(
+            input_path,
+            output_path,
+            rotation,
+            codec.as_deref(),
+        ))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to rotate the video",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments to save the frame of `video_path` at `time`
+/// seconds as a high-quality JPEG
+fn poster_args(video_path: &str, output_path: &str, time: f64) -> Vec<String> {
+    [
+        "-y",
+        "-ss",
+        &format!("{time:.3}"),
+        "-i",
+        video_path,
+        "-frames:v",
+        "1",
+        "-q:v",
+        "2",
+        output_path,
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect()
+}
+
+/// Saves the frame of `video_path` at `time` seconds to `output_path` as a
+/// poster image
+pub fn extract_poster(video_path: &str, output_path: &str, time: f64) -> Result<()> {
+    let status = ffmpeg_command()
+        .args(poster_args(video_path, output_path, time))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to extract the poster frame",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments to segment `video_path` for HLS streaming:
+/// the streams are copied into `segment_duration`-second `.ts` chunks in
+/// `output_dir`, listed by a VOD `playlist.m3u8` there
+fn hls_args(video_path: &str, output_dir: &str, segment_duration: f64) -> Vec<String> {
+    [
+        "-y",
+        "-i",
+        video_path,
+        "-c",
+        "copy",
+        "-f",
+        "hls",
+        "-hls_time",
+        &segment_duration.to_string(),
+        "-hls_playlist_type",
+        "vod",
+        "-hls_segment_filename",
+        &format!("{}/segment_%03d.ts", output_dir),
+        &format!("{}/playlist.m3u8", output_dir),
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect()
+}
+
+/// Validates an `--hls-segment-duration` in seconds
+pub fn check_hls_segment_duration(duration: f64) -> Result<()> {
+    if !duration.is_finite() || duration <= 0.0 {
+        return Err(Land2PortError::InvalidOption(format!(
+            "invalid HLS segment duration {duration}: expected more than 0 seconds"
+        )));
+    }
+    Ok(())
+}
+
+/// Segments `video_path` into `.ts` chunks with a `playlist.m3u8` in
+/// `output_dir` for HLS streaming
+pub fn segment_hls(video_path: &str, output_dir: &str, segment_duration: f64) -> Result<()> {
+    let status = ffmpeg_command()
+        .args(hls_args(video_path, output_dir, segment_duration))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to segment the video for HLS",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments to mix a music bed under `voice_path`. The
+/// music is looped indefinitely and the mix ends with the voice track, so a
+/// short bed repeats and a long one is cut off. With `duck`, a sidechain
+/// compressor keyed on the voice lowers the music while someone is speaking.
+fn music_mix_args(
+    voice_path: &str,
+    music_path: &str,
+    output_path: &str,
+    music_volume: f32,
+    duck: bool,
+) -> Vec<String> {
+    // normalize=0 keeps amix from halving the voice level
+    let mix = "amix=inputs=2:duration=first:dropout_transition=0:normalize=0[out]";
+    let filter = if duck {
+        format!(
+            "[1:a]volume={music_volume}[music];[0:a]asplit=2[voice][key];\
+             [music][key]sidechaincompress=threshold=0.05:ratio=8:attack=20:release=400[ducked];\
+             [voice][ducked]{mix}"
+        )
+    } else {
+        format!("[1:a]volume={music_volume}[music];[0:a][music]{mix}")
+    };
+
+    vec![
+        "-y".to_string(),
+        "-i".to_string(),
+        voice_path.to_string(),
+        "-stream_loop".to_string(),
+        "-1".to_string(), // Loop the music until the voice track ends
+        "-i".to_string(),
+        music_path.to_string(),
+        "-filter_complex".to_string(),
+        filter,
+        "-map".to_string(),
+        "[out]".to_string(),
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-b:a".to_string(),
+        "192k".to_string(),
+        output_path.to_string(),
+    ]
+}
+
+/// Validates a music bed volume, a linear gain where 1.0 leaves it unchanged
+pub fn check_music_volume(music_volume: f32) -> Result<()> {
+    if !music_volume.is_finite() || music_volume < 0.0 {
+        return Err(Land2PortError::InvalidOption(format!(
+            "invalid music volume {music_volume}: expected a gain of 0 or more"
+        )));
+    }
+    Ok(())
+}
+
+/// Mixes a background music track under the audio at `voice_path`, looping or
+/// trimming the music to the voice track's length, optionally ducking it
+/// while the voice is active
+pub fn mix_music(
+    voice_path: &str,
+    music_path: &str,
+    output_path: &str,
+    music_volume: f32,
+    duck: bool,
+) -> Result<()> {
+    check_music_volume(music_volume)?;
+
+    let status = ffmpeg_command()
+        .args(music_mix_args(
+            voice_path,
+            music_path,
+            output_path,
+            music_volume,
+            duck,
+        ))
         .status()
-        .context("Failed to execute ffmpeg command to combine video and audio")?;
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to mix music",
+        ))?;
 
     if !status.success() {
-        anyhow::bail!("ffmpeg command failed with status: {}", status);
+        return Err(Land2PortError::FfmpegFailed(status));
     }
 
     Ok(())
 }
 
-/// Compresses an audio file from MP4 format to MP3 format using ffmpeg
-pub fn compress_to_mp3(input_path: &str, output_path: &str) -> Result<()> {
-    let status = Command::new("ffmpeg")
+/// Integrated loudness range accepted by ffmpeg's `loudnorm` filter
+const LOUDNORM_LUFS_RANGE: std::ops::RangeInclusive<f32> = -70.0..=-5.0;
+
+/// Validates a loudness target in LUFS against the range `loudnorm` accepts
+pub fn check_target_lufs(target_lufs: f32) -> Result<()> {
+    if !LOUDNORM_LUFS_RANGE.contains(&target_lufs) {
+        return Err(Land2PortError::InvalidOption(format!(
+            "invalid target loudness {target_lufs} LUFS: expected between {} and {}",
+            LOUDNORM_LUFS_RANGE.start(),
+            LOUDNORM_LUFS_RANGE.end()
+        )));
+    }
+    Ok(())
+}
+
+/// Builds the ffmpeg arguments for a single-pass EBU R128 `loudnorm` of
+/// `input_path` to `target_lufs`, re-encoding to AAC since filtering rules
+/// out a stream copy
+fn loudnorm_args(input_path: &str, output_path: &str, target_lufs: f32) -> Vec<String> {
+    vec![
+        "-y".to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+        "-vn".to_string(), // Disable video
+        "-af".to_string(),
+        format!("loudnorm=I={target_lufs}:TP=-1.5:LRA=11"),
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-b:a".to_string(),
+        "192k".to_string(),
+        output_path.to_string(),
+    ]
+}
+
+/// Normalizes the loudness of an audio file to `target_lufs` (EBU R128) using
+/// ffmpeg's `loudnorm` filter
+pub fn normalize_loudness(input_path: &str, output_path: &str, target_lufs: f32) -> Result<()> {
+    check_target_lufs(target_lufs)?;
+
+    let status = ffmpeg_command()
+        .args(loudnorm_args(input_path, output_path, target_lufs))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to normalize audio",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Checks an `--audio-bitrate` value: a positive number of bits per second,
+/// optionally with a `k` suffix for kilobits as ffmpeg accepts (e.g. `64k`)
+pub fn check_audio_bitrate(bitrate: &str) -> Result<()> {
+    let digits = bitrate
+        .strip_suffix('k')
+        .or_else(|| bitrate.strip_suffix('K'))
+        .unwrap_or(bitrate);
+    match digits.parse::<u32>() {
+        Ok(value) if value > 0 => Ok(()),
+        _ => Err(Land2PortError::InvalidOption(format!(
+            "invalid audio bitrate '{bitrate}': expected e.g. 64k or 96000"
+        ))),
+    }
+}
+
+/// Builds the ffmpeg arguments to compress `input_path` to MP3: at a constant
+/// `bitrate` when given, otherwise LAME's variable-bitrate quality 6
+fn mp3_args(input_path: &str, output_path: &str, bitrate: Option<&str>) -> Vec<String> {
+    let mut args = vec![
+        "-i".to_string(),
+        input_path.to_string(),
+        "-vn".to_string(), // Disable video
+        "-acodec".to_string(),
+        "libmp3lame".to_string(), // Use MP3 codec
+    ];
+    match bitrate {
+        Some(bitrate) => args.extend(["-b:a".to_string(), bitrate.to_string()]),
+        // Quality 6 (range is 0-9 where lower is better) averages ~115 kbps
+        None => args.extend(["-q:a".to_string(), "6".to_string()]),
+    }
+    args.push(output_path.to_string());
+    args
+}
+
+/// Compresses an audio file from MP4 format to MP3 format using ffmpeg, at
+/// `bitrate` (`--audio-bitrate`) when given
+pub fn compress_to_mp3(input_path: &str, output_path: &str, bitrate: Option<&str>) -> Result<()> {
+    let status = ffmpeg_command()
+        .args(mp3_args(input_path, output_path, bitrate))
+        .status()
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to compress audio",
+        ))?;
+
+    if !status.success() {
+        return Err(Land2PortError::FfmpegFailed(status));
+    }
+
+    Ok(())
+}
+
+/// Converts audio to the 16 kHz mono 16-bit WAV that whisper.cpp expects
+pub fn convert_to_whisper_wav(input_path: &str, output_path: &str) -> Result<()> {
+    let status = ffmpeg_command()
         .args([
+            "-y",
             "-i",
             input_path,
-            "-vn", // Disable video
-            "-acodec",
-            "libmp3lame", // Use MP3 codec
-            "-q:a",
-            "6", // Set quality (2 is high quality, range is 0-9 where lower is better)
+            "-vn",
+            "-ar",
+            "16000",
+            "-ac",
+            "1",
+            "-c:a",
+            "pcm_s16le",
             output_path,
         ])
         .status()
-        .context("Failed to execute ffmpeg command to compress audio")?;
+        .map_err(Land2PortError::ffmpeg_not_found(
+            "Failed to execute ffmpeg command to convert audio to WAV",
+        ))?;
 
     if !status.success() {
-        anyhow::bail!("ffmpeg command failed with status: {}", status);
+        return Err(Land2PortError::FfmpegFailed(status));
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::TimedWord;
+
+    #[test]
+    fn test_parse_listing() {
+        let listing = "\
+Encoders:
+ V..... = Video
+ A..... = Audio
+ S..... = Subtitle
+ .F.... = Frame-level multithreading
+ ------
+ V....D a64multi             Multicolor charset for Commodore 64 (codec a64_multi)
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ A....D aac                  AAC (Advanced Audio Coding)
+ A....D libopus              libopus Opus (codec opus)
+ S..... ass                  ASS (Advanced SubStation Alpha) subtitle
+";
+        let encoders = parse_listing(listing);
+        assert_eq!(encoders, ["a64multi", "libx264", "aac", "libopus", "ass"]);
+        // The legend isn't mistaken for encoders
+        assert!(!encoders.contains(&"="));
+        assert!(!encoders.contains(&"libmp3lame"));
+        assert!(parse_listing("").is_empty());
+
+        let listing = "\
+File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+  E 3g2             3GP2 (3GPP2 file format)
+  E hls             Apple HTTP Live Streaming
+ DE mp4             MP4 (MPEG-4 Part 14)
+";
+        assert_eq!(parse_listing(listing), ["3g2", "hls", "mp4"]);
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("ff8800").unwrap(), "FF8800");
+        assert_eq!(parse_hex_color("#00AAFF").unwrap(), "00AAFF");
+        assert!(parse_hex_color("FFF").is_err());
+        assert!(parse_hex_color("GG0000").is_err());
+        assert!(parse_hex_color("red").is_err());
+    }
+
+    #[test]
+    fn test_ass_color_swaps_to_bgr() {
+        assert_eq!(ass_color("FF8800"), "0088FF");
+        assert_eq!(ass_color("FFFFFF"), "FFFFFF");
+    }
+
+    #[test]
+    fn test_caption_alignment() {
+        assert_eq!(caption_alignment("center", CaptionPosition::Bottom), 2);
+        assert_eq!(caption_alignment("center", CaptionPosition::Center), 5);
+        assert_eq!(caption_alignment("left", CaptionPosition::Top), 7);
+        assert_eq!(caption_alignment("right", CaptionPosition::Top), 9);
+        assert_eq!(
+            "Top".parse::<CaptionPosition>().unwrap(),
+            CaptionPosition::Top
+        );
+        assert!("middle".parse::<CaptionPosition>().is_err());
+    }
+
+    #[test]
+    fn test_parse_silence_intervals() {
+        let log = "\
+[silencedetect @ 0x7f8] silence_start: -0.0015
+[silencedetect @ 0x7f8] silence_end: 1.52 | silence_duration: 1.5215
+size=N/A time=00:00:10.00 bitrate=N/A speed= 500x
+[silencedetect @ 0x7f8] silence_start: 8.75
+";
+        assert_eq!(
+            parse_silence_intervals(log),
+            vec![
+                SilenceInterval {
+                    start: 0.0,
+                    end: Some(1.52)
+                },
+                SilenceInterval {
+                    start: 8.75,
+                    end: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_speech_bounds() {
+        let silence = |start, end| SilenceInterval { start, end };
+
+        // Leading and trailing silence are both trimmed
+        assert_eq!(
+            speech_bounds(&[silence(0.0, Some(1.5)), silence(8.75, None)], 10.0),
+            Some((1.5, 8.75))
+        );
+        // A pause in the middle is kept
+        assert_eq!(
+            speech_bounds(&[silence(4.0, Some(5.0)), silence(9.0, Some(10.0))], 10.0),
+            Some((0.0, 9.0))
+        );
+        // Nothing to trim
+        assert_eq!(speech_bounds(&[silence(4.0, Some(5.0))], 10.0), None);
+        assert_eq!(speech_bounds(&[], 10.0), None);
+        // Silent throughout: leave the input alone
+        assert_eq!(speech_bounds(&[silence(0.0, None)], 10.0), None);
+    }
+
+    #[test]
+    fn test_parse_rms_levels() {
+        let log = "\
+frame:0    pts:0       pts_time:0
+lavfi.astats.Overall.RMS_level=-23.400000
+frame:1    pts:1024    pts_time:0.0232
+lavfi.astats.Overall.RMS_level=-inf
+frame:2    pts:2048    pts_time:0.0464
+";
+        let levels = parse_rms_levels(log);
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0], (0.0, -23.4));
+        assert_eq!(levels[1].0, 0.0232);
+        assert_eq!(levels[1].1, f32::NEG_INFINITY);
+        assert!(rms_args("audio.mp4").contains(&"audio.mp4".to_string()));
+    }
+
+    #[test]
+    fn test_cue_levels() {
+        // Frames every 0.5 s: two at -20 dB, then two at -30 dB, then silence
+        let levels = [
+            (0.0, -20.0),
+            (0.5, -20.0),
+            (1.0, -30.0),
+            (1.5, -30.0),
+            (2.0, f32::NEG_INFINITY),
+        ];
+        let cues = [(0.0, 1.0), (0.5, 1.5), (2.0, 2.5), (5.0, 6.0)];
+        let levels = cue_levels(&levels, &cues);
+        assert!((levels[0].unwrap() + 20.0).abs() < 0.01);
+        // Averaged as power, so the louder frame dominates
+        assert!((levels[1].unwrap() + 22.6).abs() < 0.1);
+        // Digital silence, or a cue past the end of the audio
+        assert_eq!(levels[2], None);
+        assert_eq!(levels[3], None);
+    }
+
+    #[test]
+    fn test_dynamic_font_sizes() {
+        // Quietest at 0.75x, loudest at 1.5x, unmeasured at the base size
+        assert_eq!(
+            dynamic_font_sizes(&[Some(-30.0), Some(-20.0), None, Some(-25.0)], 8),
+            [6, 12, 8, 9]
+        );
+        // Nothing to compare against: every cue keeps the base size
+        assert_eq!(dynamic_font_sizes(&[Some(-20.0), Some(-20.0)], 8), [8, 8]);
+        assert_eq!(dynamic_font_sizes(&[None], 8), [8]);
+        assert!(dynamic_font_sizes(&[], 8).is_empty());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("90").unwrap(), 90.0);
+        assert_eq!(parse_timestamp("12.5").unwrap(), 12.5);
+        assert_eq!(parse_timestamp("01:30").unwrap(), 90.0);
+        assert_eq!(parse_timestamp("00:01:30").unwrap(), 90.0);
+        assert_eq!(parse_timestamp("1:02:03.5").unwrap(), 3723.5);
+        assert_eq!(parse_timestamp("90:00").unwrap(), 5400.0);
+
+        for invalid in [
+            "", "abc", "-5", "00:60", "00:61:00", "1:2:3:4", "1::00", "inf",
+        ] {
+            assert!(
+                parse_timestamp(invalid).is_err(),
+                "{invalid:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_clip_bounds() {
+        assert_eq!(clip_bounds(None, None, 120.0).unwrap(), (0.0, 120.0));
+        assert_eq!(clip_bounds(Some(30.0), None, 120.0).unwrap(), (30.0, 120.0));
+        assert_eq!(clip_bounds(None, Some(60.0), 120.0).unwrap(), (0.0, 60.0));
+        assert_eq!(
+            clip_bounds(Some(45.0), Some(75.0), 120.0).unwrap(),
+            (45.0, 75.0)
+        );
+
+        assert!(clip_bounds(Some(75.0), Some(45.0), 120.0).is_err());
+        assert!(clip_bounds(Some(60.0), Some(60.0), 120.0).is_err());
+        assert!(clip_bounds(Some(130.0), None, 120.0).is_err());
+        assert!(clip_bounds(None, Some(130.0), 120.0).is_err());
+    }
+
+    #[test]
+    fn test_music_mix_args() {
+        let args = music_mix_args("voice.mp4", "music.mp3", "mix.mp4", 0.2, false);
+        assert_eq!(
+            args,
+            [
+                "-y",
+                "-i",
+                "voice.mp4",
+                "-stream_loop",
+                "-1",
+                "-i",
+                "music.mp3",
+                "-filter_complex",
+                "[1:a]volume=0.2[music];[0:a][music]amix=inputs=2:duration=first:dropout_transition=0:normalize=0[out]",
+                "-map",
+                "[out]",
+                "-c:a",
+                "aac",
+                "-b:a",
+                "192k",
+                "mix.mp4",
+            ]
+        );
+
+        let ducked = music_mix_args("voice.mp4", "music.mp3", "mix.mp4", 0.5, true);
+        let filter = &ducked[8];
+        assert!(filter.starts_with("[1:a]volume=0.5[music];[0:a]asplit=2[voice][key];"));
+        assert!(filter.contains("[music][key]sidechaincompress="));
+        assert!(filter.ends_with(
+            "[voice][ducked]amix=inputs=2:duration=first:dropout_transition=0:normalize=0[out]"
+        ));
+    }
+
+    #[test]
+    fn test_check_music_volume() {
+        assert!(check_music_volume(0.0).is_ok());
+        assert!(check_music_volume(1.5).is_ok());
+        assert!(check_music_volume(-0.1).is_err());
+        assert!(check_music_volume(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_mp4_audio_codec_args() {
+        assert_eq!(mp4_audio_codec_args(Some("aac")), ["-c:a", "copy"]);
+        assert_eq!(mp4_audio_codec_args(Some("opus")), ["-c:a", "copy"]);
+        assert_eq!(mp4_audio_codec_args(None), ["-c:a", "copy"]);
+        assert_eq!(
+            mp4_audio_codec_args(Some("pcm_s16le")),
+            ["-c:a", "aac", "-b:a", "192k"]
+        );
+        assert_eq!(
+            mp4_audio_codec_args(Some("vorbis")),
+            ["-c:a", "aac", "-b:a", "192k"]
+        );
+    }
+
+    #[test]
+    fn test_loudnorm_args() {
+        let args = loudnorm_args("in.mp4", "out.mp4", -14.0);
+        assert_eq!(
+            args,
+            [
+                "-y",
+                "-i",
+                "in.mp4",
+                "-vn",
+                "-af",
+                "loudnorm=I=-14:TP=-1.5:LRA=11",
+                "-c:a",
+                "aac",
+                "-b:a",
+                "192k",
+                "out.mp4",
+            ]
+        );
+        assert!(
+            loudnorm_args("a", "b", -23.5).contains(&"loudnorm=I=-23.5:TP=-1.5:LRA=11".to_string())
+        );
+    }
+
+    #[test]
+    fn test_poster_args() {
+        assert_eq!(
+            poster_args("final_output.mp4", "poster.jpg", 12.5),
+            [
+                "-y",
+                "-ss",
+                "12.500",
+                "-i",
+                "final_output.mp4",
+                "-frames:v",
+                "1",
+                "-q:v",
+                "2",
+                "poster.jpg"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constant_frame_rate_args() {
+        let args = constant_frame_rate_args("phone.mov", "cfr.mp4", 29.97, Some("aac"));
+        assert!(args.windows(2).any(|pair| pair == ["-vf", "fps=29.97"]));
+        assert!(args.windows(2).any(|pair| pair == ["-fps_mode", "cfr"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c:a", "copy"]));
+        assert_eq!(args.last().unwrap(), "cfr.mp4");
+
+        let args = constant_frame_rate_args("phone.mkv", "cfr.mp4", 60.0, Some("vorbis"));
+        assert!(args.windows(2).any(|pair| pair == ["-c:a", "aac"]));
+    }
+
+    #[test]
+    fn test_rotate_args() {
+        let args = rotate_args("phone.mov", "rotated.mp4", Rotation::Deg90, Some("aac"));
+        assert!(
+            args.windows(2)
+                .any(|pair| pair == ["-vf", "transpose=clock"])
+        );
+        assert!(args.windows(2).any(|pair| pair == ["-c:v", "libx264"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c:a", "copy"]));
+        assert_eq!(args.last().unwrap(), "rotated.mp4");
+
+        let args = rotate_args("phone.mov", "rotated.mp4", Rotation::Deg180, Some("aac"));
+        assert!(args.windows(2).any(|pair| pair == ["-vf", "hflip,vflip"]));
+    }
+
+    #[test]
+    fn test_hls_args() {
+        assert_eq!(
+            hls_args("final_output.mp4", "runs/x", 4.0),
+            [
+                "-y",
+                "-i",
+                "final_output.mp4",
+                "-c",
+                "copy",
+                "-f",
+                "hls",
+                "-hls_time",
+                "4",
+                "-hls_playlist_type",
+                "vod",
+                "-hls_segment_filename",
+                "runs/x/segment_%03d.ts",
+                "runs/x/playlist.m3u8"
+            ]
+        );
+        assert!(check_hls_segment_duration(6.0).is_ok());
+        assert!(check_hls_segment_duration(0.0).is_err());
+        assert!(check_hls_segment_duration(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_pad_args() {
+        assert_eq!(
+            pad_args(
+                "final_output.mp4",
+                "padded.mp4",
+                15.0,
+                true,
+                OutputFormat::Mp4,
+                &video_sink::EncodeSettings {
+                    crf: Some(18),
+                    preset: Some("fast".to_string()),
+                    bitrate: None,
+                }
+            ),
+            [
+                "-y",
+                "-i",
+                "final_output.mp4",
+                "-vf",
+                "tpad=stop=-1:stop_mode=clone",
+                "-c:v",
+                "libx264",
+                "-preset",
+                "fast",
+                "-crf",
+                "18",
+                "-af",
+                "apad",
+                "-c:a",
+                "aac",
+                "-b:a",
+                "192k",
+                "-t",
+                "15.000",
+                "padded.mp4"
+            ]
+        );
+        // Silent videos get no audio filter; WebM re-encodes its VP9 video
+        let args = pad_args(
+            "in.webm",
+            "out.webm",
+            6.5,
+            false,
+            OutputFormat::Webm,
+            &video_sink::EncodeSettings::default(),
+        );
+        assert!(args.contains(&"libvpx-vp9".to_string()));
+        assert!(!args.contains(&"apad".to_string()));
+        assert!(args.ends_with(&[
+            "-t".to_string(),
+            "6.500".to_string(),
+            "out.webm".to_string()
+        ]));
+        assert!(check_pad_duration(15.0).is_ok());
+        assert!(check_pad_duration(0.0).is_err());
+    }
+
+    #[test]
+    fn test_ffmpeg_log_args() {
+        assert!(ffmpeg_log_args(false).is_empty());
+        assert_eq!(
+            ffmpeg_log_args(true),
+            ["-hide_banner", "-loglevel", "error"]
+        );
+    }
+
+    #[test]
+    fn test_mp3_args_bitrate() {
+        assert_eq!(
+            mp3_args("in.m4a", "out.mp3", Some("48k")),
+            [
+                "-i",
+                "in.m4a",
+                "-vn",
+                "-acodec",
+                "libmp3lame",
+                "-b:a",
+                "48k",
+                "out.mp3"
+            ]
+        );
+        let default_args = mp3_args("in.m4a", "out.mp3", None);
+        assert!(default_args.windows(2).any(|pair| pair == ["-q:a", "6"]));
+        assert!(!default_args.contains(&"-b:a".to_string()));
+    }
+
+    #[test]
+    fn test_check_audio_bitrate() {
+        assert!(check_audio_bitrate("64k").is_ok());
+        assert!(check_audio_bitrate("96000").is_ok());
+        assert!(check_audio_bitrate("0k").is_err());
+        assert!(matches!(
+            check_audio_bitrate("fast"),
+            Err(Land2PortError::InvalidOption(_))
+        ));
+    }
+
+    #[test]
+    fn test_combine_args_mp4() {
+        assert_eq!(
+            combine_args("v.mp4", "a.mp4", "out.mp4", OutputFormat::Mp4, Some("aac")),
+            [
+                "-y",
+                "-i",
+                "v.mp4",
+                "-i",
+                "a.mp4",
+                "-c:v",
+                "copy",
+                "-c:a",
+                "copy",
+                "-map",
+                "0:v:0",
+                "-map",
+                "1:a:0",
+                "-shortest",
+                "out.mp4",
+            ]
+        );
+        assert!(
+            combine_args(
+                "v.mp4",
+                "a.mp4",
+                "out.mp4",
+                OutputFormat::Mp4,
+                Some("pcm_s16le")
+            )
+            .windows(2)
+            .any(|pair| pair == ["-c:a", "aac"])
+        );
+    }
+
+    #[test]
+    fn test_burn_captions_args_apply_margin() {
+        // A 15% margin clears the bottom UI safe zone: 43 of the 288-unit canvas
+        let style = CaptionStyle {
+            margin_bottom: caption_margin(15.0),
+            ..CaptionStyle::default()
+        };
+        let args = burn_captions_args(
+            "v.mp4",
+            &caption_filter("transcript.srt", &style),
+            "out.mp4",
+            OutputFormat::Mp4,
+            &video_sink::EncodeSettings::default(),
+        );
+        assert_eq!(args[..4], ["-y", "-i", "v.mp4", "-vf"]);
+        assert!(args[4].starts_with("subtitles=transcript.srt:force_style='"));
+        assert!(args[4].contains(",Alignment=2,MarginV=43,"));
+        assert_eq!(
+            args[5..],
+            [
+                "-c:v", "libx264", "-preset", "medium", "-c:a", "copy", "out.mp4"
+            ]
+        );
+
+        // Top captions keep the margin, measured from the top edge
+        let style = CaptionStyle {
+            position: CaptionPosition::Top,
+            margin_bottom: caption_margin(10.0),
+            ..CaptionStyle::default()
+        };
+        let filter = caption_filter("transcript.srt", &style);
+        assert!(filter.contains(",Alignment=8,MarginV=29,"));
+    }
+
+    #[test]
+    fn test_burn_captions_args_use_encode_settings() {
+        // --crf, --preset, and --video-bitrate reach the caption re-encode
+        let encode = video_sink::EncodeSettings {
+            crf: Some(20),
+            preset: Some("slow".to_string()),
+            bitrate: None,
+        };
+        let args = burn_captions_args(
+            "v.mp4",
+            "subtitles=t.srt",
+            "out.mp4",
+            OutputFormat::Mp4,
+            &encode,
+        );
+        assert_eq!(
+            args[5..],
+            [
+                "-c:v", "libx264", "-preset", "slow", "-crf", "20", "-c:a", "copy", "out.mp4"
+            ]
+        );
+
+        let encode = video_sink::EncodeSettings {
+            bitrate: Some(2_500_000),
+            ..Default::default()
+        };
+        let args = burn_captions_args(
+            "v.mp4",
+            "subtitles=t.srt",
+            "out.mp4",
+            OutputFormat::Mp4,
+            &encode,
+        );
+        assert_eq!(
+            args[5..11],
+            ["-c:v", "libx264", "-preset", "medium", "-b:v", "2500000"]
+        );
+
+        // WebM keeps its own VP9 settings
+        let args = burn_captions_args(
+            "v.mp4",
+            "subtitles=t.srt",
+            "out.webm",
+            OutputFormat::Webm,
+            &encode,
+        );
+        assert!(args.contains(&"libvpx-vp9".to_string()));
+        assert!(!args.contains(&"libx264".to_string()));
+    }
+
+    #[test]
+    fn test_check_caption_margin() {
+        assert!(check_caption_margin("--caption-margin-bottom", 0.0).is_ok());
+        assert!(check_caption_margin("--caption-margin-bottom", 15.0).is_ok());
+        assert!(check_caption_margin("--caption-margin-top", 50.0).is_ok());
+        assert!(check_caption_margin("--caption-margin-top", 60.0).is_err());
+        assert!(check_caption_margin("--caption-margin-bottom", -5.0).is_err());
+        assert!(check_caption_margin("--caption-margin-bottom", f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_combine_args_webm() {
+        assert_eq!(
+            combine_args(
+                "v.webm",
+                "a.mp4",
+                "out.webm",
+                OutputFormat::Webm,
+                Some("aac")
+            ),
+            [
+                "-y",
+                "-i",
+                "v.webm",
+                "-i",
+                "a.mp4",
+                "-c:v",
+                "copy",
+                "-c:a",
+                "libopus",
+                "-b:a",
+                "128k",
+                "-map",
+                "0:v:0",
+                "-map",
+                "1:a:0",
+                "-shortest",
+                "out.webm",
+            ]
+        );
+        // Opus audio is already valid in WebM
+        assert!(
+            combine_args(
+                "v.webm",
+                "a.mka",
+                "out.webm",
+                OutputFormat::Webm,
+                Some("opus")
+            )
+            .windows(2)
+            .any(|pair| pair == ["-c:a", "copy"])
+        );
+    }
+
+    #[test]
+    fn test_convert_args() {
+        assert_eq!(
+            convert_args(
+                "in.mp4",
+                "out.webm",
+                OutputFormat::Webm,
+                &video_sink::EncodeSettings::default()
+            ),
+            [
+                "-y",
+                "-i",
+                "in.mp4",
+                "-c:v",
+                "libvpx-vp9",
+                "-crf",
+                "32",
+                "-b:v",
+                "0",
+                "-an",
+                "out.webm"
+            ]
+        );
+        let encode = video_sink::EncodeSettings {
+            crf: Some(23),
+            ..Default::default()
+        };
+        assert_eq!(
+            convert_args("in.mp4", "out.mp4", OutputFormat::Mp4, &encode),
+            [
+                "-y", "-i", "in.mp4", "-c:v", "libx264", "-preset", "medium", "-crf", "23", "-an",
+                "out.mp4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!("mp4".parse(), Ok(OutputFormat::Mp4));
+        assert_eq!("WebM".parse(), Ok(OutputFormat::Webm));
+        assert!("mkv".parse::<OutputFormat>().is_err());
+        assert_eq!(OutputFormat::Webm.extension(), "webm");
+    }
+
+    #[test]
+    fn test_check_target_lufs() {
+        assert!(check_target_lufs(-14.0).is_ok());
+        assert!(check_target_lufs(-70.0).is_ok());
+        assert!(check_target_lufs(0.0).is_err());
+        assert!(check_target_lufs(-80.0).is_err());
+    }
+
+    fn word(text: &str, start: f64, end: f64) -> TimedWord {
+        TimedWord {
+            text: text.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_ass_timestamp() {
+        assert_eq!(ass_timestamp(0.0), "0:00:00.00");
+        assert_eq!(ass_timestamp(3723.456), "1:02:03.46");
+    }
+
+    #[test]
+    fn test_build_karaoke_ass_highlights_each_word() {
+        let segments = vec![CaptionSegment {
+            start: 0.0,
+            end: 1.5,
+            text: "Hello there".to_string(),
+            words: vec![word("Hello", 0.1, 0.6), word("there", 0.8, 1.4)],
+        }];
+
+        let ass = build_karaoke_ass(&segments, &CaptionStyle::default(), None);
+        assert!(ass.contains("PlayResY: 288"));
+        assert!(ass.contains(
+            "Dialogue: 0,0:00:00.00,0:00:00.80,Default,{\\c&H00FFFF&}Hello{\\r} there\n"
+        ));
+        assert!(ass.contains(
+            "Dialogue: 0,0:00:00.80,0:00:01.50,Default,Hello {\\c&H00FFFF&}there{\\r}\n"
+        ));
+    }
+
+    #[test]
+    fn test_build_karaoke_ass_keeps_dynamic_size_after_highlight() {
+        let segments = vec![CaptionSegment {
+            start: 0.0,
+            end: 1.5,
+            text: "Hello there".to_string(),
+            words: vec![word("Hello", 0.1, 0.6), word("there", 0.8, 1.4)],
+        }];
+
+        let ass = build_karaoke_ass(&segments, &CaptionStyle::default(), Some(&[12]));
+        assert!(ass.contains(
+            "Dialogue: 0,0:00:00.00,0:00:00.80,Default,{\\fs12}{\\c&H00FFFF&}Hello{\\r}{\\fs12} there\n"
+        ));
+    }
+
+    #[test]
+    fn test_build_karaoke_ass_falls_back_to_line_timing() {
+        let segments = vec![CaptionSegment {
+            start: 2.0,
+            end: 4.0,
+            text: "No {word} timing".to_string(),
+            words: Vec::new(),
+        }];
+
+        let ass = build_karaoke_ass(&segments, &CaptionStyle::default(), None);
+        assert!(ass.contains("Dialogue: 0,0:00:02.00,0:00:04.00,Default,No word timing\n"));
+        assert!(!ass.contains("\\c&H"));
+    }
+}
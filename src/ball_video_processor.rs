@@ -1,5 +1,5 @@
 use crate::cli::Args;
-use crate::crop;
+use crate::crop::{self, AspectRatio};
 use crate::image::CutDetector;
 use crate::video_processor::VideoProcessor;
 use crate::video_processor_utils;
@@ -15,47 +15,49 @@ pub struct BallVideoProcessor {
     hbb_three_frames_ago: Option<Hbb>,
     hbb_two_frames_ago: Option<Hbb>,
     hbb_last_frame: Option<Hbb>,
+    prediction_damping: f32,
+    /// Consecutive frames without a ball detection
+    consecutive_misses: usize,
+    max_prediction_gap: usize,
     cut_detector: CutDetector,
 }
 
 impl BallVideoProcessor {
-    /// Creates a new ball video processor
-    pub fn new(args: &Args) -> Self {
+    /// Creates a new ball video processor for a source at `frame_rate` fps
+    pub fn new(args: &Args, frame_rate: f64) -> Self {
         Self {
             previous_crop: None,
             most_recent_image: None,
             hbb_three_frames_ago: None,
             hbb_two_frames_ago: None,
             hbb_last_frame: None,
-            cut_detector: CutDetector::new(args.cut_similarity, args.cut_start),
+            prediction_damping: args.prediction_damping,
+            consecutive_misses: 0,
+            max_prediction_gap: args.max_prediction_gap,
+            cut_detector: CutDetector::new(
+                args.cut_similarity,
+                args.cut_start,
+                video_processor_utils::min_scene_frames(args, frame_rate),
+                args.cut_mode,
+            ),
         }
     }
-}
 
-impl VideoProcessor for BallVideoProcessor {
-    /// Processes a single frame with ball-specific smoothing logic
-    fn process_frame_with_smoothing(
+    /// Chooses the crop for a frame from its ball detections: the detected
+    /// ball when there is one, a predicted position while detections are
+    /// missing for up to `max_prediction_gap` frames, and otherwise the
+    /// previous crop
+    fn choose_crop(
         &mut self,
-        img: &usls::Image,
         latest_crop: &crop::CropResult,
-        objects: &[&usls::Hbb],
-        args: &Args,
-        viewer: &mut VideoSink,
-        _smooth_duration_frames: usize,
-    ) -> Result<()> {
+        objects: &[&Hbb],
+        is_cut: bool,
+        frame_width: f32,
+        frame_height: f32,
+        aspect_ratio: AspectRatio,
+    ) -> Result<crop::CropResult> {
         let current_ball_count = objects.len();
 
-        // Determine if there was a cut
-        let is_cut = if let Some(ref most_recent) = self.most_recent_image {
-            self.cut_detector.is_cut(most_recent, img)?
-        } else {
-            true
-        };
-
-        // Update most_recent_image for next frame
-        self.most_recent_image = Some(img.clone());
-
-        // Apply the ball-specific algorithm
         let crop_result = if is_cut {
             // If there was a cut, use latest_crop
             video_processor_utils::debug_println(format_args!(
@@ -64,10 +66,13 @@ impl VideoProcessor for BallVideoProcessor {
             self.hbb_three_frames_ago = None;
             self.hbb_two_frames_ago = None;
             self.hbb_last_frame = None;
+            self.consecutive_misses = 0;
             latest_crop.clone()
         } else {
             // If no cut, check ball count
             if current_ball_count > 0 {
+                // A real detection ends any prediction gap
+                self.consecutive_misses = 0;
                 if current_ball_count > 1 {
                     // Multiple balls detected - find the highest confidence ball
                     let highest_confidence_ball = objects
@@ -91,9 +96,10 @@ impl VideoProcessor for BallVideoProcessor {
                     let single_ball_crop = crop::calculate_crop(
                         false, // Don't use stack crop for single ball
                         false, // Not graphic mode for ball processing
-                        img.width() as f32,
-                        img.height() as f32,
+                        frame_width,
+                        frame_height,
                         &[highest_confidence_ball],
+                        aspect_ratio,
                     )?;
 
                     self.hbb_three_frames_ago = self.hbb_two_frames_ago.take();
@@ -117,43 +123,54 @@ impl VideoProcessor for BallVideoProcessor {
                     latest_crop.clone()
                 }
             } else {
-                // If no balls detected, try to predict position or use previous crop
-                if let (Some(three_frames_ago), Some(two_frames_ago), Some(last_frame)) = (
-                    &self.hbb_three_frames_ago,
-                    &self.hbb_two_frames_ago,
-                    &self.hbb_last_frame,
-                ) {
+                // If no balls detected, predict the position for up to
+                // `max_prediction_gap` frames, then hold the previous crop
+                self.consecutive_misses += 1;
+                if self.consecutive_misses <= self.max_prediction_gap
+                    && let (Some(three_frames_ago), Some(two_frames_ago), Some(last_frame)) = (
+                        &self.hbb_three_frames_ago,
+                        &self.hbb_two_frames_ago,
+                        &self.hbb_last_frame,
+                    )
+                {
+                    video_processor_utils::debug_println(format_args!(
+                        "No cut, no balls detected, predicting ball position (missed {} of up to {} frames)",
+                        self.consecutive_misses, self.max_prediction_gap
+                    ));
                     let current_hbb = predict_current_hbb(
                         three_frames_ago,
                         two_frames_ago,
                         last_frame,
-                        img.width() as f32,
-                        img.height() as f32,
+                        frame_width,
+                        frame_height,
+                        self.prediction_damping,
                     );
                     let current_crop = crop::calculate_crop(
                         false, // Don't use stack crop for single ball
                         false, // Not graphic mode for ball processing
-                        img.width() as f32,
-                        img.height() as f32,
+                        frame_width,
+                        frame_height,
                         &[&current_hbb],
+                        aspect_ratio,
                     )?;
                     self.hbb_three_frames_ago = self.hbb_two_frames_ago.take();
                     self.hbb_two_frames_ago = self.hbb_last_frame.take();
                     self.hbb_last_frame = Some(current_hbb);
                     current_crop
                 } else {
-                    // Not enough history for prediction, use previous crop
+                    // Gap too long or not enough history for prediction, use
+                    // previous crop
                     self.hbb_three_frames_ago = self.hbb_two_frames_ago.take();
                     self.hbb_two_frames_ago = self.hbb_last_frame.take();
                     self.hbb_last_frame = None;
                     if let Some(prev_crop) = &self.previous_crop {
                         video_processor_utils::debug_println(format_args!(
-                            "No cut, no balls detected, insufficient history, using previous ball crop"
+                            "No cut, no balls detected, gap too long or insufficient history, using previous ball crop"
                         ));
                         prev_crop.clone()
                     } else {
                         video_processor_utils::debug_println(format_args!(
-                            "No cut, no balls detected, insufficient history, no previous crop, using latest crop"
+                            "No cut, no balls detected, gap too long or insufficient history, no previous crop, using latest crop"
                         ));
                         latest_crop.clone()
                     }
@@ -163,9 +180,42 @@ impl VideoProcessor for BallVideoProcessor {
 
         // Update previous crop
         self.previous_crop = Some(crop_result.clone());
+        Ok(crop_result)
+    }
+}
+
+impl VideoProcessor for BallVideoProcessor {
+    /// Processes a single frame with ball-specific smoothing logic
+    fn process_frame_with_smoothing(
+        &mut self,
+        img: &usls::Image,
+        latest_crop: &crop::CropResult,
+        objects: &[&usls::Hbb],
+        args: &Args,
+        viewer: &mut VideoSink,
+        _smooth_duration_frames: usize,
+    ) -> Result<()> {
+        // Determine if there was a cut
+        let is_cut = if let Some(ref most_recent) = self.most_recent_image {
+            self.cut_detector.is_cut(most_recent, img)?
+        } else {
+            true
+        };
+
+        // Update most_recent_image for next frame
+        self.most_recent_image = Some(img.clone());
+
+        let crop_result = self.choose_crop(
+            latest_crop,
+            objects,
+            is_cut,
+            img.width() as f32,
+            img.height() as f32,
+            args.aspect_ratio,
+        )?;
 
         // Process and display the chosen crop
-        video_processor_utils::process_and_display_crop(img, &crop_result, viewer, args.headless)?;
+        video_processor_utils::process_and_display_crop(img, &crop_result, viewer, args)?;
         Ok(())
     }
 
@@ -193,5 +243,88 @@ impl VideoProcessor for BallVideoProcessor {
             "hbb_last_frame: {:?}",
             self.hbb_last_frame
         ));
+        video_processor_utils::debug_println(format_args!(
+            "consecutive_misses: {:?}",
+            self.consecutive_misses
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argh::FromArgs;
+
+    const WIDTH: f32 = 1920.0;
+    const HEIGHT: f32 = 1080.0;
+
+    fn crop_x(crop: &crop::CropResult) -> f32 {
+        match crop {
+            crop::CropResult::Single(area) => area.x,
+            other => panic!("expected a single crop, got {:?}", other),
+        }
+    }
+
+    /// Feeds a ball moving right at 20 px/frame, detected except in `gap`,
+    /// and returns the crop x for each frame
+    fn run_with_gap(max_prediction_gap: &str, gap: std::ops::Range<usize>) -> Vec<f32> {
+        let args = Args::from_args(
+            &["land2port"],
+            &[
+                "--object",
+                "ball",
+                "--max-prediction-gap",
+                max_prediction_gap,
+            ],
+        )
+        .unwrap();
+        let mut processor = BallVideoProcessor::new(&args, 30.0);
+
+        (0..10)
+            .map(|frame| {
+                let ball = Hbb::from_xywh(800.0 + 20.0 * frame as f32, 500.0, 30.0, 30.0);
+                let objects: Vec<&Hbb> = if gap.contains(&frame) {
+                    vec![]
+                } else {
+                    vec![&ball]
+                };
+                let latest_crop =
+                    crop::calculate_crop(false, false, WIDTH, HEIGHT, &objects, args.aspect_ratio)
+                        .unwrap();
+                let crop = processor
+                    .choose_crop(
+                        &latest_crop,
+                        &objects,
+                        frame == 0,
+                        WIDTH,
+                        HEIGHT,
+                        args.aspect_ratio,
+                    )
+                    .unwrap();
+                crop_x(&crop)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_prediction_fills_detection_gap() {
+        let xs = run_with_gap("5", 4..7);
+
+        // The crop keeps moving at the ball's pace through the 3-frame gap and
+        // continues seamlessly once detections return
+        for step in xs.windows(2) {
+            assert!((step[1] - step[0] - 20.0).abs() < 1e-3, "{:?}", xs);
+        }
+    }
+
+    #[test]
+    fn test_prediction_stops_after_max_gap() {
+        let xs = run_with_gap("2", 4..7);
+
+        // Two predicted frames, then the crop holds until the ball reappears
+        assert!((xs[5] - xs[4] - 20.0).abs() < 1e-3, "{:?}", xs);
+        assert_eq!(xs[6], xs[5]);
+        // Snaps back to the real detection
+        assert!((xs[7] - xs[3] - 80.0).abs() < 1e-3, "{:?}", xs);
     }
 }
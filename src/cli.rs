@@ -1,13 +1,27 @@
+use crate::audio::{self, CaptionPosition, CaptionStyle, OutputFormat};
+use crate::crop::{AspectRatio, Focus, NoSubjectMode, Roi, SimilarityMetric};
+use crate::image::{CutMode, GraphicFit};
+use crate::orientation::Rotation;
+use crate::transcript::{self, CaptionFormat, TranscribeBackend, TranscriptConfig};
+use crate::video_processor_utils::{MinAreaRatio, TransitionMode};
+use crate::video_sink::{self, EncodeSettings, OutputSize};
+use anyhow::{Context, Result};
 use argh::FromArgs;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::time::Duration;
 
 /// YOLO Example
-#[derive(FromArgs, Debug)]
+#[derive(FromArgs, Debug, Clone)]
 pub struct Args {
-    /// object type: face, head, ball, sports ball, frisbee, person, car, truck, or boat
+    /// object type: face, head, ball, sports ball, frisbee, person, car,
+    /// motorcycle, truck, or boat; comma-separate COCO types (e.g.
+    /// person,sports ball) to frame several together
     #[argh(option, default = "String::from(\"face\")")]
     pub object: String,
 
-    /// source: image, image folder, video stream
+    /// source: video file, video stream, a directory of videos to batch process,
+    /// or a directory of images to process as one silent image sequence
     #[argh(option, default = "String::from(\"./video/video1.mp4\")")]
     pub source: String,
 
@@ -27,6 +41,22 @@ pub struct Args {
     #[argh(option, default = "String::from(\"s\")")]
     pub scale: String,
 
+    /// custom YOLO model (.onnx) to load instead of the preset picked from
+    /// --object and --scale; --object names its class(es) and --ver its YOLO
+    /// version
+    #[argh(option)]
+    pub model_path: Option<String>,
+
+    /// directory to download models into and reuse them from on later runs,
+    /// for offline use (Linux only; default: the usls cache under ~/.cache)
+    #[argh(option)]
+    pub model_cache_dir: Option<String>,
+
+    /// if the detection model fails to load, render a static center crop
+    /// instead of failing the run
+    #[argh(switch)]
+    pub fallback_center_crop: bool,
+
     /// smooth percentage threshold
     #[argh(option, default = "7.5")]
     pub smooth_percentage: f32,
@@ -35,15 +65,78 @@ pub struct Args {
     #[argh(option, default = "1.0")]
     pub smooth_duration: f32,
 
+    /// how crops are compared against --smooth-percentage: delta (each
+    /// coordinate moved by at most that share of the frame width) or iou
+    /// (the boxes overlap by at least 100 minus that percent) (default: delta)
+    #[argh(option, default = "SimilarityMetric::Delta")]
+    pub similarity_metric: SimilarityMetric,
+
+    /// frames the smoothing history holds before committing to a crop change
+    /// (default: smooth duration times the source frame rate)
+    #[argh(option)]
+    pub history_size: Option<usize>,
+
+    /// seconds a newly committed crop is held before the history smoother may
+    /// switch to another subject; scene cuts still switch at once (default:
+    /// 0, off)
+    #[argh(option, default = "0.0")]
+    pub min_hold_duration: f32,
+
+    /// seconds the history smoother takes to move from one crop to the next,
+    /// capped at the frames buffered (default: the whole buffered history)
+    #[argh(option)]
+    pub transition_duration: Option<f32>,
+
+    /// where the history smoother glides between crops: always, within-scene
+    /// (snapping at scene cuts), or on-cut (snapping within a scene)
+    /// (default: within-scene)
+    #[argh(option, default = "TransitionMode::WithinScene")]
+    pub transition_mode: TransitionMode,
+
+    /// seconds of buffered history a crop change must span before the
+    /// history smoother weighs switching to it; shorter changes keep the
+    /// current crop (default: a quarter of the smoothing window)
+    #[argh(option)]
+    pub min_interp_duration: Option<f32>,
+
+    /// detect interval: run detection on every n-th frame only, reusing the
+    /// last detection in between (faster, but reacts to movement up to n-1
+    /// frames late) (default: 1, every frame)
+    #[argh(option, default = "1")]
+    pub detect_interval: usize,
+
+    /// jobs: number of detection worker threads, each with its own model
+    /// instances; 1 runs the sequential pipeline (default: 1)
+    #[argh(option, default = "1")]
+    pub jobs: usize,
+
+    /// frames decoded and detected together, capped at the model's own batch
+    /// size; lower it if detection runs out of GPU memory (default: the
+    /// model's batch size)
+    #[argh(option)]
+    pub batch_size: Option<usize>,
+
     /// object probability threshold
     #[argh(option, default = "0.75")]
     pub object_prob_threshold: f32,
 
+    /// lower the object probability threshold step by step on frames where no
+    /// detection passes it (e.g. dim or motion-blurred ones), down to
+    /// --adaptive-threshold-floor, restoring it once detections pass again
+    #[argh(switch)]
+    pub adaptive_threshold: bool,
+
+    /// lowest object probability threshold --adaptive-threshold relaxes to
+    /// (default: 0.4)
+    #[argh(option, default = "0.4")]
+    pub adaptive_threshold_floor: f32,
+
     /// drop detections smaller than this fraction of the largest detection's area
     /// (default 0.05, ~1/5 its linear size); filters incidental faces (book covers,
     /// distant bystanders) that would split the subject. 0 disables; balls exempt.
-    #[argh(option, default = "0.05")]
-    pub min_area_ratio: f32,
+    /// Per class as e.g. person=0.01,face=0.002
+    #[argh(option, default = "MinAreaRatio::default()")]
+    pub min_area_ratio: MinAreaRatio,
 
     /// cut similarity threshold (default: 0.4)
     #[argh(option, default = "0.4")]
@@ -53,18 +146,140 @@ pub struct Args {
     #[argh(option, default = "0.8")]
     pub cut_start: f64,
 
+    /// minimum scene duration in seconds: ignore cuts detected sooner than
+    /// this after the previous one, so fast-cut montages don't keep resetting
+    /// smoothing (default: 0, off)
+    #[argh(option, default = "0.0")]
+    pub min_scene_duration: f32,
+
+    /// cut detection signal: luma (the default image compare) or color (also
+    /// compare per-channel color histograms, for shots graded differently but
+    /// equally bright)
+    #[argh(option, default = "CutMode::Luma")]
+    pub cut_mode: CutMode,
+
+    /// mean luma (0-255) below which a frame counts as black, e.g. mid fade;
+    /// black frames keep the previous crop instead of being re-cropped
+    /// (default: 10, 0 disables)
+    #[argh(option, default = "10.0")]
+    pub black_frame_threshold: f64,
+
+    /// output aspect ratio as W:H, e.g. 9:16, 4:5 or 1:1 (default: 9:16)
+    #[argh(option, default = "AspectRatio::PORTRAIT")]
+    pub aspect_ratio: AspectRatio,
+
+    /// comma-separated output sizes as WxH, e.g. 1080x1920,720x1280: each is
+    /// rendered from the same crops in one pass, the first as the main output
+    /// (default: the source height as the output width)
+    #[argh(option)]
+    pub output_resolution: Option<String>,
+
+    /// what to frame when no subject is detected: center (crop the frame
+    /// center), letterbox (fit the whole frame with bars), or hold-last (keep
+    /// the previous crop) (default: center)
+    #[argh(option, default = "NoSubjectMode::Center")]
+    pub no_subject_mode: NoSubjectMode,
+
+    /// which subject to frame when several are detected: all (frame them
+    /// all), largest (the biggest box), leftmost, or center (nearest the
+    /// frame center) (default: all)
+    #[argh(option, default = "Focus::All")]
+    pub focus: Focus,
+
+    /// on the first frame with several objects, pause the preview with them
+    /// numbered and frame only the one whose number key is pressed
+    #[argh(switch)]
+    pub interactive_pick: bool,
+
+    /// region of interest as x,y,w,h in source pixels: detections centered
+    /// outside it are ignored, and frames with none inside it are cropped on
+    /// its center
+    #[argh(option)]
+    pub roi: Option<Roi>,
+
+    /// margin around the subject, as a percentage of its size on each side;
+    /// zooms out crops that are shorter than the frame (default: 0)
+    #[argh(option, default = "0.0")]
+    pub crop_padding: f32,
+
+    /// ignore subject movements smaller than this percentage of the frame
+    /// width, holding the previous crop target so jitter doesn't wobble the
+    /// crop (default: 0, off)
+    #[argh(option, default = "0.0")]
+    pub deadzone: f32,
+
+    /// scale on the acceleration term of the ball trajectory prediction, from
+    /// 0 (velocity only) to 1 (full acceleration) (default: 1)
+    #[argh(option, default = "1.0")]
+    pub prediction_damping: f32,
+
+    /// how many consecutive frames without a ball detection are filled with
+    /// a predicted position before the crop holds still; 0 disables
+    /// prediction (default: 10)
+    #[argh(option, default = "10")]
+    pub max_prediction_gap: usize,
+
     /// use headless mode
     #[argh(switch)]
     pub headless: bool,
 
+    /// size of the preview window relative to the output frame, e.g. 0.25 on
+    /// a laptop or 1.0 on a 4K monitor (default: 0.5)
+    #[argh(option, default = "0.5")]
+    pub window_scale: f32,
+
+    /// dry run: run detection and crop selection, printing per-frame object
+    /// counts and chosen crops, without rendering or encoding any video
+    #[argh(switch)]
+    pub dry_run: bool,
+
+    /// print a histogram of the --object detection confidences across the
+    /// clip, with suggested --object-prob-threshold values, instead of writing
+    /// a video (implies --dry-run); --detect-interval samples fewer frames
+    #[argh(switch)]
+    pub report_confidences: bool,
+
+    /// print only errors and the final output path, e.g. when scripting
+    /// around the tool
+    #[argh(switch)]
+    pub quiet: bool,
+
     /// enable stack crop
     #[argh(switch)]
     pub use_stack_crop: bool,
 
+    /// share of the canvas height the top crop of a top/bottom stack fills,
+    /// between 0 and 1 (default: 0.5, equal halves)
+    #[argh(option, default = "0.5")]
+    pub stack_ratio: f32,
+
+    /// instead of stacking far-apart subjects, show them in one sharp crop
+    /// over a blurred, zoomed copy of the full frame
+    #[argh(switch)]
+    pub use_blur_fill: bool,
+
     /// use simple smoothing instead of history smoothing
     #[argh(switch)]
     pub use_simple_smoothing: bool,
 
+    /// use Kalman-filter smoothing: smooth crop motion with no buffering delay
+    #[argh(switch)]
+    pub use_kalman_smoothing: bool,
+
+    /// use moving-average smoothing: center the crop on the average of the
+    /// last --smooth-window crops
+    #[argh(switch)]
+    pub use_moving_average: bool,
+
+    /// crops averaged by --use-moving-average (default: 5)
+    #[argh(option, default = "5")]
+    pub smooth_window: usize,
+
+    /// for looping clips: bend the crops of the last smoothing window back to
+    /// the opening crop, so the end matches the start (history smoothing only)
+    #[argh(switch)]
+    pub loop_smoothing: bool,
+
     /// keep text
     #[argh(switch)]
     pub keep_text: bool,
@@ -73,19 +288,320 @@ pub struct Args {
     #[argh(switch)]
     pub prioritize_text: bool,
 
+    /// how a frame kept whole as a graphic is placed on the output canvas:
+    /// fit (scale to the canvas width with bars above and below) or fill
+    /// (stretch over the whole canvas) (default: fit)
+    #[argh(option, default = "GraphicFit::Fit")]
+    pub graphic_fit: GraphicFit,
+
     /// text threshold: percentage of frame area covered by detected text (default: 0.01)
     #[argh(option, default = "0.008")]
     pub text_area_threshold: f32,
 
+    /// text coverage below which a graphic frame returns to tracking; between
+    /// the two thresholds the previous frame's state is kept (default: 75% of
+    /// --text-area-threshold)
+    #[argh(option)]
+    pub text_area_exit_threshold: Option<f32>,
+
     /// text probability threshold: minimum confidence for text detections (default: 0.85)
     #[argh(option, default = "0.85")]
     pub text_prob_threshold: f32,
 
+    /// draw the detected text boxes and their confidences on the preview, to
+    /// tune --text-area-threshold and --text-prob-threshold
+    #[argh(switch)]
+    pub annotate_graphics: bool,
+
     /// add captions: extract audio, transcribe, burn captions, and recombine
     #[argh(switch)]
     pub add_captions: bool,
 
-    /// output filepath: if set, move the final video to this location
+    /// only caption the source: transcribe and burn captions onto it as is,
+    /// skipping detection and cropping (implies --add-captions)
+    #[argh(switch)]
+    pub captions_only: bool,
+
+    /// caption format: srt, vtt, or both; which caption files are delivered
+    /// next to the output video (default: srt)
+    #[argh(option, default = "CaptionFormat::Srt")]
+    pub caption_format: CaptionFormat,
+
+    /// caption font name (default: Arial)
+    #[argh(option)]
+    pub caption_font: Option<String>,
+
+    /// caption font size (default: 8)
+    #[argh(option)]
+    pub caption_font_size: Option<u32>,
+
+    /// caption text color as RRGGBB hex (default: FFFFFF)
+    #[argh(option)]
+    pub caption_color: Option<String>,
+
+    /// caption outline color as RRGGBB hex, or "none" for no outline (default: 000000)
+    #[argh(option)]
+    pub caption_outline: Option<String>,
+
+    /// caption position: top, center, or bottom (default: bottom)
+    #[argh(option)]
+    pub caption_position: Option<CaptionPosition>,
+
+    /// lift bottom captions this percentage of the video height off the
+    /// bottom edge, clear of platform UI (e.g. 15); 0 to 50
+    #[argh(option)]
+    pub caption_margin_bottom: Option<f32>,
+
+    /// keep top captions this percentage of the video height below the top
+    /// edge; needs --caption-position top; 0 to 50
+    #[argh(option)]
+    pub caption_margin_top: Option<f32>,
+
+    /// transcription language as an ISO-639-1 code, e.g. es or pt
+    /// (default: auto-detect)
+    #[argh(option)]
+    pub language: Option<String>,
+
+    /// transcription backend: cloud (OpenAI Whisper API) or local (a
+    /// whisper.cpp binary, see --whisper-model) (default: cloud)
+    #[argh(option, default = "TranscribeBackend::Cloud")]
+    pub transcribe_backend: TranscribeBackend,
+
+    /// whisper.cpp binary run by --transcribe-backend local (default: whisper-cli)
+    #[argh(option, default = "String::from(\"whisper-cli\")")]
+    pub whisper_command: String,
+
+    /// ggml model file for --transcribe-backend local, e.g. ggml-base.en.bin
+    #[argh(option)]
+    pub whisper_model: Option<String>,
+
+    /// decode with whisper.cpp at temperature 0 and no fallback, so the same
+    /// audio gives the same captions (--transcribe-backend local only)
+    #[argh(switch)]
+    pub deterministic_transcription: bool,
+
+    /// how many times to retry a transcription request that fails with a
+    /// transient error such as a timeout or rate limit (default: 3)
+    #[argh(option, default = "3")]
+    pub transcribe_retries: u32,
+
+    /// seconds to wait before the first transcription retry, doubled for each
+    /// further retry (default: 2)
+    #[argh(option, default = "2.0")]
+    pub transcribe_retry_delay: f64,
+
+    /// bitrate of the MP3 sent for transcription, e.g. 64k; lower it to stay
+    /// under a backend's upload limit on long files (default: variable
+    /// bitrate, about 115k)
+    #[argh(option)]
+    pub audio_bitrate: Option<String>,
+
+    /// translate captions to this ISO-639-1 language before burning, e.g. en;
+    /// the source-language transcript is kept as transcript.original.srt
+    #[argh(option)]
+    pub translate_to: Option<String>,
+
+    /// external translator for --translate-to: run with the target language as
+    /// its argument, it reads a JSON array of cue texts on stdin and prints the
+    /// translated array on stdout (default: Whisper, English only)
+    #[argh(option)]
+    pub translator_command: Option<String>,
+
+    /// karaoke captions: highlight each word as it is spoken using word-level
+    /// timestamps, falling back to whole lines where timing is missing
+    #[argh(switch)]
+    pub caption_karaoke: bool,
+
+    /// also write transcript.json: caption segments with per-word timings,
+    /// for building interactive transcripts
+    #[argh(switch)]
+    pub transcript_json: bool,
+
+    /// karaoke highlight color as RRGGBB hex (default: FFFF00)
+    #[argh(option)]
+    pub caption_highlight_color: Option<String>,
+
+    /// size each caption by how loud the speech under it is, from 0.75x the
+    /// caption font size for the quietest cue to 1.5x for the loudest
+    #[argh(switch)]
+    pub caption_dynamic_size: bool,
+
+    /// wrap caption lines at word boundaries to at most this many characters;
+    /// cues longer than two lines are split and re-timed (default: no wrapping)
+    #[argh(option)]
+    pub caption_max_chars: Option<usize>,
+
+    /// process only the part of the source from this time on, in seconds or
+    /// HH:MM:SS (default: the start)
+    #[argh(option)]
+    pub start_time: Option<String>,
+
+    /// process only the part of the source up to this time, in seconds or
+    /// HH:MM:SS (default: the end)
+    #[argh(option)]
+    pub end_time: Option<String>,
+
+    /// trim leading and trailing silence from the source before processing
+    #[argh(switch)]
+    pub trim_silence: bool,
+
+    /// audio level in dB below which --trim-silence treats audio as silent (default: -50)
+    #[argh(option, default = "-50.0")]
+    pub silence_threshold_db: f32,
+
+    /// minimum length in seconds of silence --trim-silence removes (default: 0.5)
+    #[argh(option, default = "0.5")]
+    pub silence_min_duration: f64,
+
+    /// turn the source clockwise by 0, 90, 180, or 270 degrees before
+    /// processing, for sideways or upside-down footage
+    #[argh(option)]
+    pub rotate: Option<Rotation>,
+
+    /// detect sideways or upside-down footage from detection confidence on a
+    /// sample of frames, and turn it upright before processing
+    #[argh(switch)]
+    pub auto_rotate: bool,
+
+    /// background music to mix under the final audio, looped or trimmed to
+    /// the video's length; requires --add-captions
+    #[argh(option)]
+    pub music: Option<String>,
+
+    /// music volume as a linear gain, where 1.0 is unchanged (default: 0.2)
+    #[argh(option, default = "0.2")]
+    pub music_volume: f32,
+
+    /// duck the music under speech with a sidechain compressor
+    #[argh(switch)]
+    pub duck_music: bool,
+
+    /// normalize audio loudness (EBU R128) before recombining; requires --add-captions
+    #[argh(switch)]
+    pub normalize_audio: bool,
+
+    /// loudness target in LUFS for --normalize-audio (default: -14)
+    #[argh(option, default = "-14.0")]
+    pub target_lufs: f32,
+
+    /// output quality as an x264 constant rate factor, 0-51, lower is better;
+    /// exclusive with --video-bitrate (default: x264's 23)
+    #[argh(option)]
+    pub crf: Option<u32>,
+
+    /// x264 preset: ultrafast ... veryslow; slower presets give smaller files
+    /// at the same quality (default: medium)
+    #[argh(option)]
+    pub preset: Option<String>,
+
+    /// target average video bitrate, e.g. 2500k or 4M, for predictable file
+    /// sizes; exclusive with --crf
+    #[argh(option)]
+    pub video_bitrate: Option<String>,
+
+    /// container of the delivered video: mp4 (H.264/AAC) or webm (VP9/Opus)
+    /// (default: mp4)
+    #[argh(option, default = "OutputFormat::Mp4")]
+    pub output_format: OutputFormat,
+
+    /// cap the output frame rate, dropping source frames before detection,
+    /// e.g. 30 to process a 60 fps source at half the work (default: source
+    /// rate)
+    #[argh(option)]
+    pub max_fps: Option<f64>,
+
+    /// keep a variable-frame-rate source's timing as is instead of resampling
+    /// it to a constant rate first
+    #[argh(switch)]
+    pub no_normalize_fps: bool,
+
+    /// export crops: write each frame's crop decision to crops.json in the run directory
+    #[argh(switch)]
+    pub export_crops: bool,
+
+    /// write each frame's crop rectangle(s), variant, object count, and
+    /// graphic flag to this CSV file (two rows for stacked crops)
+    #[argh(option)]
+    pub crop_log_csv: Option<String>,
+
+    /// also write debug_overlay.mp4: the full source frames with the detections
+    /// and the chosen crop drawn on them (not with --headless)
+    #[argh(switch)]
+    pub debug_overlay: bool,
+
+    /// write preview_grid.png to the run directory: a contact sheet of every
+    /// Nth frame with its crop drawn on, for reviewing the framing (works with
+    /// --dry-run)
+    #[argh(option)]
+    pub preview_grid: Option<usize>,
+
+    /// write the detected scene cuts (frame index, seconds, and timecode) to
+    /// cuts.json in the run directory
+    #[argh(switch)]
+    pub export_cuts: bool,
+
+    /// save poster.jpg, a still from the final video, in the run directory;
+    /// taken from the middle unless --poster-time or --poster-auto is set
+    #[argh(switch)]
+    pub poster: bool,
+
+    /// time in seconds of the --poster frame in the final video
+    #[argh(option)]
+    pub poster_time: Option<f64>,
+
+    /// take the --poster frame with the most confident detection
+    #[argh(switch)]
+    pub poster_auto: bool,
+
+    /// also segment the final video into .ts chunks with a playlist.m3u8 in
+    /// the run directory for HLS streaming
+    #[argh(switch)]
+    pub output_hls: bool,
+
+    /// length in seconds of each --output-hls segment (default: 6)
+    #[argh(option, default = "6.0")]
+    pub hls_segment_duration: f64,
+
+    /// hold the final video's last frame, over silence, until it lasts this
+    /// many seconds; an error if the video is already longer
+    #[argh(option)]
+    pub pad_to_duration: Option<f64>,
+
+    /// also copy the source, after any --start-time/--end-time clipping and
+    /// --trim-silence, to landscape.mp4 in the run directory for A/B review
+    #[argh(switch)]
+    pub keep_source_copy: bool,
+
+    /// import crops: render the per-frame crops from a crops.json file instead
+    /// of running detection
+    #[argh(option)]
+    pub import_crops: Option<String>,
+
+    /// run directory for intermediate and final files instead of a new
+    /// timestamped one under runs/ (a parent of one directory per video when
+    /// the source is a directory); it must be empty unless --overwrite is set
+    #[argh(option)]
+    pub output_dir: Option<String>,
+
+    /// resume a previous run in this run directory, reusing its extracted
+    /// audio, transcript and processed video when they are complete, so only
+    /// the stages after them run again
+    #[argh(option)]
+    pub resume_from: Option<String>,
+
+    /// allow --output-dir to be a directory that already has files in it,
+    /// overwriting any from a previous run
+    #[argh(switch)]
+    pub overwrite: bool,
+
+    /// delete intermediate files (extracted audio, uncaptioned video, ...)
+    /// from the run directory once the final video is delivered; transcripts
+    /// and metrics are kept, and nothing is deleted if the run fails
+    #[argh(switch)]
+    pub cleanup: bool,
+
+    /// output filepath: if set, move the final video to this location (a
+    /// directory when the source is a directory)
     #[argh(option, default = "String::from(\"\")")]
     pub output_filepath: String,
 
@@ -94,4 +610,902 @@ pub struct Args {
     /// encode directly over a network mount (e.g. GCS FUSE on Cloud Run)
     #[argh(switch)]
     pub local_stage: bool,
+
+    /// config: TOML file of argument values (keys use the snake_case field
+    /// names); flags given on the command line override file values
+    #[argh(option)]
+    pub config: Option<String>,
+}
+
+/// A config file value written either as a number or as a string, for options
+/// like `min_area_ratio` that take one number or a list
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum NumberOrString {
+    Number(f64),
+    String(String),
+}
+
+impl NumberOrString {
+    fn into_string(self) -> String {
+        match self {
+            Self::Number(number) => number.to_string(),
+            Self::String(text) => text,
+        }
+    }
+}
+
+/// Mirror of `Args` loaded from a `--config` TOML file. Every key is optional so
+/// a file may set only some arguments; unknown keys are rejected.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub object: Option<String>,
+    pub source: Option<String>,
+    pub dtype: Option<String>,
+    pub ver: Option<f32>,
+    pub device: Option<String>,
+    pub scale: Option<String>,
+    pub model_path: Option<String>,
+    pub model_cache_dir: Option<String>,
+    pub fallback_center_crop: Option<bool>,
+    pub smooth_percentage: Option<f32>,
+    pub smooth_duration: Option<f32>,
+    pub similarity_metric: Option<String>,
+    pub history_size: Option<usize>,
+    pub min_hold_duration: Option<f32>,
+    pub transition_duration: Option<f32>,
+    pub min_interp_duration: Option<f32>,
+    pub transition_mode: Option<String>,
+    pub detect_interval: Option<usize>,
+    pub jobs: Option<usize>,
+    pub batch_size: Option<usize>,
+    pub object_prob_threshold: Option<f32>,
+    pub adaptive_threshold: Option<bool>,
+    pub adaptive_threshold_floor: Option<f32>,
+    pub min_area_ratio: Option<NumberOrString>,
+    pub cut_similarity: Option<f64>,
+    pub cut_start: Option<f64>,
+    pub min_scene_duration: Option<f32>,
+    pub cut_mode: Option<String>,
+    pub black_frame_threshold: Option<f64>,
+    pub aspect_ratio: Option<String>,
+    pub output_resolution: Option<String>,
+    pub no_subject_mode: Option<String>,
+    pub focus: Option<String>,
+    pub roi: Option<String>,
+    pub crop_padding: Option<f32>,
+    pub deadzone: Option<f32>,
+    pub prediction_damping: Option<f32>,
+    pub max_prediction_gap: Option<usize>,
+    pub headless: Option<bool>,
+    pub window_scale: Option<f32>,
+    pub dry_run: Option<bool>,
+    pub report_confidences: Option<bool>,
+    pub quiet: Option<bool>,
+    pub use_stack_crop: Option<bool>,
+    pub stack_ratio: Option<f32>,
+    pub use_blur_fill: Option<bool>,
+    pub use_simple_smoothing: Option<bool>,
+    pub use_kalman_smoothing: Option<bool>,
+    pub use_moving_average: Option<bool>,
+    pub loop_smoothing: Option<bool>,
+    pub smooth_window: Option<usize>,
+    pub keep_text: Option<bool>,
+    pub prioritize_text: Option<bool>,
+    pub graphic_fit: Option<String>,
+    pub text_area_threshold: Option<f32>,
+    pub text_area_exit_threshold: Option<f32>,
+    pub text_prob_threshold: Option<f32>,
+    pub interactive_pick: Option<bool>,
+    pub annotate_graphics: Option<bool>,
+    pub add_captions: Option<bool>,
+    pub captions_only: Option<bool>,
+    pub caption_format: Option<String>,
+    pub caption_font: Option<String>,
+    pub caption_font_size: Option<u32>,
+    pub caption_color: Option<String>,
+    pub caption_outline: Option<String>,
+    pub caption_position: Option<String>,
+    pub caption_margin_bottom: Option<f32>,
+    pub caption_margin_top: Option<f32>,
+    pub language: Option<String>,
+    pub transcribe_backend: Option<String>,
+    pub whisper_command: Option<String>,
+    pub whisper_model: Option<String>,
+    pub deterministic_transcription: Option<bool>,
+    pub transcribe_retries: Option<u32>,
+    pub transcribe_retry_delay: Option<f64>,
+    pub audio_bitrate: Option<String>,
+    pub translate_to: Option<String>,
+    pub translator_command: Option<String>,
+    pub caption_karaoke: Option<bool>,
+    pub caption_dynamic_size: Option<bool>,
+    pub transcript_json: Option<bool>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub trim_silence: Option<bool>,
+    pub silence_threshold_db: Option<f32>,
+    pub silence_min_duration: Option<f64>,
+    pub rotate: Option<String>,
+    pub auto_rotate: Option<bool>,
+    pub music: Option<String>,
+    pub music_volume: Option<f32>,
+    pub duck_music: Option<bool>,
+    pub normalize_audio: Option<bool>,
+    pub target_lufs: Option<f32>,
+    pub caption_highlight_color: Option<String>,
+    pub caption_max_chars: Option<usize>,
+    pub crf: Option<u32>,
+    pub preset: Option<String>,
+    pub video_bitrate: Option<String>,
+    pub output_format: Option<String>,
+    pub max_fps: Option<f64>,
+    pub no_normalize_fps: Option<bool>,
+    pub export_crops: Option<bool>,
+    pub crop_log_csv: Option<String>,
+    pub export_cuts: Option<bool>,
+    pub poster: Option<bool>,
+    pub poster_time: Option<f64>,
+    pub poster_auto: Option<bool>,
+    pub output_hls: Option<bool>,
+    pub hls_segment_duration: Option<f64>,
+    pub pad_to_duration: Option<f64>,
+    pub keep_source_copy: Option<bool>,
+    pub debug_overlay: Option<bool>,
+    pub preview_grid: Option<usize>,
+    pub import_crops: Option<String>,
+    pub output_dir: Option<String>,
+    pub resume_from: Option<String>,
+    pub overwrite: Option<bool>,
+    pub cleanup: Option<bool>,
+    pub output_filepath: Option<String>,
+    pub local_stage: Option<bool>,
+}
+
+impl ConfigFile {
+    /// Reads and parses a TOML config file
+    pub fn load(path: &str) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("Reading config file {path}"))?;
+        toml::from_str(&contents).with_context(|| format!("Parsing config file {path}"))
+    }
+}
+
+impl Args {
+    /// The object types named by `--object`, split on commas. Alongside other
+    /// types, `ball` stands for the generic model's `sports ball`, as the
+    /// dedicated ball model detects nothing else.
+    pub fn object_names(&self) -> Vec<&str> {
+        let names: Vec<&str> = self
+            .object
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.len() < 2 || self.model_path.is_some() {
+            return names;
+        }
+        names
+            .into_iter()
+            .map(|name| if name == "ball" { "sports ball" } else { name })
+            .collect()
+    }
+
+    /// Parses the command line and, when `--config` is given, fills in every
+    /// argument that wasn't passed explicitly from the config file
+    pub fn from_env_with_config() -> Result<Self> {
+        let args: Args = argh::from_env();
+        let command_line: Vec<String> = std::env::args().skip(1).collect();
+        args.with_config_file(&explicit_flags(&command_line))
+    }
+
+    /// Loads `self.config` (if set) and merges it beneath the explicit flags
+    pub fn with_config_file(mut self, explicit: &HashSet<String>) -> Result<Self> {
+        if let Some(path) = self.config.clone() {
+            self.merge_config(ConfigFile::load(&path)?, explicit)?;
+        }
+        Ok(self)
+    }
+
+    /// Applies config values to every field not named in `explicit`
+    fn merge_config(&mut self, config: ConfigFile, explicit: &HashSet<String>) -> Result<()> {
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if let Some(value) = config.$field
+                        && !explicit.contains(stringify!($field))
+                    {
+                        self.$field = value;
+                    }
+                )*
+            };
+        }
+
+        merge!(
+            object,
+            source,
+            dtype,
+            ver,
+            device,
+            scale,
+            smooth_percentage,
+            smooth_duration,
+            min_hold_duration,
+            detect_interval,
+            jobs,
+            object_prob_threshold,
+            adaptive_threshold,
+            adaptive_threshold_floor,
+            cut_similarity,
+            cut_start,
+            min_scene_duration,
+            black_frame_threshold,
+            fallback_center_crop,
+            headless,
+            window_scale,
+            dry_run,
+            report_confidences,
+            quiet,
+            use_stack_crop,
+            stack_ratio,
+            use_blur_fill,
+            crop_padding,
+            deadzone,
+            prediction_damping,
+            max_prediction_gap,
+            use_simple_smoothing,
+            use_kalman_smoothing,
+            use_moving_average,
+            loop_smoothing,
+            smooth_window,
+            keep_text,
+            prioritize_text,
+            text_area_threshold,
+            text_prob_threshold,
+            interactive_pick,
+            annotate_graphics,
+            add_captions,
+            captions_only,
+            caption_karaoke,
+            caption_dynamic_size,
+            transcript_json,
+            whisper_command,
+            deterministic_transcription,
+            transcribe_retries,
+            transcribe_retry_delay,
+            trim_silence,
+            silence_threshold_db,
+            silence_min_duration,
+            auto_rotate,
+            music_volume,
+            duck_music,
+            normalize_audio,
+            target_lufs,
+            no_normalize_fps,
+            export_crops,
+            export_cuts,
+            poster,
+            poster_auto,
+            output_hls,
+            hls_segment_duration,
+            keep_source_copy,
+            debug_overlay,
+            overwrite,
+            cleanup,
+            output_filepath,
+            local_stage,
+        );
+
+        // Fields that are themselves optional in `Args`
+        macro_rules! merge_optional {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if config.$field.is_some() && !explicit.contains(stringify!($field)) {
+                        self.$field = config.$field;
+                    }
+                )*
+            };
+        }
+
+        merge_optional!(
+            model_path,
+            model_cache_dir,
+            history_size,
+            transition_duration,
+            min_interp_duration,
+            text_area_exit_threshold,
+            batch_size,
+            import_crops,
+            crop_log_csv,
+            preview_grid,
+            output_resolution,
+            caption_font,
+            caption_font_size,
+            caption_color,
+            caption_outline,
+            caption_margin_bottom,
+            caption_margin_top,
+            caption_highlight_color,
+            caption_max_chars,
+            language,
+            translate_to,
+            translator_command,
+            whisper_model,
+            start_time,
+            end_time,
+            music,
+            crf,
+            preset,
+            video_bitrate,
+            audio_bitrate,
+            poster_time,
+            pad_to_duration,
+            max_fps,
+            output_dir,
+            resume_from,
+        );
+
+        if let Some(caption_format) = config.caption_format
+            && !explicit.contains("caption_format")
+        {
+            self.caption_format = caption_format
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing caption_format in config file")?;
+        }
+
+        if let Some(caption_position) = config.caption_position
+            && !explicit.contains("caption_position")
+        {
+            self.caption_position = Some(
+                caption_position
+                    .parse()
+                    .map_err(|e: String| anyhow::anyhow!(e))
+                    .context("Parsing caption_position in config file")?,
+            );
+        }
+
+        if let Some(rotate) = config.rotate
+            && !explicit.contains("rotate")
+        {
+            self.rotate = Some(
+                rotate
+                    .parse()
+                    .map_err(|e: String| anyhow::anyhow!(e))
+                    .context("Parsing rotate in config file")?,
+            );
+        }
+
+        if let Some(aspect_ratio) = config.aspect_ratio
+            && !explicit.contains("aspect_ratio")
+        {
+            self.aspect_ratio = aspect_ratio
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing aspect_ratio in config file")?;
+        }
+
+        if let Some(roi) = config.roi
+            && !explicit.contains("roi")
+        {
+            self.roi = Some(
+                roi.parse()
+                    .map_err(|e: String| anyhow::anyhow!(e))
+                    .context("Parsing roi in config file")?,
+            );
+        }
+
+        if let Some(no_subject_mode) = config.no_subject_mode
+            && !explicit.contains("no_subject_mode")
+        {
+            self.no_subject_mode = no_subject_mode
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing no_subject_mode in config file")?;
+        }
+
+        if let Some(output_format) = config.output_format
+            && !explicit.contains("output_format")
+        {
+            self.output_format = output_format
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing output_format in config file")?;
+        }
+
+        if let Some(similarity_metric) = config.similarity_metric
+            && !explicit.contains("similarity_metric")
+        {
+            self.similarity_metric = similarity_metric
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing similarity_metric in config file")?;
+        }
+
+        if let Some(transition_mode) = config.transition_mode
+            && !explicit.contains("transition_mode")
+        {
+            self.transition_mode = transition_mode
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing transition_mode in config file")?;
+        }
+
+        if let Some(min_area_ratio) = config.min_area_ratio
+            && !explicit.contains("min_area_ratio")
+        {
+            self.min_area_ratio = min_area_ratio
+                .into_string()
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing min_area_ratio in config file")?;
+        }
+
+        if let Some(focus) = config.focus
+            && !explicit.contains("focus")
+        {
+            self.focus = focus
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing focus in config file")?;
+        }
+
+        if let Some(cut_mode) = config.cut_mode
+            && !explicit.contains("cut_mode")
+        {
+            self.cut_mode = cut_mode
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing cut_mode in config file")?;
+        }
+
+        if let Some(transcribe_backend) = config.transcribe_backend
+            && !explicit.contains("transcribe_backend")
+        {
+            self.transcribe_backend = transcribe_backend
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing transcribe_backend in config file")?;
+        }
+
+        if let Some(graphic_fit) = config.graphic_fit
+            && !explicit.contains("graphic_fit")
+        {
+            self.graphic_fit = graphic_fit
+                .parse()
+                .map_err(|e: String| anyhow::anyhow!(e))
+                .context("Parsing graphic_fit in config file")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Args {
+    /// Builds the caption style from the `--caption-*` flags, falling back to
+    /// the default style for any that are omitted
+    pub fn caption_style(&self) -> Result<CaptionStyle> {
+        let mut style = CaptionStyle::default();
+        if let Some(font) = &self.caption_font {
+            style.font_name = font.clone();
+        }
+        if let Some(font_size) = self.caption_font_size {
+            style.font_size = font_size;
+        }
+        if let Some(color) = &self.caption_color {
+            style.font_color = audio::parse_hex_color(color).context("Invalid --caption-color")?;
+        }
+        if let Some(outline) = &self.caption_outline {
+            if outline.eq_ignore_ascii_case("none") {
+                style.outline_color = None;
+                style.outline_thickness = None;
+            } else {
+                style.outline_color =
+                    Some(audio::parse_hex_color(outline).context("Invalid --caption-outline")?);
+            }
+        }
+        if let Some(position) = self.caption_position {
+            style.position = position;
+        }
+        // The style's single vertical margin counts from whichever edge the
+        // captions sit on
+        match (self.caption_margin_bottom, self.caption_margin_top) {
+            (Some(_), Some(_)) => anyhow::bail!(
+                "--caption-margin-bottom and --caption-margin-top are mutually exclusive"
+            ),
+            (Some(percent), None) => {
+                if style.position != CaptionPosition::Bottom {
+                    anyhow::bail!("--caption-margin-bottom needs --caption-position bottom");
+                }
+                audio::check_caption_margin("--caption-margin-bottom", percent)?;
+                style.margin_bottom = audio::caption_margin(percent);
+            }
+            (None, Some(percent)) => {
+                if style.position != CaptionPosition::Top {
+                    anyhow::bail!("--caption-margin-top needs --caption-position top");
+                }
+                audio::check_caption_margin("--caption-margin-top", percent)?;
+                style.margin_bottom = audio::caption_margin(percent);
+            }
+            (None, None) => {}
+        }
+        if let Some(color) = &self.caption_highlight_color {
+            style.highlight_color =
+                audio::parse_hex_color(color).context("Invalid --caption-highlight-color")?;
+        }
+        Ok(style)
+    }
+
+    /// Builds the transcription config, applying `--language` when given
+    pub fn transcript_config(&self) -> Result<TranscriptConfig> {
+        let mut config = TranscriptConfig::default();
+        if let Some(language) = &self.language {
+            config.language =
+                Some(transcript::parse_language(language).context("Invalid --language")?);
+        }
+        if !self.transcribe_retry_delay.is_finite() || self.transcribe_retry_delay < 0.0 {
+            anyhow::bail!(
+                "Invalid --transcribe-retry-delay {}: expected a non-negative number of seconds",
+                self.transcribe_retry_delay
+            );
+        }
+        config.retries = self.transcribe_retries;
+        config.retry_delay = Duration::from_secs_f64(self.transcribe_retry_delay);
+        config.deterministic = self.deterministic_transcription;
+        Ok(config)
+    }
+
+    /// ffmpeg encoders the delivery steps need with these options: MP3 for the
+    /// transcription audio and the output format's codecs for the caption
+    /// burn, the `--pad-to-duration` re-encode, or the conversion when a
+    /// non-MP4 format skips captions
+    pub fn required_encoders(&self) -> Vec<&'static str> {
+        let mut encoders = Vec::new();
+        if self.add_captions {
+            encoders.push("libmp3lame");
+        }
+        if self.add_captions
+            || self.pad_to_duration.is_some()
+            || self.output_format != OutputFormat::Mp4
+        {
+            encoders.extend(self.output_format.encoders());
+        }
+        encoders
+    }
+
+    /// ffmpeg muxers the options need beyond the output format's own:
+    /// `hls` for `--output-hls`
+    pub fn required_muxers(&self) -> Vec<&'static str> {
+        if self.output_hls {
+            vec!["hls"]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Builds the output encoder settings from `--crf`, `--preset`, and
+    /// `--video-bitrate`
+    pub fn encode_settings(&self) -> Result<EncodeSettings> {
+        let settings = EncodeSettings {
+            crf: self.crf,
+            preset: self.preset.clone(),
+            bitrate: self
+                .video_bitrate
+                .as_deref()
+                .map(video_sink::parse_bitrate)
+                .transpose()
+                .context("Invalid --video-bitrate")?,
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// The validated `--output-resolution` sizes, main output first; empty
+    /// when the option isn't set
+    pub fn output_sizes(&self) -> Result<Vec<OutputSize>> {
+        self.output_resolution
+            .as_deref()
+            .map(|text| {
+                video_sink::parse_output_sizes(text, self.aspect_ratio)
+                    .context("Invalid --output-resolution")
+            })
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+
+    /// The validated `--translate-to` language, if translation was requested
+    pub fn translate_target(&self) -> Result<Option<String>> {
+        self.translate_to
+            .as_deref()
+            .map(|language| transcript::parse_language(language).context("Invalid --translate-to"))
+            .transpose()
+    }
+
+    /// Parses `--start-time` and `--end-time` into seconds, checking that the
+    /// start comes first when both are given. Whether they fall inside the
+    /// source is checked against its duration once it is known.
+    pub fn clip_times(&self) -> Result<(Option<f64>, Option<f64>)> {
+        let start = self
+            .start_time
+            .as_deref()
+            .map(|time| audio::parse_timestamp(time).context("Invalid --start-time"))
+            .transpose()?;
+        let end = self
+            .end_time
+            .as_deref()
+            .map(|time| audio::parse_timestamp(time).context("Invalid --end-time"))
+            .transpose()?;
+        if let (Some(start), Some(end)) = (start, end)
+            && start >= end
+        {
+            anyhow::bail!("--start-time ({start}s) must be before --end-time ({end}s)");
+        }
+        Ok((start, end))
+    }
+}
+
+/// Collects the field names (`--smooth-duration` -> `smooth_duration`) of the
+/// flags present on the command line
+pub fn explicit_flags(command_line: &[String]) -> HashSet<String> {
+    command_line
+        .iter()
+        .filter_map(|arg| arg.strip_prefix("--"))
+        .map(|flag| flag.split('=').next().unwrap_or(flag).replace('-', "_"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(name: &str, contents: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("land2port_{}_{name}.toml", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_config_file_merge_precedence() {
+        let path = write_config(
+            "merge",
+            r#"
+object = "head"
+scale = "m"
+smooth_duration = 2.5
+aspect_ratio = "4:5"
+use_stack_crop = true
+"#,
+        );
+        let command_line: Vec<String> = ["--config", path.as_str(), "--scale", "l", "--headless"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let cli_args: Vec<&str> = command_line.iter().map(String::as_str).collect();
+
+        let args = Args::from_args(&["land2port"], &cli_args)
+            .unwrap()
+            .with_config_file(&explicit_flags(&command_line))
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // File values fill in fields not given on the command line
+        assert_eq!(args.object, "head");
+        assert_eq!(args.smooth_duration, 2.5);
+        assert_eq!(args.aspect_ratio, AspectRatio::new(4.0, 5.0));
+        assert!(args.use_stack_crop);
+        // Explicit flags win over the file
+        assert_eq!(args.scale, "l");
+        assert!(args.headless);
+        // Fields absent from both keep their defaults
+        assert_eq!(args.device, "cpu:0");
+        assert_eq!(args.smooth_percentage, 7.5);
+    }
+
+    #[test]
+    fn test_config_file_no_normalize_fps() {
+        let path = write_config("no_normalize_fps", "no_normalize_fps = true\n");
+        let args = Args::from_args(&["land2port"], &["--config", &path])
+            .unwrap()
+            .with_config_file(&HashSet::new())
+            .unwrap();
+        assert!(args.no_normalize_fps);
+
+        // An explicit flag keeps the switch on even when the file turns it off
+        std::fs::write(&path, "no_normalize_fps = false\n").unwrap();
+        let command_line: Vec<String> = ["--config", path.as_str(), "--no-normalize-fps"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let cli_args: Vec<&str> = command_line.iter().map(String::as_str).collect();
+        let args = Args::from_args(&["land2port"], &cli_args)
+            .unwrap()
+            .with_config_file(&explicit_flags(&command_line))
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(args.no_normalize_fps);
+    }
+
+    #[test]
+    fn test_caption_style_from_args() {
+        let args = Args::from_args(
+            &["land2port"],
+            &[
+                "--caption-color",
+                "#ff8800",
+                "--caption-outline",
+                "none",
+                "--caption-position",
+                "top",
+                "--caption-highlight-color",
+                "00ff00",
+            ],
+        )
+        .unwrap();
+        let style = args.caption_style().unwrap();
+        assert_eq!(style.font_color, "FF8800");
+        assert_eq!(style.outline_color, None);
+        assert_eq!(style.position, CaptionPosition::Top);
+        assert_eq!(style.highlight_color, "00FF00");
+        // Omitted flags keep the defaults
+        assert_eq!(style.font_name, CaptionStyle::default().font_name);
+        assert_eq!(style.font_size, CaptionStyle::default().font_size);
+
+        let args = Args::from_args(&["land2port"], &["--caption-color", "white"]).unwrap();
+        assert!(args.caption_style().is_err());
+
+        // Safe-zone margins are converted to the 288-unit caption canvas and
+        // must match the caption position
+        let args = Args::from_args(&["land2port"], &["--caption-margin-bottom", "15"]).unwrap();
+        assert_eq!(args.caption_style().unwrap().margin_bottom, 43);
+        let args = Args::from_args(
+            &["land2port"],
+            &["--caption-position", "top", "--caption-margin-top", "10"],
+        )
+        .unwrap();
+        assert_eq!(args.caption_style().unwrap().margin_bottom, 29);
+        let args = Args::from_args(&["land2port"], &["--caption-margin-top", "10"]).unwrap();
+        assert!(args.caption_style().is_err());
+        let args = Args::from_args(&["land2port"], &["--caption-margin-bottom", "80"]).unwrap();
+        assert!(args.caption_style().is_err());
+    }
+
+    #[test]
+    fn test_target_lufs_accepts_negative_values() {
+        let args = Args::from_args(&["land2port"], &[]).unwrap();
+        assert_eq!(args.target_lufs, -14.0);
+
+        let args = Args::from_args(&["land2port"], &["--target-lufs", "-16"]).unwrap();
+        assert_eq!(args.target_lufs, -16.0);
+    }
+
+    #[test]
+    fn test_transcript_config_language() {
+        let args = Args::from_args(&["land2port"], &[]).unwrap();
+        assert_eq!(args.transcript_config().unwrap().language, None);
+
+        let args = Args::from_args(&["land2port"], &["--language", "ES"]).unwrap();
+        assert_eq!(
+            args.transcript_config().unwrap().language.as_deref(),
+            Some("es")
+        );
+
+        let args = Args::from_args(&["land2port"], &["--language", "spanish"]).unwrap();
+        assert!(args.transcript_config().is_err());
+    }
+
+    #[test]
+    fn test_transcript_config_retries() {
+        let args = Args::from_args(&["land2port"], &[]).unwrap();
+        let config = args.transcript_config().unwrap();
+        assert_eq!(config.retries, 3);
+        assert_eq!(config.retry_delay, Duration::from_secs(2));
+
+        let args = Args::from_args(
+            &["land2port"],
+            &[
+                "--transcribe-retries",
+                "5",
+                "--transcribe-retry-delay",
+                "0.5",
+            ],
+        )
+        .unwrap();
+        let config = args.transcript_config().unwrap();
+        assert_eq!(config.retries, 5);
+        assert_eq!(config.retry_delay, Duration::from_millis(500));
+
+        let args = Args::from_args(&["land2port"], &["--transcribe-retry-delay", "-1"]).unwrap();
+        assert!(args.transcript_config().is_err());
+    }
+
+    #[test]
+    fn test_clip_times() {
+        let args = Args::from_args(&["land2port"], &[]).unwrap();
+        assert_eq!(args.clip_times().unwrap(), (None, None));
+
+        let args = Args::from_args(
+            &["land2port"],
+            &["--start-time", "00:01:00", "--end-time", "90"],
+        )
+        .unwrap();
+        assert_eq!(args.clip_times().unwrap(), (Some(60.0), Some(90.0)));
+
+        let args = Args::from_args(
+            &["land2port"],
+            &["--start-time", "2:00", "--end-time", "90"],
+        )
+        .unwrap();
+        assert!(args.clip_times().is_err());
+
+        let args = Args::from_args(&["land2port"], &["--end-time", "1:75"]).unwrap();
+        let err = args.clip_times().unwrap_err();
+        assert!(format!("{err:#}").contains("--end-time"));
+    }
+
+    #[test]
+    fn test_object_names() {
+        let args = Args::from_args(&["land2port"], &["--object", "ball"]).unwrap();
+        assert_eq!(args.object_names(), ["ball"]);
+
+        // In a list, ball comes from the general model
+        let args = Args::from_args(&["land2port"], &["--object", "person, ball"]).unwrap();
+        assert_eq!(args.object_names(), ["person", "sports ball"]);
+
+        // Custom models keep their own class names
+        let args = Args::from_args(
+            &["land2port"],
+            &["--object", "person,ball", "--model-path", "custom.onnx"],
+        )
+        .unwrap();
+        assert_eq!(args.object_names(), ["person", "ball"]);
+    }
+
+    #[test]
+    fn test_required_encoders() {
+        let args = Args::from_args(&["land2port"], &[]).unwrap();
+        assert!(args.required_encoders().is_empty());
+
+        let args = Args::from_args(&["land2port"], &["--add-captions"]).unwrap();
+        assert_eq!(args.required_encoders(), ["libmp3lame", "libx264"]);
+
+        let args = Args::from_args(&["land2port"], &["--output-format", "webm"]).unwrap();
+        assert_eq!(args.required_encoders(), ["libvpx-vp9", "libopus"]);
+        assert!(args.required_muxers().is_empty());
+
+        let args = Args::from_args(&["land2port"], &["--pad-to-duration", "15"]).unwrap();
+        assert_eq!(args.required_encoders(), ["libx264"]);
+
+        let args = Args::from_args(&["land2port"], &["--output-hls"]).unwrap();
+        assert_eq!(args.required_muxers(), ["hls"]);
+    }
+
+    #[test]
+    fn test_config_file_min_area_ratio_number_or_list() {
+        for (name, value, expected) in [
+            ("ratio_number", "0.02", MinAreaRatio::uniform(0.02)),
+            (
+                "ratio_list",
+                "\"face=0.002\"",
+                "face=0.002".parse().unwrap(),
+            ),
+        ] {
+            let path = write_config(name, &format!("min_area_ratio = {value}\n"));
+            let args = Args::from_args(&["land2port"], &["--config", &path])
+                .unwrap()
+                .with_config_file(&HashSet::new())
+                .unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(args.min_area_ratio, expected);
+        }
+    }
+
+    #[test]
+    fn test_config_file_rejects_unknown_keys() {
+        let path = write_config("unknown", "scale = \"m\"\nsmoth_duration = 2.0\n");
+        let err = ConfigFile::load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(format!("{err:#}").contains("smoth_duration"));
+    }
 }
@@ -1,5 +1,5 @@
 use crate::cli::Args;
-use anyhow::Result;
+use crate::error::{Land2PortError, Result};
 use usls::{Config, NAMES_COCO_80, Task};
 
 /// Determines the model file path based on object type, version, and scale
@@ -29,9 +29,119 @@ fn get_model_path(object: &str, ver: f32, scale: &str) -> String {
     }
 }
 
+/// Every `--object` type the preset models can detect
+const OBJECT_TYPES: &[&str] = &[
+    "face",
+    "head",
+    "ball",
+    "sports ball",
+    "frisbee",
+    "person",
+    "car",
+    "motorcycle",
+    "truck",
+    "boat",
+];
+
+/// Index of a COCO object type in `NAMES_COCO_80`, for the types the
+/// generic YOLO model is restricted to
+fn coco_class_id(object: &str) -> Option<usize> {
+    match object {
+        "person" => Some(0),
+        "car" => Some(2),
+        "motorcycle" => Some(3),
+        "truck" => Some(7),
+        "boat" => Some(8),
+        "frisbee" => Some(29),
+        "sports ball" => Some(32),
+        _ => None,
+    }
+}
+
+/// Checks the `--object` list. Several types can only be detected together
+/// when they all come from the generic COCO model; face and head each use a
+/// dedicated model (ball does too, but `Args::object_names` already maps it to
+/// `sports ball` in a list). A custom `--model-path` model may detect any mix
+/// of its own classes; otherwise every name must be a known object type.
+pub fn check_object_names(objects: &[&str], custom_model: bool) -> Result<()> {
+    if objects.is_empty() {
+        return Err(Land2PortError::InvalidOption(
+            "--object must name at least one object type".to_string(),
+        ));
+    }
+    if !custom_model
+        && let Some(unknown) = objects.iter().find(|object| !OBJECT_TYPES.contains(object))
+    {
+        return Err(Land2PortError::InvalidOption(format!(
+            "unknown --object type '{unknown}': expected one of {}",
+            OBJECT_TYPES.join(", ")
+        )));
+    }
+    if objects.len() > 1
+        && !custom_model
+        && let Some(dedicated) = objects
+            .iter()
+            .find(|object| !get_model_path(object, 8.0, "m").is_empty())
+    {
+        return Err(Land2PortError::InvalidOption(format!(
+            "--object {dedicated} uses its own model and can't be combined with other \
+             object types; only types from the general model, such as person, ball and \
+             car, can be detected together"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a `--model-path` file exists before any processing starts
+pub fn check_model_path(model_path: &str) -> Result<()> {
+    if !std::path::Path::new(model_path).is_file() {
+        return Err(Land2PortError::Model(format!(
+            "model file not found: {model_path}"
+        )));
+    }
+    Ok(())
+}
+
+/// Points usls' cache at `dir` for `--model-cache-dir`, so the COCO model
+/// downloaded on the first run is reused by later runs, including offline
+/// ones. usls keeps downloads (and the TensorRT engine cache) under
+/// `dirs::cache_dir()/usls`, which on Linux follows `XDG_CACHE_HOME`; other
+/// platforms keep the default location. Must run while the process is still
+/// single-threaded, before the async runtime starts.
+pub fn use_model_cache_dir(dir: &str) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        eprintln!("warning: --model-cache-dir only applies on Linux; using usls' default cache");
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir).map_err(Land2PortError::io(format!(
+        "Creating model cache directory {dir}"
+    )))?;
+    let dir = std::fs::canonicalize(dir).map_err(Land2PortError::io(format!(
+        "Resolving model cache directory {dir}"
+    )))?;
+    // SAFETY: `main` calls this before building the tokio runtime, so no
+    // other thread exists to read the environment concurrently
+    unsafe { std::env::set_var("XDG_CACHE_HOME", &dir) };
+    Ok(())
+}
+
+/// Model file to load: the `--model-path` override when given, otherwise the
+/// preset for the object type, version, and scale. Empty selects the generic
+/// COCO model.
+fn model_path(args: &Args) -> String {
+    if let Some(model_path) = &args.model_path {
+        return model_path.clone();
+    }
+    match args.object_names().as_slice() {
+        [object] => get_model_path(object, args.ver, &args.scale),
+        _ => String::new(),
+    }
+}
+
 /// Builds a YOLO model configuration from command line arguments
-pub fn build_config(args: &Args) -> Result<Config> {
-    let model_path = get_model_path(&args.object, args.ver, &args.scale);
+pub fn build_config(args: &Args) -> anyhow::Result<Config> {
+    let objects = args.object_names();
+    let model_path = model_path(args);
 
     let mut config = Config::yolo()
         .with_task(Task::ObjectDetection)
@@ -42,18 +152,16 @@ pub fn build_config(args: &Args) -> Result<Config> {
         .with_model_device(args.device.parse()?)
         .with_model_num_dry_run(2);
 
+    // Custom models carry their own class names
     if model_path.is_empty() {
         config = config.with_class_names(&NAMES_COCO_80);
-        config = match args.object.as_str() {
-            "person" => config.retain_classes(&[0]),
-            "car" => config.retain_classes(&[2]),
-            "motorcycle" => config.retain_classes(&[3]),
-            "truck" => config.retain_classes(&[7]),
-            "boat" => config.retain_classes(&[8]),
-            "frisbee" => config.retain_classes(&[29]),
-            "sports ball" => config.retain_classes(&[32]),
-            _ => config,
-        };
+        let class_ids: Vec<usize> = objects
+            .iter()
+            .filter_map(|object| coco_class_id(object))
+            .collect();
+        if class_ids.len() == objects.len() {
+            config = config.retain_classes(&class_ids);
+        }
     }
 
     Ok(config)
@@ -62,6 +170,7 @@ pub fn build_config(args: &Args) -> Result<Config> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use argh::FromArgs;
 
     #[test]
     fn test_get_model_path() {
@@ -114,4 +223,53 @@ mod tests {
         assert_eq!(get_model_path("car", 8.0, "m"), "");
         assert_eq!(get_model_path("sports ball", 8.0, "m"), "");
     }
+
+    #[test]
+    fn test_check_object_names() {
+        assert!(check_object_names(&["face"], false).is_ok());
+        assert!(check_object_names(&["ball"], false).is_ok());
+        assert!(check_object_names(&["person", "sports ball"], false).is_ok());
+        assert!(check_object_names(&["person", "car", "truck"], false).is_ok());
+
+        // Dedicated models can't be mixed with other types...
+        assert!(check_object_names(&["person", "face"], false).is_err());
+        assert!(check_object_names(&["face", "head"], false).is_err());
+        assert!(check_object_names(&[], false).is_err());
+        // Typos are caught instead of silently detecting nothing
+        assert!(check_object_names(&["fase"], false).is_err());
+        assert!(check_object_names(&["person", "sports-ball"], false).is_err());
+        // ...but a custom model's classes can be anything
+        assert!(check_object_names(&["mascot", "ball"], true).is_ok());
+        assert!(check_object_names(&[], true).is_err());
+
+        assert_eq!(coco_class_id("sports ball"), Some(32));
+        assert_eq!(coco_class_id("ball"), None);
+    }
+
+    #[test]
+    fn test_model_path_override() {
+        let args = Args::from_args(&["land2port"], &["--object", "head"]).unwrap();
+        assert_eq!(model_path(&args), "./model/v8-head-fp16.onnx");
+
+        // A custom model replaces the preset regardless of object, version, and scale
+        let args = Args::from_args(
+            &["land2port"],
+            &[
+                "--object",
+                "mascot",
+                "--model-path",
+                "./mascot.onnx",
+                "--scale",
+                "n",
+            ],
+        )
+        .unwrap();
+        assert_eq!(model_path(&args), "./mascot.onnx");
+
+        assert!(matches!(
+            check_model_path("./model/does-not-exist.onnx"),
+            Err(Land2PortError::Model(_))
+        ));
+        assert!(check_model_path("Cargo.toml").is_ok());
+    }
 }
@@ -1,8 +1,263 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 use usls::Hbb;
 
+/// Aspect ratio (width:height) of the output canvas, parsed from `W:H`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AspectRatio {
+    pub width: f32,
+    pub height: f32,
+    /// Share of the canvas height the top crop of a top/bottom stack fills
+    pub stack_ratio: f32,
+}
+
+/// Default `stack_ratio`: a top/bottom stack split into equal halves
+pub const DEFAULT_STACK_RATIO: f32 = 0.5;
+
+impl AspectRatio {
+    /// The default 9:16 portrait canvas
+    pub const PORTRAIT: AspectRatio = AspectRatio {
+        width: 9.0,
+        height: 16.0,
+        stack_ratio: DEFAULT_STACK_RATIO,
+    };
+
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            stack_ratio: DEFAULT_STACK_RATIO,
+        }
+    }
+
+    /// The same canvas with a top/bottom stack split at `stack_ratio`
+    pub fn with_stack_ratio(self, stack_ratio: f32) -> Self {
+        Self {
+            stack_ratio,
+            ..self
+        }
+    }
+
+    /// Width divided by height of the output canvas
+    pub fn ratio(&self) -> f32 {
+        self.width / self.height
+    }
+
+    /// Width divided by height of a single crop. Canvases taller than 3:4 keep
+    /// a 3:4 crop and letterbox it (the 9:16 layout, which leaves room for
+    /// captions below the subject); wider canvases crop at their own ratio.
+    pub fn single_crop_ratio(&self) -> f32 {
+        self.ratio().max(3.0 / 4.0)
+    }
+
+    /// Width divided by height of the top crop in a two-way stack, which
+    /// fills `stack_ratio` of the canvas height (9:8 for an evenly split 9:16
+    /// canvas)
+    pub fn stack_top_ratio(&self) -> f32 {
+        self.ratio() / self.stack_ratio
+    }
+
+    /// Width divided by height of the bottom crop in a two-way stack, which
+    /// fills the rest of the canvas height
+    pub fn stack_bottom_ratio(&self) -> f32 {
+        self.ratio() / (1.0 - self.stack_ratio)
+    }
+
+    /// Whether a two-way stack is laid out side by side (left/right halves)
+    /// rather than top/bottom: square canvases and wider have no height to
+    /// split, so each subject gets a tall half instead of a flat strip
+    pub fn stacks_side_by_side(&self) -> bool {
+        self.ratio() >= 1.0
+    }
+
+    /// Width divided by height of each crop in a side-by-side stack, where
+    /// each crop fills half of the canvas width (1:2 for a 1:1 canvas)
+    pub fn side_by_side_ratio(&self) -> f32 {
+        self.ratio() / 2.0
+    }
+
+    /// Width divided by height of the two-subject crop in the three-subject
+    /// stack, which fills 6/16 of the canvas height (9:6 for a 9:16 canvas)
+    pub fn three_stack_double_ratio(&self) -> f32 {
+        self.ratio() * 16.0 / 6.0
+    }
+
+    /// Width divided by height of the one-subject crop in the three-subject
+    /// stack, which fills 10/16 of the canvas height (9:10 for a 9:16 canvas)
+    pub fn three_stack_single_ratio(&self) -> f32 {
+        self.ratio() * 16.0 / 10.0
+    }
+
+    /// Height of the output canvas for the given output width
+    pub fn canvas_height(&self, width: u32) -> u32 {
+        (width as f32 * (self.height / self.width)) as u32
+    }
+}
+
+impl Default for AspectRatio {
+    fn default() -> Self {
+        Self::PORTRAIT
+    }
+}
+
+impl FromStr for AspectRatio {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("invalid aspect ratio '{s}': expected W:H, e.g. 9:16, 4:5 or 1:1");
+        let (width, height) = s.split_once(':').ok_or_else(invalid)?;
+        let width: f32 = width.trim().parse().map_err(|_| invalid())?;
+        let height: f32 = height.trim().parse().map_err(|_| invalid())?;
+        if !(width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0) {
+            return Err(invalid());
+        }
+        Ok(Self::new(width, height))
+    }
+}
+
+impl fmt::Display for AspectRatio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.width, self.height)
+    }
+}
+
+/// Fixed region of interest in source pixels, parsed from `--roi x,y,w,h`.
+/// Detections centered outside it are ignored, and the crop falls back to its
+/// center when nothing is detected inside it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Roi {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Roi {
+    /// Whether the center of `hbb` lies inside the region
+    pub fn contains(&self, hbb: &Hbb) -> bool {
+        (self.x..=self.x + self.width).contains(&hbb.cx())
+            && (self.y..=self.y + self.height).contains(&hbb.cy())
+    }
+
+    /// Horizontal center of the region
+    pub fn center_x(&self) -> f32 {
+        self.x + self.width / 2.0
+    }
+}
+
+impl FromStr for Roi {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("invalid ROI '{s}': expected x,y,w,h in source pixels");
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<f32>().map_err(|_| invalid()))
+            .collect::<std::result::Result<Vec<f32>, String>>()?;
+        let [x, y, width, height] = values[..] else {
+            return Err(invalid());
+        };
+        if !(x >= 0.0 && y >= 0.0 && width > 0.0 && height > 0.0)
+            || !(x + width + y + height).is_finite()
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// What to frame when no subject is detected, from `--no-subject-mode`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NoSubjectMode {
+    /// Crop the center of the frame
+    #[default]
+    Center,
+    /// Scale the whole frame into the canvas with bars
+    Letterbox,
+    /// Keep the previous frame's crop
+    HoldLast,
+}
+
+impl FromStr for NoSubjectMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "center" => Ok(Self::Center),
+            "letterbox" => Ok(Self::Letterbox),
+            "hold-last" => Ok(Self::HoldLast),
+            _ => Err(format!(
+                "invalid no-subject mode '{s}': expected center, letterbox, or hold-last"
+            )),
+        }
+    }
+}
+
+/// Which of several detected subjects the crop frames, from `--focus`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Focus {
+    /// Frame every subject (stacking or following one as the layout allows)
+    #[default]
+    All,
+    /// Only the subject with the largest box, usually the closest
+    Largest,
+    /// Only the leftmost subject
+    Leftmost,
+    /// Only the subject nearest the frame's horizontal center
+    Center,
+}
+
+impl FromStr for Focus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "largest" => Ok(Self::Largest),
+            "leftmost" => Ok(Self::Leftmost),
+            "center" => Ok(Self::Center),
+            _ => Err(format!(
+                "invalid focus '{s}': expected all, largest, leftmost, or center"
+            )),
+        }
+    }
+}
+
+/// How two crops are compared when deciding whether the crop moved, from
+/// `--similarity-metric`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SimilarityMetric {
+    /// Each of x, y, width and height moved by at most the threshold
+    /// percentage of the frame width
+    #[default]
+    Delta,
+    /// The boxes' intersection over union is at least `1 - threshold / 100`
+    Iou,
+}
+
+impl FromStr for SimilarityMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "delta" => Ok(Self::Delta),
+            "iou" => Ok(Self::Iou),
+            _ => Err(format!(
+                "invalid similarity metric '{s}': expected delta or iou"
+            )),
+        }
+    }
+}
+
 /// Represents a crop area in the image
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CropArea {
     pub x: f32,
     pub y: f32,
@@ -50,11 +305,58 @@ impl CropArea {
         let h_ok = is_within_threshold("height", self.height, other.height);
         x_ok && y_ok && w_ok && h_ok
     }
+
+    /// Checks if this crop area's width and height are each within
+    /// `threshold_percent` of the other's, relative to the larger of the two.
+    /// Catches a crop zooming in or out around the same center, which moves
+    /// every coordinate by only a small fraction of the frame.
+    pub fn is_same_scale(&self, other: &CropArea, threshold_percent: f32) -> bool {
+        let threshold = threshold_percent / 100.0;
+        let is_within_ratio = |a: f32, b: f32| -> bool {
+            let larger = a.max(b);
+            larger <= 0.0 || (a - b).abs() / larger <= threshold + f32::EPSILON
+        };
+        is_within_ratio(self.width, other.width) && is_within_ratio(self.height, other.height)
+    }
+
+    /// Area of the overlap of the two crop areas divided by the area they
+    /// cover together, from 0 (disjoint) to 1 (identical)
+    pub fn intersection_over_union(&self, other: &CropArea) -> f32 {
+        let overlap_w = (self.x + self.width).min(other.x + other.width) - self.x.max(other.x);
+        let overlap_h = (self.y + self.height).min(other.y + other.height) - self.y.max(other.y);
+        let intersection = overlap_w.max(0.0) * overlap_h.max(0.0);
+        let union = self.width * self.height + other.width * other.height - intersection;
+        if union <= 0.0 {
+            return 0.0;
+        }
+        intersection / union
+    }
+
+    /// Checks if this crop area is similar to another under `metric`, with
+    /// `threshold_percent` as the allowed change (see [`SimilarityMetric`])
+    pub fn is_similar(
+        &self,
+        other: &CropArea,
+        frame_width: f32,
+        threshold_percent: f32,
+        metric: SimilarityMetric,
+    ) -> bool {
+        match metric {
+            SimilarityMetric::Delta => {
+                self.is_within_percentage(other, frame_width, threshold_percent)
+                    && self.is_same_scale(other, threshold_percent)
+            }
+            SimilarityMetric::Iou => {
+                self.intersection_over_union(other) + f32::EPSILON
+                    >= 1.0 - threshold_percent / 100.0
+            }
+        }
+    }
 }
 
 // Helper utilities to reduce duplication across crop calculations
-fn compute_three_four_width(frame_height: f32) -> f32 {
-    frame_height * (3.0 / 4.0)
+fn single_crop_width(frame_width: f32, frame_height: f32, aspect_ratio: AspectRatio) -> f32 {
+    (frame_height * aspect_ratio.single_crop_ratio()).min(frame_width)
 }
 
 fn clamp_x_for_width(x: f32, width: f32, frame_width: f32) -> f32 {
@@ -67,20 +369,58 @@ fn clamp_x_for_width(x: f32, width: f32, frame_width: f32) -> f32 {
     }
 }
 
-fn make_single_crop_centered(center_x: f32, frame_width: f32, frame_height: f32) -> CropArea {
-    let height = frame_height;
-    let width = compute_three_four_width(frame_height);
+fn make_single_crop_centered(
+    center_x: f32,
+    frame_width: f32,
+    frame_height: f32,
+    aspect_ratio: AspectRatio,
+) -> CropArea {
+    let width = single_crop_width(frame_width, frame_height, aspect_ratio);
+    // Only shorter than the frame when the requested ratio is wider than the source
+    let height = (width / aspect_ratio.single_crop_ratio()).min(frame_height);
     let x = clamp_x_for_width(center_x - width / 2.0, width, frame_width);
-    CropArea::new(x, 0.0, width, height)
+    let y = (frame_height - height) / 2.0;
+    CropArea::new(x, y, width, height)
 }
 
 fn center_x_of_bbox(bbox: &CropArea) -> f32 {
     bbox.x + bbox.width / 2.0
 }
 
-fn half_stack_dims(frame_width: f32, frame_height: f32) -> (f32, f32, f32) {
+/// Horizontal center for a crop of `crop_width` framing all `heads`, weighted
+/// by detection confidence (1 when missing). Each head's extent is pulled
+/// toward the confidence-weighted centroid in proportion to its confidence
+/// relative to the most confident head, so a barely-detected box pulls the
+/// framing less than a strong one. Equal confidences give the bounding-box
+/// center, and the result never leaves a head outside a crop that fits them.
+fn confidence_weighted_center_x(heads: &[&Hbb], crop_width: f32) -> f32 {
+    let bbox = calculate_bounding_box(heads);
+    let weight = |head: &Hbb| head.confidence().unwrap_or(1.0).max(f32::EPSILON);
+    let max_weight = heads.iter().map(|h| weight(h)).fold(0.0, f32::max);
+    if heads.iter().all(|h| weight(h) == max_weight) {
+        return center_x_of_bbox(&bbox);
+    }
+
+    let total_weight: f32 = heads.iter().map(|h| weight(h)).sum();
+    let centroid = heads.iter().map(|h| h.cx() * weight(h)).sum::<f32>() / total_weight;
+    let mut min_x = f32::MAX;
+    let mut max_x = f32::MIN;
+    for head in heads {
+        let pull = weight(head) / max_weight;
+        min_x = min_x.min(centroid + pull * (head.xmin() - centroid));
+        max_x = max_x.max(centroid + pull * (head.xmax() - centroid));
+    }
+
+    let slack = ((crop_width - bbox.width) / 2.0).max(0.0);
+    let bbox_center = center_x_of_bbox(&bbox);
+    ((min_x + max_x) / 2.0).clamp(bbox_center - slack, bbox_center + slack)
+}
+
+/// Width, height and default y of one half-width crop in a top/bottom stack,
+/// where `crop_ratio` is the segment's width divided by its height
+fn half_stack_dims(frame_width: f32, frame_height: f32, crop_ratio: f32) -> (f32, f32, f32) {
     let crop_width = frame_width * 0.5;
-    let crop_height = crop_width * (8.0 / 9.0);
+    let crop_height = (crop_width / crop_ratio).min(frame_height);
     let default_y = (frame_height - crop_height) / 2.0;
     (crop_width, crop_height, default_y)
 }
@@ -127,14 +467,31 @@ fn vertical_y_for_heads(
 }
 
 /// Represents the result of calculating crop areas
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CropResult {
     /// A single crop area
     Single(CropArea),
-    /// Two crop areas that should be stacked vertically
+    /// Two crop areas that should be stacked vertically, or placed side by
+    /// side (first on the left) on square and wider canvases
     Stacked(CropArea, CropArea),
     /// Resize the entire frame (for graphic mode)
     Resize(CropArea),
+    /// A single crop area shown sharp over a blurred, zoomed copy of the
+    /// full frame (for subjects too far apart to share a normal crop)
+    BlurFill(CropArea),
+}
+
+impl CropResult {
+    /// The crop rectangles in source-frame coordinates: both halves of a
+    /// stacked crop (first crop first), or the single area otherwise
+    pub fn areas(&self) -> Vec<&CropArea> {
+        match self {
+            CropResult::Stacked(first, second) => vec![first, second],
+            CropResult::Single(area) | CropResult::Resize(area) | CropResult::BlurFill(area) => {
+                vec![area]
+            }
+        }
+    }
 }
 
 /// Calculates crop area when no heads are detected
@@ -142,27 +499,54 @@ pub fn calculate_no_heads_crop(
     frame_width: f32,
     frame_height: f32,
     is_graphic: bool,
+    aspect_ratio: AspectRatio,
 ) -> CropResult {
     if is_graphic {
         // For graphic mode, return a resize crop that covers the entire frame
         CropResult::Resize(CropArea::new(0.0, 0.0, frame_width, frame_height))
     } else {
-        // For no heads, center a 3:4 crop on the frame center
+        // For no heads, center a single crop on the frame center
         let center_x = frame_width / 2.0;
         CropResult::Single(make_single_crop_centered(
             center_x,
             frame_width,
             frame_height,
+            aspect_ratio,
         ))
     }
 }
 
+/// Calculates the crop for a non-graphic frame with no detected subject.
+/// `HoldLast` reuses `previous_crop`, falling back to a center crop when there
+/// is none yet (e.g. the video opens without a subject).
+pub fn calculate_no_subject_crop(
+    mode: NoSubjectMode,
+    frame_width: f32,
+    frame_height: f32,
+    previous_crop: Option<&CropResult>,
+    aspect_ratio: AspectRatio,
+) -> CropResult {
+    match (mode, previous_crop) {
+        (NoSubjectMode::Letterbox, _) => {
+            CropResult::Resize(CropArea::new(0.0, 0.0, frame_width, frame_height))
+        }
+        (NoSubjectMode::HoldLast, Some(previous_crop)) => previous_crop.clone(),
+        _ => calculate_no_heads_crop(frame_width, frame_height, false, aspect_ratio),
+    }
+}
+
 /// Calculates crop area for a single head
-pub fn calculate_single_head_crop(frame_width: f32, frame_height: f32, head: &Hbb) -> CropResult {
+pub fn calculate_single_head_crop(
+    frame_width: f32,
+    frame_height: f32,
+    head: &Hbb,
+    aspect_ratio: AspectRatio,
+) -> CropResult {
     CropResult::Single(make_single_crop_centered(
         head.cx(),
         frame_width,
         frame_height,
+        aspect_ratio,
     ))
 }
 
@@ -173,22 +557,29 @@ pub fn calculate_two_heads_crop(
     frame_height: f32,
     head1: &Hbb,
     head2: &Hbb,
+    aspect_ratio: AspectRatio,
 ) -> CropResult {
     // Calculate the bounding box of the two heads
     let bbox = calculate_bounding_box(&[head1, head2]);
 
-    // Check if the width of the bounding box is less than or equal to 3/4 of the frame height
-    if bbox.width <= frame_height * 0.75 {
-        // Return a single crop centered on the bounding box
-        let center_x = center_x_of_bbox(&bbox);
+    // Check if the bounding box fits inside a single crop
+    let crop_width = single_crop_width(frame_width, frame_height, aspect_ratio);
+    if bbox.width <= crop_width {
+        // Return a single crop centered on the heads, weighted by confidence
+        let center_x = confidence_weighted_center_x(&[head1, head2], crop_width);
         CropResult::Single(make_single_crop_centered(
             center_x,
             frame_width,
             frame_height,
+            aspect_ratio,
         ))
     } else if use_stack_crop {
-        // Return two crops with specific dimensions and positions
-        let (crop_width, crop_height, default_y) = half_stack_dims(frame_width, frame_height);
+        // Return two crops with specific dimensions and positions; the left
+        // head's crop goes on top, so it takes the top segment's shape
+        let (crop_width, top_height, top_y) =
+            half_stack_dims(frame_width, frame_height, aspect_ratio.stack_top_ratio());
+        let (_, bottom_height, bottom_y) =
+            half_stack_dims(frame_width, frame_height, aspect_ratio.stack_bottom_ratio());
 
         let (left_head, right_head) = if head1.cx() <= head2.cx() {
             (head1, head2)
@@ -197,8 +588,8 @@ pub fn calculate_two_heads_crop(
         };
 
         // Determine vertical placement for each side
-        let crop1_y = vertical_y_for_heads(&[left_head], default_y, frame_height, crop_height);
-        let crop2_y = vertical_y_for_heads(&[right_head], default_y, frame_height, crop_height);
+        let crop1_y = vertical_y_for_heads(&[left_head], top_y, frame_height, top_height);
+        let crop2_y = vertical_y_for_heads(&[right_head], bottom_y, frame_height, bottom_height);
 
         // Calculate default x positions
         let mut crop1_x = 0.0;
@@ -243,14 +634,14 @@ pub fn calculate_two_heads_crop(
         }
 
         // First crop
-        let crop1 = CropArea::new(crop1_x, crop1_y, crop_width, crop_height);
+        let crop1 = CropArea::new(crop1_x, crop1_y, crop_width, top_height);
 
         // Second crop
-        let crop2 = CropArea::new(crop2_x, crop2_y, crop_width, crop_height);
+        let crop2 = CropArea::new(crop2_x, crop2_y, crop_width, bottom_height);
 
         CropResult::Stacked(crop1, crop2)
     } else {
-        calculate_crop_from_largest_head(frame_width, frame_height, &[head1, head2])
+        calculate_crop_from_largest_head(frame_width, frame_height, &[head1, head2], aspect_ratio)
     }
 }
 
@@ -260,15 +651,18 @@ pub fn calculate_three_heads_crop(
     frame_width: f32,
     frame_height: f32,
     heads: &[&Hbb],
+    aspect_ratio: AspectRatio,
 ) -> CropResult {
     let bbox = calculate_bounding_box(heads);
+    let crop_width = single_crop_width(frame_width, frame_height, aspect_ratio);
 
-    if bbox.width <= frame_height * (3.0 / 4.0) {
-        let center_x = center_x_of_bbox(&bbox);
+    if bbox.width <= crop_width {
+        let center_x = confidence_weighted_center_x(heads, crop_width);
         return CropResult::Single(make_single_crop_centered(
             center_x,
             frame_width,
             frame_height,
+            aspect_ratio,
         ));
     }
 
@@ -292,11 +686,14 @@ pub fn calculate_three_heads_crop(
         let spacing_ratio = spacing1.max(spacing2) / spacing1.min(spacing2);
         let equally_spaced = spacing_ratio <= 2.0;
 
-        if similar_size && equally_spaced {
-            // Create special stacked crop designed to work together for 9:16 final image
-            let stack_height = frame_height * 0.8; // 80% of frame height
-            let double_width = stack_height * 1.5; // 9:6 aspect ratio
-            let single_width = stack_height * 0.9; // 9:10 aspect ratio
+        // Special stacked crops designed to work together for the final canvas
+        let stack_height = frame_height * 0.8; // 80% of frame height
+        let double_width = stack_height * aspect_ratio.three_stack_double_ratio(); // 9:6 for 9:16
+        let single_width = stack_height * aspect_ratio.three_stack_single_ratio(); // 9:10 for 9:16
+        // Wide canvases make the two-head crop wider than the source; use the fallback then
+        let fits_frame = double_width <= frame_width;
+
+        if similar_size && equally_spaced && fits_frame {
             let default_y = frame_height * 0.1; // 10% from top
 
             // Heads in left-to-right order (no fragile tolerance matching).
@@ -347,16 +744,19 @@ pub fn calculate_three_heads_crop(
                 return CropResult::Stacked(top_crop, bottom_crop);
             }
         } else {
-            // Mirror the two-heads stacked crop: two half-width crops side-by-side with 8:9 height
-            let (crop_width, crop_height, default_y) = half_stack_dims(frame_width, frame_height);
+            // Mirror the two-heads stacked crop: two half-width crops side-by-side
+            let (crop_width, top_height, top_y) =
+                half_stack_dims(frame_width, frame_height, aspect_ratio.stack_top_ratio());
+            let (_, bottom_height, bottom_y) =
+                half_stack_dims(frame_width, frame_height, aspect_ratio.stack_bottom_ratio());
 
             // Default crop positions
             let mut x1 = 0.0;
             let mut x2 = crop_width;
-            let mut crop1_y = default_y;
-            let mut crop2_y = default_y;
-            let crop1_default = CropArea::new(x1, crop1_y, crop_width, crop_height);
-            let crop2_default = CropArea::new(x2, crop2_y, crop_width, crop_height);
+            let mut crop1_y = top_y;
+            let mut crop2_y = bottom_y;
+            let crop1_default = CropArea::new(x1, crop1_y, crop_width, top_height);
+            let crop2_default = CropArea::new(x2, crop2_y, crop_width, bottom_height);
 
             // Check if all heads are fully contained in at least one default crop
             let all_heads_contained = heads.iter().all(|head| {
@@ -382,14 +782,13 @@ pub fn calculate_three_heads_crop(
                     }
                 }
 
-                let left_y =
-                    vertical_y_for_heads(&left_heads, default_y, frame_height, crop_height);
+                let left_y = vertical_y_for_heads(&left_heads, top_y, frame_height, top_height);
 
                 let right_y =
-                    vertical_y_for_heads(&right_heads, default_y, frame_height, crop_height);
+                    vertical_y_for_heads(&right_heads, bottom_y, frame_height, bottom_height);
 
-                let crop1 = CropArea::new(0.0, left_y, crop_width, crop_height);
-                let crop2 = CropArea::new(crop_width, right_y, crop_width, crop_height);
+                let crop1 = CropArea::new(0.0, left_y, crop_width, top_height);
+                let crop2 = CropArea::new(crop_width, right_y, crop_width, bottom_height);
                 return CropResult::Stacked(crop1, crop2);
             }
 
@@ -407,11 +806,11 @@ pub fn calculate_three_heads_crop(
 
             // Vertical positioning per side (top/bottom bias like two-heads)
             if !crop1_heads.is_empty() {
-                crop1_y = vertical_y_for_heads(&crop1_heads, default_y, frame_height, crop_height);
+                crop1_y = vertical_y_for_heads(&crop1_heads, top_y, frame_height, top_height);
             }
 
             if !crop2_heads.is_empty() {
-                crop2_y = vertical_y_for_heads(&crop2_heads, default_y, frame_height, crop_height);
+                crop2_y = vertical_y_for_heads(&crop2_heads, bottom_y, frame_height, bottom_height);
             }
 
             // Horizontal positioning to contain assigned heads
@@ -452,8 +851,8 @@ pub fn calculate_three_heads_crop(
             }
 
             // Create the crops
-            let mut crop1 = CropArea::new(x1, crop1_y, crop_width, crop_height);
-            let mut crop2 = CropArea::new(x2, crop2_y, crop_width, crop_height);
+            let mut crop1 = CropArea::new(x1, crop1_y, crop_width, top_height);
+            let mut crop2 = CropArea::new(x2, crop2_y, crop_width, bottom_height);
 
             // Verify that every head is fully contained in at least one crop, adjust if not
             for head in heads {
@@ -468,18 +867,18 @@ pub fn calculate_three_heads_crop(
                     if dist_to_crop1 <= dist_to_crop2 {
                         let new_x1 = head_xmin;
                         x1 = new_x1.max(0.0).min(crop_width);
-                        crop1 = CropArea::new(x1, crop1_y, crop_width, crop_height);
+                        crop1 = CropArea::new(x1, crop1_y, crop_width, top_height);
                     } else {
                         let new_x2 = head_xmax - crop_width;
                         x2 = new_x2.max(0.0).min(crop_width);
-                        crop2 = CropArea::new(x2, crop2_y, crop_width, crop_height);
+                        crop2 = CropArea::new(x2, crop2_y, crop_width, bottom_height);
                     }
                 }
             }
             return CropResult::Stacked(crop1, crop2);
         }
     } else {
-        return calculate_crop_from_largest_head(frame_width, frame_height, heads);
+        return calculate_crop_from_largest_head(frame_width, frame_height, heads, aspect_ratio);
     }
 }
 
@@ -488,15 +887,18 @@ pub fn calculate_four_or_more_heads_crop(
     frame_width: f32,
     frame_height: f32,
     heads: &[&Hbb],
+    aspect_ratio: AspectRatio,
 ) -> CropResult {
     let bbox = calculate_bounding_box(heads);
+    let crop_width = single_crop_width(frame_width, frame_height, aspect_ratio);
 
-    if bbox.width <= frame_height * (3.0 / 4.0) {
-        let center_x = center_x_of_bbox(&bbox);
+    if bbox.width <= crop_width {
+        let center_x = confidence_weighted_center_x(heads, crop_width);
         return CropResult::Single(make_single_crop_centered(
             center_x,
             frame_width,
             frame_height,
+            aspect_ratio,
         ));
     }
 
@@ -510,10 +912,10 @@ pub fn calculate_four_or_more_heads_crop(
     });
 
     if has_dominant_head {
-        return calculate_crop_from_largest_head(frame_width, frame_height, heads);
+        return calculate_crop_from_largest_head(frame_width, frame_height, heads, aspect_ratio);
     }
 
-    calculate_no_heads_crop(frame_width, frame_height, false)
+    calculate_no_heads_crop(frame_width, frame_height, false, aspect_ratio)
 }
 
 /// Calculates crop area from the largest head
@@ -521,6 +923,7 @@ pub fn calculate_crop_from_largest_head(
     frame_width: f32,
     frame_height: f32,
     heads: &[&Hbb],
+    aspect_ratio: AspectRatio,
 ) -> CropResult {
     // Find the largest head by area
     let largest_head = heads
@@ -533,9 +936,86 @@ pub fn calculate_crop_from_largest_head(
         head_center_x,
         frame_width,
         frame_height,
+        aspect_ratio,
+    ))
+}
+
+/// Keeps the heads centered inside `roi` (all of them without one)
+pub fn heads_in_roi<'a>(heads: Vec<&'a Hbb>, roi: Option<Roi>) -> Vec<&'a Hbb> {
+    match roi {
+        Some(roi) => heads
+            .into_iter()
+            .filter(|head| roi.contains(head))
+            .collect(),
+        None => heads,
+    }
+}
+
+/// Single crop centered on `roi`, for frames with nothing detected inside it
+pub fn calculate_roi_crop(
+    roi: Roi,
+    frame_width: f32,
+    frame_height: f32,
+    aspect_ratio: AspectRatio,
+) -> CropResult {
+    CropResult::Single(make_single_crop_centered(
+        roi.center_x(),
+        frame_width,
+        frame_height,
+        aspect_ratio,
     ))
 }
 
+/// Share of the distance to the `--roi` center that a single crop is pulled
+const ROI_WEIGHT: f32 = 0.5;
+
+/// Pulls a single crop [`ROI_WEIGHT`] of the way toward `roi`'s center, as
+/// far as it can go while still containing `heads`. Stacked and resized
+/// crops are returned unchanged.
+pub fn weight_toward_roi(
+    crop: CropResult,
+    heads: &[&Hbb],
+    roi: Roi,
+    frame_width: f32,
+) -> CropResult {
+    let CropResult::Single(area) = crop else {
+        return crop;
+    };
+    let center = area.x + area.width / 2.0;
+    let mut target = center + (roi.center_x() - center) * ROI_WEIGHT;
+    if !heads.is_empty() {
+        // Keep the subjects in the crop; leave it alone if they don't fit
+        let bbox = calculate_bounding_box(heads);
+        let min_center = bbox.x + bbox.width - area.width / 2.0;
+        let max_center = bbox.x + area.width / 2.0;
+        target = if min_center <= max_center {
+            target.clamp(min_center, max_center)
+        } else {
+            center
+        };
+    }
+    let x = (target - area.width / 2.0).clamp(0.0, (frame_width - area.width).max(0.0));
+    CropResult::Single(CropArea::new(x, area.y, area.width, area.height))
+}
+
+/// Reduces the detected heads to the ones the crop should frame under
+/// `focus`: all of them, or the single head it selects
+pub fn focus_heads<'a>(heads: &[&'a Hbb], focus: Focus, frame_width: f32) -> Vec<&'a Hbb> {
+    let by = |key: &dyn Fn(&Hbb) -> f32| {
+        heads
+            .iter()
+            .copied()
+            .min_by(|a, b| key(a).total_cmp(&key(b)))
+    };
+    let chosen = match focus {
+        Focus::All => return heads.to_vec(),
+        Focus::Largest => by(&|head| -head.area()),
+        Focus::Leftmost => by(&|head| head.cx()),
+        Focus::Center => by(&|head| (head.cx() - frame_width / 2.0).abs()),
+    };
+    chosen.into_iter().collect()
+}
+
 /// Calculates the optimal crop area based on detected heads
 ///
 /// # Arguments
@@ -544,43 +1024,273 @@ pub fn calculate_crop_from_largest_head(
 /// * `frame_width` - Width of the input frame
 /// * `frame_height` - Height of the input frame
 /// * `heads` - Vector of head detections that have already been filtered by confidence threshold
+/// * `aspect_ratio` - Aspect ratio of the output canvas the crops are laid out for
+///
+/// Stacked results are computed for a top/bottom layout and re-laid out side
+/// by side when the canvas calls for it (see [`AspectRatio::stacks_side_by_side`]).
 pub fn calculate_crop(
     use_stack_crop: bool,
     is_graphic: bool,
     frame_width: f32,
     frame_height: f32,
     heads: &[&Hbb],
+    aspect_ratio: AspectRatio,
 ) -> Result<CropResult> {
-    match heads.len() {
-        0 => Ok(calculate_no_heads_crop(
-            frame_width,
-            frame_height,
-            is_graphic,
-        )),
-        1 => Ok(calculate_single_head_crop(
-            frame_width,
-            frame_height,
-            heads[0],
-        )),
-        2 => Ok(calculate_two_heads_crop(
+    let crop = match heads.len() {
+        0 => calculate_no_heads_crop(frame_width, frame_height, is_graphic, aspect_ratio),
+        1 => calculate_single_head_crop(frame_width, frame_height, heads[0], aspect_ratio),
+        2 => calculate_two_heads_crop(
             use_stack_crop,
             frame_width,
             frame_height,
             heads[0],
             heads[1],
-        )),
-        3 => Ok(calculate_three_heads_crop(
+            aspect_ratio,
+        ),
+        3 => calculate_three_heads_crop(
             use_stack_crop,
             frame_width,
             frame_height,
             heads,
-        )),
-        4.. => Ok(calculate_four_or_more_heads_crop(
+            aspect_ratio,
+        ),
+        4.. => calculate_four_or_more_heads_crop(frame_width, frame_height, heads, aspect_ratio),
+    };
+
+    if aspect_ratio.stacks_side_by_side() {
+        Ok(side_by_side_crop(
+            crop,
             frame_width,
             frame_height,
             heads,
+            aspect_ratio,
+        ))
+    } else {
+        Ok(crop)
+    }
+}
+
+/// Re-lays out a top/bottom stack for a side-by-side canvas: each area becomes
+/// a tall crop at half the canvas ratio, centered on the heads it framed (or
+/// on itself when it framed none). Other crop results are returned unchanged.
+fn side_by_side_crop(
+    crop: CropResult,
+    frame_width: f32,
+    frame_height: f32,
+    heads: &[&Hbb],
+    aspect_ratio: AspectRatio,
+) -> CropResult {
+    let CropResult::Stacked(first, second) = crop else {
+        return crop;
+    };
+
+    let ratio = aspect_ratio.side_by_side_ratio();
+    let width = (frame_height * ratio).min(frame_width / 2.0);
+    let height = (width / ratio).min(frame_height);
+    let relayout = |area: &CropArea| {
+        let framed: Vec<&Hbb> = heads
+            .iter()
+            .copied()
+            .filter(|head| head.cx() >= area.x && head.cx() <= area.x + area.width)
+            .collect();
+        let center_x = if framed.is_empty() {
+            center_x_of_bbox(area)
+        } else {
+            confidence_weighted_center_x(&framed, width)
+        };
+        let default_y = (frame_height - height) / 2.0;
+        CropArea::new(
+            clamp_x_for_width(center_x - width / 2.0, width, frame_width),
+            vertical_y_for_heads(&framed, default_y, frame_height, height),
+            width,
+            height,
+        )
+    };
+    CropResult::Stacked(relayout(&first), relayout(&second))
+}
+
+/// Like [`calculate_crop`], but when two or three heads are too far apart to
+/// share a single crop and the result falls back to following one of them,
+/// follows `primary` (e.g. the tracker's most stable object) instead of
+/// whichever head happens to be largest this frame
+pub fn calculate_crop_with_primary(
+    use_stack_crop: bool,
+    is_graphic: bool,
+    frame_width: f32,
+    frame_height: f32,
+    heads: &[&Hbb],
+    primary: Option<&Hbb>,
+    aspect_ratio: AspectRatio,
+) -> Result<CropResult> {
+    let crop = calculate_crop(
+        use_stack_crop,
+        is_graphic,
+        frame_width,
+        frame_height,
+        heads,
+        aspect_ratio,
+    )?;
+
+    let follows_one_head = matches!(crop, CropResult::Single(_))
+        && (2..=3).contains(&heads.len())
+        && calculate_bounding_box(heads).width
+            > single_crop_width(frame_width, frame_height, aspect_ratio);
+    match primary {
+        Some(primary) if follows_one_head => Ok(calculate_single_head_crop(
+            frame_width,
+            frame_height,
+            primary,
+            aspect_ratio,
         )),
+        _ => Ok(crop),
+    }
+}
+
+/// Checks that a `--crop-padding` value is a usable percentage
+pub fn check_crop_padding(padding_percent: f32) -> Result<()> {
+    if !padding_percent.is_finite() || padding_percent < 0.0 {
+        anyhow::bail!("invalid crop padding {padding_percent}: expected a percentage of 0 or more");
+    }
+    Ok(())
+}
+
+/// Checks that a `--stack-ratio` value leaves room for both stacked crops
+pub fn check_stack_ratio(stack_ratio: f32) -> Result<()> {
+    if !(stack_ratio > 0.0 && stack_ratio < 1.0) {
+        anyhow::bail!("invalid stack ratio {stack_ratio}: expected a value between 0 and 1");
+    }
+    Ok(())
+}
+
+/// Grows `area` about its center, keeping its aspect ratio, so each side gains
+/// `padding_percent` of the subject's size: the heads centered inside `area`,
+/// or all `heads` when none are. Growth stops at the frame size and the result
+/// is shifted back inside the frame.
+fn pad_crop_area(
+    area: &CropArea,
+    heads: &[&Hbb],
+    padding_percent: f32,
+    frame_width: f32,
+    frame_height: f32,
+) -> CropArea {
+    let inside: Vec<&Hbb> = heads
+        .iter()
+        .copied()
+        .filter(|h| {
+            (area.x..=area.x + area.width).contains(&h.cx())
+                && (area.y..=area.y + area.height).contains(&h.cy())
+        })
+        .collect();
+    let subject = calculate_bounding_box(if inside.is_empty() { heads } else { &inside });
+    let pad_x = subject.width * padding_percent / 100.0;
+    let pad_y = subject.height * padding_percent / 100.0;
+
+    let scale = ((area.width + 2.0 * pad_x) / area.width)
+        .max((area.height + 2.0 * pad_y) / area.height)
+        .min(frame_width / area.width)
+        .min(frame_height / area.height)
+        .max(1.0);
+    let width = area.width * scale;
+    let height = area.height * scale;
+    let x = area.x + (area.width - width) / 2.0;
+    let y = area.y + (area.height - height) / 2.0;
+    CropArea::new(
+        x.clamp(0.0, (frame_width - width).max(0.0)),
+        y.clamp(0.0, (frame_height - height).max(0.0)),
+        width,
+        height,
+    )
+}
+
+/// For `--crop-padding`: zooms single and stacked crops out so the subject
+/// gets `padding_percent` of its own size as margin on each side. Crops that
+/// already span the frame height (the usual single crop) can't grow and are
+/// unchanged; other crop types are returned as is.
+pub fn pad_crop(
+    crop: CropResult,
+    heads: &[&Hbb],
+    padding_percent: f32,
+    frame_width: f32,
+    frame_height: f32,
+) -> CropResult {
+    if padding_percent <= 0.0 || heads.is_empty() {
+        return crop;
+    }
+    let pad =
+        |area: &CropArea| pad_crop_area(area, heads, padding_percent, frame_width, frame_height);
+    match crop {
+        CropResult::Single(area) => CropResult::Single(pad(&area)),
+        CropResult::Stacked(top, bottom) => CropResult::Stacked(pad(&top), pad(&bottom)),
+        other => other,
+    }
+}
+
+/// Checks that a `--deadzone` value is a usable percentage of the frame width
+pub fn check_deadzone(deadzone_percent: f32) -> Result<()> {
+    if !(deadzone_percent.is_finite() && (0.0..100.0).contains(&deadzone_percent)) {
+        anyhow::bail!(
+            "invalid deadzone {deadzone_percent}: expected a percentage from 0 to under 100"
+        );
+    }
+    Ok(())
+}
+
+/// For `--deadzone`: keeps `previous` when `latest` is the same kind of crop
+/// and none of its areas' centers moved more than `deadzone_percent` of the
+/// frame width, so subject jitter doesn't nudge the crop target. Larger moves,
+/// a change of layout (e.g. single to stacked), and a zero deadzone return
+/// `latest`.
+pub fn apply_deadzone(
+    latest: CropResult,
+    previous: Option<&CropResult>,
+    deadzone_percent: f32,
+    frame_width: f32,
+) -> CropResult {
+    let Some(previous) = previous else {
+        return latest;
+    };
+    if deadzone_percent <= 0.0
+        || std::mem::discriminant(&latest) != std::mem::discriminant(previous)
+    {
+        return latest;
+    }
+
+    let deadzone = frame_width * deadzone_percent / 100.0;
+    let center_y = |area: &CropArea| area.y + area.height / 2.0;
+    let within_deadzone = latest
+        .areas()
+        .into_iter()
+        .zip(previous.areas())
+        .all(|(new, old)| {
+            (center_x_of_bbox(new) - center_x_of_bbox(old)).abs() <= deadzone
+                && (center_y(new) - center_y(old)).abs() <= deadzone
+        });
+    if within_deadzone {
+        previous.clone()
+    } else {
+        latest
+    }
+}
+
+/// For `--use-blur-fill`: replaces a stacked crop with one blur-fill crop
+/// spanning every head (padded by the widest head on each side), which keeps
+/// the subjects side by side instead of splitting them one above the other.
+/// Any other crop is returned unchanged.
+pub fn blur_fill_instead_of_stack(
+    crop: CropResult,
+    frame_width: f32,
+    frame_height: f32,
+    heads: &[&Hbb],
+) -> CropResult {
+    if !matches!(crop, CropResult::Stacked(_, _)) || heads.is_empty() {
+        return crop;
     }
+
+    let bbox = calculate_bounding_box(heads);
+    let padding = heads.iter().map(|h| h.width()).fold(0.0, f32::max);
+    let width = (bbox.width + 2.0 * padding).min(frame_width);
+    let x = clamp_x_for_width(center_x_of_bbox(&bbox) - width / 2.0, width, frame_width);
+    CropResult::BlurFill(CropArea::new(x, 0.0, width, frame_height))
 }
 
 /// Calculates the bounding box that contains all given heads
@@ -645,22 +1355,31 @@ pub fn crop_types_different(first_crop: &CropResult, second_crop: &CropResult) -
         (CropResult::Single(_), CropResult::Single(_)) => false,
         (CropResult::Stacked(_, _), CropResult::Stacked(_, _)) => false,
         (CropResult::Resize(_), CropResult::Resize(_)) => false,
+        (CropResult::BlurFill(_), CropResult::BlurFill(_)) => false,
         _ => true,
     }
 }
 
-/// Checks if two crop results are similar based on a threshold percentage
-pub fn is_crop_similar(crop1: &CropResult, crop2: &CropResult, width: f32, threshold: f32) -> bool {
+/// Checks if two crop results are similar based on a threshold percentage,
+/// compared with `metric`
+pub fn is_crop_similar(
+    crop1: &CropResult,
+    crop2: &CropResult,
+    width: f32,
+    threshold: f32,
+    metric: SimilarityMetric,
+) -> bool {
     match (crop1, crop2) {
         (CropResult::Single(crop1), CropResult::Single(crop2)) => {
-            crop1.is_within_percentage(crop2, width, threshold)
+            crop1.is_similar(crop2, width, threshold, metric)
         }
         (CropResult::Stacked(crop1_1, crop1_2), CropResult::Stacked(crop2_1, crop2_2)) => {
-            crop1_1.is_within_percentage(crop2_1, width, threshold)
-                && crop1_2.is_within_percentage(crop2_2, width, threshold)
+            crop1_1.is_similar(crop2_1, width, threshold, metric)
+                && crop1_2.is_similar(crop2_2, width, threshold, metric)
         }
-        (CropResult::Resize(crop1), CropResult::Resize(crop2)) => {
-            crop1.is_within_percentage(crop2, width, threshold)
+        (CropResult::Resize(crop1), CropResult::Resize(crop2))
+        | (CropResult::BlurFill(crop1), CropResult::BlurFill(crop2)) => {
+            crop1.is_similar(crop2, width, threshold, metric)
         }
         _ => false, // If crop types don't match, use the new crop
     }
@@ -687,7 +1406,10 @@ fn crop_result_distance(crop1: &CropResult, crop2: &CropResult) -> f32 {
             let dist2 = crop_area_distance(crop1_2, crop2_2);
             (dist1 + dist2) / 2.0
         }
-        (CropResult::Resize(crop1), CropResult::Resize(crop2)) => crop_area_distance(crop1, crop2),
+        (CropResult::Resize(crop1), CropResult::Resize(crop2))
+        | (CropResult::BlurFill(crop1), CropResult::BlurFill(crop2)) => {
+            crop_area_distance(crop1, crop2)
+        }
         _ => f32::MAX, // If crop types don't match, consider them very different
     }
 }
@@ -718,6 +1440,7 @@ pub fn select_closest_crop<'a>(
 
 #[cfg(test)]
 mod tests {
+    use super::SimilarityMetric::{Delta, Iou};
     use super::*;
 
     #[test]
@@ -730,7 +1453,13 @@ mod tests {
         let h3 = Hbb::from_xywh(f32::NAN, 400.0, 200.0, 200.0); // cx NaN
         let heads = [&h1, &h2, &h3];
         // Must return without panicking.
-        let _ = calculate_three_heads_crop(true, frame_width, frame_height, &heads);
+        let _ = calculate_three_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
     }
 
     #[test]
@@ -742,10 +1471,20 @@ mod tests {
         let middle = Hbb::from_xywh(860.0, 400.0, 200.0, 200.0); // cx 960
         let right = Hbb::from_xywh(1420.0, 400.0, 200.0, 200.0); // cx 1520
 
-        let sorted =
-            calculate_three_heads_crop(true, frame_width, frame_height, &[&left, &middle, &right]);
-        let scrambled =
-            calculate_three_heads_crop(true, frame_width, frame_height, &[&right, &left, &middle]);
+        let sorted = calculate_three_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &[&left, &middle, &right],
+            AspectRatio::PORTRAIT,
+        );
+        let scrambled = calculate_three_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &[&right, &left, &middle],
+            AspectRatio::PORTRAIT,
+        );
 
         // Left/middle/right identification must not depend on input order.
         assert_eq!(format!("{sorted:?}"), format!("{scrambled:?}"));
@@ -799,12 +1538,22 @@ mod tests {
         assert_eq!(bbox.height, 0.0);
     }
 
+    #[test]
+    fn test_crop_result_areas() {
+        let first = CropArea::new(0.0, 10.0, 960.0, 853.0);
+        let second = CropArea::new(960.0, 20.0, 960.0, 853.0);
+        let stacked = CropResult::Stacked(first.clone(), second.clone());
+        assert_eq!(stacked.areas(), [&first, &second]);
+        assert_eq!(CropResult::Resize(first.clone()).areas(), [&first]);
+        assert_eq!(CropResult::BlurFill(second.clone()).areas(), [&second]);
+    }
+
     #[test]
     fn test_calculate_no_heads_crop() {
         let frame_width = 1920.0;
         let frame_height = 1080.0;
 
-        let crop = calculate_no_heads_crop(frame_width, frame_height, false);
+        let crop = calculate_no_heads_crop(frame_width, frame_height, false, AspectRatio::PORTRAIT);
 
         match crop {
             CropResult::Single(crop) => {
@@ -837,7 +1586,7 @@ mod tests {
         let frame_width = 1920.0;
         let frame_height = 1080.0;
 
-        let crop = calculate_no_heads_crop(frame_width, frame_height, true);
+        let crop = calculate_no_heads_crop(frame_width, frame_height, true, AspectRatio::PORTRAIT);
 
         match crop {
             CropResult::Resize(crop) => {
@@ -858,7 +1607,8 @@ mod tests {
 
         // Test centered head
         let head = Hbb::from_cxcywh(frame_width / 2.0, frame_height / 2.0, 100.0, 100.0);
-        let crop = calculate_single_head_crop(frame_width, frame_height, &head);
+        let crop =
+            calculate_single_head_crop(frame_width, frame_height, &head, AspectRatio::PORTRAIT);
 
         match crop {
             CropResult::Single(crop) => {
@@ -883,7 +1633,8 @@ mod tests {
 
         // Test head on far left
         let head = Hbb::from_cxcywh(50.0, frame_height / 2.0, 100.0, 100.0);
-        let crop = calculate_single_head_crop(frame_width, frame_height, &head);
+        let crop =
+            calculate_single_head_crop(frame_width, frame_height, &head, AspectRatio::PORTRAIT);
 
         match crop {
             CropResult::Single(crop) => {
@@ -908,7 +1659,8 @@ mod tests {
 
         // Test head on far right
         let head = Hbb::from_cxcywh(frame_width - 50.0, frame_height / 2.0, 100.0, 100.0);
-        let crop = calculate_single_head_crop(frame_width, frame_height, &head);
+        let crop =
+            calculate_single_head_crop(frame_width, frame_height, &head, AspectRatio::PORTRAIT);
 
         match crop {
             CropResult::Single(crop) => {
@@ -940,7 +1692,14 @@ mod tests {
         // Test close heads - heads are within 3/4 of frame height
         let head1 = Hbb::from_xywh(300.0, 300.0, 100.0, 100.0);
         let head2 = Hbb::from_xywh(450.0, 300.0, 100.0, 100.0);
-        let crop = calculate_two_heads_crop(true, frame_width, frame_height, &head1, &head2);
+        let crop = calculate_two_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &head1,
+            &head2,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -979,7 +1738,14 @@ mod tests {
         // Test far heads - heads are more than 3/4 of frame height apart
         let head1 = Hbb::from_cxcywh(frame_width / 4.0, frame_height / 2.0, 100.0, 100.0);
         let head2 = Hbb::from_cxcywh(3.0 * frame_width / 4.0, frame_height / 2.0, 100.0, 100.0);
-        let crop = calculate_two_heads_crop(true, frame_width, frame_height, &head1, &head2);
+        let crop = calculate_two_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &head1,
+            &head2,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Stacked(crop1, crop2) => {
@@ -1026,7 +1792,14 @@ mod tests {
         // Test with one head at the top and one at the bottom
         let head1 = Hbb::from_cxcywh(frame_width / 4.0, 50.0, 100.0, 100.0); // Head near top
         let head2 = Hbb::from_cxcywh(3.0 * frame_width / 4.0, frame_height - 50.0, 100.0, 100.0); // Head near bottom
-        let crop = calculate_two_heads_crop(true, frame_width, frame_height, &head1, &head2);
+        let crop = calculate_two_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &head1,
+            &head2,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Stacked(crop1, crop2) => {
@@ -1077,9 +1850,16 @@ mod tests {
         // Second head is far to the right, ensuring the bounding box is wider than 3/4 of frame height
         let head2 = Hbb::from_cxcywh(frame_width - 200.0, frame_height / 2.0, 100.0, 100.0);
 
-        let crop = calculate_two_heads_crop(true, frame_width, frame_height, &head1, &head2);
-
-        match crop {
+        let crop = calculate_two_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &head1,
+            &head2,
+            AspectRatio::PORTRAIT,
+        );
+
+        match crop {
             CropResult::Stacked(crop1, crop2) => {
                 // Both crops should have width equal to half frame width
                 let expected_width = frame_width * 0.5;
@@ -1128,7 +1908,14 @@ mod tests {
         let head1 = Hbb::from_xyxy(1063.6982, 335.45892, 1262.3218, 646.60675);
         let head2 = Hbb::from_xyxy(1846.0652, 228.14204, 1919.9954, 533.70746);
 
-        let crop = calculate_two_heads_crop(true, frame_width, frame_height, &head1, &head2);
+        let crop = calculate_two_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &head1,
+            &head2,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Stacked(crop1, crop2) => {
@@ -1191,7 +1978,12 @@ mod tests {
         let head3 = Hbb::from_cxcywh(frame_width / 2.0 + 100.0, frame_height / 2.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1234,7 +2026,12 @@ mod tests {
         let head3 = Hbb::from_cxcywh(1800.0, frame_height / 2.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1273,7 +2070,12 @@ mod tests {
             &head_right_bottom2,
         ];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1305,7 +2107,12 @@ mod tests {
         let head3 = Hbb::from_cxcywh(1600.0, frame_height / 2.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1350,7 +2157,12 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width - 100.0, frame_height - 100.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3, &head4, &head5, &head6];
 
-        let crop = calculate_crop_from_largest_head(frame_width, frame_height, &heads);
+        let crop = calculate_crop_from_largest_head(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1412,7 +2224,12 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width - 250.0, frame_height / 2.0, 200.0, 200.0);
         let heads = vec![&head1, &head2, &head3, &head4, &head5, &head6];
 
-        let crop = calculate_crop_from_largest_head(frame_width, frame_height, &heads);
+        let crop = calculate_crop_from_largest_head(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1446,14 +2263,30 @@ mod tests {
 
         // Test no heads
         let heads: Vec<&Hbb> = vec![];
-        let crop = calculate_crop(true, false, frame_width, frame_height, &heads).unwrap();
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
 
         // Test single head
         let head = Hbb::from_cxcywh(frame_width / 2.0, frame_height / 2.0, 100.0, 100.0)
             .with_confidence(0.9);
         let hbbs = vec![&head];
-        let crop = calculate_crop(true, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
 
         // Test two heads
@@ -1462,7 +2295,15 @@ mod tests {
         let head2 = Hbb::from_cxcywh(3.0 * frame_width / 4.0, frame_height / 2.0, 100.0, 100.0)
             .with_confidence(0.9);
         let hbbs = vec![&head1, &head2];
-        let crop = calculate_crop(true, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Stacked(_, _)));
 
         // Test three heads
@@ -1478,7 +2319,15 @@ mod tests {
         )
         .with_confidence(0.9);
         let hbbs = vec![&head1, &head2, &head3];
-        let crop = calculate_crop(true, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Stacked(_, _)));
 
         // Test more than five heads
@@ -1505,7 +2354,15 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width - 100.0, frame_height - 100.0, 100.0, 100.0)
             .with_confidence(0.9);
         let hbbs = vec![&head1, &head2, &head3, &head4, &head5, &head6];
-        let crop = calculate_crop(true, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
     }
 
@@ -1516,14 +2373,30 @@ mod tests {
 
         // Test no heads with graphic mode
         let heads: Vec<&Hbb> = vec![];
-        let crop = calculate_crop(true, true, frame_width, frame_height, &heads).unwrap();
+        let crop = calculate_crop(
+            true,
+            true,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Resize(_)));
 
         // Test single head with graphic mode (should still be Single, not Resize)
         let head = Hbb::from_cxcywh(frame_width / 2.0, frame_height / 2.0, 100.0, 100.0)
             .with_confidence(0.9);
         let hbbs = vec![&head];
-        let crop = calculate_crop(true, true, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            true,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
 
         // Test more than five heads with graphic mode
@@ -1550,7 +2423,15 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width - 100.0, frame_height - 100.0, 100.0, 100.0)
             .with_confidence(0.9);
         let hbbs = vec![&head1, &head2, &head3, &head4, &head5, &head6];
-        let crop = calculate_crop(true, true, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            true,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
     }
 
@@ -1611,7 +2492,14 @@ mod tests {
         // Test far heads with use_stack_crop = false
         let head1 = Hbb::from_cxcywh(frame_width / 4.0, frame_height / 2.0, 100.0, 100.0);
         let head2 = Hbb::from_cxcywh(3.0 * frame_width / 4.0, frame_height / 2.0, 100.0, 100.0);
-        let crop = calculate_two_heads_crop(false, frame_width, frame_height, &head1, &head2);
+        let crop = calculate_two_heads_crop(
+            false,
+            frame_width,
+            frame_height,
+            &head1,
+            &head2,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1644,7 +2532,12 @@ mod tests {
         let head3 = Hbb::from_cxcywh(1800.0, frame_height / 2.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -1674,7 +2567,13 @@ mod tests {
         let head2 = Hbb::from_xyxy(531.13, 213.28334, 704.7175, 470.2871);
         let head3 = Hbb::from_xyxy(943.43054, 278.49518, 1161.655, 579.9011);
         let heads = vec![&head1, &head2, &head3];
-        let crop = calculate_three_heads_crop(true, frame_width, frame_height, &heads);
+        let crop = calculate_three_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
         match crop {
             CropResult::Stacked(crop1, crop2) => {
                 // First crop should be optimized for two heads (80% height, 9:6 aspect ratio)
@@ -1735,7 +2634,13 @@ mod tests {
         let head2 = Hbb::from_xyxy(864.88776, 344.61285, 1026.0613, 568.9608);
         let head3 = Hbb::from_xyxy(1477.2578, 277.67084, 1673.3591, 527.8382);
         let heads = vec![&head1, &head2, &head3];
-        let crop = calculate_three_heads_crop(true, frame_width, frame_height, &heads);
+        let crop = calculate_three_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
         match crop {
             CropResult::Stacked(crop1, crop2) => {
                 // First crop should be optimized for two heads (80% height, 9:6 aspect ratio)
@@ -1799,7 +2704,13 @@ mod tests {
         let head3 = Hbb::from_cxcywh(1520.0, frame_height / 2.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3];
 
-        let crop = calculate_three_heads_crop(true, frame_width, frame_height, &heads);
+        let crop = calculate_three_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Stacked(crop1, crop2) => {
@@ -1863,7 +2774,13 @@ mod tests {
         let head3 = Hbb::from_cxcywh(1520.0, frame_height / 2.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3];
 
-        let crop = calculate_three_heads_crop(true, frame_width, frame_height, &heads);
+        let crop = calculate_three_heads_crop(
+            true,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         // Should fall back to the general stacked logic when special case doesn't apply
         match crop {
@@ -1907,7 +2824,15 @@ mod tests {
         let head2 = Hbb::from_cxcywh(3.0 * frame_width / 4.0, frame_height / 2.0, 100.0, 100.0)
             .with_confidence(0.9);
         let hbbs = vec![&head1, &head2];
-        let crop = calculate_crop(false, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            false,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
 
         // Test three heads with use_stack_crop = false
@@ -1923,7 +2848,15 @@ mod tests {
         )
         .with_confidence(0.9);
         let hbbs = vec![&head1, &head2, &head3];
-        let crop = calculate_crop(false, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            false,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
     }
 
@@ -1967,17 +2900,315 @@ mod tests {
         // Test identical resize crops
         let crop1 = CropResult::Resize(CropArea::new(0.0, 0.0, frame_width, 1080.0));
         let crop2 = CropResult::Resize(CropArea::new(0.0, 0.0, frame_width, 1080.0));
-        assert!(is_crop_similar(&crop1, &crop2, frame_width, threshold));
+        assert!(is_crop_similar(
+            &crop1,
+            &crop2,
+            frame_width,
+            threshold,
+            Delta
+        ));
 
         // Test different resize crops (should be similar since they're both full frame)
         let crop1 = CropResult::Resize(CropArea::new(0.0, 0.0, frame_width, 1080.0));
         let crop2 = CropResult::Resize(CropArea::new(0.0, 0.0, frame_width + 10.0, 1080.0));
-        assert!(is_crop_similar(&crop1, &crop2, frame_width, threshold));
+        assert!(is_crop_similar(
+            &crop1,
+            &crop2,
+            frame_width,
+            threshold,
+            Delta
+        ));
 
         // Test resize vs single (should be false)
         let crop1 = CropResult::Resize(CropArea::new(0.0, 0.0, frame_width, 1080.0));
         let crop2 = CropResult::Single(CropArea::new(100.0, 100.0, 200.0, 200.0));
-        assert!(!is_crop_similar(&crop1, &crop2, frame_width, threshold));
+        assert!(!is_crop_similar(
+            &crop1,
+            &crop2,
+            frame_width,
+            threshold,
+            Delta
+        ));
+    }
+
+    #[test]
+    fn test_intersection_over_union() {
+        let a = CropArea::new(0.0, 0.0, 100.0, 100.0);
+        assert_eq!(a.intersection_over_union(&a), 1.0);
+        // Half overlapping: 50 * 100 shared of 150 * 100 covered
+        let b = CropArea::new(50.0, 0.0, 100.0, 100.0);
+        assert!((a.intersection_over_union(&b) - 1.0 / 3.0).abs() < 1e-6);
+        let far = CropArea::new(500.0, 0.0, 100.0, 100.0);
+        assert_eq!(a.intersection_over_union(&far), 0.0);
+    }
+
+    #[test]
+    fn test_similarity_metrics_on_differently_sized_boxes() {
+        let frame_width = 1920.0;
+        let threshold = 10.0;
+
+        // Same corner, but the second box is 10% of the frame wider and taller:
+        // every coordinate delta is within 10%, yet the boxes barely match.
+        // Both metrics reject it; delta through the scale check.
+        let small = CropResult::Single(CropArea::new(100.0, 100.0, 200.0, 200.0));
+        let large = CropResult::Single(CropArea::new(100.0, 100.0, 392.0, 392.0));
+        assert!(!is_crop_similar(
+            &small,
+            &large,
+            frame_width,
+            threshold,
+            Delta
+        ));
+        assert!(!is_crop_similar(
+            &small,
+            &large,
+            frame_width,
+            threshold,
+            Iou
+        ));
+
+        // A small shift of a large crop keeps a high overlap under both
+        let crop = CropResult::Single(CropArea::new(400.0, 0.0, 600.0, 1080.0));
+        let shifted = CropResult::Single(CropArea::new(430.0, 0.0, 600.0, 1080.0));
+        assert!(is_crop_similar(
+            &crop,
+            &shifted,
+            frame_width,
+            threshold,
+            Delta
+        ));
+        assert!(is_crop_similar(
+            &crop,
+            &shifted,
+            frame_width,
+            threshold,
+            Iou
+        ));
+    }
+
+    #[test]
+    fn test_same_position_different_scale_is_not_similar() {
+        let frame_width = 1920.0;
+        let threshold = 10.0;
+
+        // Zooming in around the same center: width 608 -> 520 moves x by 44px
+        // and width by 88px, both under 10% of the frame, but the crop shrinks
+        // by ~14%
+        let wide = CropArea::new(656.0, 0.0, 608.0, 1080.0);
+        let zoomed = CropArea::new(700.0, 0.0, 520.0, 1080.0);
+        assert!(wide.is_within_percentage(&zoomed, frame_width, threshold));
+        assert!(!wide.is_same_scale(&zoomed, threshold));
+        assert!(!is_crop_similar(
+            &CropResult::Single(wide.clone()),
+            &CropResult::Single(zoomed.clone()),
+            frame_width,
+            threshold,
+            Delta
+        ));
+
+        // A height-only change breaks similarity too
+        let short = CropArea::new(656.0, 60.0, 608.0, 960.0);
+        assert!(!wide.is_same_scale(&short, threshold));
+
+        // A small zoom within the threshold stays similar
+        let slightly_zoomed = CropArea::new(671.0, 0.0, 578.0, 1080.0);
+        assert!(wide.is_same_scale(&slightly_zoomed, threshold));
+        assert!(is_crop_similar(
+            &CropResult::Single(wide.clone()),
+            &CropResult::Single(slightly_zoomed),
+            frame_width,
+            threshold,
+            Delta
+        ));
+
+        // Either half of a stacked crop changing scale breaks similarity
+        let top = CropArea::new(0.0, 0.0, 1080.0, 960.0);
+        let stacked = CropResult::Stacked(top.clone(), wide.clone());
+        let stacked_zoomed = CropResult::Stacked(top, zoomed);
+        assert!(!is_crop_similar(
+            &stacked,
+            &stacked_zoomed,
+            frame_width,
+            threshold,
+            Delta
+        ));
+
+        // Degenerate zero-sized boxes compare equal in scale
+        let empty = CropArea::new(0.0, 0.0, 0.0, 0.0);
+        assert!(empty.is_same_scale(&empty, threshold));
+    }
+
+    #[test]
+    fn test_low_confidence_outlier_moves_center_less() {
+        let center_of = |heads: &[&Hbb]| match calculate_crop(
+            false,
+            false,
+            1920.0,
+            1080.0,
+            heads,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap()
+        {
+            CropResult::Single(area) => area.x + area.width / 2.0,
+            other => panic!("expected a single crop, got {:?}", other),
+        };
+
+        let subject = Hbb::from_xywh(850.0, 400.0, 100.0, 100.0).with_confidence(0.9);
+        let weak = Hbb::from_xywh(1050.0, 400.0, 100.0, 100.0).with_confidence(0.3);
+        let strong = Hbb::from_xywh(1050.0, 400.0, 100.0, 100.0).with_confidence(0.9);
+
+        let alone = center_of(&[&subject]);
+        let with_weak = center_of(&[&subject, &weak]);
+        let with_strong = center_of(&[&subject, &strong]);
+
+        // Equal confidences frame the bounding box as before
+        assert!((with_strong - 1000.0).abs() < 1e-3);
+        // The weak detection still pulls, but by a third as much
+        assert!((with_weak - alone) < (with_strong - alone));
+        assert!((with_weak - 933.333).abs() < 1e-2, "{}", with_weak);
+
+        // Both boxes stay inside the crop when they fit in it
+        let CropResult::Single(area) = calculate_crop(
+            false,
+            false,
+            1920.0,
+            1080.0,
+            &[&subject, &weak],
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap() else {
+            panic!("expected a single crop");
+        };
+        assert!(area.x <= subject.xmin() && weak.xmax() <= area.x + area.width);
+    }
+
+    #[test]
+    fn test_heads_in_roi_excludes_outside_detections() {
+        let roi: Roi = "600,200,800,600".parse().unwrap();
+        let on_stage = Hbb::from_xywh(900.0, 300.0, 100.0, 100.0);
+        let audience_left = Hbb::from_xywh(100.0, 700.0, 100.0, 100.0);
+        let audience_right = Hbb::from_xywh(1600.0, 300.0, 100.0, 100.0);
+        // Overlaps the ROI edge, but its center is outside
+        let straddling = Hbb::from_xywh(1350.0, 300.0, 200.0, 100.0);
+        let heads = vec![&audience_left, &on_stage, &audience_right, &straddling];
+
+        let kept = heads_in_roi(heads.clone(), Some(roi));
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].cx(), on_stage.cx());
+        assert_eq!(heads_in_roi(heads, None).len(), 4);
+
+        // With only the stage subject left, the crop follows it
+        let CropResult::Single(area) =
+            calculate_crop(false, false, 1920.0, 1080.0, &kept, AspectRatio::PORTRAIT).unwrap()
+        else {
+            panic!("one subject gives a single crop");
+        };
+        assert!(area.x <= on_stage.cx() && on_stage.cx() <= area.x + area.width);
+    }
+
+    #[test]
+    fn test_roi_crop_centers_on_roi() {
+        let roi: Roi = "1200,0,400,1080".parse().unwrap();
+        let CropResult::Single(area) =
+            calculate_roi_crop(roi, 1920.0, 1080.0, AspectRatio::PORTRAIT)
+        else {
+            panic!("the ROI fallback is a single crop");
+        };
+        assert!((area.x + area.width / 2.0 - roi.center_x()).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_weight_toward_roi() {
+        let roi: Roi = "1200,0,400,1080".parse().unwrap();
+        let center_x = |crop| match crop {
+            CropResult::Single(area) => area.x + area.width / 2.0,
+            other => panic!("expected a single crop, got {:?}", other),
+        };
+
+        // A lone subject at x=1100 lets the crop move halfway to the ROI at 1400
+        let head = Hbb::from_xywh(1050.0, 300.0, 100.0, 100.0);
+        let crop = calculate_single_head_crop(1920.0, 1080.0, &head, AspectRatio::PORTRAIT);
+        let weighted = weight_toward_roi(crop, &[&head], roi, 1920.0);
+        assert!((center_x(weighted) - 1250.0).abs() < 1.0);
+
+        // Further away, the pull stops once the subject reaches the crop's left edge
+        let head = Hbb::from_xywh(200.0, 300.0, 100.0, 100.0);
+        let crop = calculate_single_head_crop(1920.0, 1080.0, &head, AspectRatio::PORTRAIT);
+        let CropResult::Single(area) = weight_toward_roi(crop, &[&head], roi, 1920.0) else {
+            panic!("weighting keeps a single crop");
+        };
+        assert!((area.x - 200.0).abs() < 1.0);
+
+        // Resized frames have nothing to pull
+        let resize = CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0));
+        assert_eq!(weight_toward_roi(resize.clone(), &[], roi, 1920.0), resize);
+    }
+
+    #[test]
+    fn test_roi_from_str() {
+        assert_eq!(
+            "10, 20, 300, 400".parse(),
+            Ok(Roi {
+                x: 10.0,
+                y: 20.0,
+                width: 300.0,
+                height: 400.0
+            })
+        );
+        assert!("10,20,300".parse::<Roi>().is_err());
+        assert!("10,20,0,400".parse::<Roi>().is_err());
+        assert!("-5,20,300,400".parse::<Roi>().is_err());
+        assert!("a,b,c,d".parse::<Roi>().is_err());
+    }
+
+    #[test]
+    fn test_focus_heads() {
+        let small_left = Hbb::from_xywh(100.0, 300.0, 80.0, 80.0);
+        let large_right = Hbb::from_xywh(1500.0, 200.0, 300.0, 300.0);
+        let medium_center = Hbb::from_xywh(900.0, 250.0, 150.0, 150.0);
+        let heads = [&small_left, &large_right, &medium_center];
+
+        let focused_cx = |focus| {
+            let focused = focus_heads(&heads, focus, 1920.0);
+            assert_eq!(focused.len(), 1);
+            focused[0].cx()
+        };
+
+        assert_eq!(focus_heads(&heads, Focus::All, 1920.0).len(), 3);
+        assert_eq!(focused_cx(Focus::Largest), large_right.cx());
+        assert_eq!(focused_cx(Focus::Leftmost), small_left.cx());
+        assert_eq!(focused_cx(Focus::Center), medium_center.cx());
+        assert!(focus_heads(&[], Focus::Largest, 1920.0).is_empty());
+
+        // The focused head drives a single crop centered on it
+        let crop = calculate_crop(
+            true,
+            false,
+            1920.0,
+            1080.0,
+            &focus_heads(&heads, Focus::Largest, 1920.0),
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
+        let CropResult::Single(area) = crop else {
+            panic!("a single focused head gives a single crop");
+        };
+        assert!(area.x <= large_right.cx() && large_right.cx() <= area.x + area.width);
+    }
+
+    #[test]
+    fn test_focus_from_str() {
+        assert_eq!("largest".parse(), Ok(Focus::Largest));
+        assert_eq!("Center".parse(), Ok(Focus::Center));
+        assert!("biggest".parse::<Focus>().is_err());
+    }
+
+    #[test]
+    fn test_similarity_metric_from_str() {
+        assert_eq!("delta".parse(), Ok(SimilarityMetric::Delta));
+        assert_eq!("IoU".parse(), Ok(SimilarityMetric::Iou));
+        assert!("area".parse::<SimilarityMetric>().is_err());
     }
 
     #[test]
@@ -1994,7 +3225,13 @@ mod tests {
             CropArea::new(0.0, 60.0, 1080.0, 960.0),
             CropArea::new(840.0, 60.0, 1080.0, 960.0),
         );
-        assert!(is_crop_similar(&crop1, &crop2, frame_width, threshold));
+        assert!(is_crop_similar(
+            &crop1,
+            &crop2,
+            frame_width,
+            threshold,
+            Delta
+        ));
     }
 
     #[test]
@@ -2058,7 +3295,12 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width / 2.0 + 100.0, frame_height / 2.0, 100.0, 100.0);
         let heads = vec![&head1, &head2, &head3, &head4, &head5, &head6];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -2105,7 +3347,12 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width / 2.0, frame_height / 2.0, 300.0, 300.0);
         let heads = vec![&head1, &head2, &head3, &head4, &head5, &head6];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -2141,7 +3388,12 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width / 2.0, frame_height / 2.0, 300.0, 300.0);
         let heads = vec![&head1, &head2, &head3, &head4, &head5, &head6];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -2202,7 +3454,12 @@ mod tests {
             &head20, &head21,
         ];
 
-        let crop = calculate_four_or_more_heads_crop(frame_width, frame_height, &heads);
+        let crop = calculate_four_or_more_heads_crop(
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        );
 
         match crop {
             CropResult::Single(crop) => {
@@ -2260,7 +3517,15 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width - 100.0, frame_height - 100.0, 100.0, 100.0)
             .with_confidence(0.9);
         let hbbs = vec![&head1, &head2, &head3, &head4, &head5, &head6];
-        let crop = calculate_crop(true, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
 
         // Test six heads with one large head
@@ -2278,7 +3543,454 @@ mod tests {
         let head6 = Hbb::from_cxcywh(frame_width / 2.0, frame_height / 2.0, 300.0, 300.0)
             .with_confidence(0.9);
         let hbbs = vec![&head1, &head2, &head3, &head4, &head5, &head6];
-        let crop = calculate_crop(true, false, frame_width, frame_height, &hbbs).unwrap();
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &hbbs,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
         assert!(matches!(crop, CropResult::Single(_)));
     }
+
+    #[test]
+    fn test_aspect_ratio_parse() {
+        assert_eq!(
+            "9:16".parse::<AspectRatio>().unwrap(),
+            AspectRatio::PORTRAIT
+        );
+        assert_eq!(
+            "4:5".parse::<AspectRatio>().unwrap(),
+            AspectRatio::new(4.0, 5.0)
+        );
+        assert_eq!(
+            " 1 : 1 ".parse::<AspectRatio>().unwrap(),
+            AspectRatio::new(1.0, 1.0)
+        );
+        assert!("16x9".parse::<AspectRatio>().is_err());
+        assert!("0:1".parse::<AspectRatio>().is_err());
+        assert!("a:b".parse::<AspectRatio>().is_err());
+        assert_eq!(AspectRatio::PORTRAIT.to_string(), "9:16");
+    }
+
+    #[test]
+    fn test_crop_dimensions_per_aspect_ratio() {
+        let frame_width = 1920.0;
+        let frame_height = 1080.0;
+        let head1 = Hbb::from_xywh(100.0, 300.0, 100.0, 100.0);
+        let head2 = Hbb::from_xywh(1700.0, 300.0, 100.0, 100.0);
+
+        // (aspect ratio, expected single crop width, expected stacked crop height)
+        let cases = [
+            (AspectRatio::PORTRAIT, 810.0, 960.0 / 1.125),
+            (AspectRatio::new(4.0, 5.0), 864.0, 600.0),
+            (AspectRatio::new(1.0, 1.0), 1080.0, 480.0),
+        ];
+
+        for (aspect_ratio, single_width, stacked_height) in cases {
+            match calculate_no_heads_crop(frame_width, frame_height, false, aspect_ratio) {
+                CropResult::Single(crop) => {
+                    assert!((crop.width - single_width).abs() < 1.0, "{aspect_ratio}");
+                    assert!((crop.height - frame_height).abs() < 1.0, "{aspect_ratio}");
+                }
+                _ => panic!("Expected single crop for {aspect_ratio}"),
+            }
+
+            match calculate_two_heads_crop(
+                true,
+                frame_width,
+                frame_height,
+                &head1,
+                &head2,
+                aspect_ratio,
+            ) {
+                CropResult::Stacked(crop1, crop2) => {
+                    for crop in [crop1, crop2] {
+                        assert!(
+                            (crop.width - frame_width / 2.0).abs() < 1.0,
+                            "{aspect_ratio}"
+                        );
+                        assert!((crop.height - stacked_height).abs() < 1.0, "{aspect_ratio}");
+                        assert!(crop.y >= 0.0 && crop.y + crop.height <= frame_height);
+                    }
+                }
+                _ => panic!("Expected stacked crop for {aspect_ratio}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_crop_stacked_layout_per_aspect_ratio() {
+        let frame_width = 1920.0;
+        let frame_height = 1080.0;
+        let head1 = Hbb::from_xywh(100.0, 300.0, 100.0, 100.0);
+        let head2 = Hbb::from_xywh(1700.0, 300.0, 100.0, 100.0);
+        let heads = [&head1, &head2];
+
+        // Portrait: two half-width 9:8 crops for a top/bottom stack
+        assert!(!AspectRatio::PORTRAIT.stacks_side_by_side());
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
+        let CropResult::Stacked(top, bottom) = crop else {
+            panic!("Expected stacked crop, got {crop:?}");
+        };
+        assert_eq!((top.x, top.width), (0.0, 960.0));
+        assert_eq!((bottom.x, bottom.width), (960.0, 960.0));
+        assert!((top.height - 960.0 / 1.125).abs() < 1.0);
+
+        // Square: two full-height 1:2 crops side by side, each centered on its
+        // head and clamped inside the frame
+        let square = AspectRatio::new(1.0, 1.0);
+        assert!(square.stacks_side_by_side());
+        let crop = calculate_crop(true, false, frame_width, frame_height, &heads, square).unwrap();
+        assert_eq!(
+            crop,
+            CropResult::Stacked(
+                CropArea::new(0.0, 0.0, 540.0, 1080.0),
+                CropArea::new(1380.0, 0.0, 540.0, 1080.0),
+            )
+        );
+
+        // Heads away from the frame edges get crops centered on them
+        let head1 = Hbb::from_xywh(300.0, 300.0, 100.0, 100.0);
+        let head2 = Hbb::from_xywh(1450.0, 300.0, 100.0, 100.0);
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &[&head1, &head2],
+            square,
+        )
+        .unwrap();
+        assert_eq!(
+            crop,
+            CropResult::Stacked(
+                CropArea::new(80.0, 0.0, 540.0, 1080.0),
+                CropArea::new(1230.0, 0.0, 540.0, 1080.0),
+            )
+        );
+    }
+
+    #[test]
+    fn test_stack_ratio_sizes_stacked_crops() {
+        let frame_width = 1920.0;
+        let frame_height = 1080.0;
+        let head1 = Hbb::from_xywh(100.0, 300.0, 100.0, 100.0);
+        let head2 = Hbb::from_xywh(1700.0, 300.0, 100.0, 100.0);
+
+        // A 0.6 split gives the top crop 60% of a 9:16 canvas (9:9.6) and
+        // the bottom crop 40% (9:6.4), both half the frame wide
+        let aspect_ratio = AspectRatio::PORTRAIT.with_stack_ratio(0.6);
+        let crop = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &[&head1, &head2],
+            aspect_ratio,
+        )
+        .unwrap();
+        let CropResult::Stacked(top, bottom) = crop else {
+            panic!("Expected stacked crop, got {crop:?}");
+        };
+        assert_eq!((top.width, bottom.width), (960.0, 960.0));
+        assert!((top.height - 1024.0).abs() < 1.0, "{top:?}");
+        assert!(
+            (bottom.height - 960.0 * 6.4 / 9.0).abs() < 1.0,
+            "{bottom:?}"
+        );
+        assert!((top.height / bottom.height - 1.5).abs() < 0.01);
+        for crop in [&top, &bottom] {
+            assert!(crop.y >= 0.0 && crop.y + crop.height <= frame_height);
+        }
+
+        // The default split keeps equal 9:8 halves
+        assert_eq!(AspectRatio::PORTRAIT.stack_top_ratio(), 1.125);
+        assert_eq!(AspectRatio::PORTRAIT.stack_bottom_ratio(), 1.125);
+
+        assert!(check_stack_ratio(0.6).is_ok());
+        assert!(check_stack_ratio(0.0).is_err());
+        assert!(check_stack_ratio(1.0).is_err());
+        assert!(check_stack_ratio(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_calculate_crop_with_primary_follows_primary_head() {
+        let frame_width = 1920.0;
+        let frame_height = 1080.0;
+        let large = Hbb::from_xywh(100.0, 400.0, 220.0, 220.0);
+        let small = Hbb::from_xywh(1500.0, 400.0, 200.0, 200.0);
+        let heads = [&large, &small];
+
+        // Without stacking, two far-apart heads follow the largest by default...
+        let default_crop = calculate_crop(
+            false,
+            false,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
+        assert_eq!(
+            default_crop,
+            calculate_single_head_crop(frame_width, frame_height, &large, AspectRatio::PORTRAIT)
+        );
+
+        // ...but follow the primary when one is given
+        let primary_crop = calculate_crop_with_primary(
+            false,
+            false,
+            frame_width,
+            frame_height,
+            &heads,
+            Some(&small),
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
+        assert_eq!(
+            primary_crop,
+            calculate_single_head_crop(frame_width, frame_height, &small, AspectRatio::PORTRAIT)
+        );
+
+        // Heads that fit one crop together are still framed together
+        let near = Hbb::from_xywh(400.0, 400.0, 200.0, 200.0);
+        let together = [&large, &near];
+        assert_eq!(
+            calculate_crop_with_primary(
+                false,
+                false,
+                frame_width,
+                frame_height,
+                &together,
+                Some(&near),
+                AspectRatio::PORTRAIT,
+            )
+            .unwrap(),
+            calculate_crop(
+                false,
+                false,
+                frame_width,
+                frame_height,
+                &together,
+                AspectRatio::PORTRAIT,
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_blur_fill_instead_of_stack() {
+        let frame_width = 1920.0;
+        let frame_height = 1080.0;
+        let left = Hbb::from_xywh(300.0, 400.0, 200.0, 200.0);
+        let right = Hbb::from_xywh(1300.0, 400.0, 200.0, 200.0);
+        let heads = [&left, &right];
+
+        let stacked = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
+        assert!(matches!(stacked, CropResult::Stacked(_, _)));
+
+        // The stack becomes one crop spanning both heads plus a head of padding
+        match blur_fill_instead_of_stack(stacked, frame_width, frame_height, &heads) {
+            CropResult::BlurFill(crop) => {
+                assert_eq!(crop, CropArea::new(100.0, 0.0, 1600.0, frame_height));
+            }
+            other => panic!("Expected blur fill crop, got {other:?}"),
+        }
+
+        // Padding never pushes the crop outside the frame
+        let edge = Hbb::from_xywh(0.0, 400.0, 200.0, 200.0);
+        let far = Hbb::from_xywh(1720.0, 400.0, 200.0, 200.0);
+        let stacked = CropResult::Stacked(
+            CropArea::new(0.0, 0.0, 960.0, 540.0),
+            CropArea::new(960.0, 0.0, 960.0, 540.0),
+        );
+        assert_eq!(
+            blur_fill_instead_of_stack(stacked, frame_width, frame_height, &[&edge, &far]),
+            CropResult::BlurFill(CropArea::new(0.0, 0.0, frame_width, frame_height))
+        );
+
+        // Crops that aren't stacked are left alone
+        let single =
+            calculate_single_head_crop(frame_width, frame_height, &left, AspectRatio::PORTRAIT);
+        assert_eq!(
+            blur_fill_instead_of_stack(single.clone(), frame_width, frame_height, &heads),
+            single
+        );
+    }
+
+    #[test]
+    fn test_calculate_no_subject_crop() {
+        let frame_width = 1920.0;
+        let frame_height = 1080.0;
+        let center =
+            calculate_no_heads_crop(frame_width, frame_height, false, AspectRatio::PORTRAIT);
+        let previous = CropResult::Single(CropArea::new(200.0, 0.0, 810.0, 1080.0));
+
+        let crop_for = |mode, previous_crop| {
+            calculate_no_subject_crop(
+                mode,
+                frame_width,
+                frame_height,
+                previous_crop,
+                AspectRatio::PORTRAIT,
+            )
+        };
+
+        assert_eq!(crop_for(NoSubjectMode::Center, Some(&previous)), center);
+        assert_eq!(
+            crop_for(NoSubjectMode::Letterbox, Some(&previous)),
+            CropResult::Resize(CropArea::new(0.0, 0.0, frame_width, frame_height))
+        );
+        assert_eq!(crop_for(NoSubjectMode::HoldLast, Some(&previous)), previous);
+        // Nothing to hold yet falls back to the center crop
+        assert_eq!(crop_for(NoSubjectMode::HoldLast, None), center);
+    }
+
+    #[test]
+    fn test_no_subject_mode_from_str() {
+        assert_eq!("center".parse(), Ok(NoSubjectMode::Center));
+        assert_eq!("Letterbox".parse(), Ok(NoSubjectMode::Letterbox));
+        assert_eq!("hold-last".parse(), Ok(NoSubjectMode::HoldLast));
+        assert!("blank".parse::<NoSubjectMode>().is_err());
+    }
+
+    #[test]
+    fn test_pad_crop() {
+        let frame_width = 1920.0;
+        let frame_height = 1080.0;
+        let left = Hbb::from_xywh(300.0, 100.0, 200.0, 200.0);
+        let right = Hbb::from_xywh(1300.0, 100.0, 200.0, 200.0);
+        let heads = [&left, &right];
+
+        let stacked = calculate_crop(
+            true,
+            false,
+            frame_width,
+            frame_height,
+            &heads,
+            AspectRatio::PORTRAIT,
+        )
+        .unwrap();
+        let CropResult::Stacked(top, bottom) = &stacked else {
+            panic!("Expected stacked crop");
+        };
+
+        for padding in [10.0, 50.0, 400.0] {
+            let CropResult::Stacked(padded_top, padded_bottom) =
+                pad_crop(stacked.clone(), &heads, padding, frame_width, frame_height)
+            else {
+                panic!("Padding should keep the crop type");
+            };
+            for (original, padded) in [(top, &padded_top), (bottom, &padded_bottom)] {
+                assert!(padded.width > original.width, "padding {padding}");
+                // Aspect ratio is preserved...
+                assert!(
+                    (padded.width / padded.height - original.width / original.height).abs() < 1e-3
+                );
+                // ...and the crop stays inside the frame
+                assert!(padded.x >= 0.0 && padded.x + padded.width <= frame_width + 1e-3);
+                assert!(padded.y >= 0.0 && padded.y + padded.height <= frame_height + 1e-3);
+            }
+        }
+
+        // 10% of a 200px head is at least 20px of margin on each side
+        let CropResult::Stacked(padded_top, _) =
+            pad_crop(stacked.clone(), &heads, 10.0, frame_width, frame_height)
+        else {
+            panic!("Padding should keep the crop type");
+        };
+        assert!(padded_top.height - top.height >= 40.0 - 1e-3);
+
+        // A full-height single crop has no room to grow, and 0% is a no-op
+        let single =
+            calculate_single_head_crop(frame_width, frame_height, &left, AspectRatio::PORTRAIT);
+        assert_eq!(
+            pad_crop(single.clone(), &heads, 20.0, frame_width, frame_height),
+            single
+        );
+        assert_eq!(
+            pad_crop(stacked.clone(), &heads, 0.0, frame_width, frame_height),
+            stacked
+        );
+    }
+
+    #[test]
+    fn test_deadzone_ignores_micro_movements() {
+        let frame_width = 1920.0;
+        let previous = CropResult::Single(CropArea::new(500.0, 0.0, 810.0, 1080.0));
+
+        // A 2% deadzone is 38.4px of a 1920px frame: a 10px wobble holds the
+        // previous target, while a 60px move updates it
+        let jitter = CropResult::Single(CropArea::new(510.0, 0.0, 810.0, 1080.0));
+        assert_eq!(
+            apply_deadzone(jitter.clone(), Some(&previous), 2.0, frame_width),
+            previous
+        );
+        let moved = CropResult::Single(CropArea::new(560.0, 0.0, 810.0, 1080.0));
+        assert_eq!(
+            apply_deadzone(moved.clone(), Some(&previous), 2.0, frame_width),
+            moved
+        );
+
+        // Stacked crops hold only while both halves stay inside the deadzone
+        let stacked = CropResult::Stacked(
+            CropArea::new(0.0, 100.0, 960.0, 853.0),
+            CropArea::new(960.0, 100.0, 960.0, 853.0),
+        );
+        let wobble = CropResult::Stacked(
+            CropArea::new(20.0, 110.0, 960.0, 853.0),
+            CropArea::new(940.0, 100.0, 960.0, 853.0),
+        );
+        assert_eq!(
+            apply_deadzone(wobble, Some(&stacked), 2.0, frame_width),
+            stacked
+        );
+        let shifted = CropResult::Stacked(
+            CropArea::new(0.0, 100.0, 960.0, 853.0),
+            CropArea::new(900.0, 100.0, 960.0, 853.0),
+        );
+        assert_eq!(
+            apply_deadzone(shifted.clone(), Some(&stacked), 2.0, frame_width),
+            shifted
+        );
+
+        // A change of layout, no previous crop, or no deadzone always updates
+        assert_eq!(
+            apply_deadzone(jitter.clone(), Some(&stacked), 2.0, frame_width),
+            jitter
+        );
+        assert_eq!(
+            apply_deadzone(jitter.clone(), None, 2.0, frame_width),
+            jitter
+        );
+        assert_eq!(
+            apply_deadzone(jitter.clone(), Some(&previous), 0.0, frame_width),
+            jitter
+        );
+
+        assert!(check_deadzone(0.0).is_ok());
+        assert!(check_deadzone(2.5).is_ok());
+        assert!(check_deadzone(-1.0).is_err());
+        assert!(check_deadzone(100.0).is_err());
+        assert!(check_deadzone(f32::NAN).is_err());
+    }
 }
@@ -0,0 +1,273 @@
+use crate::crop::CropResult;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The crop decision for one output frame, as written to `crops.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FrameCrop {
+    pub frame: usize,
+    pub object_count: usize,
+    pub is_graphic: bool,
+    pub crop: CropResult,
+}
+
+/// Header row of the `--crop-log-csv` file
+pub const CSV_HEADER: &str = "frame_index,x,y,width,height,variant,object_count,is_graphic";
+
+impl FrameCrop {
+    /// Formats this frame as CSV rows, one per crop rectangle, so a stacked
+    /// crop yields two rows sharing a frame index (first crop first)
+    pub fn csv_rows(&self) -> Vec<String> {
+        let variant = match &self.crop {
+            CropResult::Single(_) => "single",
+            CropResult::Stacked(_, _) => "stacked",
+            CropResult::Resize(_) => "resize",
+            CropResult::BlurFill(_) => "blur_fill",
+        };
+        self.crop
+            .areas()
+            .into_iter()
+            .map(|area| {
+                format!(
+                    "{},{},{},{},{},{},{},{}",
+                    self.frame,
+                    area.x,
+                    area.y,
+                    area.width,
+                    area.height,
+                    variant,
+                    self.object_count,
+                    self.is_graphic
+                )
+            })
+            .collect()
+    }
+}
+
+/// Records per-frame crop decisions for `--export-crops` and `--crop-log-csv`.
+///
+/// Detections are recorded in decode order when each frame is analyzed, while
+/// crops are recorded in output order when each frame is rendered (which, for
+/// the history smoother, can be many frames later). Both orders match frame
+/// order, so the two streams are zipped by index when exported.
+#[derive(Default, Debug)]
+pub struct CropLog {
+    detections: Vec<(usize, bool)>,
+    crops: Vec<CropResult>,
+}
+
+impl CropLog {
+    /// Records the detection summary for the next decoded frame
+    pub fn record_detection(&mut self, object_count: usize, is_graphic: bool) {
+        self.detections.push((object_count, is_graphic));
+    }
+
+    /// Records the crop chosen for the next rendered frame
+    pub fn record_crop(&mut self, crop: &CropResult) {
+        self.crops.push(crop.clone());
+    }
+
+    /// Pairs each rendered frame's crop with its detection summary
+    pub fn frames(&self) -> Vec<FrameCrop> {
+        self.crops
+            .iter()
+            .zip(self.detections.iter())
+            .enumerate()
+            .map(|(frame, (crop, &(object_count, is_graphic)))| FrameCrop {
+                frame,
+                object_count,
+                is_graphic,
+                crop: crop.clone(),
+            })
+            .collect()
+    }
+
+    /// Serializes the recorded frames as a JSON array
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.frames()).context("Serializing crop decisions")
+    }
+
+    /// Writes the recorded frames to `path` as JSON
+    pub fn write_json(&self, path: &str) -> Result<()> {
+        std::fs::write(path, self.to_json()?)
+            .with_context(|| format!("Writing crop decisions to {}", path))
+    }
+
+    /// Formats the recorded frames as CSV with a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for row in self.frames().iter().flat_map(FrameCrop::csv_rows) {
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Writes the recorded frames to `path` as CSV
+    pub fn write_csv(&self, path: &str) -> Result<()> {
+        std::fs::write(path, self.to_csv()).with_context(|| format!("Writing crop log to {}", path))
+    }
+}
+
+/// Loads the per-frame crops from a `crops.json` file written by `--export-crops`
+/// (possibly hand-edited), ordered by frame index
+pub fn load_json(path: &str) -> Result<Vec<CropResult>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Reading crop decisions from {}", path))?;
+    parse_json(&contents).with_context(|| format!("Parsing crop decisions in {}", path))
+}
+
+fn parse_json(contents: &str) -> Result<Vec<CropResult>> {
+    let mut frames: Vec<FrameCrop> = serde_json::from_str(contents)?;
+    frames.sort_by_key(|f| f.frame);
+    for (index, frame) in frames.iter().enumerate() {
+        if frame.frame != index {
+            anyhow::bail!(
+                "expected an entry for frame {} but found frame {}",
+                index,
+                frame.frame
+            );
+        }
+    }
+    Ok(frames.into_iter().map(|f| f.crop).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crop::CropArea;
+
+    #[test]
+    fn test_crop_log_json_shape() {
+        let mut log = CropLog::default();
+        let crops = [
+            CropResult::Single(CropArea::new(555.0, 0.0, 810.0, 1080.0)),
+            CropResult::Stacked(
+                CropArea::new(0.0, 10.0, 960.0, 853.0),
+                CropArea::new(960.0, 20.0, 960.0, 853.0),
+            ),
+            CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0)),
+        ];
+        for (count, crop) in crops.iter().enumerate() {
+            log.record_detection(count, count == 2);
+            log.record_crop(crop);
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&log.to_json().unwrap()).unwrap();
+        let frames = json.as_array().unwrap();
+        assert_eq!(frames.len(), 3);
+
+        assert_eq!(frames[0]["frame"], 0);
+        assert_eq!(frames[0]["object_count"], 0);
+        assert_eq!(frames[0]["is_graphic"], false);
+        assert_eq!(frames[0]["crop"]["Single"]["x"], 555.0);
+        assert_eq!(frames[0]["crop"]["Single"]["width"], 810.0);
+
+        let stacked = frames[1]["crop"]["Stacked"].as_array().unwrap();
+        assert_eq!(stacked.len(), 2);
+        assert_eq!(stacked[0]["y"], 10.0);
+        assert_eq!(stacked[1]["x"], 960.0);
+
+        assert_eq!(frames[2]["is_graphic"], true);
+        assert_eq!(frames[2]["crop"]["Resize"]["height"], 1080.0);
+    }
+
+    #[test]
+    fn test_crop_log_json_round_trip() {
+        let mut log = CropLog::default();
+        let crops = vec![
+            CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0)),
+            CropResult::Stacked(
+                CropArea::new(0.0, 10.0, 960.0, 853.0),
+                CropArea::new(960.0, 20.0, 960.0, 853.0),
+            ),
+        ];
+        for crop in &crops {
+            log.record_detection(2, false);
+            log.record_crop(crop);
+        }
+
+        assert_eq!(parse_json(&log.to_json().unwrap()).unwrap(), crops);
+    }
+
+    #[test]
+    fn test_parse_json_rejects_missing_frames() {
+        let json = r#"[
+            {"frame": 0, "object_count": 1, "is_graphic": false,
+             "crop": {"Single": {"x": 0.0, "y": 0.0, "width": 10.0, "height": 10.0}}},
+            {"frame": 2, "object_count": 1, "is_graphic": false,
+             "crop": {"Single": {"x": 0.0, "y": 0.0, "width": 10.0, "height": 10.0}}}
+        ]"#;
+        assert!(parse_json(json).is_err());
+    }
+
+    #[test]
+    fn test_crop_log_pairs_by_frame_order() {
+        let mut log = CropLog::default();
+        // Detections run ahead of rendering while frames sit in history
+        log.record_detection(1, false);
+        log.record_detection(2, false);
+        log.record_crop(&CropResult::Single(CropArea::new(0.0, 0.0, 10.0, 10.0)));
+
+        let frames = log.frames();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].object_count, 1);
+    }
+
+    #[test]
+    fn test_csv_rows_per_variant() {
+        let frame = |crop| FrameCrop {
+            frame: 7,
+            object_count: 2,
+            is_graphic: false,
+            crop,
+        };
+
+        assert_eq!(
+            frame(CropResult::Single(CropArea::new(555.0, 0.0, 810.0, 1080.0))).csv_rows(),
+            ["7,555,0,810,1080,single,2,false"]
+        );
+        assert_eq!(
+            frame(CropResult::Stacked(
+                CropArea::new(0.0, 10.5, 960.0, 853.0),
+                CropArea::new(960.0, 20.0, 960.0, 853.0),
+            ))
+            .csv_rows(),
+            [
+                "7,0,10.5,960,853,stacked,2,false",
+                "7,960,20,960,853,stacked,2,false"
+            ]
+        );
+        assert_eq!(
+            frame(CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0))).csv_rows(),
+            ["7,0,0,1920,1080,resize,2,false"]
+        );
+        assert_eq!(
+            frame(CropResult::BlurFill(CropArea::new(
+                100.0, 0.0, 608.0, 1080.0
+            )))
+            .csv_rows(),
+            ["7,100,0,608,1080,blur_fill,2,false"]
+        );
+    }
+
+    #[test]
+    fn test_crop_log_csv() {
+        let mut log = CropLog::default();
+        log.record_detection(0, true);
+        log.record_crop(&CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0)));
+        log.record_detection(2, false);
+        log.record_crop(&CropResult::Stacked(
+            CropArea::new(0.0, 0.0, 960.0, 853.0),
+            CropArea::new(960.0, 0.0, 960.0, 853.0),
+        ));
+
+        assert_eq!(
+            log.to_csv(),
+            "frame_index,x,y,width,height,variant,object_count,is_graphic\n\
+             0,0,0,1920,1080,resize,0,true\n\
+             1,0,0,960,853,stacked,2,false\n\
+             1,960,0,960,853,stacked,2,false\n"
+        );
+    }
+}
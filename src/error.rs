@@ -0,0 +1,98 @@
+use std::process::ExitStatus;
+use thiserror::Error;
+
+/// Failure kinds of the processing pipeline, for callers that need to tell
+/// them apart. The CLI reports them through `anyhow` like any other error.
+#[derive(Debug, Error)]
+pub enum Land2PortError {
+    /// ffmpeg couldn't be started, usually because it isn't on `PATH`
+    #[error("{context}")]
+    FfmpegNotFound {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// ffmpeg was built without encoders the chosen options need
+    #[error(
+        "ffmpeg was built without the {} encoder(s) these options need; install a full \
+         ffmpeg build (e.g. from ffmpeg.org or your package manager) that includes them",
+        .0.join(", ")
+    )]
+    FfmpegEncodersMissing(Vec<String>),
+    /// ffmpeg was built without muxers the chosen options need
+    #[error(
+        "ffmpeg was built without the {} muxer(s) these options need; install a full \
+         ffmpeg build (e.g. from ffmpeg.org or your package manager) that includes them",
+        .0.join(", ")
+    )]
+    FfmpegMuxersMissing(Vec<String>),
+    /// An ffmpeg command ran but exited unsuccessfully
+    #[error("ffmpeg command failed with status: {0}")]
+    FfmpegFailed(ExitStatus),
+    /// The detection model couldn't be found or loaded
+    #[error("{0}")]
+    Model(String),
+    /// An option (or config file value) is invalid
+    #[error("{0}")]
+    InvalidOption(String),
+    /// The source produced no frames to process
+    #[error("no frames were processed from source {0}")]
+    EmptySource(String),
+    /// Reading or writing a file failed
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Any other failure
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Land2PortError>;
+
+impl Land2PortError {
+    /// Wraps an error from starting ffmpeg, with `context` naming what it was
+    /// asked to do
+    pub fn ffmpeg_not_found(context: &str) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |source| Self::FfmpegNotFound {
+            context: context.to_string(),
+            source,
+        }
+    }
+
+    /// Wraps a file error, with `context` naming the operation
+    pub fn io(context: String) -> impl FnOnce(std::io::Error) -> Self {
+        move |source| Self::Io { context, source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_keep_their_kind_through_anyhow() {
+        let missing = Land2PortError::FfmpegEncodersMissing(vec![
+            "libvpx-vp9".to_string(),
+            "libopus".to_string(),
+        ]);
+        assert!(
+            missing
+                .to_string()
+                .starts_with("ffmpeg was built without the libvpx-vp9, libopus encoder(s)")
+        );
+
+        // main.rs reports errors through anyhow; the kind is still recoverable
+        let reported = anyhow::Error::from(missing);
+        assert!(matches!(
+            reported.downcast_ref::<Land2PortError>(),
+            Some(Land2PortError::FfmpegEncodersMissing(_))
+        ));
+
+        // Other failures wrapped on the way in keep their message
+        let other = Land2PortError::from(anyhow::anyhow!("detection workers exited early"));
+        assert_eq!(other.to_string(), "detection workers exited early");
+    }
+}
@@ -13,13 +13,15 @@ pub struct FrameData {
 /// A structure to maintain a history of frame data
 pub struct CropHistory {
     frames: VecDeque<FrameData>,
+    capacity: usize,
 }
 
 impl CropHistory {
-    /// Create a new empty history
-    pub fn new() -> Self {
+    /// Create a new empty history that is full once it holds `capacity` frames
+    pub fn new(capacity: usize) -> Self {
         Self {
-            frames: VecDeque::new(),
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
         }
     }
 
@@ -51,6 +53,11 @@ impl CropHistory {
     pub fn is_empty(&self) -> bool {
         self.frames.is_empty()
     }
+
+    /// Check if the history holds `capacity` frames (or more)
+    pub fn is_full(&self) -> bool {
+        self.frames.len() >= self.capacity
+    }
 }
 
 #[cfg(test)]
@@ -65,7 +72,7 @@ mod tests {
 
     #[test]
     fn test_history_is_fifo() {
-        let mut history = CropHistory::new();
+        let mut history = CropHistory::new(3);
         assert!(history.is_empty());
 
         for i in 0..3 {
@@ -77,6 +84,7 @@ mod tests {
         }
 
         assert_eq!(history.len(), 3);
+        assert!(history.is_full());
         assert_eq!(history.peek_front().unwrap().object_count, 0);
         assert_eq!(history.pop_front().unwrap().object_count, 0);
         assert_eq!(history.pop_front().unwrap().object_count, 1);
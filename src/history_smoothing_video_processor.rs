@@ -6,6 +6,68 @@ use crate::video_processor::VideoProcessor;
 use crate::video_processor_utils;
 use crate::video_sink::VideoSink;
 use anyhow::Result;
+use std::collections::VecDeque;
+
+/// Output stage for `--loop-smoothing`. Holds back the last `length` frames
+/// so that, once the video ends, their crops can be bent toward the opening
+/// crop and the clip ends where it starts. Renders straight through when
+/// `length` is 0.
+struct LoopTail {
+    length: usize,
+    /// First crop rendered, which the tail bends back to
+    opening_crop: Option<crop::CropResult>,
+    frames: VecDeque<(usls::Image, crop::CropResult)>,
+}
+
+impl LoopTail {
+    fn new(length: usize) -> Self {
+        Self {
+            length,
+            opening_crop: None,
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Renders a frame, or queues it while it is among the last `length`
+    fn render(
+        &mut self,
+        img: &usls::Image,
+        crop_result: &crop::CropResult,
+        viewer: &mut VideoSink,
+        args: &Args,
+    ) -> Result<()> {
+        if self.length == 0 {
+            return video_processor_utils::process_and_display_crop(img, crop_result, viewer, args);
+        }
+
+        self.opening_crop.get_or_insert_with(|| crop_result.clone());
+        self.frames.push_back((img.clone(), crop_result.clone()));
+        if self.frames.len() > self.length
+            && let Some((img, crop_result)) = self.frames.pop_front()
+        {
+            video_processor_utils::process_and_display_crop(&img, &crop_result, viewer, args)?;
+        }
+        Ok(())
+    }
+
+    /// Renders the queued tail, moving each frame's crop a step further
+    /// toward the opening crop so the last frame lands on it
+    fn finish(&mut self, viewer: &mut VideoSink, args: &Args) -> Result<()> {
+        let Some(opening_crop) = &self.opening_crop else {
+            return Ok(());
+        };
+        let tail_length = self.frames.len();
+        for (index, (img, crop_result)) in self.frames.drain(..).enumerate() {
+            let bent = video_processor_utils::interpolate_crop_results(
+                &crop_result,
+                opening_crop,
+                tail_length,
+            );
+            video_processor_utils::process_and_display_crop(&img, &bent[index], viewer, args)?;
+        }
+        Ok(())
+    }
+}
 
 /// Video processor that handles cropping with history smoothing
 pub struct HistorySmoothingVideoProcessor {
@@ -14,21 +76,64 @@ pub struct HistorySmoothingVideoProcessor {
     last_image: Option<usls::Image>,
     history: history::CropHistory,
     cut_detector: CutDetector,
+    /// Frames a newly committed crop is held before another target may
+    /// replace it, from `--min-hold-duration` (cuts still switch immediately)
+    min_hold_frames: usize,
+    /// Frames left in the current hold
+    hold_remaining: usize,
+    /// Frames a crop change is interpolated over, from
+    /// `--transition-duration`; `None` spans the whole buffered history
+    transition_frames: Option<usize>,
+    /// Frames a crop change must span before crop selection considers it,
+    /// from `--min-interp-duration`; `None` is a quarter of the smoothing
+    /// window
+    min_interp_frames: Option<usize>,
+    /// Which crop changes glide and which snap, from `--transition-mode`
+    transition_mode: video_processor_utils::TransitionMode,
+    /// Whether a scene cut came since the crop last committed, so the next
+    /// change crosses it
+    crossed_cut: bool,
+    /// Where frames are rendered: directly, or through the `--loop-smoothing`
+    /// tail
+    output: LoopTail,
 }
 
 impl HistorySmoothingVideoProcessor {
-    /// Creates a new video processor
-    pub fn new(args: &Args) -> Self {
+    /// Creates a new video processor for a source at `frame_rate` fps, sizing
+    /// its history and minimum scene length from it
+    pub fn new(args: &Args, frame_rate: f64) -> Self {
         Self {
             previous_crop: None,
             previous_object_count: 0,
             last_image: None,
-            history: history::CropHistory::new(),
-            cut_detector: CutDetector::new(args.cut_similarity, args.cut_start),
+            history: history::CropHistory::new(video_processor_utils::history_size(
+                args, frame_rate,
+            )),
+            cut_detector: CutDetector::new(
+                args.cut_similarity,
+                args.cut_start,
+                video_processor_utils::min_scene_frames(args, frame_rate),
+                args.cut_mode,
+            ),
+            min_hold_frames: video_processor_utils::min_hold_frames(args, frame_rate),
+            hold_remaining: 0,
+            transition_frames: video_processor_utils::transition_frames(args, frame_rate),
+            min_interp_frames: video_processor_utils::min_interp_frames(args, frame_rate),
+            transition_mode: args.transition_mode,
+            crossed_cut: false,
+            output: LoopTail::new(if args.loop_smoothing {
+                video_processor_utils::history_size(args, frame_rate)
+            } else {
+                0
+            }),
         }
     }
 
-    /// Processes frames from history with interpolated crops
+    /// Processes frames from history with interpolated crops, moving to the
+    /// new crop over `transition_frames` (at most `interpolation_length`, the
+    /// frames buffered) and holding it for the rest; when `--transition-mode`
+    /// calls for a snap, it jumps straight to the new crop instead
+    ///
     /// Returns the crop that was used for processing
     fn process_history_with_interpolation(
         &mut self,
@@ -38,14 +143,16 @@ impl HistorySmoothingVideoProcessor {
         use_crop_selection: bool,
         smooth_duration_frames: usize,
         viewer: &mut VideoSink,
-        headless: bool,
+        args: &Args,
     ) -> Result<crop::CropResult> {
         // We know self.previous_crop is Some at this point since this method is only called
         // when we have a previous crop
         let prev_crop = self.previous_crop.as_ref().unwrap();
+        // Changes spanning too few frames to be trusted keep the current crop
+        let min_interp_frames = self.min_interp_frames.unwrap_or(smooth_duration_frames / 4);
 
         let crop_to_use = if use_crop_selection {
-            if interpolation_length < smooth_duration_frames/4 {
+            if interpolation_length < min_interp_frames {
                 prev_crop
             } else if crop::crop_types_different(prev_crop, change_crop) {
                 if !crop::crop_types_different(prev_crop, latest_crop) {
@@ -64,10 +171,19 @@ impl HistorySmoothingVideoProcessor {
             change_crop
         };
 
+        let transition_length = if !self.transition_mode.interpolates(self.crossed_cut) {
+            0
+        } else {
+            match self.transition_frames {
+                Some(frames) => frames.min(interpolation_length),
+                None => interpolation_length,
+            }
+        };
+        self.crossed_cut = false;
         let interpolated_crops = video_processor_utils::interpolate_crop_results(
             prev_crop,
             crop_to_use,
-            interpolation_length,
+            transition_length,
         );
 
         let mut frame_index = 0;
@@ -77,12 +193,8 @@ impl HistorySmoothingVideoProcessor {
             } else {
                 crop_to_use
             };
-            video_processor_utils::process_and_display_crop(
-                &frame.image,
-                crop_result,
-                viewer,
-                headless,
-            )?;
+            self.output
+                .render(&frame.image, crop_result, viewer, args)?;
             frame_index += 1;
         }
 
@@ -102,6 +214,8 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
         smooth_duration_frames: usize,
     ) -> Result<()> {
         let current_object_count = objects.len();
+        let holding = self.hold_remaining > 0;
+        self.hold_remaining = self.hold_remaining.saturating_sub(1);
         // Compare with previous crop if it exists
         let mut object_count = current_object_count;
         let crop_result: Option<crop::CropResult> = if let Some(prev_crop) = &self.previous_crop {
@@ -112,6 +226,7 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
                 prev_crop,
                 img.width() as f32,
                 args.smooth_percentage,
+                args.similarity_metric,
             );
             let is_cut = if let Some(ref last_image) = self.last_image {
                 self.cut_detector.is_cut(last_image, img)?
@@ -119,7 +234,14 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
                 true
             };
 
-            if is_cut {
+            // Unless cuts snap, a cut is a crop change like any other: the new
+            // crop is buffered and glides in, even through a hold
+            let glide_across_cut = is_cut && self.transition_mode.interpolates(true);
+            if glide_across_cut {
+                self.crossed_cut = true;
+            }
+
+            if is_cut && !glide_across_cut {
                 video_processor_utils::debug_println(format_args!("is_cut"));
                 if !self.history.is_empty() {
                     let change_crop = self.history.peek_front().unwrap().crop.clone();
@@ -130,25 +252,23 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
                         true, // use crop selection logic
                         smooth_duration_frames,
                         viewer,
-                        args.headless,
+                        args,
                     )?;
                 }
                 object_count = current_object_count;
                 Some(latest_crop.clone())
-            } else if is_same_class && is_latest_crop_similar {
+            } else if (is_same_class && is_latest_crop_similar) || (holding && !self.crossed_cut) {
                 video_processor_utils::debug_println(format_args!(
-                    "is_same_class && is_latest_crop_similar"
+                    "is_same_class && is_latest_crop_similar, or holding: {}",
+                    holding
                 ));
                 if !self.history.is_empty() {
                     while let Some(frame) = self.history.pop_front() {
-                        video_processor_utils::process_and_display_crop(
-                            &frame.image,
-                            prev_crop,
-                            viewer,
-                            args.headless,
-                        )?;
+                        self.output.render(&frame.image, prev_crop, viewer, args)?;
                     }
                 }
+                // The crop held, so the next change happens within this scene
+                self.crossed_cut = false;
                 object_count = self.previous_object_count;
                 Some(prev_crop.clone())
             } else {
@@ -176,6 +296,7 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
                         &change_crop,
                         img.width() as f32,
                         args.smooth_percentage,
+                        args.similarity_metric,
                     );
                     let is_change_object_count_similar =
                         crop::is_crop_class_same(current_object_count, change_object_count);
@@ -190,7 +311,7 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
                     ));
 
                     if is_change_crop_similar && is_change_object_count_similar {
-                        if self.history.len() == smooth_duration_frames {
+                        if self.history.is_full() {
                             let crop_to_use = self.process_history_with_interpolation(
                                 &change_crop,
                                 latest_crop,
@@ -198,7 +319,7 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
                                 false, // don't use crop selection logic, just use change_crop
                                 smooth_duration_frames,
                                 viewer,
-                                args.headless,
+                                args,
                             )?;
                             crop_result = Some(crop_to_use);
                         } else {
@@ -213,7 +334,7 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
                             true, // use crop selection logic
                             smooth_duration_frames,
                             viewer,
-                            args.headless,
+                            args,
                         )?;
                         crop_result = Some(crop_to_use);
                     }
@@ -227,14 +348,13 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
 
         self.last_image = Some(img.clone());
         if let Some(crop_result) = crop_result {
+            // A new target was committed: hold it before allowing another switch
+            if self.previous_crop.as_ref() != Some(&crop_result) {
+                self.hold_remaining = self.min_hold_frames;
+            }
             self.previous_crop = Some(crop_result.clone());
             self.previous_object_count = object_count;
-            video_processor_utils::process_and_display_crop(
-                img,
-                &crop_result,
-                viewer,
-                args.headless,
-            )?;
+            self.output.render(img, &crop_result, viewer, args)?;
         }
         Ok(())
     }
@@ -274,15 +394,233 @@ impl VideoProcessor for HistorySmoothingVideoProcessor {
             // Use the previous crop for all remaining frames
             if let Some(prev_crop) = &self.previous_crop {
                 while let Some(frame) = self.history.pop_front() {
-                    video_processor_utils::process_and_display_crop(
-                        &frame.image,
-                        prev_crop,
-                        viewer,
-                        args.headless,
-                    )?;
+                    self.output.render(&frame.image, prev_crop, viewer, args)?;
                 }
             }
         }
-        Ok(())
+        // With --loop-smoothing, bend the held-back ending to the opening crop
+        self.output.finish(viewer, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argh::FromArgs;
+
+    /// A small non-uniform frame, so cut detection sees repeats as the same
+    fn pattern_image() -> usls::Image {
+        let mut pattern = ::image::RgbImage::new(64, 36);
+        for (x, y, pixel) in pattern.enumerate_pixels_mut() {
+            *pixel = ::image::Rgb([(x * 4) as u8, (y * 7) as u8, 128]);
+        }
+        usls::Image::from(pattern)
+    }
+
+    /// Crop the smoother starts on in these tests
+    fn crop_a() -> crop::CropResult {
+        crop::CropResult::Single(crop::CropArea::new(100.0, 0.0, 608.0, 1080.0))
+    }
+
+    /// Crop the smoother is asked to move to, far enough from A to count as a change
+    fn crop_b() -> crop::CropResult {
+        crop::CropResult::Single(crop::CropArea::new(1200.0, 0.0, 608.0, 1080.0))
+    }
+
+    /// A dry-run sink that logs every rendered crop
+    fn logged_sink(name: &str, frame_rate: f64) -> VideoSink {
+        VideoSink::new(
+            std::env::temp_dir().join(name),
+            frame_rate,
+            Default::default(),
+        )
+        .with_crop_log()
+    }
+
+    /// Finalizes `viewer` and returns the crops it rendered, in order
+    fn rendered(mut viewer: VideoSink) -> Vec<crop::CropResult> {
+        let crops = viewer
+            .crop_log()
+            .unwrap()
+            .frames()
+            .into_iter()
+            .map(|frame| frame.crop)
+            .collect();
+        viewer.finalize().unwrap();
+        crops
+    }
+
+    /// Runs one frame of crop A followed by crop B for the rest, returning
+    /// the crops rendered so far (history-buffered frames are not rendered)
+    fn rendered_crops(min_hold_duration: &str) -> Vec<crop::CropResult> {
+        let args = Args::from_args(
+            &["land2port"],
+            &[
+                "--headless",
+                "--dry-run",
+                "--min-hold-duration",
+                min_hold_duration,
+            ],
+        )
+        .unwrap();
+        // 10 fps: a 1 s hold and the default 1 s history are both 10 frames
+        let mut processor = HistorySmoothingVideoProcessor::new(&args, 10.0);
+        let mut viewer = logged_sink("land2port_hold_test.mp4", 10.0);
+
+        let img = pattern_image();
+        let subject = usls::Hbb::from_xywh(0.0, 0.0, 10.0, 10.0);
+        let (a, b) = (crop_a(), crop_b());
+        for frame in 0..15 {
+            let latest = if frame == 0 { &a } else { &b };
+            viewer.crop_log_mut().unwrap().record_detection(1, false);
+            processor
+                .process_frame_with_smoothing(&img, latest, &[&subject], &args, &mut viewer, 10)
+                .unwrap();
+        }
+        rendered(viewer)
+    }
+
+    #[test]
+    fn test_min_hold_ignores_candidate_in_hold_window() {
+        // Without a hold, B fills the history and is committed
+        let unheld = rendered_crops("0");
+        assert_eq!(unheld.last(), Some(&crop_b()));
+
+        // With a 1 s hold, frames within it keep A and B only starts to buffer
+        // once the hold ends
+        let held = rendered_crops("1");
+        assert_eq!(held.len(), 11);
+        assert!(held.iter().all(|crop| crop == &crop_a()));
+    }
+
+    /// Commits a move from crop A to crop B over 10 buffered frames and
+    /// returns how many of them were rendered somewhere short of B
+    fn transition_steps(transition_duration: Option<&str>) -> usize {
+        let mut flags = vec!["--headless", "--dry-run"];
+        if let Some(duration) = transition_duration {
+            flags.extend(["--transition-duration", duration]);
+        }
+        transition_steps_with(&flags, false)
+    }
+
+    /// `transition_steps` with the given flags, for a move that crosses a
+    /// scene cut when `across_cut` is set
+    fn transition_steps_with(flags: &[&str], across_cut: bool) -> usize {
+        let args = Args::from_args(&["land2port"], flags).unwrap();
+        let mut processor = HistorySmoothingVideoProcessor::new(&args, 10.0);
+        processor.crossed_cut = across_cut;
+        let mut viewer = logged_sink("land2port_transition_test.mp4", 10.0);
+
+        let img = pattern_image();
+        let b = crop_b();
+        processor.previous_crop = Some(crop_a());
+        for _ in 0..10 {
+            processor.history.add(b.clone(), img.clone(), 1);
+            viewer.crop_log_mut().unwrap().record_detection(1, false);
+        }
+        processor
+            .process_history_with_interpolation(&b, &b, 10, false, 10, &mut viewer, &args)
+            .unwrap();
+
+        rendered(viewer)
+            .into_iter()
+            .filter(|crop| crop != &b)
+            .count()
+    }
+
+    #[test]
+    fn test_transition_duration_sets_interpolated_steps() {
+        // By default the move spans all 10 buffered frames, the last one on B
+        assert_eq!(transition_steps(None), 9);
+        // 0.4 s at 10 fps: 4 frames of movement, then B for the rest
+        assert_eq!(transition_steps(Some("0.4")), 3);
+        // Longer than the buffer: clamped to the 10 frames available
+        assert_eq!(transition_steps(Some("3")), 9);
+        // 0 jumps straight to B
+        assert_eq!(transition_steps(Some("0")), 0);
+    }
+
+    #[test]
+    fn test_transition_mode_selects_glide_or_snap() {
+        let flags = |mode| ["--headless", "--dry-run", "--transition-mode", mode];
+        // within-scene, the default, glides unless the move crosses a cut
+        assert_eq!(transition_steps_with(&flags("within-scene"), false), 9);
+        assert_eq!(transition_steps_with(&flags("within-scene"), true), 0);
+        // on-cut is the opposite
+        assert_eq!(transition_steps_with(&flags("on-cut"), false), 0);
+        assert_eq!(transition_steps_with(&flags("on-cut"), true), 9);
+        // always glides either way
+        assert_eq!(transition_steps_with(&flags("always"), false), 9);
+        assert_eq!(transition_steps_with(&flags("always"), true), 9);
+    }
+
+    #[test]
+    fn test_min_interp_duration_scales_with_frame_rate() {
+        // A change to B spanning 5 buffered frames, with a 3 s smoothing window
+        let keeps_current = |flags: &[&str], frame_rate: f64| {
+            let args = Args::from_args(&["land2port"], flags).unwrap();
+            let mut processor = HistorySmoothingVideoProcessor::new(&args, frame_rate);
+            let mut viewer = logged_sink("land2port_min_interp_test.mp4", frame_rate);
+            let img = pattern_image();
+            let b = crop_b();
+            processor.previous_crop = Some(crop_a());
+            for _ in 0..5 {
+                processor.history.add(b.clone(), img.clone(), 1);
+            }
+            let window = (3.0 * frame_rate) as usize;
+            let crop = processor
+                .process_history_with_interpolation(&b, &b, 5, true, window, &mut viewer, &args)
+                .unwrap();
+            viewer.finalize().unwrap();
+            crop == crop_a()
+        };
+
+        // By default a quarter of the 30-frame window: 5 frames are too few
+        assert!(keeps_current(&["--headless", "--dry-run"], 10.0));
+        // 0.3 s at 10 fps is 3 frames, which the change spans
+        let flags = ["--headless", "--dry-run", "--min-interp-duration", "0.3"];
+        assert!(!keeps_current(&flags, 10.0));
+        // At 20 fps the same 0.3 s needs 6 frames, so A is kept
+        assert!(keeps_current(&flags, 20.0));
+    }
+
+    #[test]
+    fn test_loop_smoothing_ends_on_opening_crop() {
+        let args = Args::from_args(
+            &["land2port"],
+            &["--headless", "--dry-run", "--loop-smoothing"],
+        )
+        .unwrap();
+        // 10 fps with the default 1 s history: a 10-frame tail
+        let mut processor = HistorySmoothingVideoProcessor::new(&args, 10.0);
+        let mut viewer = logged_sink("land2port_loop_test.mp4", 10.0);
+
+        let img = pattern_image();
+        let subject = usls::Hbb::from_xywh(0.0, 0.0, 10.0, 10.0);
+        let (a, b) = (crop_a(), crop_b());
+
+        for frame in 0..40 {
+            let latest = if frame == 0 { &a } else { &b };
+            viewer.crop_log_mut().unwrap().record_detection(1, false);
+            processor
+                .process_frame_with_smoothing(&img, latest, &[&subject], &args, &mut viewer, 10)
+                .unwrap();
+        }
+        processor.finalize_processing(&args, &mut viewer).unwrap();
+        let xs: Vec<f32> = rendered(viewer)
+            .into_iter()
+            .map(|crop| match crop {
+                crop::CropResult::Single(area) => area.x,
+                other => panic!("expected a single crop, got {:?}", other),
+            })
+            .collect();
+
+        // Every frame is rendered once, the middle settles on B, and the
+        // tail bends back so the last frame matches the first
+        assert_eq!(xs.len(), 40);
+        assert_eq!(xs[0], 100.0);
+        assert!(xs.contains(&1200.0));
+        assert!((xs[39] - xs[0]).abs() < 1.0, "{:?}", xs);
+        assert!(xs[30] > xs[35] && xs[35] > xs[39], "{:?}", xs);
     }
 }
@@ -1,10 +1,12 @@
-use crate::crop::CropResult;
+use crate::crop::{AspectRatio, CropResult};
 use crate::video_processor_utils;
 use crate::video_sink::make_even;
 use anyhow::{Context, Result};
 use fast_image_resize::images::Image as FirImage;
 use fast_image_resize::{FilterType, PixelType, ResizeAlg, ResizeOptions, Resizer};
 use image::{RgbImage, imageops::resize};
+use serde::Serialize;
+use std::str::FromStr;
 use usls::Image;
 
 /// SIMD-accelerated RGB resize via fast_image_resize, using the CatmullRom
@@ -51,11 +53,95 @@ fn downscale_for_cut(img: &Image, target_w: u32, target_h: u32) -> RgbImage {
     }
 }
 
+/// How a full-frame graphic (`CropResult::Resize`) is placed on the output
+/// canvas, from `--graphic-fit`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GraphicFit {
+    /// Scale the whole frame to the canvas width, keeping its aspect ratio,
+    /// with black bars above and below
+    #[default]
+    Fit,
+    /// Stretch the frame over the whole canvas, distorting it
+    Fill,
+}
+
+impl FromStr for GraphicFit {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "fit" => Ok(Self::Fit),
+            "fill" => Ok(Self::Fill),
+            _ => Err(format!("invalid graphic fit '{s}': expected fit or fill")),
+        }
+    }
+}
+
+/// Histogram bins per RGB channel for `CutMode::Color`. Coarse enough that
+/// noise and small movements land in the same bins.
+const COLOR_HISTOGRAM_BINS: usize = 32;
+
+/// Which signal cut detection compares, from `--cut-mode`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CutMode {
+    /// The hybrid image compare alone, which is dominated by luminance
+    #[default]
+    Luma,
+    /// Also compare per-channel color histograms, catching cuts between shots
+    /// graded differently but with similar brightness
+    Color,
+}
+
+impl FromStr for CutMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "luma" => Ok(Self::Luma),
+            "color" => Ok(Self::Color),
+            _ => Err(format!("invalid cut mode '{s}': expected luma or color")),
+        }
+    }
+}
+
+/// Similarity of two frames' color distributions in [0, 1]: the histogram
+/// intersection of each RGB channel, taking the least similar channel
+fn color_histogram_similarity(a: &RgbImage, b: &RgbImage) -> f64 {
+    let histograms = |img: &RgbImage| {
+        let mut bins = [[0usize; COLOR_HISTOGRAM_BINS]; 3];
+        for pixel in img.pixels() {
+            for (channel, &value) in pixel.0.iter().enumerate() {
+                bins[channel][value as usize * COLOR_HISTOGRAM_BINS / 256] += 1;
+            }
+        }
+        bins
+    };
+    let (hist_a, hist_b) = (histograms(a), histograms(b));
+    let (total_a, total_b) = (a.pixels().len().max(1), b.pixels().len().max(1));
+
+    (0..3)
+        .map(|channel| {
+            (0..COLOR_HISTOGRAM_BINS)
+                .map(|bin| {
+                    let share_a = hist_a[channel][bin] as f64 / total_a as f64;
+                    let share_b = hist_b[channel][bin] as f64 / total_b as f64;
+                    share_a.min(share_b)
+                })
+                .sum::<f64>()
+        })
+        .fold(1.0, f64::min)
+}
+
 /// Stateful cut detector that maintains previous similarity scores
 pub struct CutDetector {
     pub previous_score: Option<f64>,
     similarity_threshold: f64,
     previous_similarity_threshold: f64,
+    /// Cuts closer than this many frames to the previous cut are ignored
+    min_scene_frames: usize,
+    /// Frames compared since the last reported cut; `None` before the first
+    frames_since_cut: Option<usize>,
+    mode: CutMode,
     /// Downscaled buffer of the previous frame (image2 of the prior call),
     /// reused as image1 of the next call so each frame is converted and
     /// downscaled only once instead of twice.
@@ -68,11 +154,21 @@ impl CutDetector {
     /// # Arguments
     /// * `similarity_threshold` - The threshold below which a cut is detected (default: 0.15)
     /// * `previous_similarity_threshold` - The threshold above which the previous score must be to consider a cut (default: 0.7)
-    pub fn new(similarity_threshold: f64, previous_similarity_threshold: f64) -> Self {
+    /// * `min_scene_frames` - Minimum frames between reported cuts, so fast-cut montages don't reset smoothing every few frames (0 disables)
+    /// * `mode` - Whether to also compare color histograms (default: luma only)
+    pub fn new(
+        similarity_threshold: f64,
+        previous_similarity_threshold: f64,
+        min_scene_frames: usize,
+        mode: CutMode,
+    ) -> Self {
         Self {
             previous_score: None,
             similarity_threshold,
             previous_similarity_threshold,
+            min_scene_frames,
+            frames_since_cut: None,
+            mode,
             prev_small: None,
         }
     }
@@ -86,9 +182,9 @@ impl CutDetector {
     ///
     /// # Returns
     /// `true` if the similarity is less than similarity_threshold AND previous_score is greater than previous_similarity_threshold,
-    /// `false` otherwise
+    /// and at least `min_scene_frames` frames have passed since the last cut, `false` otherwise
     pub fn is_cut(&mut self, image1: &Image, image2: &Image) -> Result<bool> {
-        let similarity = crate::metrics::time("cut_detect", || -> Result<_> {
+        let current_score = crate::metrics::time("cut_detect", || -> Result<_> {
             // Downscale both frames before comparing. The full-resolution hybrid
             // compare dominated per-frame runtime; cut detection is scale-stable,
             // so this is far cheaper at near-identical accuracy.
@@ -106,12 +202,15 @@ impl CutDetector {
             };
             let small2 = downscale_for_cut(image2, target_w, target_h);
 
-            // Use rgb_hybrid_compare to get the similarity score
-            let score = image_compare::rgb_hybrid_compare(&small1, &small2)?;
+            // Use rgb_hybrid_compare to get the similarity score; in color mode
+            // a change in color distribution alone can also lower it
+            let mut score = image_compare::rgb_hybrid_compare(&small1, &small2)?.score;
+            if self.mode == CutMode::Color {
+                score = score.min(color_histogram_similarity(&small1, &small2));
+            }
             self.prev_small = Some(small2);
             Ok(score)
         })?;
-        let current_score = similarity.score;
 
         video_processor_utils::debug_println(format_args!("similarity: {:?}", current_score));
 
@@ -133,10 +232,137 @@ impl CutDetector {
         // Update previous score for next comparison
         self.previous_score = Some(current_score);
 
+        // Debounce cuts that come too soon after the last one
+        self.frames_since_cut = self.frames_since_cut.map(|frames| frames + 1);
+        let scene_too_short = self
+            .frames_since_cut
+            .is_some_and(|frames| frames < self.min_scene_frames);
+        if is_cut && scene_too_short {
+            video_processor_utils::debug_println(format_args!(
+                "cut suppressed: scene shorter than {} frames",
+                self.min_scene_frames
+            ));
+            return Ok(false);
+        }
+        if is_cut {
+            self.frames_since_cut = Some(0);
+        }
+
         Ok(is_cut)
     }
 }
 
+/// Mean Rec. 601 luma of a frame in [0, 255], measured on the same small
+/// copy cut detection compares so it costs little per frame
+pub fn mean_luma(image: &Image) -> f64 {
+    let w = image.width().max(1);
+    let h = image.height().max(1);
+    let target_w = CUT_DETECT_WIDTH.min(w);
+    let target_h = (((target_w as f32) * (h as f32 / w as f32)).round() as u32).max(1);
+    let small = downscale_for_cut(image, target_w, target_h);
+
+    let total: f64 = small
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b] = pixel.0;
+            0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+        })
+        .sum();
+    total / small.pixels().len().max(1) as f64
+}
+
+/// Whether a frame is black, e.g. the middle of a fade to black, because its
+/// mean luma is below `threshold`. A threshold of 0 never flags a frame.
+pub fn is_black_frame(image: &Image, threshold: f64) -> bool {
+    threshold > 0.0 && mean_luma(image) < threshold
+}
+
+/// Checks that a `--black-frame-threshold` is a luma between 0 and 255
+pub fn check_black_frame_threshold(threshold: f64) -> Result<()> {
+    if !(0.0..=255.0).contains(&threshold) {
+        anyhow::bail!("invalid black frame threshold {threshold}: expected 0 to 255");
+    }
+    Ok(())
+}
+
+/// A detected scene cut, as written to `cuts.json`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CutEvent {
+    /// Index of the first frame after the cut
+    pub frame: usize,
+    /// Time of that frame in seconds
+    pub seconds: f64,
+    /// The same time as an `HH:MM:SS:FF` timecode, for comparing against an
+    /// edit decision list
+    pub timecode: String,
+}
+
+/// Records the scene cuts in a video for `--export-cuts`, running its own
+/// `CutDetector` over every pair of consecutive frames (independent of
+/// whichever smoothing processor is in use)
+pub struct CutLog {
+    detector: CutDetector,
+    previous: Option<Image>,
+    frame_rate: f64,
+    next_frame: usize,
+    cuts: Vec<CutEvent>,
+}
+
+impl CutLog {
+    pub fn new(detector: CutDetector, frame_rate: f64) -> Self {
+        Self {
+            detector,
+            previous: None,
+            frame_rate,
+            next_frame: 0,
+            cuts: Vec::new(),
+        }
+    }
+
+    /// Compares the next frame with the one before it, recording a cut when
+    /// the detector finds one and returning it
+    pub fn observe(&mut self, image: &Image) -> Result<Option<&CutEvent>> {
+        let is_cut = match &self.previous {
+            Some(previous) => self.detector.is_cut(previous, image)?,
+            None => false,
+        };
+        if is_cut {
+            self.record_cut(self.next_frame);
+        }
+        self.previous = Some(image.clone());
+        self.next_frame += 1;
+        Ok(if is_cut { self.cuts.last() } else { None })
+    }
+
+    fn record_cut(&mut self, frame: usize) {
+        let seconds = frame as f64 / self.frame_rate;
+        let fps = self.frame_rate.round().max(1.0) as usize;
+        let total_seconds = frame / fps;
+        self.cuts.push(CutEvent {
+            frame,
+            seconds,
+            timecode: format!(
+                "{:02}:{:02}:{:02}:{:02}",
+                total_seconds / 3600,
+                total_seconds / 60 % 60,
+                total_seconds % 60,
+                frame % fps
+            ),
+        });
+    }
+
+    /// The cuts found so far, in frame order
+    pub fn cuts(&self) -> &[CutEvent] {
+        &self.cuts
+    }
+
+    /// Writes the recorded cuts to `path` as a JSON array
+    pub fn write_json(&self, path: &str) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.cuts).context("Serializing scene cuts")?;
+        std::fs::write(path, json).with_context(|| format!("Writing scene cuts to {}", path))
+    }
+}
+
 /// Clamps a crop rectangle (produced by float crop math, which can go out of
 /// bounds for non-landscape or degenerate inputs) to valid integer pixel bounds
 /// inside a `frame_w` x `frame_h` frame. Guarantees `x + width <= frame_w`,
@@ -157,21 +383,70 @@ fn clamp_crop_rect(
     (x, y, width, height)
 }
 
+/// Factor by which the blur-fill background is shrunk before blurring. Blurring
+/// a small copy and scaling it back up looks the same as a wide blur over the
+/// full-size canvas at a fraction of the cost.
+const BLUR_FILL_DOWNSCALE: u32 = 8;
+
+/// Gaussian blur sigma, in downscaled pixels, for the blur-fill background
+const BLUR_FILL_SIGMA: f32 = 3.0;
+
+/// Builds the blur-fill background: the frame scaled to cover the whole
+/// `width` x `height` canvas (center-cropped to its aspect ratio) and blurred
+fn blurred_background(src: &RgbImage, width: u32, height: u32) -> Result<RgbImage> {
+    let (frame_w, frame_h) = src.dimensions();
+    let canvas_ratio = width as f32 / height as f32;
+    let (cover_w, cover_h) = if frame_w as f32 / frame_h as f32 > canvas_ratio {
+        (frame_h as f32 * canvas_ratio, frame_h as f32)
+    } else {
+        (frame_w as f32, frame_w as f32 / canvas_ratio)
+    };
+    let (x, y, cover_w, cover_h) = clamp_crop_rect(
+        (frame_w as f32 - cover_w) / 2.0,
+        (frame_h as f32 - cover_h) / 2.0,
+        cover_w,
+        cover_h,
+        frame_w,
+        frame_h,
+    );
+    let cover = image::imageops::crop_imm(src, x, y, cover_w, cover_h).to_image();
+
+    let small = fir_resize(
+        cover,
+        (width / BLUR_FILL_DOWNSCALE).max(1),
+        (height / BLUR_FILL_DOWNSCALE).max(1),
+    )?;
+    let blurred = image::imageops::blur(&small, BLUR_FILL_SIGMA);
+    fir_resize(blurred, width, height)
+}
+
 /// Creates a new image by cropping the input image according to the crop result
 ///
 /// # Arguments
 /// * `image` - The input image to crop
 /// * `crop_result` - The crop result specifying how to crop the image
 /// * `target_width` - The desired width of the output image
+/// * `aspect_ratio` - The aspect ratio of the output canvas
+/// * `graphic_fit` - How a full-frame resize is placed on the canvas
 ///
 /// # Returns
-/// A new image containing either a single 9:16 crop or two crops stacked vertically:
+/// A new image with the requested aspect ratio (9:16 by default) containing
+/// either a single crop or two crops stacked vertically:
 /// - For three heads: top crop (9:6) + bottom crop (9:10) = 9:16 final image
-/// - For other cases: two equal crops stacked to create 9:16 final image
+/// - For other cases: the top crop fills the canvas ratio's `stack_ratio` of
+///   the height (half by default) and the bottom crop the rest
+///
+/// On square and wider canvases the two crops are placed side by side instead,
+/// each scaled to fill half of the canvas width.
+///
+/// A blur-fill crop is scaled to fit the canvas and centered over a blurred,
+/// zoomed copy of the whole frame.
 pub fn create_cropped_image(
     image: &Image,
     crop_result: &CropResult,
     target_width: u32,
+    aspect_ratio: AspectRatio,
+    graphic_fit: GraphicFit,
 ) -> Result<Image> {
     // Borrow the inner RgbImage directly (no clone); the crops are read-only.
     let src = &image.image;
@@ -203,12 +478,13 @@ pub fn create_cropped_image(
                 cropped
             };
 
-            // Create a new image with 9:16 aspect ratio and black background
-            let output_height = make_even((target_width as f32 * (16.0 / 9.0)) as u32);
+            // Create a new image with the output aspect ratio and black background
+            let output_height = make_even(aspect_ratio.canvas_height(target_width));
             let mut result = RgbImage::new(target_width, output_height);
 
-            // Calculate y offset (1/16 of the height)
-            let y_offset = output_height / 16;
+            // Calculate y offset (1/16 of the height), pulled up when the crop
+            // already fills the canvas (e.g. 1:1 or 4:5 output)
+            let y_offset = (output_height / 16).min(output_height.saturating_sub(scaled.height()));
 
             // Overlay the scaled image at the calculated y offset
             image::imageops::overlay(&mut result, &scaled, 0, y_offset as i64);
@@ -217,10 +493,10 @@ pub fn create_cropped_image(
             Ok(Image::from(result))
         }
         CropResult::Stacked(crop1, crop2) => {
-            // For stacked crops, we create a 9:16 image by:
+            // For stacked crops, we create an image at the output aspect ratio by:
             // 1. Cropping both areas from the source image
             // 2. Scaling crops based on their aspect ratios
-            // 3. Stacking them vertically to create the final 9:16 image
+            // 3. Stacking them vertically to create the final image
 
             // Crop both areas from the source image (rects clamped to bounds)
             let (x1, y1, w1, h1) =
@@ -231,40 +507,71 @@ pub fn create_cropped_image(
                 clamp_crop_rect(crop2.x, crop2.y, crop2.width, crop2.height, frame_w, frame_h);
             let crop2_img = image::imageops::crop_imm(src, x2, y2, w2, h2).to_image();
 
-            // Calculate the target 9:16 aspect ratio height
-            let target_height = make_even((target_width as f32 * (16.0 / 9.0)) as u32);
+            // Calculate the target canvas height for the output aspect ratio
+            let target_height = make_even(aspect_ratio.canvas_height(target_width));
+
+            if aspect_ratio.stacks_side_by_side() {
+                // Left and right halves, each filling the full canvas height
+                let left_width = target_width / 2;
+                let right_width = target_width - left_width;
+                let scaled1 = fir_resize(crop1_img, left_width, target_height)?;
+                let scaled2 = fir_resize(crop2_img, right_width, target_height)?;
+
+                let mut result = RgbImage::new(target_width, target_height);
+                image::imageops::overlay(&mut result, &scaled1, 0, 0);
+                image::imageops::overlay(&mut result, &scaled2, left_width as i64, 0);
+                return Ok(Image::from(result));
+            }
 
             // Determine scaling strategy based on crop aspect ratios
             let crop1_aspect = crop1.width / crop1.height;
             let crop2_aspect = crop2.width / crop2.height;
+            let double_aspect = aspect_ratio.three_stack_double_ratio();
+            let single_aspect = aspect_ratio.three_stack_single_ratio();
 
-            let is_crop1_double = (crop1_aspect - 1.5).abs() < 0.1;
-            let is_crop2_double = (crop2_aspect - 1.5).abs() < 0.1;
-            let is_crop1_single = (crop1_aspect - 0.9).abs() < 0.1;
-            let is_crop2_single = (crop2_aspect - 0.9).abs() < 0.1;
-
-            let (top_height, bottom_height) = if is_crop1_double && is_crop2_single {
+            // Some stack ratios come close to the three-head shapes, so those
+            // only win when they fit the crops better than the two-way stack
+            let layout_error = |top_aspect: f32, bottom_aspect: f32| {
+                (crop1_aspect - top_aspect).abs() + (crop2_aspect - bottom_aspect).abs()
+            };
+            let stack_error = layout_error(
+                aspect_ratio.stack_top_ratio(),
+                aspect_ratio.stack_bottom_ratio(),
+            );
+
+            let is_crop1_double = (crop1_aspect - double_aspect).abs() < 0.1;
+            let is_crop2_double = (crop2_aspect - double_aspect).abs() < 0.1;
+            let is_crop1_single = (crop1_aspect - single_aspect).abs() < 0.1;
+            let is_crop2_single = (crop2_aspect - single_aspect).abs() < 0.1;
+
+            let (top_height, bottom_height) = if is_crop1_double
+                && is_crop2_single
+                && layout_error(double_aspect, single_aspect) < stack_error
+            {
                 // Special case: top crop is 9:6, bottom is 9:10
                 let top_height = (target_height as f32 * (6.0 / 16.0)) as u32;
                 let bottom_height = (target_height as f32 * (10.0 / 16.0)) as u32;
                 (top_height, bottom_height)
-            } else if is_crop1_single && is_crop2_double {
+            } else if is_crop1_single
+                && is_crop2_double
+                && layout_error(single_aspect, double_aspect) < stack_error
+            {
                 // Special case: top crop is 9:10, bottom is 9:6 (reversed arrangement)
                 let top_height = (target_height as f32 * (10.0 / 16.0)) as u32;
                 let bottom_height = (target_height as f32 * (6.0 / 16.0)) as u32;
                 (top_height, bottom_height)
             } else {
-                // Default case: equal height crops (like 9:8 + 9:8)
-                // Scale both to half height
-                let half_height = target_height / 2;
-                (half_height, half_height)
+                // Default case: the top crop takes `stack_ratio` of the height
+                // (9:8 + 9:8 when split evenly) and the bottom crop the rest
+                let top_height = (target_height as f32 * aspect_ratio.stack_ratio).round() as u32;
+                (top_height, target_height - top_height)
             };
 
             // Scale both crops to fit the target width and their calculated heights
             let scaled1 = fir_resize(crop1_img, target_width, top_height)?;
             let scaled2 = fir_resize(crop2_img, target_width, bottom_height)?;
 
-            // Create a new image with 9:16 aspect ratio
+            // Create a new image with the output aspect ratio
             let mut result = RgbImage::new(target_width, target_height);
 
             // Copy the first crop to the top portion
@@ -285,6 +592,15 @@ pub fn create_cropped_image(
             // Use imageops::crop to get the cropped region (should be the entire frame)
             let cropped = image::imageops::crop_imm(src, x, y, width, height).to_image();
 
+            if graphic_fit == GraphicFit::Fill {
+                let output_height = make_even(aspect_ratio.canvas_height(target_width));
+                return Ok(Image::from(fir_resize(
+                    cropped,
+                    target_width,
+                    output_height,
+                )?));
+            }
+
             // Scale the cropped image to match target width, preserving the
             // actual (post-clamp) aspect ratio.
             let scaled = if cropped.width() != target_width {
@@ -300,12 +616,12 @@ pub fn create_cropped_image(
                 cropped
             };
 
-            // Create a new image with 9:16 aspect ratio and black background
-            let output_height = make_even((target_width as f32 * (16.0 / 9.0)) as u32);
+            // Create a new image with the output aspect ratio and black background
+            let output_height = make_even(aspect_ratio.canvas_height(target_width));
             let mut result = RgbImage::new(target_width, output_height);
 
-            // Calculate y offset (1/8 of the height)
-            let y_offset = output_height / 8;
+            // Calculate y offset (1/8 of the height), never pushing the frame off the canvas
+            let y_offset = (output_height / 8).min(output_height.saturating_sub(scaled.height()));
 
             // Overlay the scaled image at the calculated y offset
             image::imageops::overlay(&mut result, &scaled, 0, y_offset as i64);
@@ -313,7 +629,83 @@ pub fn create_cropped_image(
             // Convert back to usls::Image
             Ok(Image::from(result))
         }
+        CropResult::BlurFill(crop) => {
+            let output_height = make_even(aspect_ratio.canvas_height(target_width));
+            let mut result = blurred_background(src, target_width, output_height)?;
+
+            let (x, y, width, height) =
+                clamp_crop_rect(crop.x, crop.y, crop.width, crop.height, frame_w, frame_h);
+            let cropped = image::imageops::crop_imm(src, x, y, width, height).to_image();
+
+            // Fit the sharp crop inside the canvas, preserving its aspect ratio
+            let scale =
+                (target_width as f32 / width as f32).min(output_height as f32 / height as f32);
+            let scaled_width = ((width as f32 * scale) as u32).clamp(1, target_width);
+            let scaled_height = ((height as f32 * scale) as u32).clamp(1, output_height);
+            let scaled = if (scaled_width, scaled_height) != (width, height) {
+                fir_resize(cropped, scaled_width, scaled_height)?
+            } else {
+                cropped
+            };
+
+            // Center the sharp crop over the background
+            let x_offset = (target_width - scaled_width) / 2;
+            let y_offset = (output_height - scaled_height) / 2;
+            image::imageops::overlay(&mut result, &scaled, x_offset as i64, y_offset as i64);
+
+            Ok(Image::from(result))
+        }
+    }
+}
+
+/// Width of each `--preview-grid` tile
+pub const PREVIEW_TILE_WIDTH: u32 = 320;
+
+/// Tiles per row of the `--preview-grid` contact sheet
+pub const PREVIEW_GRID_COLUMNS: u32 = 6;
+
+/// Most tiles a `--preview-grid` contact sheet holds (20 rows); longer clips
+/// are sampled more sparsely so the sheet stays a reasonable size
+pub const PREVIEW_MAX_TILES: usize = 120;
+
+/// Shrinks a frame to a contact sheet tile `tile_width` wide, keeping its
+/// aspect ratio
+pub fn contact_sheet_tile(img: &Image, tile_width: u32) -> RgbImage {
+    let src = &img.image;
+    let tile_height =
+        (src.height() as u64 * tile_width as u64 / src.width().max(1) as u64).max(1) as u32;
+    resize(
+        src,
+        tile_width,
+        tile_height,
+        image::imageops::FilterType::Triangle,
+    )
+}
+
+/// Tiles frames into one contact sheet, `columns` to a row in order, each in
+/// a cell the size of the largest tile
+pub fn contact_sheet(tiles: &[RgbImage], columns: u32) -> RgbImage {
+    let columns = columns.clamp(1, tiles.len().max(1) as u32);
+    let cell_w = tiles.iter().map(|tile| tile.width()).max().unwrap_or(0);
+    let cell_h = tiles.iter().map(|tile| tile.height()).max().unwrap_or(0);
+    let rows = (tiles.len() as u32).div_ceil(columns);
+    let mut sheet = RgbImage::new(cell_w * columns, cell_h * rows);
+    for (index, tile) in (0u32..).zip(tiles) {
+        let x = (index % columns) * cell_w;
+        let y = (index / columns) * cell_h;
+        image::imageops::overlay(&mut sheet, tile, x as i64, y as i64);
     }
+    sheet
+}
+
+/// Writes the contact sheet of `tiles` to `path` as a PNG
+pub fn save_contact_sheet(tiles: &[RgbImage], columns: u32, path: &str) -> Result<()> {
+    if tiles.is_empty() {
+        anyhow::bail!("no frames to tile into {}", path);
+    }
+    contact_sheet(tiles, columns)
+        .save_with_format(path, image::ImageFormat::Png)
+        .with_context(|| format!("Writing contact sheet {}", path))
 }
 
 #[cfg(test)]
@@ -383,7 +775,14 @@ mod tests {
         let crop_result = CropResult::Single(crop);
 
         // Create the cropped image with target width of 1080
-        let cropped = create_cropped_image(&image, &crop_result, 1080).unwrap();
+        let cropped = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT,
+            GraphicFit::Fit,
+        )
+        .unwrap();
 
         // Verify dimensions - should be 9:16 aspect ratio
         assert_eq!(cropped.width(), 1080); // Width matches target width
@@ -403,6 +802,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_crop_renders_at_each_output_width() {
+        let image = Image::from(RgbImage::new(1920, 1080));
+        let single = CropResult::Single(CropArea::new(360.0, 0.0, 810.0, 1080.0));
+        let stacked = CropResult::Stacked(
+            CropArea::new(0.0, 100.0, 960.0, 853.0),
+            CropArea::new(960.0, 100.0, 960.0, 853.0),
+        );
+
+        // The same crop decisions scale to every --output-resolution size
+        for crop_result in [&single, &stacked] {
+            for (width, height) in [(1080, 1920), (720, 1280), (540, 960)] {
+                let cropped = create_cropped_image(
+                    &image,
+                    crop_result,
+                    width,
+                    AspectRatio::PORTRAIT,
+                    GraphicFit::Fit,
+                )
+                .unwrap();
+                assert_eq!((cropped.width(), cropped.height()), (width, height));
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_crop_square_canvas() {
+        let image = Image::from(RgbImage::from_pixel(
+            1920,
+            1080,
+            image::Rgb([255, 255, 255]),
+        ));
+
+        // A 1:1 canvas crops the full frame height at its own ratio
+        let crop_result = CropResult::Single(CropArea::new(420.0, 0.0, 1080.0, 1080.0));
+        let cropped = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::new(1.0, 1.0),
+            GraphicFit::Fit,
+        )
+        .unwrap();
+
+        assert_eq!(cropped.width(), 1080);
+        assert_eq!(cropped.height(), 1080);
+
+        // The crop fills the canvas, so there is no letterbox band
+        assert_eq!(cropped.get_pixel(540, 0)[0], 255);
+        assert_eq!(cropped.get_pixel(540, 1079)[0], 255);
+    }
+
     #[test]
     fn test_stacked_crops() {
         // Create a test image
@@ -426,7 +877,14 @@ mod tests {
         let crop_result = CropResult::Stacked(crop1, crop2);
 
         // Create the cropped image with target width of 1080
-        let cropped = create_cropped_image(&image, &crop_result, 1080).unwrap();
+        let cropped = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT,
+            GraphicFit::Fit,
+        )
+        .unwrap();
 
         // Verify dimensions - should be 9:16 aspect ratio
         assert_eq!(cropped.width(), 1080); // Width matches target width
@@ -436,6 +894,78 @@ mod tests {
         // The crops should maintain their relative proportions but fit into the 9:16 frame
     }
 
+    #[test]
+    fn test_stacked_crops_side_by_side_on_square_canvas() {
+        // Red on the left half of the frame, blue on the right
+        let mut rgb_image = RgbImage::new(1920, 1080);
+        for (x, _, pixel) in rgb_image.enumerate_pixels_mut() {
+            *pixel = if x < 960 {
+                image::Rgb([255, 0, 0])
+            } else {
+                image::Rgb([0, 0, 255])
+            };
+        }
+        let image = Image::from(rgb_image);
+
+        let crop_result = CropResult::Stacked(
+            CropArea::new(100.0, 0.0, 540.0, 1080.0),
+            CropArea::new(1300.0, 0.0, 540.0, 1080.0),
+        );
+        let cropped = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::new(1.0, 1.0),
+            GraphicFit::Fit,
+        )
+        .unwrap();
+
+        assert_eq!(cropped.width(), 1080);
+        assert_eq!(cropped.height(), 1080);
+        // The first crop fills the left half top to bottom, the second the right
+        for y in [0, 540, 1079] {
+            assert_eq!(cropped.get_pixel(100, y).0, [255, 0, 0]);
+            assert_eq!(cropped.get_pixel(980, y).0, [0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn test_stack_ratio_splits_canvas_height() {
+        // Red on the left half of the frame, blue on the right
+        let mut rgb_image = RgbImage::new(1920, 1080);
+        for (x, _, pixel) in rgb_image.enumerate_pixels_mut() {
+            *pixel = if x < 960 {
+                image::Rgb([255, 0, 0])
+            } else {
+                image::Rgb([0, 0, 255])
+            };
+        }
+        let image = Image::from(rgb_image);
+
+        // Stacked crops sized for a 0.6 split of a 9:16 canvas
+        let crop_result = CropResult::Stacked(
+            CropArea::new(0.0, 28.0, 960.0, 1024.0),
+            CropArea::new(960.0, 198.0, 960.0, 960.0 * 6.4 / 9.0),
+        );
+        let cropped = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT.with_stack_ratio(0.6),
+            GraphicFit::Fit,
+        )
+        .unwrap();
+
+        // The top crop fills the first 60% of the 1920 rows, the bottom the rest
+        assert_eq!((cropped.width(), cropped.height()), (1080, 1920));
+        for y in [0, 1151] {
+            assert_eq!(cropped.get_pixel(540, y).0, [255, 0, 0]);
+        }
+        for y in [1152, 1919] {
+            assert_eq!(cropped.get_pixel(540, y).0, [0, 0, 255]);
+        }
+    }
+
     #[test]
     fn test_three_heads_special_case_stacked_crops() {
         // Create a test image
@@ -467,7 +997,14 @@ mod tests {
         let crop_result = CropResult::Stacked(crop1, crop2);
 
         // Create the cropped image with target width of 1080
-        let cropped = create_cropped_image(&image, &crop_result, 1080).unwrap();
+        let cropped = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT,
+            GraphicFit::Fit,
+        )
+        .unwrap();
 
         // Verify dimensions - should be 9:16 aspect ratio
         assert_eq!(cropped.width(), 1080); // Width matches target width
@@ -479,9 +1016,22 @@ mod tests {
         // and the shorter/wider crop should take less vertical space
     }
 
+    #[test]
+    fn test_is_black_frame() {
+        let black = Image::from(RgbImage::new(320, 180));
+        let dim = Image::from(RgbImage::from_pixel(320, 180, image::Rgb([20, 20, 20])));
+
+        assert_eq!(mean_luma(&black), 0.0);
+        assert!(is_black_frame(&black, 10.0));
+        assert!(!is_black_frame(&dim, 10.0));
+        // 0 turns the check off
+        assert!(!is_black_frame(&black, 0.0));
+        assert!(check_black_frame_threshold(256.0).is_err());
+    }
+
     #[test]
     fn test_cut_detector() {
-        let mut detector = CutDetector::new(0.15, 0.7);
+        let mut detector = CutDetector::new(0.15, 0.7, 0, CutMode::Luma);
 
         // Create two identical images
         let mut rgb_image1 = RgbImage::new(100, 100);
@@ -522,6 +1072,87 @@ mod tests {
         assert!(is_cut == (detector.previous_score.unwrap() < 0.15));
     }
 
+    #[test]
+    fn test_cut_detector_min_scene_frames() {
+        let frame = |value: u8| Image::from(RgbImage::from_pixel(32, 18, image::Rgb([value; 3])));
+        let frames: Vec<Image> = (0..10)
+            .map(|i| frame(if i % 2 == 0 { 0 } else { 255 }))
+            .collect();
+
+        // Thresholds that make every comparison a cut, so only the debounce
+        // decides: without it every alternating frame is reported...
+        let cuts = |min_scene_frames| -> Vec<usize> {
+            let mut detector = CutDetector::new(1.01, -1.0, min_scene_frames, CutMode::Luma);
+            (1..frames.len())
+                .filter(|&i| detector.is_cut(&frames[i - 1], &frames[i]).unwrap())
+                .collect()
+        };
+        assert_eq!(cuts(0), (1..10).collect::<Vec<_>>());
+
+        // ...with it, cuts are at least `min_scene_frames` apart
+        assert_eq!(cuts(3), [1, 4, 7]);
+        assert_eq!(cuts(4), [1, 5, 9]);
+    }
+
+    #[test]
+    fn test_cut_detector_color_mode_catches_chroma_cut() {
+        // Pure red and a green of the same luma (0.299 * 255 ≈ 0.587 * 130):
+        // the shots differ only in chroma
+        let red = Image::from(RgbImage::from_pixel(64, 36, image::Rgb([255, 0, 0])));
+        let green = Image::from(RgbImage::from_pixel(64, 36, image::Rgb([0, 130, 0])));
+
+        assert!(color_histogram_similarity(&red.image, &red.image) > 0.99);
+        assert!(color_histogram_similarity(&red.image, &green.image) < 0.01);
+
+        let mut detector = CutDetector::new(0.15, 0.7, 0, CutMode::Color);
+        assert!(!detector.is_cut(&red, &red).unwrap());
+        assert!(detector.is_cut(&red, &green).unwrap());
+    }
+
+    #[test]
+    fn test_cut_mode_from_str() {
+        assert_eq!("luma".parse(), Ok(CutMode::Luma));
+        assert_eq!("Color".parse(), Ok(CutMode::Color));
+        assert!("chroma".parse::<CutMode>().is_err());
+    }
+
+    #[test]
+    fn test_cut_log() {
+        let mut cut_log = CutLog::new(CutDetector::new(0.15, 0.7, 0, CutMode::Luma), 25.0);
+
+        // Identical frames are never cuts
+        let mut rgb = RgbImage::new(64, 36);
+        for y in 0..36 {
+            for x in 0..64 {
+                rgb.put_pixel(x, y, image::Rgb([(x * 4) as u8, (y * 7) as u8, 32]));
+            }
+        }
+        for _ in 0..3 {
+            let cut = cut_log.observe(&Image::from(rgb.clone())).unwrap();
+            assert!(cut.is_none());
+        }
+        assert!(cut_log.cuts().is_empty());
+
+        // Timecodes count whole seconds plus leftover frames at the frame rate
+        cut_log.record_cut(3);
+        cut_log.record_cut(25 * 3661 + 12);
+        assert_eq!(
+            cut_log.cuts(),
+            [
+                CutEvent {
+                    frame: 3,
+                    seconds: 0.12,
+                    timecode: "00:00:00:03".to_string(),
+                },
+                CutEvent {
+                    frame: 91537,
+                    seconds: 3661.48,
+                    timecode: "01:01:01:12".to_string(),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_downscale_for_cut_dims() {
         // A frame larger than CUT_DETECT_WIDTH is downscaled, preserving aspect.
@@ -542,7 +1173,7 @@ mod tests {
         // Drive a 3-frame sequence the way the processors do: is_cut(prev, cur)
         // then prev = cur. Identical consecutive large frames must not be cuts,
         // exercising the cached-prev_small path on the second call.
-        let mut detector = CutDetector::new(0.15, 0.7);
+        let mut detector = CutDetector::new(0.15, 0.7, 0, CutMode::Luma);
         let mut rgb = RgbImage::new(640, 360);
         for y in 0..360 {
             for x in 0..640 {
@@ -562,7 +1193,7 @@ mod tests {
     fn test_cut_detector_downscales_large_frames() {
         // Two identical large frames must not register as a cut once the
         // downscale path (frames wider than CUT_DETECT_WIDTH) is exercised.
-        let mut detector = CutDetector::new(0.15, 0.7);
+        let mut detector = CutDetector::new(0.15, 0.7, 0, CutMode::Luma);
         let mut rgb = RgbImage::new(640, 360);
         for y in 0..360 {
             for x in 0..640 {
@@ -574,6 +1205,91 @@ mod tests {
         assert!(!detector.is_cut(&a, &b).unwrap());
     }
 
+    #[test]
+    fn test_blur_fill_crop() {
+        // Left half red, right half blue
+        let rgb_image = RgbImage::from_fn(1920, 1080, |x, _| {
+            if x < 960 {
+                image::Rgb([255, 0, 0])
+            } else {
+                image::Rgb([0, 0, 255])
+            }
+        });
+        let image = Image::from(rgb_image);
+
+        let crop_result = CropResult::BlurFill(CropArea::new(0.0, 0.0, 1920.0, 1080.0));
+        let filled = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT,
+            GraphicFit::Fit,
+        )
+        .unwrap();
+        assert_eq!(filled.width(), 1080);
+        assert_eq!(filled.height(), 1920);
+
+        // The whole frame is scaled to the canvas width (1080x607) and centered,
+        // keeping both sides: red on the left, blue on the right
+        let middle = 1920 / 2;
+        assert_eq!(filled.get_pixel(100, middle).0, [255, 0, 0]);
+        assert_eq!(filled.get_pixel(980, middle).0, [0, 0, 255]);
+
+        // Above and below it is the background rather than black letterboxing
+        for y in [10, 1910] {
+            let pixel = filled.get_pixel(100, y).0;
+            assert!(
+                pixel[0] > 100,
+                "expected background at y={y}, got {pixel:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resize_crop_fit_and_fill_geometry() {
+        let image = Image::from(RgbImage::from_pixel(1920, 1080, image::Rgb([255, 0, 0])));
+        let crop_result = CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0));
+
+        // Fit: the 16:9 frame is scaled to 1080x607 and placed 1/8 down the
+        // 9:16 canvas, with black bars above and below
+        let fit = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT,
+            GraphicFit::Fit,
+        )
+        .unwrap();
+        assert_eq!((fit.width(), fit.height()), (1080, 1920));
+        for x in [0, 540, 1079] {
+            assert_eq!(fit.get_pixel(x, 239).0, [0, 0, 0]);
+            assert_eq!(fit.get_pixel(x, 240).0, [255, 0, 0]);
+            assert_eq!(fit.get_pixel(x, 240 + 606).0, [255, 0, 0]);
+            assert_eq!(fit.get_pixel(x, 240 + 607).0, [0, 0, 0]);
+        }
+
+        // Fill: the frame is stretched over the whole canvas
+        let fill = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT,
+            GraphicFit::Fill,
+        )
+        .unwrap();
+        assert_eq!((fill.width(), fill.height()), (1080, 1920));
+        for y in [0, 960, 1919] {
+            assert_eq!(fill.get_pixel(540, y).0, [255, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn test_graphic_fit_from_str() {
+        assert_eq!("fit".parse(), Ok(GraphicFit::Fit));
+        assert_eq!("FILL".parse(), Ok(GraphicFit::Fill));
+        assert!("stretch".parse::<GraphicFit>().is_err());
+    }
+
     #[test]
     fn test_resize_crop() {
         // Create a test image
@@ -596,7 +1312,14 @@ mod tests {
         let crop_result = CropResult::Resize(crop);
 
         // Create the resized image with target width of 1080
-        let resized = create_cropped_image(&image, &crop_result, 1080).unwrap();
+        let resized = create_cropped_image(
+            &image,
+            &crop_result,
+            1080,
+            AspectRatio::PORTRAIT,
+            GraphicFit::Fit,
+        )
+        .unwrap();
 
         // Verify dimensions - should be 9:16 aspect ratio
         assert_eq!(resized.width(), 1080); // Width matches target width
@@ -615,4 +1338,27 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_contact_sheet_tiles_in_rows() {
+        let frame = Image::from(RgbImage::new(1920, 1080));
+        let tile = contact_sheet_tile(&frame, PREVIEW_TILE_WIDTH);
+        assert_eq!(tile.dimensions(), (320, 180));
+
+        // 7 tiles, 3 to a row: three rows, the last holding a single tile
+        let tiles: Vec<RgbImage> = (0..7u8)
+            .map(|i| RgbImage::from_pixel(4, 2, image::Rgb([10 + i * 30, 0, 0])))
+            .collect();
+        let sheet = contact_sheet(&tiles, 3);
+        assert_eq!(sheet.dimensions(), (12, 6));
+        assert_eq!(sheet.get_pixel(0, 0)[0], 10);
+        assert_eq!(sheet.get_pixel(4, 0)[0], 40);
+        assert_eq!(sheet.get_pixel(0, 2)[0], 100);
+        assert_eq!(sheet.get_pixel(0, 4)[0], 190);
+        // The rest of the last row stays black
+        assert_eq!(sheet.get_pixel(4, 4)[0], 0);
+
+        // Fewer tiles than columns make a single, narrower row
+        assert_eq!(contact_sheet(&tiles[..2], 6).dimensions(), (8, 2));
+    }
 }
@@ -0,0 +1,341 @@
+use crate::cli::Args;
+use crate::crop;
+use crate::video_processor::VideoProcessor;
+use crate::video_processor_utils;
+use crate::video_sink::VideoSink;
+use anyhow::Result;
+
+/// Standard deviation of the per-frame change in crop velocity, in pixels.
+/// Small values favour smooth, steady pans over reacting to every detection.
+const PROCESS_NOISE_STD: f32 = 0.5;
+
+/// Innovations beyond this many standard deviations are treated as a jump
+/// (a cut or a new subject) and snap the filter instead of gliding to it
+const JUMP_GATE_SIGMAS: f32 = 3.0;
+
+/// Constant-velocity Kalman filter over one coordinate, stepping one frame at a
+/// time. State is position and velocity; only position is measured.
+#[derive(Debug, Clone)]
+pub struct Kalman1D {
+    position: f32,
+    velocity: f32,
+    /// State covariance, row-major `[[pp, pv], [vp, vv]]` (symmetric)
+    covariance: [[f32; 2]; 2],
+    process_variance: f32,
+    measurement_variance: f32,
+}
+
+impl Kalman1D {
+    /// Creates a filter at rest at `position`
+    pub fn new(position: f32, process_std: f32, measurement_std: f32) -> Self {
+        let measurement_variance = measurement_std * measurement_std;
+        Self {
+            position,
+            velocity: 0.0,
+            covariance: [[measurement_variance, 0.0], [0.0, measurement_variance]],
+            process_variance: process_std * process_std,
+            measurement_variance,
+        }
+    }
+
+    /// Advances one frame and folds in `measurement`, returning the filtered
+    /// position. Returns `None` (leaving the filter untouched) when the
+    /// measurement falls outside the jump gate.
+    pub fn update(&mut self, measurement: f32) -> Option<f32> {
+        let [[pp, pv], [_, vv]] = self.covariance;
+        let q = self.process_variance;
+
+        // Predict: x' = F x, P' = F P F^T + Q with F = [[1, 1], [0, 1]] and the
+        // discrete white-noise acceleration model for Q
+        let predicted_position = self.position + self.velocity;
+        let pp = pp + 2.0 * pv + vv + q / 4.0;
+        let pv = pv + vv + q / 2.0;
+        let vv = vv + q;
+
+        // Correct with the position measurement (H = [1, 0])
+        let innovation = measurement - predicted_position;
+        let innovation_variance = pp + self.measurement_variance;
+        if innovation.abs() > JUMP_GATE_SIGMAS * innovation_variance.sqrt() {
+            return None;
+        }
+        let position_gain = pp / innovation_variance;
+        let velocity_gain = pv / innovation_variance;
+
+        self.position = predicted_position + position_gain * innovation;
+        self.velocity += velocity_gain * innovation;
+        self.covariance = [
+            [(1.0 - position_gain) * pp, (1.0 - position_gain) * pv],
+            [(1.0 - position_gain) * pv, vv - velocity_gain * pv],
+        ];
+        Some(self.position)
+    }
+}
+
+/// Filters for the center of one crop area
+#[derive(Debug, Clone)]
+struct AreaTrack {
+    x: Kalman1D,
+    y: Kalman1D,
+}
+
+impl AreaTrack {
+    fn new(area: &crop::CropArea, measurement_std: f32) -> Self {
+        Self {
+            x: Kalman1D::new(
+                area.x + area.width / 2.0,
+                PROCESS_NOISE_STD,
+                measurement_std,
+            ),
+            y: Kalman1D::new(
+                area.y + area.height / 2.0,
+                PROCESS_NOISE_STD,
+                measurement_std,
+            ),
+        }
+    }
+
+    /// Filters the area's center, keeping its size and clamping it inside the
+    /// frame. `None` when either coordinate jumped.
+    fn update(
+        &mut self,
+        area: &crop::CropArea,
+        frame_width: f32,
+        frame_height: f32,
+    ) -> Option<crop::CropArea> {
+        let center_x = self.x.update(area.x + area.width / 2.0)?;
+        let center_y = self.y.update(area.y + area.height / 2.0)?;
+        let max_x = (frame_width - area.width).max(0.0);
+        let max_y = (frame_height - area.height).max(0.0);
+        Some(crop::CropArea::new(
+            (center_x - area.width / 2.0).clamp(0.0, max_x),
+            (center_y - area.height / 2.0).clamp(0.0, max_y),
+            area.width,
+            area.height,
+        ))
+    }
+}
+
+/// Video processor that smooths crop movement with a Kalman filter per crop
+/// center, emitting each frame's crop immediately (no history buffering)
+#[derive(Default)]
+pub struct KalmanSmoothingVideoProcessor {
+    tracks: Vec<AreaTrack>,
+    previous_crop: Option<crop::CropResult>,
+}
+
+impl KalmanSmoothingVideoProcessor {
+    /// Creates a new Kalman smoothing video processor
+    pub fn new() -> Self {
+        Self {
+            tracks: Vec::new(),
+            previous_crop: None,
+        }
+    }
+
+    /// Restarts the filters at `latest_crop`'s areas and returns it unchanged
+    fn reset(&mut self, latest_crop: &crop::CropResult, measurement_std: f32) -> crop::CropResult {
+        self.tracks = match latest_crop {
+            crop::CropResult::Single(area) | crop::CropResult::BlurFill(area) => {
+                vec![AreaTrack::new(area, measurement_std)]
+            }
+            crop::CropResult::Stacked(top, bottom) => vec![
+                AreaTrack::new(top, measurement_std),
+                AreaTrack::new(bottom, measurement_std),
+            ],
+            // The full-frame resize has nothing to track
+            crop::CropResult::Resize(_) => Vec::new(),
+        };
+        latest_crop.clone()
+    }
+
+    /// Filters the latest crop. The measurement noise is `smooth_percentage`
+    /// of the frame width; a change of crop type or a jump beyond the gate
+    /// snaps straight to the new crop.
+    pub fn smooth(
+        &mut self,
+        latest_crop: &crop::CropResult,
+        frame_width: f32,
+        frame_height: f32,
+        smooth_percentage: f32,
+    ) -> crop::CropResult {
+        let measurement_std = (frame_width * smooth_percentage / 100.0).max(1.0);
+
+        let same_type = self
+            .previous_crop
+            .as_ref()
+            .is_some_and(|prev| !crop::crop_types_different(prev, latest_crop));
+
+        let smoothed = if !same_type {
+            video_processor_utils::debug_println(format_args!("Kalman reset: crop type changed"));
+            None
+        } else {
+            match latest_crop {
+                crop::CropResult::Single(area) => self.tracks[0]
+                    .update(area, frame_width, frame_height)
+                    .map(crop::CropResult::Single),
+                crop::CropResult::BlurFill(area) => self.tracks[0]
+                    .update(area, frame_width, frame_height)
+                    .map(crop::CropResult::BlurFill),
+                crop::CropResult::Stacked(top, bottom) => {
+                    let top = self.tracks[0].update(top, frame_width, frame_height);
+                    let bottom = self.tracks[1].update(bottom, frame_width, frame_height);
+                    top.zip(bottom)
+                        .map(|(top, bottom)| crop::CropResult::Stacked(top, bottom))
+                }
+                crop::CropResult::Resize(_) => Some(latest_crop.clone()),
+            }
+        };
+
+        let crop_result = smoothed.unwrap_or_else(|| {
+            video_processor_utils::debug_println(format_args!("Kalman snap to latest crop"));
+            self.reset(latest_crop, measurement_std)
+        });
+        self.previous_crop = Some(crop_result.clone());
+        crop_result
+    }
+}
+
+impl VideoProcessor for KalmanSmoothingVideoProcessor {
+    /// Processes a single frame with Kalman-filtered crop movement
+    fn process_frame_with_smoothing(
+        &mut self,
+        img: &usls::Image,
+        latest_crop: &crop::CropResult,
+        _objects: &[&usls::Hbb],
+        args: &Args,
+        viewer: &mut VideoSink,
+        _smooth_duration_frames: usize,
+    ) -> Result<()> {
+        let crop_result = self.smooth(
+            latest_crop,
+            img.width() as f32,
+            img.height() as f32,
+            args.smooth_percentage,
+        );
+
+        video_processor_utils::process_and_display_crop(img, &crop_result, viewer, args)
+    }
+
+    /// Override debug info to include the previous smoothed crop
+    fn print_debug_info(
+        &self,
+        objects: &[&usls::Hbb],
+        latest_crop: &crop::CropResult,
+        is_graphic: bool,
+    ) {
+        video_processor_utils::print_default_debug_info(objects, latest_crop, is_graphic);
+        video_processor_utils::debug_println(format_args!(
+            "previous_crop: {:?}",
+            self.previous_crop
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic noise in [-amplitude, amplitude] from a simple LCG
+    fn noise(seed: &mut u32, amplitude: f32) -> f32 {
+        *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        ((*seed >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0) * amplitude
+    }
+
+    fn variance(errors: &[f32]) -> f32 {
+        errors.iter().map(|e| e * e).sum::<f32>() / errors.len() as f32
+    }
+
+    #[test]
+    fn test_kalman_reduces_noise_on_straight_line() {
+        let mut seed = 7;
+        let truth = |t: usize| 200.0 + 3.0 * t as f32;
+        let mut filter = Kalman1D::new(truth(0), PROCESS_NOISE_STD, 40.0);
+
+        let mut input_errors = Vec::new();
+        let mut output_errors = Vec::new();
+        for t in 1..300 {
+            let measurement = truth(t) + noise(&mut seed, 40.0);
+            let filtered = filter
+                .update(measurement)
+                .expect("noise stays inside the gate");
+            // Let the velocity estimate converge before comparing
+            if t > 60 {
+                input_errors.push(measurement - truth(t));
+                output_errors.push(filtered - truth(t));
+            }
+        }
+
+        assert!(
+            variance(&output_errors) < variance(&input_errors) / 4.0,
+            "filtered variance {} vs input {}",
+            variance(&output_errors),
+            variance(&input_errors)
+        );
+    }
+
+    #[test]
+    fn test_smooth_single_crop_lowers_variance() {
+        let mut processor = KalmanSmoothingVideoProcessor::new();
+        let mut seed = 42;
+        let truth = |t: usize| 300.0 + 2.0 * t as f32;
+
+        let mut input_errors = Vec::new();
+        let mut output_errors = Vec::new();
+        for t in 0..200 {
+            let x = truth(t) + noise(&mut seed, 30.0);
+            let latest = crop::CropResult::Single(crop::CropArea::new(x, 0.0, 608.0, 1080.0));
+            let crop::CropResult::Single(area) = processor.smooth(&latest, 1920.0, 1080.0, 2.0)
+            else {
+                panic!("crop type should be preserved");
+            };
+            assert_eq!(area.width, 608.0);
+            if t > 40 {
+                input_errors.push(x - truth(t));
+                output_errors.push(area.x - truth(t));
+            }
+        }
+
+        assert!(variance(&output_errors) < variance(&input_errors));
+    }
+
+    #[test]
+    fn test_smooth_snaps_on_crop_type_change_and_jumps() {
+        let mut processor = KalmanSmoothingVideoProcessor::new();
+        let single = |x| crop::CropResult::Single(crop::CropArea::new(x, 0.0, 608.0, 1080.0));
+
+        assert_eq!(
+            processor.smooth(&single(100.0), 1920.0, 1080.0, 2.0),
+            single(100.0)
+        );
+        // A jump far outside the measurement noise snaps instead of gliding
+        assert_eq!(
+            processor.smooth(&single(1200.0), 1920.0, 1080.0, 2.0),
+            single(1200.0)
+        );
+
+        let stacked = crop::CropResult::Stacked(
+            crop::CropArea::new(0.0, 0.0, 960.0, 540.0),
+            crop::CropArea::new(960.0, 0.0, 960.0, 540.0),
+        );
+        assert_eq!(processor.smooth(&stacked, 1920.0, 1080.0, 2.0), stacked);
+    }
+
+    #[test]
+    fn test_smooth_clamps_inside_frame() {
+        let mut processor = KalmanSmoothingVideoProcessor::new();
+        let single = |x| crop::CropResult::Single(crop::CropArea::new(x, 0.0, 608.0, 1080.0));
+
+        // Build up rightward velocity, then hold at the right edge
+        for t in 0..30 {
+            processor.smooth(&single(1000.0 + 10.0 * t as f32), 1920.0, 1080.0, 5.0);
+        }
+        for _ in 0..10 {
+            let crop::CropResult::Single(area) =
+                processor.smooth(&single(1312.0), 1920.0, 1080.0, 5.0)
+            else {
+                panic!("crop type should be preserved");
+            };
+            assert!(area.x + area.width <= 1920.0);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Landscape-to-portrait video conversion. The `land2port` binary is a CLI
+//! over these modules; embedders drive `video_processor::VideoProcessor`
+//! directly, with a `progress::ProgressReporter` in place of the terminal
+//! output.
+
+pub mod audio;
+pub mod ball_video_processor;
+pub mod cli;
+pub mod config;
+pub mod crop;
+pub mod crop_log;
+pub mod error;
+pub mod history;
+pub mod history_smoothing_video_processor;
+pub mod image;
+pub mod kalman_smoothing_video_processor;
+pub mod metrics;
+pub mod moving_average_smoothing_video_processor;
+pub mod orientation;
+pub mod progress;
+pub mod simple_smoothing_video_processor;
+pub mod tracker;
+pub mod transcript;
+pub mod video_processor;
+pub mod video_processor_utils;
+pub mod video_sink;
@@ -1,26 +1,18 @@
-use crate::video_processor::VideoProcessor;
 use anyhow::{Context, Result};
 use chrono::Local;
+use land2port::video_processor::VideoProcessor;
+use land2port::video_processor_utils::status_println;
+use land2port::{
+    audio, ball_video_processor, cli, config, crop, history_smoothing_video_processor, image,
+    kalman_smoothing_video_processor, metrics, moving_average_smoothing_video_processor,
+    orientation, simple_smoothing_video_processor, transcript, video_processor,
+    video_processor_utils, video_sink,
+};
 use std::env;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
-mod audio;
-mod ball_video_processor;
-mod cli;
-mod config;
-mod crop;
-mod history;
-mod history_smoothing_video_processor;
-mod image;
-mod metrics;
-mod simple_smoothing_video_processor;
-mod transcript;
-mod video_processor;
-mod video_processor_utils;
-mod video_sink;
-
 /// Validates that `--source` refers to something we can read before doing any
 /// work, so a typo fails fast with a clear message instead of a cryptic ffmpeg
 /// or DataLoader error after the run directory has already been created.
@@ -35,9 +27,195 @@ fn validate_source(source: &str) -> Result<()> {
     Ok(())
 }
 
+/// Video file extensions picked up when `--source` is a directory.
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v", "mkv", "avi", "webm"];
+
+/// Lists the video files (by extension, case-insensitive) directly inside
+/// `dir`, sorted by path so batch runs are processed in a stable order.
+fn list_video_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut videos = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("Reading source directory {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let is_video = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+        if path.is_file() && is_video {
+            videos.push(path);
+        }
+    }
+    videos.sort();
+    Ok(videos)
+}
+
+/// Image file extensions that make a `--source` directory an image sequence.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp", "webp", "tif", "tiff"];
+
+/// True when `source` is a directory of images (and no videos), which is
+/// processed as a single silent image sequence rather than as a batch.
+fn is_image_sequence(source: &Path) -> Result<bool> {
+    if !source.is_dir() || !list_video_files(source)?.is_empty() {
+        return Ok(false);
+    }
+    for entry in fs::read_dir(source)
+        .with_context(|| format!("Reading source directory {}", source.display()))?
+    {
+        let path = entry?.path();
+        let is_image = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+        if path.is_file() && is_image {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// File stem of a batch video, used to name its outputs
+fn video_stem(video: &Path) -> String {
+    video
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("output"))
+}
+
+/// Output path for one video of a batch run: `<output_dir>/<video stem>.<extension>`.
+fn batch_output_path(output_dir: &str, video: &Path, extension: &str) -> String {
+    Path::new(output_dir)
+        .join(format!("{}.{}", video_stem(video), extension))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// `--crop-log-csv` path for one video of a batch run, so each video keeps
+/// its own log: `<dir>/<csv stem>.<video stem>.csv` beside the given file.
+fn batch_crop_log_path(crop_log_csv: &str, video: &Path) -> String {
+    let path = Path::new(crop_log_csv);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("crops"));
+    path.with_file_name(format!("{}.{}.csv", stem, video_stem(video)))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Where the `--keep-source-copy` original is delivered next to the output
+/// video: `<final stem>_landscape.<source extension>`, so batch runs writing
+/// into one directory keep one copy per video.
+fn landscape_delivery_path(landscape: &str, final_path: &str) -> String {
+    let final_path = Path::new(final_path);
+    let stem = final_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("output"));
+    let extension = Path::new(landscape)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("mp4");
+    final_path
+        .with_file_name(format!("{}_landscape.{}", stem, extension))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Run directory for one video of a batch run with `--output-dir`:
+/// `<output_dir>/<video stem>`.
+fn batch_run_dir(output_dir: &str, video: &Path) -> String {
+    Path::new(output_dir)
+        .join(video_stem(video))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Creates the run directory and returns its absolute path: `output_dir` when
+/// given (which must be empty unless `overwrite` is set), otherwise a new
+/// timestamped directory.
+fn create_output_dir(output_dir: Option<&str>, overwrite: bool) -> Result<String> {
+    let Some(output_dir) = output_dir else {
+        return create_timestamped_output_dir();
+    };
+
+    let output_dir = env::current_dir()
+        .context("Getting current working directory")?
+        .join(output_dir);
+    if !overwrite
+        && let Ok(mut entries) = fs::read_dir(&output_dir)
+        && entries.next().is_some()
+    {
+        anyhow::bail!(
+            "output directory {} is not empty; pass --overwrite to reuse it",
+            output_dir.display()
+        );
+    }
+    fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Creating output directory {}", output_dir.display()))?;
+    Ok(output_dir.to_string_lossy().into_owned())
+}
+
+/// Returns the absolute path of the `--resume-from` run directory, which must
+/// already exist
+fn resume_run_dir(run_dir: &str) -> Result<String> {
+    let run_dir = env::current_dir()
+        .context("Getting current working directory")?
+        .join(run_dir);
+    if !run_dir.is_dir() {
+        anyhow::bail!("run directory to resume not found: {}", run_dir.display());
+    }
+    Ok(run_dir.to_string_lossy().into_owned())
+}
+
+/// Whether a previous run's audio or video at `path` can be reused: present,
+/// and complete enough for ffprobe to read a duration (an encode cut short
+/// has none)
+fn is_reusable_media(path: &str) -> bool {
+    Path::new(path).is_file()
+        && video_sink::probe_duration(path).is_some_and(|duration| duration > 0.0)
+}
+
+/// With `--resume-from`, whether the previous run's output of a
+/// preprocessing step (clipping, trimming, rotation, frame rate
+/// normalization) at `path` can stand in for running the step again
+fn resumable_intermediate(args: &cli::Args, path: &str) -> bool {
+    args.resume_from.is_some() && is_reusable_media(path)
+}
+
+/// Whether a previous run's caption file at `path` can be reused: present and
+/// holding at least one timed cue (`-->` in SRT and VTT, `Dialogue:` in ASS)
+fn is_reusable_captions(path: &str) -> bool {
+    fs::read_to_string(path).is_ok_and(|text| text.contains("-->") || text.contains("Dialogue:"))
+}
+
+/// With `--resume-from`, the extracted audio and the captions to burn that a
+/// previous run left in `output_dir`, when every caption file these options
+/// deliver is there to reuse
+fn resumable_captions(args: &cli::Args, output_dir: &str) -> Option<(String, String)> {
+    args.resume_from.as_ref()?;
+    let extracted_audio = format!("{}/extracted_audio.mp4", output_dir);
+    let srt_path = format!("{}/transcript.srt", output_dir);
+    let subtitles_path = if args.caption_karaoke {
+        format!("{}/transcript.ass", output_dir)
+    } else if args.caption_dynamic_size {
+        format!("{}/transcript.sized.srt", output_dir)
+    } else {
+        srt_path.clone()
+    };
+    let json_path = format!("{}/transcript.json", output_dir);
+    let vtt_path = format!("{}/transcript.vtt", output_dir);
+    let reusable = is_reusable_media(&extracted_audio)
+        && is_reusable_captions(&srt_path)
+        && is_reusable_captions(&subtitles_path)
+        && (!args.transcript_json || Path::new(&json_path).is_file())
+        && (!args.caption_format.includes_vtt() || is_reusable_captions(&vtt_path));
+    reusable.then_some((extracted_audio, subtitles_path))
+}
+
 /// Creates a timestamped output directory and returns its absolute path.
 /// Uses LAND2PORT_RUNS_DIR if set (e.g. /app/runs in the container), else cwd/runs.
-fn create_output_dir() -> Result<String> {
+fn create_timestamped_output_dir() -> Result<String> {
     let timestamp = Local::now().format("%Y%m%d_%H%M%S_%f").to_string();
     let base: PathBuf = match env::var("LAND2PORT_RUNS_DIR") {
         Ok(d) => PathBuf::from(d),
@@ -63,10 +241,18 @@ fn sync_output_file(path: &str) -> Result<()> {
         .with_context(|| format!("Opening output file for fsync: {}", path))?;
     f.sync_all()
         .with_context(|| format!("Fsyncing output file: {}", path))?;
-    println!("Output file synced: {}", path);
+    status_println(format_args!("Output file synced: {}", path));
     Ok(())
 }
 
+/// Prints the finished video's path on its own line under `--quiet`, the
+/// one line of output scripts capture; status messages report it otherwise
+fn print_output_path(path: &str) {
+    if video_processor_utils::is_quiet() {
+        println!("{}", path);
+    }
+}
+
 /// Copy a file to a destination path, creating parent dirs. Uses io::copy for
 /// compatibility with FUSE (e.g. GCS) where fs::copy can fail. Source should be
 /// an absolute path so it resolves regardless of cwd.
@@ -83,12 +269,12 @@ fn copy_to_output(source: &str, dest: &str) -> Result<()> {
         );
     }
     let meta = fs::metadata(source_path).with_context(|| format!("Stat source file {}", source))?;
-    println!(
+    status_println(format_args!(
         "Copying source {} ({}) to {}",
         source_path.display(),
         human_size(meta.len()),
         dest
-    );
+    ));
 
     if let Some(parent) = dest_path.parent() {
         fs::create_dir_all(parent)
@@ -106,6 +292,280 @@ fn copy_to_output(source: &str, dest: &str) -> Result<()> {
     Ok(())
 }
 
+/// Saves `poster.jpg` in `output_dir` from the frame of `video` at
+/// `--poster-time`, at the most confident detection with `--poster-auto`, or
+/// otherwise in the middle of the video
+fn write_poster(
+    args: &cli::Args,
+    output_dir: &str,
+    video: &str,
+    best_frame_time: Option<f64>,
+) -> Result<()> {
+    let duration = video_sink::probe_duration(video);
+    let time = if let Some(time) = args.poster_time {
+        if let Some(duration) = duration
+            && time >= duration
+        {
+            anyhow::bail!("--poster-time ({time}s) is past the end of the video ({duration:.3}s)");
+        }
+        time
+    } else if let Some(time) = best_frame_time.filter(|_| args.poster_auto) {
+        time
+    } else {
+        if args.poster_auto {
+            status_println(format_args!(
+                "No detections to pick a poster frame from; using the middle of the video"
+            ));
+        }
+        duration.map_or(0.0, |duration| duration / 2.0)
+    };
+
+    let poster = format!("{}/poster.jpg", output_dir);
+    metrics::time("poster", || audio::extract_poster(video, &poster, time))?;
+    status_println(format_args!(
+        "Poster frame at {time:.3}s saved to: {}",
+        poster
+    ));
+    Ok(())
+}
+
+/// Copies the `--keep-source-copy` original from the run directory next to
+/// the delivered `final_path`
+fn deliver_landscape_copy(landscape: &str, final_path: &str) -> Result<()> {
+    let dest = landscape_delivery_path(landscape, final_path);
+    metrics::time("keep_source_copy", || copy_to_output(landscape, &dest))?;
+    status_println(format_args!("Landscape copy delivered to: {}", dest));
+    Ok(())
+}
+
+/// Writes `srt_path`'s captions to `sized_path` with each cue's font size
+/// scaled by how loud `audio_path` is under it, for `--caption-dynamic-size`
+fn write_sized_captions(
+    args: &cli::Args,
+    audio_path: &str,
+    srt_path: &str,
+    sized_path: &str,
+) -> Result<()> {
+    let srt =
+        fs::read_to_string(srt_path).with_context(|| format!("Reading captions {}", srt_path))?;
+    let font_sizes = audio::cue_font_sizes(
+        audio_path,
+        &transcript::srt_cue_times(&srt),
+        args.caption_style()?.font_size,
+    )?;
+    fs::write(sized_path, transcript::size_srt_cues(&srt, &font_sizes))
+        .with_context(|| format!("Writing sized captions {}", sized_path))?;
+    Ok(())
+}
+
+/// Segments the final `video` into `.ts` chunks with a `playlist.m3u8` in
+/// the run directory for `--output-hls`
+fn write_hls(args: &cli::Args, output_dir: &str, video: &str) -> Result<()> {
+    status_println(format_args!("Segmenting video for HLS..."));
+    metrics::time("hls", || {
+        audio::segment_hls(video, output_dir, args.hls_segment_duration)
+    })?;
+    status_println(format_args!(
+        "HLS playlist saved to: {}/playlist.m3u8",
+        output_dir
+    ));
+    Ok(())
+}
+
+/// Extends the final `video` in place to `--pad-to-duration` by holding its
+/// last frame; one already at the target is left alone, and one longer than
+/// it is an error
+fn pad_output(args: &cli::Args, output_dir: &str, video: &str) -> Result<()> {
+    let Some(target) = args.pad_to_duration else {
+        return Ok(());
+    };
+    let duration = video_sink::probe_duration(video)
+        .with_context(|| format!("Probing the duration of {} to pad it", video))?;
+    if duration > target + audio::PAD_DURATION_TOLERANCE {
+        anyhow::bail!(
+            "the final video runs {duration:.3}s, longer than --pad-to-duration {target}s"
+        );
+    }
+    if (target - duration).abs() < 0.001 {
+        return Ok(());
+    }
+
+    let padded = format!(
+        "{}/padded_output.{}",
+        output_dir,
+        args.output_format.extension()
+    );
+    status_println(format_args!(
+        "Padding video from {duration:.3}s to {target}s..."
+    ));
+    let encode = args.encode_settings()?;
+    metrics::time("pad", || {
+        audio::pad_video(video, &padded, target, args.output_format, &encode)
+    })?;
+    metrics::time("stage_out", || move_to_output(&padded, video))?;
+    Ok(())
+}
+
+/// Errors when `--output-filepath` names a directory rather than a file, so
+/// the mistake is reported before any processing instead of at delivery
+fn check_output_file_path(path: &str) -> Result<()> {
+    if Path::new(path).is_dir() || path.ends_with('/') || path.ends_with(std::path::MAIN_SEPARATOR)
+    {
+        anyhow::bail!(
+            "--output-filepath {} is a directory; pass a file path such as {}",
+            path,
+            Path::new(path).join("output.mp4").display()
+        );
+    }
+    Ok(())
+}
+
+/// Moves the finished video to `dest`, creating parent dirs. A rename is
+/// tried first; when it fails (e.g. across devices or onto a FUSE mount) the
+/// file is copied with `copy_to_output` and the source removed.
+fn move_to_output(source: &str, dest: &str) -> Result<()> {
+    check_output_file_path(dest)?;
+    if let Some(parent) = Path::new(dest).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Creating destination directory {}", parent.display()))?;
+    }
+    if let Err(e) = fs::rename(source, dest) {
+        status_println(format_args!(
+            "Rename to {} failed ({}), copying instead",
+            dest, e
+        ));
+        copy_to_output(source, dest)?;
+        fs::remove_file(source).with_context(|| format!("Removing moved file {}", source))?;
+    }
+    Ok(())
+}
+
+/// Finishes the extra `--output-resolution` renderings of `processed_video`
+/// the way the main output was finished: `subtitles` are burned in and `audio`
+/// muxed in when given, and the video converted to the output format. Each is
+/// delivered next to `final_path` with its size in the name (e.g.
+/// `final_output_720x1280.mp4`); the delivered paths are returned.
+fn finish_extra_sizes(
+    args: &cli::Args,
+    output_dir: &str,
+    processed_video: &str,
+    final_path: &str,
+    subtitles: Option<&str>,
+    audio_path: Option<&str>,
+) -> Result<Vec<String>> {
+    let extension = args.output_format.extension();
+    let caption_style = subtitles.map(|_| args.caption_style()).transpose()?;
+    let encode = args.encode_settings()?;
+    let mut delivered = Vec::new();
+    for &size in args.output_sizes()?.iter().skip(1) {
+        let mut video = video_sink::resolution_output_path(processed_video, size);
+        let dest = video_sink::resolution_output_path(final_path, size);
+        if let Some(subtitles) = subtitles {
+            let captioned = format!("{}/captioned_video_{}.{}", output_dir, size, extension);
+            metrics::time("burn_captions", || {
+                audio::burn_captions(
+                    &video,
+                    subtitles,
+                    &captioned,
+                    caption_style.clone(),
+                    args.output_format,
+                    &encode,
+                )
+            })?;
+            video = captioned;
+        }
+        if let Some(audio_path) = audio_path {
+            let combined = format!("{}/final_output_{}.{}", output_dir, size, extension);
+            metrics::time("combine_av", || {
+                audio::combine_video_audio(&video, audio_path, &combined, args.output_format)
+            })?;
+            video = combined;
+        } else if subtitles.is_none() && args.output_format != audio::OutputFormat::Mp4 {
+            let converted = format!("{}/final_output_{}.{}", output_dir, size, extension);
+            metrics::time("convert_output", || {
+                audio::convert_video(&video, &converted, args.output_format, &encode)
+            })?;
+            video = converted;
+        }
+        // The direct-write path already encoded this size next to the output
+        if video != dest {
+            metrics::time("stage_out", || move_to_output(&video, &dest))?;
+        }
+        pad_output(args, output_dir, &dest)?;
+        sync_output_file(&dest)?;
+        status_println(format_args!("{} video saved to: {}", size, dest));
+        print_output_path(&dest);
+        delivered.push(dest);
+    }
+    Ok(delivered)
+}
+
+/// Intermediate files a run may leave in its run directory, deleted by
+/// `--cleanup` once the final video is delivered. Transcripts, crop and cut
+/// exports, and metrics are small and kept.
+const INTERMEDIATE_FILES: &[&str] = &[
+    "clipped_input.mp4",
+    "trimmed_input.mp4",
+    "cfr_input.mp4",
+    "rotated_input.mp4",
+    "extracted_audio.mp4",
+    "compressed_audio.mp3",
+    "compressed_audio.wav",
+    "processed_video.mp4",
+    "processed_video.webm",
+    "captioned_video.mp4",
+    "captioned_video.webm",
+    "mixed_audio.mp4",
+    "normalized_audio.mp4",
+    "final_output.mp4",
+    "final_output.webm",
+    "padded_output.mp4",
+    "padded_output.webm",
+];
+
+/// Whether `name` is one of `INTERMEDIATE_FILES`, or an `--output-resolution`
+/// rendering of one (e.g. `processed_video_720x1280.mp4`)
+fn is_intermediate_file(name: &str) -> bool {
+    if INTERMEDIATE_FILES.contains(&name) {
+        return true;
+    }
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    stem.rsplit_once('_').is_some_and(|(base, size)| {
+        size.parse::<video_sink::OutputSize>().is_ok()
+            && INTERMEDIATE_FILES.contains(&format!("{}.{}", base, extension).as_str())
+    })
+}
+
+/// Deletes the intermediate files in `output_dir` (including a staged copy of
+/// the source), except the `delivered` videos that live there, and returns
+/// the number of bytes freed
+fn cleanup_intermediates(output_dir: &str, delivered: &[String]) -> Result<u64> {
+    let mut freed = 0;
+    let entries = fs::read_dir(output_dir)
+        .with_context(|| format!("Reading output directory {}", output_dir))?;
+    for entry in entries {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let is_intermediate = is_intermediate_file(&name) || name.starts_with("staged_input.");
+        if !is_intermediate
+            || !path.is_file()
+            || delivered.iter().any(|video| path == Path::new(video))
+        {
+            continue;
+        }
+        freed += fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        fs::remove_file(&path).with_context(|| format!("Removing {}", path.display()))?;
+    }
+    Ok(freed)
+}
+
 fn human_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
@@ -121,58 +581,619 @@ fn human_size(bytes: u64) -> String {
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    metrics::init();
-    let mut args: cli::Args = argh::from_env();
+fn main() -> Result<()> {
+    let args = cli::Args::from_env_with_config()?;
+    // usls reads its cache location from the environment, which is only safe
+    // to change before the runtime spawns its worker threads
+    if let Some(cache_dir) = &args.model_cache_dir {
+        config::use_model_cache_dir(cache_dir)?;
+    }
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("Starting the async runtime")?
+        .block_on(run(args))
+}
+
+async fn run(mut args: cli::Args) -> Result<()> {
+    video_processor_utils::set_quiet(args.quiet);
 
-    // Fail fast on a missing source before creating run dirs or extracting audio.
+    // Fail fast on a missing source or invalid caption/encoder options before
+    // creating run dirs or extracting audio.
     validate_source(&args.source)?;
+    let image_sequence = is_image_sequence(Path::new(&args.source))?;
+    if image_sequence {
+        if args.start_time.is_some() || args.end_time.is_some() || args.trim_silence {
+            anyhow::bail!(
+                "--start-time, --end-time and --trim-silence need a video source, not an image folder"
+            );
+        }
+        if args.keep_source_copy {
+            anyhow::bail!("--keep-source-copy needs a video source, not an image folder");
+        }
+        if args.captions_only {
+            anyhow::bail!("--captions-only needs a video source, not an image folder");
+        }
+        if args.rotate.is_some() || args.auto_rotate {
+            anyhow::bail!("--rotate and --auto-rotate need a video source, not an image folder");
+        }
+        // An image sequence has no audio track, so only detection and cropping run
+        if args.add_captions {
+            status_println(format_args!(
+                "Source is an image sequence with no audio; skipping audio and captions"
+            ));
+        }
+        args.add_captions = false;
+        args.transcript_json = false;
+        args.caption_dynamic_size = false;
+        args.music = None;
+        args.normalize_audio = false;
+    }
+    if args.rotate.is_some() && args.auto_rotate {
+        anyhow::bail!("--rotate and --auto-rotate are mutually exclusive");
+    }
+    if args.resume_from.is_some() {
+        if args.output_dir.is_some() {
+            anyhow::bail!("--resume-from and --output-dir are mutually exclusive");
+        }
+        if Path::new(&args.source).is_dir() && !image_sequence {
+            anyhow::bail!("--resume-from resumes a single run; it needs a video --source");
+        }
+    }
+    if args.preview_grid == Some(0) {
+        anyhow::bail!("--preview-grid must be at least 1");
+    }
+    if args.captions_only {
+        if args.import_crops.is_some()
+            || args.export_crops
+            || args.export_cuts
+            || args.crop_log_csv.is_some()
+            || args.debug_overlay
+            || args.preview_grid.is_some()
+            || args.output_resolution.is_some()
+        {
+            anyhow::bail!(
+                "--captions-only skips detection and cropping; it cannot be combined with --import-crops, --export-crops, --export-cuts, --crop-log-csv, --debug-overlay, --preview-grid or --output-resolution"
+            );
+        }
+        args.add_captions = true;
+    }
+    if args.report_confidences {
+        if args.import_crops.is_some() || args.captions_only {
+            anyhow::bail!(
+                "--report-confidences cannot be combined with --import-crops or --captions-only, which skip detection"
+            );
+        }
+        // Analysis only: detect across the clip without writing any video
+        args.dry_run = true;
+    }
+    config::check_object_names(&args.object_names(), args.model_path.is_some())?;
+    // With --fallback-center-crop a missing model is left to fail at load
+    // time, where it falls back to a center crop instead of aborting here
+    if let Some(model_path) = &args.model_path
+        && !args.fallback_center_crop
+    {
+        config::check_model_path(model_path)?;
+    }
+    args.caption_style()?;
+    args.transcript_config()?;
+    transcript::transcriber_for(
+        args.transcribe_backend,
+        &args.whisper_command,
+        args.whisper_model.as_deref(),
+    )?;
+    args.encode_settings()?;
+    args.output_sizes()?;
+    video_sink::check_window_scale(args.window_scale)?;
+    args.clip_times()?;
+    crop::check_crop_padding(args.crop_padding)?;
+    crop::check_deadzone(args.deadzone)?;
+    crop::check_stack_ratio(args.stack_ratio)?;
+    args.aspect_ratio = args.aspect_ratio.with_stack_ratio(args.stack_ratio);
+    video_processor_utils::check_prediction_damping(args.prediction_damping)?;
+    image::check_black_frame_threshold(args.black_frame_threshold)?;
+    if let Some(duration) = args.transition_duration {
+        video_processor_utils::check_transition_duration(duration)?;
+    }
+    if let Some(duration) = args.min_interp_duration {
+        video_processor_utils::check_min_interp_duration(duration)?;
+    }
+    if let Some(max_fps) = args.max_fps {
+        video_processor_utils::check_max_fps(max_fps)?;
+    }
+    if args.adaptive_threshold {
+        video_processor_utils::check_adaptive_threshold_floor(
+            args.adaptive_threshold_floor,
+            args.object_prob_threshold,
+        )?;
+    }
+    if let Some(exit) = args.text_area_exit_threshold {
+        video_processor_utils::check_text_area_exit_threshold(exit, args.text_area_threshold)?;
+    }
+    if let Some(target) = args.translate_target()? {
+        if args.caption_karaoke {
+            anyhow::bail!("--translate-to cannot be combined with --caption-karaoke");
+        }
+        if let transcript::Translation::Whisper =
+            transcript::translation_for(&target, args.translator_command.as_deref())?
+            && args.transcribe_backend == transcript::TranscribeBackend::Local
+        {
+            anyhow::bail!(
+                "--transcribe-backend local cannot translate with Whisper; pass --translator-command"
+            );
+        }
+    }
+    if args.transcribe_backend == transcript::TranscribeBackend::Local
+        && (args.caption_karaoke || args.transcript_json)
+    {
+        anyhow::bail!(
+            "--caption-karaoke and --transcript-json need word timestamps, which only --transcribe-backend cloud provides"
+        );
+    }
+    if args.deterministic_transcription
+        && args.transcribe_backend != transcript::TranscribeBackend::Local
+    {
+        anyhow::bail!(
+            "--deterministic-transcription needs --transcribe-backend local; the cloud API's output can't be pinned"
+        );
+    }
+    if let Some(music) = &args.music {
+        if !args.add_captions {
+            anyhow::bail!("--music requires --add-captions");
+        }
+        if !Path::new(music).is_file() {
+            anyhow::bail!("music file not found: {music}");
+        }
+        audio::check_music_volume(args.music_volume)?;
+    }
+    if args.export_cuts && args.import_crops.is_some() {
+        anyhow::bail!(
+            "--export-cuts cannot be combined with --import-crops, which skips detection"
+        );
+    }
+    if (args.poster_time.is_some() || args.poster_auto) && !args.poster {
+        anyhow::bail!("--poster-time and --poster-auto require --poster");
+    }
+    if args.poster_time.is_some() && args.poster_auto {
+        anyhow::bail!("--poster-time and --poster-auto are mutually exclusive");
+    }
+    if let Some(time) = args.poster_time
+        && !(0.0..).contains(&time)
+    {
+        anyhow::bail!("invalid poster time {time}: expected 0 or more seconds");
+    }
+    if args.output_hls {
+        if args.output_format != audio::OutputFormat::Mp4 {
+            anyhow::bail!("--output-hls needs --output-format mp4 for its .ts segments");
+        }
+        audio::check_hls_segment_duration(args.hls_segment_duration)?;
+    }
+    if let Some(target) = args.pad_to_duration {
+        audio::check_pad_duration(target)?;
+    }
+    if args.poster_auto && args.import_crops.is_some() {
+        anyhow::bail!(
+            "--poster-auto cannot be combined with --import-crops, which skips detection"
+        );
+    }
+    if let Some(max_chars) = args.caption_max_chars {
+        if !args.add_captions {
+            anyhow::bail!("--caption-max-chars requires --add-captions");
+        }
+        if args.caption_karaoke {
+            anyhow::bail!(
+                "--caption-max-chars cannot be combined with --caption-karaoke, whose captions are built from word timings"
+            );
+        }
+        if max_chars == 0 {
+            anyhow::bail!("--caption-max-chars must be at least 1");
+        }
+    }
+    if args.transcript_json && !args.add_captions {
+        anyhow::bail!("--transcript-json requires --add-captions");
+    }
+    if args.caption_dynamic_size && !args.add_captions {
+        anyhow::bail!("--caption-dynamic-size requires --add-captions");
+    }
+    if args.debug_overlay && args.headless {
+        anyhow::bail!(
+            "--debug-overlay draws on the preview frames, so it cannot be used with --headless"
+        );
+    }
+    if args.interactive_pick {
+        if args.headless {
+            anyhow::bail!(
+                "--interactive-pick asks in the preview window, so it cannot be used with --headless"
+            );
+        }
+        if args.import_crops.is_some() || args.captions_only {
+            anyhow::bail!(
+                "--interactive-pick cannot be combined with --import-crops or --captions-only, which skip detection"
+            );
+        }
+        if args.focus != crop::Focus::All {
+            anyhow::bail!("--interactive-pick and --focus both choose the subject; use one");
+        }
+    }
+    if args.annotate_graphics {
+        if args.headless {
+            anyhow::bail!(
+                "--annotate-graphics draws on the preview frames, so it cannot be used with --headless"
+            );
+        }
+        if !args.keep_text && !args.prioritize_text {
+            anyhow::bail!(
+                "--annotate-graphics requires --keep-text or --prioritize-text, which run text detection"
+            );
+        }
+    }
+    let smoothing_flags = [
+        args.use_simple_smoothing,
+        args.use_kalman_smoothing,
+        args.use_moving_average,
+    ];
+    if smoothing_flags.iter().filter(|&&flag| flag).count() > 1 {
+        anyhow::bail!(
+            "--use-simple-smoothing, --use-kalman-smoothing and --use-moving-average are mutually exclusive"
+        );
+    }
+    if args.loop_smoothing && (args.object == "ball" || smoothing_flags.iter().any(|&flag| flag)) {
+        anyhow::bail!(
+            "--loop-smoothing only works with the default history smoothing, not with ball tracking or another smoothing mode"
+        );
+    }
+    if args.smooth_window == 0 {
+        anyhow::bail!("--smooth-window must be at least 1");
+    }
+    let output_extension = Path::new(&args.output_filepath)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+    if let Some(extension) = output_extension
+        && (!Path::new(&args.source).is_dir() || image_sequence)
+        && ["mp4", "webm"].contains(&extension.as_str())
+        && extension != args.output_format.extension()
+    {
+        anyhow::bail!(
+            "--output-filepath ends in .{extension} but --output-format is {}; use a .{} file",
+            args.output_format.extension(),
+            args.output_format.extension()
+        );
+    }
+    if let Some(bitrate) = &args.audio_bitrate {
+        audio::check_audio_bitrate(bitrate)?;
+    }
+    if args.normalize_audio {
+        if !args.add_captions {
+            anyhow::bail!("--normalize-audio requires --add-captions");
+        }
+        audio::check_target_lufs(args.target_lufs)?;
+    }
+
+    if Path::new(&args.source).is_dir() && !image_sequence {
+        process_directory(&args).await
+    } else {
+        process_source(args).await
+    }
+}
+
+/// Processes every video in the `--source` directory, each into its own
+/// timestamped run directory. A failed video is logged and skipped so the rest
+/// of the batch still runs; a summary of succeeded/failed files is printed at
+/// the end, and the run errors if any file failed.
+async fn process_directory(args: &cli::Args) -> Result<()> {
+    let videos = list_video_files(Path::new(&args.source))?;
+    if videos.is_empty() {
+        anyhow::bail!("no video files found in source directory: {}", args.source);
+    }
+    status_println(format_args!(
+        "Batch processing {} videos from {}",
+        videos.len(),
+        args.source
+    ));
+
+    let mut succeeded = Vec::new();
+    let mut failed = Vec::new();
+    for (index, video) in videos.iter().enumerate() {
+        status_println(format_args!(
+            "\n[{}/{}] Processing {}",
+            index + 1,
+            videos.len(),
+            video.display()
+        ));
+
+        let mut video_args = args.clone();
+        video_args.source = video.to_string_lossy().into_owned();
+        if !args.output_filepath.is_empty() {
+            video_args.output_filepath =
+                batch_output_path(&args.output_filepath, video, args.output_format.extension());
+        }
+        if let Some(output_dir) = &args.output_dir {
+            video_args.output_dir = Some(batch_run_dir(output_dir, video));
+        }
+        if let Some(crop_log_csv) = &args.crop_log_csv {
+            video_args.crop_log_csv = Some(batch_crop_log_path(crop_log_csv, video));
+        }
+
+        match process_source(video_args).await {
+            Ok(()) => succeeded.push(video),
+            Err(e) => {
+                eprintln!("Failed to process {}: {:#}", video.display(), e);
+                failed.push(video);
+            }
+        }
+    }
+
+    status_println(format_args!("\n==== batch summary ===="));
+    status_println(format_args!("succeeded: {}", succeeded.len()));
+    for video in &succeeded {
+        status_println(format_args!("  {}", video.display()));
+    }
+    status_println(format_args!("failed: {}", failed.len()));
+    for video in &failed {
+        status_println(format_args!("  {}", video.display()));
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!("{} of {} videos failed", failed.len(), videos.len());
+    }
+    Ok(())
+}
+
+/// Runs the full pipeline (audio extraction, transcription, cropping, and
+/// recombination) for a single source video.
+async fn process_source(mut args: cli::Args) -> Result<()> {
+    metrics::init();
+
+    if !args.output_filepath.is_empty() {
+        check_output_file_path(&args.output_filepath)?;
+    }
 
     let cwd = env::current_dir().context("Getting current working directory")?;
-    println!("Working directory: {}", cwd.display());
+    status_println(format_args!("Working directory: {}", cwd.display()));
 
-    // Create timestamped output directory (absolute path)
-    let output_dir = create_output_dir()?;
-    println!("Created output directory: {}", output_dir);
+    // Create timestamped output directory (absolute path), or pick up the
+    // previous run's to reuse its intermediates
+    let output_dir = if let Some(run_dir) = &args.resume_from {
+        let output_dir = resume_run_dir(run_dir)?;
+        status_println(format_args!("Resuming run in: {}", output_dir));
+        output_dir
+    } else {
+        let output_dir = create_output_dir(args.output_dir.as_deref(), args.overwrite)?;
+        status_println(format_args!("Created output directory: {}", output_dir));
+        output_dir
+    };
 
     // Local-staging: copy the source onto local disk (the output_dir lives on the
     // container's local fs) so decode reads from local storage instead of a
     // network mount. Output is likewise written locally and copied back at the
     // end (handled by the non-direct-write path below).
-    if args.local_stage && !args.source.is_empty() {
+    if args.local_stage && !args.source.is_empty() && Path::new(&args.source).is_file() {
         let ext = Path::new(&args.source)
             .extension()
             .and_then(|e| e.to_str())
             .unwrap_or("mp4");
         let staged_source = format!("{}/staged_input.{}", output_dir, ext);
         metrics::time("stage_in", || copy_to_output(&args.source, &staged_source))?;
-        println!("Staged source locally: {}", staged_source);
+        status_println(format_args!("Staged source locally: {}", staged_source));
         args.source = staged_source;
     }
 
-    // When output_filepath is set and we're not adding captions, write directly
-    // there so we avoid the copy step and any temp-file behavior in the video
-    // library (usls) that can leave the file missing at the expected temp path
-    // (e.g. on GCS FUSE). With --local-stage we deliberately skip this direct
-    // write so the encode goes to local disk first.
-    let processed_video =
-        if !args.add_captions && !args.output_filepath.is_empty() && !args.local_stage {
-            if let Some(parent) = Path::new(&args.output_filepath).parent() {
-                fs::create_dir_all(parent)
-                    .with_context(|| format!("Creating output directory {}", parent.display()))?;
-            }
-            println!("Writing processed video directly to: {}", args.output_filepath);
-            args.output_filepath.clone()
+    // Cut out the requested clip first, so every later step (silence trimming,
+    // detection, transcription, captions) runs on it with timestamps from zero
+    let (start_time, end_time) = args.clip_times()?;
+    let clipped_source = format!("{}/clipped_input.mp4", output_dir);
+    if (start_time.is_some() || end_time.is_some())
+        && resumable_intermediate(&args, &clipped_source)
+    {
+        status_println(format_args!("Reusing clipped source: {clipped_source}"));
+        args.source = clipped_source;
+    } else if start_time.is_some() || end_time.is_some() {
+        audio::check_ffmpeg_installed(&["libx264"])?;
+        let duration = video_sink::probe_duration(&args.source)
+            .with_context(|| format!("Could not determine the duration of {}", args.source))?;
+        let (start, end) = audio::clip_bounds(start_time, end_time, duration)?;
+        metrics::time("clip", || {
+            audio::cut_segment(&args.source, &clipped_source, start, end)
+        })?;
+        status_println(format_args!(
+            "Clipped source to {start:.3}s-{end:.3}s: {clipped_source}"
+        ));
+        args.source = clipped_source;
+    }
+
+    // Trim dead air before anything reads the source, so cropping and
+    // transcription both run on the trimmed video and captions stay in sync
+    let trimmed_source = format!("{}/trimmed_input.mp4", output_dir);
+    if args.trim_silence && resumable_intermediate(&args, &trimmed_source) {
+        status_println(format_args!("Reusing trimmed source: {trimmed_source}"));
+        args.source = trimmed_source;
+    } else if args.trim_silence {
+        audio::check_ffmpeg_installed(&["libx264"])?;
+        args.source = metrics::time("trim_silence", || {
+            audio::trim_silence(
+                &args.source,
+                &trimmed_source,
+                args.silence_threshold_db,
+                args.silence_min_duration,
+            )
+        })?;
+        if args.source == trimmed_source {
+            status_println(format_args!(
+                "Trimmed leading/trailing silence: {}",
+                trimmed_source
+            ));
         } else {
-            format!("{}/processed_video.mp4", output_dir)
-        };
+            status_println(format_args!("No leading/trailing silence to trim"));
+        }
+    }
 
-    // If adding captions, prepare audio/transcription artifacts first
-    let (extracted_audio, srt_path) = if args.add_captions {
-        // Verify ffmpeg is installed
-        audio::check_ffmpeg_installed()?;
+    // Keep the landscape original covering the same range as the crop, before
+    // any frame rate normalization re-encodes it
+    let landscape_copy = if args.keep_source_copy {
+        if !Path::new(&args.source).is_file() {
+            anyhow::bail!(
+                "--keep-source-copy needs a video file source, not {}",
+                args.source
+            );
+        }
+        let extension = Path::new(&args.source)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("mp4");
+        let landscape = format!("{}/landscape.{}", output_dir, extension);
+        metrics::time("keep_source_copy", || {
+            copy_to_output(&args.source, &landscape)
+        })?;
+        status_println(format_args!("Landscape copy saved to: {}", landscape));
+        Some(landscape)
+    } else {
+        None
+    };
+
+    // Turn sideways or upside-down footage upright before anything detects
+    // on it or transcribes it
+    let rotated_source = format!("{}/rotated_input.mp4", output_dir);
+    let rotation = if (args.rotate.is_some() || args.auto_rotate)
+        && resumable_intermediate(&args, &rotated_source)
+    {
+        status_println(format_args!("Reusing rotated source: {rotated_source}"));
+        args.source = rotated_source.clone();
+        orientation::Rotation::Deg0
+    } else if let Some(rotation) = args.rotate {
+        rotation
+    } else if args.auto_rotate {
+        let rotation = metrics::time("detect_rotation", || {
+            orientation::detect_rotation(&args, &output_dir)
+        })?;
+        status_println(format_args!(
+            "Detected rotation: {} degrees",
+            rotation.degrees()
+        ));
+        rotation
+    } else {
+        orientation::Rotation::Deg0
+    };
+    if rotation != orientation::Rotation::Deg0 {
+        if !Path::new(&args.source).is_file() {
+            anyhow::bail!(
+                "--rotate and --auto-rotate need a video file source, not {}",
+                args.source
+            );
+        }
+        audio::check_ffmpeg_installed(&["libx264"])?;
+        metrics::time("rotate", || {
+            audio::rotate_video(&args.source, &rotated_source, rotation)
+        })?;
+        status_println(format_args!(
+            "Rotated source {} degrees clockwise: {}",
+            rotation.degrees(),
+            rotated_source
+        ));
+        args.source = rotated_source;
+    }
+
+    // Phones record variable frame rate video, but frame timing, smoothing
+    // windows and caption sync all assume frame n plays at n / fps. Resample
+    // such sources to a constant rate up front so everything downstream holds,
+    // unless --no-normalize-fps asks to keep their timing.
+    let cfr_source = format!("{}/cfr_input.mp4", output_dir);
+    if !args.no_normalize_fps && resumable_intermediate(&args, &cfr_source) {
+        status_println(format_args!(
+            "Reusing constant frame rate source: {cfr_source}"
+        ));
+        args.source = cfr_source;
+    } else if !args.no_normalize_fps
+        && Path::new(&args.source).is_file()
+        && video_sink::probe_is_vfr(&args.source)
+    {
+        audio::check_ffmpeg_installed(&["libx264"])?;
+        let fps = video_sink::probe_fps(&args.source);
+        metrics::time("normalize_fps", || {
+            audio::normalize_frame_rate(&args.source, &cfr_source, fps)
+        })?;
+        status_println(format_args!(
+            "Normalized variable frame rate to {fps:.3} fps: {cfr_source}"
+        ));
+        args.source = cfr_source;
+    }
 
+    // Padding only ever lengthens the video, so a source already past the
+    // target is reported now rather than after processing. Its duration is
+    // also where captions must end, however long the padding holds.
+    let content_end = match args.pad_to_duration {
+        Some(target) if Path::new(&args.source).is_file() => {
+            let duration = video_sink::probe_duration(&args.source);
+            if let Some(duration) = duration
+                && duration > target + audio::PAD_DURATION_TOLERANCE
+            {
+                anyhow::bail!(
+                    "the source runs {duration:.3}s, longer than --pad-to-duration {target}s"
+                );
+            }
+            duration
+        }
+        _ => None,
+    };
+
+    // Without captions the source audio is muxed straight back in, skipping
+    // extraction and transcription. Image sequences and silent sources have
+    // none to carry over, and a live stream can't be read a second time.
+    let source_audio = !args.add_captions
+        && !args.dry_run
+        && !args.source.contains("://")
+        && audio::probe_audio_codec(&args.source).is_some();
+
+    // When output_filepath is set and there is nothing to add to the encoded
+    // video, write directly there so we avoid the copy step and any temp-file
+    // behavior in the video library (usls) that can leave the file missing at
+    // the expected temp path (e.g. on GCS FUSE). With --local-stage we
+    // deliberately skip this direct write so the encode goes to local disk
+    // first, and with --resume-from so the processed video stays in the run
+    // directory for a later resume. The encoder only writes MP4, so other
+    // output formats are converted from the run directory.
+    // With --captions-only the source itself is captioned, uncropped.
+    let processed_video = if args.captions_only {
+        args.source.clone()
+    } else if !args.add_captions
+        && !source_audio
+        && !args.output_filepath.is_empty()
+        && !args.local_stage
+        && args.resume_from.is_none()
+        && args.output_format == audio::OutputFormat::Mp4
+    {
+        if let Some(parent) = Path::new(&args.output_filepath).parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Creating output directory {}", parent.display()))?;
+        }
+        status_println(format_args!(
+            "Writing processed video directly to: {}",
+            args.output_filepath
+        ));
+        args.output_filepath.clone()
+    } else {
+        format!("{}/processed_video.mp4", output_dir)
+    };
+
+    // Check that ffmpeg has the encoders delivery needs before spending time
+    // on detection and transcription
+    let delivery_encoders = args.required_encoders();
+    if !args.dry_run && !delivery_encoders.is_empty() {
+        audio::check_ffmpeg_installed(&delivery_encoders)?;
+    }
+    if !args.dry_run {
+        audio::check_ffmpeg_muxers(&args.required_muxers())?;
+    }
+
+    // If adding captions, prepare audio/transcription artifacts first
+    let (extracted_audio, subtitles_path) = if args.add_captions
+        && !args.dry_run
+        && let Some((extracted_audio, subtitles_path)) = resumable_captions(&args, &output_dir)
+    {
+        status_println(format_args!(
+            "Reusing extracted audio and captions from the previous run"
+        ));
+        (Some(extracted_audio), Some(subtitles_path))
+    } else if args.add_captions && !args.dry_run {
         let extracted_audio = format!("{}/extracted_audio.mp4", output_dir);
         let compressed_audio = format!("{}/compressed_audio.mp3", output_dir);
         let srt_path = format!("{}/transcript.srt", output_dir);
@@ -181,88 +1202,315 @@ async fn main() -> Result<()> {
         metrics::time("audio_extract", || {
             audio::extract_audio(&args.source, &extracted_audio)
         })?;
-        println!("Audio extracted successfully to: {}", extracted_audio);
+        status_println(format_args!(
+            "Audio extracted successfully to: {}",
+            extracted_audio
+        ));
 
         // Compress the extracted audio to MP3
         metrics::time("audio_compress", || {
-            audio::compress_to_mp3(&extracted_audio, &compressed_audio)
+            audio::compress_to_mp3(
+                &extracted_audio,
+                &compressed_audio,
+                args.audio_bitrate.as_deref(),
+            )
         })?;
-        println!("Audio compressed to MP3: {}", compressed_audio);
+        status_println(format_args!(
+            "Audio compressed to MP3: {}",
+            compressed_audio
+        ));
 
         // Transcribe audio
-        println!("Transcribing audio to: {}", srt_path);
-        let transcript_config = transcript::TranscriptConfig::default();
+        status_println(format_args!("Transcribing audio to: {}", srt_path));
+        let transcript_config = args.transcript_config()?;
+        let transcriber = transcript::transcriber_for(
+            args.transcribe_backend,
+            &args.whisper_command,
+            args.whisper_model.as_deref(),
+        )?;
         let transcribe_start = std::time::Instant::now();
-        transcript::transcribe_audio(
-            Path::new(&compressed_audio),
-            Path::new(&srt_path),
-            &transcript_config,
-        )
-        .await?;
+        let mut subtitles_path = srt_path.clone();
+        // Word-level timing backs both karaoke captions and transcript.json
+        let word_segments = if args.caption_karaoke || args.transcript_json {
+            Some(
+                transcript::transcribe_audio_words(
+                    Path::new(&compressed_audio),
+                    &transcript_config,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+        let word_segments = match (word_segments, content_end) {
+            (Some(segments), Some(end)) => Some(transcript::clamp_segments(segments, end)),
+            (segments, _) => segments,
+        };
+        if args.caption_karaoke
+            && let Some(segments) = &word_segments
+        {
+            fs::write(&srt_path, transcript::segments_to_srt(segments))?;
+
+            // Burn word-highlighted ASS captions instead of the plain SRT
+            let ass_path = format!("{}/transcript.ass", output_dir);
+            let font_sizes = if args.caption_dynamic_size {
+                let cues: Vec<(f64, f64)> = segments
+                    .iter()
+                    .map(|segment| (segment.start, segment.end))
+                    .collect();
+                let font_size = args.caption_style()?.font_size;
+                Some(metrics::time("caption_sizes", || {
+                    audio::cue_font_sizes(&extracted_audio, &cues, font_size)
+                })?)
+            } else {
+                None
+            };
+            fs::write(
+                &ass_path,
+                audio::build_karaoke_ass(segments, &args.caption_style()?, font_sizes.as_deref()),
+            )?;
+            status_println(format_args!("Karaoke captions written to: {}", ass_path));
+            subtitles_path = ass_path;
+        } else if let Some(target) = args.translate_target()? {
+            // Keep the source-language transcript and translate into transcript.srt
+            let original_path = format!("{}/transcript.original.srt", output_dir);
+            if let Some(segments) = &word_segments {
+                fs::write(&original_path, transcript::segments_to_srt(segments))?;
+            } else {
+                transcriber
+                    .transcribe(
+                        Path::new(&compressed_audio),
+                        Path::new(&original_path),
+                        &transcript_config,
+                    )
+                    .await?;
+            }
+            status_println(format_args!(
+                "Original transcript kept at: {}",
+                original_path
+            ));
+
+            match transcript::translation_for(&target, args.translator_command.as_deref())? {
+                transcript::Translation::Whisper => {
+                    transcript::translate_audio_to_english(
+                        Path::new(&compressed_audio),
+                        Path::new(&srt_path),
+                        &transcript_config,
+                    )
+                    .await?
+                }
+                transcript::Translation::Text(translator) => transcript::translate_srt_file(
+                    Path::new(&original_path),
+                    Path::new(&srt_path),
+                    &target,
+                    translator.as_ref(),
+                )?,
+            }
+            status_println(format_args!("Captions translated to: {}", target));
+        } else if let Some(segments) = &word_segments {
+            fs::write(&srt_path, transcript::segments_to_srt(segments))?;
+        } else {
+            transcriber
+                .transcribe(
+                    Path::new(&compressed_audio),
+                    Path::new(&srt_path),
+                    &transcript_config,
+                )
+                .await?;
+        }
         metrics::record("transcribe", transcribe_start.elapsed());
-        println!("Transcription completed successfully");
+        status_println(format_args!("Transcription completed successfully"));
+
+        if let Some(end) = content_end {
+            transcript::clamp_srt_file(Path::new(&srt_path), end)?;
+        }
+
+        if let Some(max_chars) = args.caption_max_chars {
+            transcript::wrap_srt_file(Path::new(&srt_path), max_chars)?;
+            status_println(format_args!(
+                "Captions wrapped to {} characters per line",
+                max_chars
+            ));
+        }
+
+        if args.caption_dynamic_size && !args.caption_karaoke {
+            let sized_path = format!("{}/transcript.sized.srt", output_dir);
+            metrics::time("caption_sizes", || {
+                write_sized_captions(&args, &extracted_audio, &srt_path, &sized_path)
+            })?;
+            status_println(format_args!(
+                "Captions sized by loudness written to: {}",
+                sized_path
+            ));
+            subtitles_path = sized_path;
+        }
+
+        if args.transcript_json
+            && let Some(segments) = &word_segments
+        {
+            let json_path = format!("{}/transcript.json", output_dir);
+            transcript::write_transcript_json(segments, Path::new(&json_path))?;
+            status_println(format_args!(
+                "Word-timed transcript written to: {}",
+                json_path
+            ));
+        }
+
+        if args.caption_format.includes_vtt() {
+            let vtt_path = format!("{}/transcript.vtt", output_dir);
+            transcript::write_vtt(Path::new(&srt_path), Path::new(&vtt_path))?;
+            status_println(format_args!("WebVTT captions written to: {}", vtt_path));
+        }
 
-        (Some(extracted_audio), Some(srt_path))
+        (Some(extracted_audio), Some(subtitles_path))
     } else {
         (None, None)
     };
 
+    let outputs = video_processor::OutputPaths::from_args(&args, &output_dir);
+
     // Choose processor based on object type and smoothing preference
-    metrics::time("process_video", || -> Result<()> {
-        if args.object == "ball" {
-            let mut processor = ball_video_processor::BallVideoProcessor::new(&args);
-            processor.process_video(&args, &processed_video)
-        } else if args.use_simple_smoothing {
-            let mut processor =
-                simple_smoothing_video_processor::SimpleSmoothingVideoProcessor::new();
-            processor.process_video(&args, &processed_video)
-        } else {
-            let mut processor =
-                history_smoothing_video_processor::HistorySmoothingVideoProcessor::new(&args);
-            processor.process_video(&args, &processed_video)
-        }
-    })?;
+    let frame_rate =
+        video_processor_utils::output_frame_rate(&args, video_sink::probe_fps(&args.source));
+    let best_frame_time = if args.captions_only {
+        status_println(format_args!(
+            "Captions only: skipping detection and cropping"
+        ));
+        None
+    } else if args.resume_from.is_some()
+        && is_reusable_media(&processed_video)
+        && args.output_sizes()?.iter().skip(1).all(|&size| {
+            is_reusable_media(&video_sink::resolution_output_path(&processed_video, size))
+        })
+    {
+        status_println(format_args!(
+            "Reusing processed video from the previous run: {}",
+            processed_video
+        ));
+        None
+    } else {
+        metrics::time("process_video", || {
+            if args.object == "ball" {
+                let mut processor =
+                    ball_video_processor::BallVideoProcessor::new(&args, frame_rate);
+                processor.process_video(&args, &processed_video, &outputs, None)
+            } else if args.use_simple_smoothing {
+                let mut processor =
+                    simple_smoothing_video_processor::SimpleSmoothingVideoProcessor::new();
+                processor.process_video(&args, &processed_video, &outputs, None)
+            } else if args.use_kalman_smoothing {
+                let mut processor =
+                    kalman_smoothing_video_processor::KalmanSmoothingVideoProcessor::new();
+                processor.process_video(&args, &processed_video, &outputs, None)
+            } else if args.use_moving_average {
+                let mut processor =
+                    moving_average_smoothing_video_processor::MovingAverageSmoothingVideoProcessor::new(
+                        &args, frame_rate,
+                    );
+                processor.process_video(&args, &processed_video, &outputs, None)
+            } else {
+                let mut processor =
+                    history_smoothing_video_processor::HistorySmoothingVideoProcessor::new(
+                        &args, frame_rate,
+                    );
+                processor.process_video(&args, &processed_video, &outputs, None)
+            }
+        })?
+    };
 
-    if args.add_captions {
-        let captioned_video = format!("{}/captioned_video.mp4", output_dir);
-        let final_video = format!("{}/final_output.mp4", output_dir);
+    if args.dry_run {
+        status_println(format_args!("Dry run complete: no video was written"));
+    } else if args.add_captions {
+        let extension = args.output_format.extension();
+        let captioned_video = format!("{}/captioned_video.{}", output_dir, extension);
+        let final_video = format!("{}/final_output.{}", output_dir, extension);
 
         // Burn captions into the video
-        println!("Burning captions into video...");
-        let caption_style = audio::CaptionStyle::default();
+        status_println(format_args!("Burning captions into video..."));
+        let caption_style = args.caption_style()?;
+        let encode = args.encode_settings()?;
         metrics::time("burn_captions", || {
             audio::burn_captions(
                 &processed_video,
-                &srt_path.as_ref().unwrap(),
+                &subtitles_path.as_ref().unwrap(),
                 &captioned_video,
                 Some(caption_style),
+                args.output_format,
+                &encode,
             )
         })?;
-        println!("Captions burned successfully");
+        status_println(format_args!("Captions burned successfully"));
+
+        let mut final_audio = extracted_audio.unwrap();
+        if let Some(music) = &args.music {
+            let mixed_audio = format!("{}/mixed_audio.mp4", output_dir);
+            status_println(format_args!("Mixing background music: {}", music));
+            metrics::time("mix_music", || {
+                audio::mix_music(
+                    &final_audio,
+                    music,
+                    &mixed_audio,
+                    args.music_volume,
+                    args.duck_music,
+                )
+            })?;
+            final_audio = mixed_audio;
+        }
+
+        // Normalize after mixing so the loudness target covers the music too
+        if args.normalize_audio {
+            let normalized_audio = format!("{}/normalized_audio.mp4", output_dir);
+            status_println(format_args!(
+                "Normalizing audio to {} LUFS...",
+                args.target_lufs
+            ));
+            metrics::time("normalize_audio", || {
+                audio::normalize_loudness(&final_audio, &normalized_audio, args.target_lufs)
+            })?;
+            final_audio = normalized_audio;
+        }
 
         // Add audio to the final video
-        println!("Adding audio to video...");
+        status_println(format_args!("Adding audio to video..."));
         metrics::time("combine_av", || {
             audio::combine_video_audio(
                 &captioned_video,
-                &extracted_audio.as_ref().unwrap(),
+                &final_audio,
                 &final_video,
+                args.output_format,
             )
         })?;
-        println!(
+        status_println(format_args!(
             "Audio added successfully. Final video saved to: {}",
             final_video
-        );
+        ));
 
-        // Copy final video to output_filepath if specified
+        // Move final video to output_filepath if specified
         if !args.output_filepath.is_empty() {
             metrics::time("stage_out", || {
-                copy_to_output(&final_video, &args.output_filepath)
+                move_to_output(&final_video, &args.output_filepath)
             })?;
-            println!(
-                "Final video copied successfully to: {}",
+            status_println(format_args!(
+                "Final video moved to: {}",
                 args.output_filepath
-            );
+            ));
+
+            // Deliver the requested caption files next to the output video
+            let output_path = Path::new(&args.output_filepath);
+            for (enabled, ext) in [
+                (args.caption_format.includes_srt(), "srt"),
+                (args.caption_format.includes_vtt(), "vtt"),
+            ] {
+                if enabled {
+                    let captions = format!("{}/transcript.{}", output_dir, ext);
+                    let dest = output_path
+                        .with_extension(ext)
+                        .to_string_lossy()
+                        .into_owned();
+                    copy_to_output(&captions, &dest)?;
+                    status_println(format_args!("Captions copied to: {}", dest));
+                }
+            }
         }
         // Ensure the output is flushed to GCS before exiting
         let final_path = if !args.output_filepath.is_empty() {
@@ -270,28 +1518,122 @@ async fn main() -> Result<()> {
         } else {
             &final_video
         };
+        pad_output(&args, &output_dir, final_path)?;
         sync_output_file(final_path)?;
+        print_output_path(final_path);
+        if !args.output_filepath.is_empty()
+            && let Some(landscape) = &landscape_copy
+        {
+            deliver_landscape_copy(landscape, final_path)?;
+        }
+        let extra_videos = finish_extra_sizes(
+            &args,
+            &output_dir,
+            &processed_video,
+            final_path,
+            subtitles_path.as_deref(),
+            Some(final_audio.as_str()),
+        )?;
+        if args.output_hls {
+            write_hls(&args, &output_dir, final_path)?;
+        }
+        if args.poster {
+            write_poster(&args, &output_dir, final_path, best_frame_time)?;
+        }
+        if args.cleanup {
+            let mut delivered = extra_videos;
+            delivered.push(final_path.clone());
+            let freed = cleanup_intermediates(&output_dir, &delivered)?;
+            status_println(format_args!(
+                "Cleaned up intermediate files ({} freed)",
+                human_size(freed)
+            ));
+        }
     } else {
-        println!("Processed video saved to: {}", processed_video);
+        status_println(format_args!(
+            "Processed video saved to: {}",
+            processed_video
+        ));
 
-        // Copy only when we wrote to a temp path and a destination is set; the
+        let extension = args.output_format.extension();
+        let final_video = format!("{}/final_output.{}", output_dir, extension);
+        let mut delivered_video = processed_video.clone();
+        if args.output_format != audio::OutputFormat::Mp4 {
+            let converted = if source_audio {
+                format!("{}/processed_video.{}", output_dir, extension)
+            } else {
+                final_video.clone()
+            };
+            status_println(format_args!("Converting video to: {}", converted));
+            let encode = args.encode_settings()?;
+            metrics::time("convert_output", || {
+                audio::convert_video(&processed_video, &converted, args.output_format, &encode)
+            })?;
+            delivered_video = converted;
+        }
+
+        if source_audio {
+            status_println(format_args!("Adding source audio to video..."));
+            metrics::time("combine_av", || {
+                audio::combine_video_audio(
+                    &delivered_video,
+                    &args.source,
+                    &final_video,
+                    args.output_format,
+                )
+            })?;
+            status_println(format_args!("Final video saved to: {}", final_video));
+            delivered_video = final_video;
+        }
+
+        // Move only when we wrote to a temp path and a destination is set; the
         // direct-write path above already wrote straight to output_filepath.
-        if !args.output_filepath.is_empty() && processed_video != args.output_filepath {
+        if !args.output_filepath.is_empty() && delivered_video != args.output_filepath {
             metrics::time("stage_out", || {
-                copy_to_output(&processed_video, &args.output_filepath)
+                move_to_output(&delivered_video, &args.output_filepath)
             })?;
-            println!(
-                "Processed video copied successfully to: {}",
+            status_println(format_args!(
+                "Final video moved to: {}",
                 args.output_filepath
-            );
+            ));
         }
         // Ensure the output is flushed to GCS before exiting
         let final_path = if !args.output_filepath.is_empty() {
             &args.output_filepath
         } else {
-            &processed_video
+            &delivered_video
         };
+        pad_output(&args, &output_dir, final_path)?;
         sync_output_file(final_path)?;
+        print_output_path(final_path);
+        if !args.output_filepath.is_empty()
+            && let Some(landscape) = &landscape_copy
+        {
+            deliver_landscape_copy(landscape, final_path)?;
+        }
+        let extra_videos = finish_extra_sizes(
+            &args,
+            &output_dir,
+            &processed_video,
+            final_path,
+            None,
+            source_audio.then_some(args.source.as_str()),
+        )?;
+        if args.output_hls {
+            write_hls(&args, &output_dir, final_path)?;
+        }
+        if args.poster {
+            write_poster(&args, &output_dir, final_path, best_frame_time)?;
+        }
+        if args.cleanup {
+            let mut delivered = extra_videos;
+            delivered.push(final_path.clone());
+            let freed = cleanup_intermediates(&output_dir, &delivered)?;
+            status_println(format_args!(
+                "Cleaned up intermediate files ({} freed)",
+                human_size(freed)
+            ));
+        }
     }
 
     // Write the performance report next to the run artifacts, and (when an
@@ -327,6 +1669,196 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_list_video_files_filters_and_sorts() {
+        let dir = std::env::temp_dir().join("land2port_list_video_files_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested.mp4")).unwrap();
+        for name in ["b.mp4", "a.MOV", "notes.txt", "c.mkv"] {
+            fs::write(dir.join(name), b"x").unwrap();
+        }
+
+        let videos = list_video_files(&dir).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        let names: Vec<_> = videos
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["a.MOV", "b.mp4", "c.mkv"]);
+    }
+
+    #[test]
+    fn test_is_image_sequence() {
+        let dir = std::env::temp_dir().join("land2port_image_sequence_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // An empty folder is neither a batch nor an image sequence
+        assert!(!is_image_sequence(&dir).unwrap());
+
+        for name in ["frame_0002.PNG", "frame_0001.png", "notes.txt"] {
+            fs::write(dir.join(name), b"x").unwrap();
+        }
+        let images = is_image_sequence(&dir).unwrap();
+        // A video in the folder makes it a batch instead
+        fs::write(dir.join("clip.mp4"), b"x").unwrap();
+        let with_video = is_image_sequence(&dir).unwrap();
+        let single_file = is_image_sequence(&dir.join("frame_0001.png")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(images);
+        assert!(!with_video);
+        assert!(!single_file);
+    }
+
+    #[test]
+    fn test_batch_output_path() {
+        let path = batch_output_path("/data/output", Path::new("/data/input/clip 1.mov"), "mp4");
+        assert_eq!(path, "/data/output/clip 1.mp4");
+    }
+
+    #[test]
+    fn test_batch_crop_log_path() {
+        let path = batch_crop_log_path("logs/crops.csv", Path::new("/data/input/clip 1.mov"));
+        assert_eq!(path, "logs/crops.clip 1.csv");
+    }
+
+    #[test]
+    fn test_landscape_delivery_path() {
+        let path = landscape_delivery_path("/tmp/run/landscape.mov", "/data/output/clip.mp4");
+        assert_eq!(path, "/data/output/clip_landscape.mov");
+    }
+
+    #[test]
+    fn test_batch_run_dir() {
+        let dir = batch_run_dir("ci/out", Path::new("/data/input/clip 1.mov"));
+        assert_eq!(dir, "ci/out/clip 1");
+    }
+
+    #[test]
+    fn test_create_output_dir_named() {
+        let dir = std::env::temp_dir().join("land2port_create_output_dir_test");
+        let _ = fs::remove_dir_all(&dir);
+        let dir_str = dir.to_str().unwrap();
+
+        // Created when missing, and an empty directory is reused
+        assert_eq!(create_output_dir(Some(dir_str), false).unwrap(), dir_str);
+        assert_eq!(create_output_dir(Some(dir_str), false).unwrap(), dir_str);
+
+        // A directory with files in it needs --overwrite
+        fs::write(dir.join("processed_video.mp4"), b"x").unwrap();
+        let err = create_output_dir(Some(dir_str), false).unwrap_err();
+        assert!(err.to_string().contains("--overwrite"));
+        let result = create_output_dir(Some(dir_str), true);
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(result.unwrap(), dir_str);
+    }
+
+    #[test]
+    fn test_resume_run_dir_and_reusable_captions() {
+        let dir = std::env::temp_dir().join("land2port_resume_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let srt = dir.join("transcript.srt");
+        fs::write(&srt, "1\n00:00:00,000 --> 00:00:01,500\nHello\n\n").unwrap();
+        let ass = dir.join("transcript.ass");
+        fs::write(
+            &ass,
+            "[Events]\nDialogue: 0,0:00:00.00,0:00:01.50,Default,Hello\n",
+        )
+        .unwrap();
+        let empty = dir.join("empty.srt");
+        fs::write(&empty, "").unwrap();
+
+        let resumed = resume_run_dir(dir.to_str().unwrap());
+        let missing = resume_run_dir(dir.join("missing").to_str().unwrap());
+        let srt_ok = is_reusable_captions(srt.to_str().unwrap());
+        let ass_ok = is_reusable_captions(ass.to_str().unwrap());
+        let empty_ok = is_reusable_captions(empty.to_str().unwrap());
+        let absent_ok = is_reusable_captions(dir.join("transcript.vtt").to_str().unwrap());
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(resumed.unwrap(), dir.to_str().unwrap());
+        assert!(missing.is_err());
+        assert!(srt_ok);
+        assert!(ass_ok);
+        assert!(!empty_ok);
+        assert!(!absent_ok);
+    }
+
+    #[test]
+    fn test_move_to_output() {
+        let dir = std::env::temp_dir().join("land2port_move_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("final_output.mp4");
+        fs::write(&source, b"video").unwrap();
+
+        let dest = dir.join("delivered/clip.mp4");
+        move_to_output(source.to_str().unwrap(), dest.to_str().unwrap()).unwrap();
+        let moved = fs::read(&dest).unwrap();
+        let source_left = source.exists();
+
+        // A directory is not a valid destination
+        let dir_error = move_to_output(dest.to_str().unwrap(), dir.to_str().unwrap());
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(moved, b"video");
+        assert!(!source_left);
+        assert!(dir_error.is_err());
+        assert!(check_output_file_path("out/").is_err());
+        assert!(check_output_file_path("out/clip.mp4").is_ok());
+    }
+
+    #[test]
+    fn test_cleanup_intermediates_keeps_final_and_outputs() {
+        let dir = std::env::temp_dir().join("land2port_cleanup_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "staged_input.mov",
+            "extracted_audio.mp4",
+            "compressed_audio.mp3",
+            "processed_video.mp4",
+            "processed_video_720x1280.mp4",
+            "captioned_video.mp4",
+            "final_output.mp4",
+            "final_output_720x1280.mp4",
+            "transcript.srt",
+            "metrics.json",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(name), b"xx").unwrap();
+        }
+
+        let output_dir = dir.to_str().unwrap();
+        // The main output and an extra --output-resolution one were delivered
+        let delivered: Vec<String> = ["final_output.mp4", "final_output_720x1280.mp4"]
+            .iter()
+            .map(|name| dir.join(name).to_string_lossy().into_owned())
+            .collect();
+        let freed = cleanup_intermediates(output_dir, &delivered).unwrap();
+        let mut left: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(freed, 12);
+        assert_eq!(
+            left,
+            [
+                "final_output.mp4",
+                "final_output_720x1280.mp4",
+                "metrics.json",
+                "notes.txt",
+                "transcript.srt"
+            ]
+        );
+    }
+
     #[test]
     fn test_validate_source_url_is_skipped() {
         assert!(validate_source("rtsp://example.com/stream").is_ok());
@@ -1,3 +1,4 @@
+use crate::video_processor_utils;
 use anyhow::{Context, Result};
 use std::collections::BTreeMap;
 use std::fs;
@@ -26,9 +27,13 @@ fn registry() -> &'static Mutex<Registry> {
     REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
 }
 
-/// Marks the start of the run; total_wall_s is measured from here.
+/// Marks the start of the run; total_wall_s is measured from here. Clears
+/// anything recorded before, so each video in a batch gets its own report.
 pub fn init() {
-    registry().lock().unwrap().started = Some(Instant::now());
+    *registry().lock().unwrap() = Registry {
+        started: Some(Instant::now()),
+        ..Registry::default()
+    };
 }
 
 /// Records one timed invocation of a stage.
@@ -141,14 +146,15 @@ fn render_summary(reg: &Registry) -> String {
     out
 }
 
-/// Prints the human-readable summary to stdout and writes the JSON report
-/// to each of the given paths (fsynced so GCS FUSE flushes before exit).
+/// Prints the human-readable summary to stdout (unless `--quiet`) and writes
+/// the JSON report to each of the given paths (fsynced so GCS FUSE flushes
+/// before exit).
 pub fn write_report(paths: &[&str]) -> Result<()> {
     let (json, summary) = {
         let reg = registry().lock().unwrap();
         (render_json(&reg), render_summary(&reg))
     };
-    println!("{}", summary);
+    video_processor_utils::status_println(format_args!("{}", summary));
 
     for path in paths {
         if let Some(parent) = Path::new(path).parent() {
@@ -161,7 +167,7 @@ pub fn write_report(paths: &[&str]) -> Result<()> {
             .with_context(|| format!("Writing metrics file {}", path))?;
         file.sync_all()
             .with_context(|| format!("Fsyncing metrics file {}", path))?;
-        println!("Metrics written to: {}", path);
+        video_processor_utils::status_println(format_args!("Metrics written to: {}", path));
     }
     Ok(())
 }
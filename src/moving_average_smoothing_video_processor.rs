@@ -0,0 +1,225 @@
+use crate::cli::Args;
+use crate::crop;
+use crate::image::CutDetector;
+use crate::video_processor::VideoProcessor;
+use crate::video_processor_utils;
+use crate::video_sink::VideoSink;
+use anyhow::Result;
+use std::collections::VecDeque;
+
+/// Video processor that moves the crop to the average center of the last
+/// `window` crops, emitting each frame's crop immediately
+pub struct MovingAverageSmoothingVideoProcessor {
+    window: usize,
+    /// Recent crop centers, oldest first
+    centers: VecDeque<(f32, f32)>,
+    previous_crop: Option<crop::CropResult>,
+    most_recent_image: Option<usls::Image>,
+    cut_detector: CutDetector,
+}
+
+impl MovingAverageSmoothingVideoProcessor {
+    /// Creates a moving-average processor over the last `--smooth-window`
+    /// crops, for a source at `frame_rate` fps
+    pub fn new(args: &Args, frame_rate: f64) -> Self {
+        Self {
+            window: args.smooth_window.max(1),
+            centers: VecDeque::new(),
+            previous_crop: None,
+            most_recent_image: None,
+            cut_detector: CutDetector::new(
+                args.cut_similarity,
+                args.cut_start,
+                video_processor_utils::min_scene_frames(args, frame_rate),
+                args.cut_mode,
+            ),
+        }
+    }
+
+    /// Averages the latest crop's center with the previous ones in the window,
+    /// keeping its size and clamping it inside the frame. Stacked and resize
+    /// crops pass through unaveraged, and a scene cut or a change of crop type
+    /// restarts the window.
+    pub fn smooth(
+        &mut self,
+        latest_crop: &crop::CropResult,
+        is_cut: bool,
+        frame_width: f32,
+        frame_height: f32,
+    ) -> crop::CropResult {
+        let same_type = self
+            .previous_crop
+            .as_ref()
+            .is_some_and(|prev| !crop::crop_types_different(prev, latest_crop));
+        if is_cut {
+            video_processor_utils::debug_println(format_args!("Moving average reset: cut"));
+            self.centers.clear();
+        } else if !same_type {
+            video_processor_utils::debug_println(format_args!(
+                "Moving average reset: crop type changed"
+            ));
+            self.centers.clear();
+        }
+
+        let crop_result = match latest_crop {
+            crop::CropResult::Single(area) => {
+                crop::CropResult::Single(self.average(area, frame_width, frame_height))
+            }
+            crop::CropResult::BlurFill(area) => {
+                crop::CropResult::BlurFill(self.average(area, frame_width, frame_height))
+            }
+            crop::CropResult::Stacked(_, _) | crop::CropResult::Resize(_) => latest_crop.clone(),
+        };
+        self.previous_crop = Some(crop_result.clone());
+        crop_result
+    }
+
+    /// Adds `area`'s center to the window and returns `area` moved to the
+    /// window's average center
+    fn average(
+        &mut self,
+        area: &crop::CropArea,
+        frame_width: f32,
+        frame_height: f32,
+    ) -> crop::CropArea {
+        self.centers
+            .push_back((area.x + area.width / 2.0, area.y + area.height / 2.0));
+        while self.centers.len() > self.window {
+            self.centers.pop_front();
+        }
+
+        let count = self.centers.len() as f32;
+        let center_x = self.centers.iter().map(|(x, _)| x).sum::<f32>() / count;
+        let center_y = self.centers.iter().map(|(_, y)| y).sum::<f32>() / count;
+        let max_x = (frame_width - area.width).max(0.0);
+        let max_y = (frame_height - area.height).max(0.0);
+        crop::CropArea::new(
+            (center_x - area.width / 2.0).clamp(0.0, max_x),
+            (center_y - area.height / 2.0).clamp(0.0, max_y),
+            area.width,
+            area.height,
+        )
+    }
+}
+
+impl VideoProcessor for MovingAverageSmoothingVideoProcessor {
+    /// Processes a single frame with moving-average crop movement
+    fn process_frame_with_smoothing(
+        &mut self,
+        img: &usls::Image,
+        latest_crop: &crop::CropResult,
+        _objects: &[&usls::Hbb],
+        args: &Args,
+        viewer: &mut VideoSink,
+        _smooth_duration_frames: usize,
+    ) -> Result<()> {
+        let is_cut = if let Some(ref most_recent) = self.most_recent_image {
+            self.cut_detector.is_cut(most_recent, img)?
+        } else {
+            true
+        };
+        self.most_recent_image = Some(img.clone());
+
+        let crop_result = self.smooth(latest_crop, is_cut, img.width() as f32, img.height() as f32);
+
+        video_processor_utils::process_and_display_crop(img, &crop_result, viewer, args)
+    }
+
+    /// Override debug info to include the previous smoothed crop
+    fn print_debug_info(
+        &self,
+        objects: &[&usls::Hbb],
+        latest_crop: &crop::CropResult,
+        is_graphic: bool,
+    ) {
+        video_processor_utils::print_default_debug_info(objects, latest_crop, is_graphic);
+        video_processor_utils::debug_println(format_args!(
+            "previous_crop: {:?}",
+            self.previous_crop
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argh::FromArgs;
+
+    fn moving_average(window: &str) -> MovingAverageSmoothingVideoProcessor {
+        let args =
+            Args::from_args(&["land2port"], &["--headless", "--smooth-window", window]).unwrap();
+        MovingAverageSmoothingVideoProcessor::new(&args, 30.0)
+    }
+
+    fn single_at(x: f32) -> crop::CropResult {
+        crop::CropResult::Single(crop::CropArea::new(x, 0.0, 600.0, 1080.0))
+    }
+
+    fn x_of(crop: &crop::CropResult) -> f32 {
+        match crop {
+            crop::CropResult::Single(area) => area.x,
+            other => panic!("expected a single crop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_moving_average_on_ramp() {
+        let mut processor = moving_average("3");
+        let xs: Vec<f32> = (0..6)
+            .map(|t| {
+                x_of(&processor.smooth(&single_at(100.0 + 30.0 * t as f32), false, 1920.0, 1080.0))
+            })
+            .collect();
+
+        // The window fills over the first frames, then lags a ramp by one step
+        assert_eq!(xs, [100.0, 115.0, 130.0, 160.0, 190.0, 220.0]);
+    }
+
+    #[test]
+    fn test_moving_average_passes_through_stacked_and_resize() {
+        let mut processor = moving_average("4");
+        processor.smooth(&single_at(100.0), false, 1920.0, 1080.0);
+
+        let stacked = crop::CropResult::Stacked(
+            crop::CropArea::new(0.0, 0.0, 960.0, 540.0),
+            crop::CropArea::new(960.0, 0.0, 960.0, 540.0),
+        );
+        assert_eq!(processor.smooth(&stacked, false, 1920.0, 1080.0), stacked);
+        let resize = crop::CropResult::Resize(crop::CropArea::new(0.0, 0.0, 1920.0, 1080.0));
+        assert_eq!(processor.smooth(&resize, false, 1920.0, 1080.0), resize);
+
+        // Returning to a single crop starts a fresh window
+        assert_eq!(
+            x_of(&processor.smooth(&single_at(700.0), false, 1920.0, 1080.0)),
+            700.0
+        );
+    }
+
+    #[test]
+    fn test_moving_average_stays_in_frame() {
+        let mut processor = moving_average("2");
+        processor.smooth(&single_at(1320.0), false, 1920.0, 1080.0);
+        // A wider crop centered near the edge is clamped inside the frame
+        let wide = crop::CropResult::Single(crop::CropArea::new(1000.0, 0.0, 900.0, 1080.0));
+        match processor.smooth(&wide, false, 1920.0, 1080.0) {
+            crop::CropResult::Single(area) => assert!(area.x + area.width <= 1920.0),
+            other => panic!("expected a single crop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_moving_average_restarts_on_cut() {
+        let mut processor = moving_average("4");
+        processor.smooth(&single_at(100.0), false, 1920.0, 1080.0);
+        processor.smooth(&single_at(100.0), false, 1920.0, 1080.0);
+        // The new scene's crop is used as-is rather than averaged with the old one
+        assert_eq!(
+            x_of(&processor.smooth(&single_at(1200.0), true, 1920.0, 1080.0)),
+            1200.0
+        );
+        assert_eq!(
+            x_of(&processor.smooth(&single_at(1300.0), false, 1920.0, 1080.0)),
+            1250.0
+        );
+    }
+}
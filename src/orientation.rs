@@ -0,0 +1,213 @@
+use crate::audio;
+use crate::cli::Args;
+use crate::video_processor;
+use crate::video_processor_utils;
+use crate::video_sink;
+use anyhow::{Context, Result};
+use image::RgbImage;
+use std::fs;
+use std::str::FromStr;
+use usls::{Model, Y};
+
+/// Number of frames `--auto-rotate` samples from the source
+const SAMPLE_FRAMES: usize = 5;
+
+/// How many times the upright orientation's score a rotation must reach
+/// before `--auto-rotate` applies it
+const ROTATION_MARGIN: f32 = 1.5;
+
+/// A clockwise rotation that turns the source upright
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim() {
+            "0" => Ok(Self::Deg0),
+            "90" => Ok(Self::Deg90),
+            "180" => Ok(Self::Deg180),
+            "270" => Ok(Self::Deg270),
+            _ => Err(format!(
+                "invalid rotation '{s}': expected 0, 90, 180, or 270 degrees"
+            )),
+        }
+    }
+}
+
+impl Rotation {
+    /// Every rotation, upright first
+    pub const ALL: [Rotation; 4] = [Self::Deg0, Self::Deg90, Self::Deg180, Self::Deg270];
+
+    /// Clockwise rotation in degrees
+    pub fn degrees(self) -> u32 {
+        match self {
+            Self::Deg0 => 0,
+            Self::Deg90 => 90,
+            Self::Deg180 => 180,
+            Self::Deg270 => 270,
+        }
+    }
+
+    /// The ffmpeg video filter applying this rotation, if any
+    pub fn ffmpeg_filter(self) -> Option<&'static str> {
+        match self {
+            Self::Deg0 => None,
+            Self::Deg90 => Some("transpose=clock"),
+            Self::Deg180 => Some("hflip,vflip"),
+            Self::Deg270 => Some("transpose=cclock"),
+        }
+    }
+
+    /// Rotates a frame the way `ffmpeg_filter` rotates the video
+    fn apply(self, frame: &RgbImage) -> RgbImage {
+        match self {
+            Self::Deg0 => frame.clone(),
+            Self::Deg90 => image::imageops::rotate90(frame),
+            Self::Deg180 => image::imageops::rotate180(frame),
+            Self::Deg270 => image::imageops::rotate270(frame),
+        }
+    }
+}
+
+/// Evenly spaced times to sample, in the middle of `count` equal stretches of
+/// the source; just the first frame when its duration is unknown
+fn sample_times(duration: Option<f64>, count: usize) -> Vec<f64> {
+    match duration {
+        Some(duration) if duration > 0.0 => (0..count)
+            .map(|index| (index as f64 + 0.5) * duration / count as f64)
+            .collect(),
+        _ => vec![0.0],
+    }
+}
+
+/// Confidence of the most confident `--object` detection in a frame, or 0
+fn best_confidence(detection: &Y, object_names: &[&str]) -> f32 {
+    detection
+        .hbbs
+        .iter()
+        .filter(|hbb| hbb.name().is_some_and(|name| object_names.contains(&name)))
+        .filter_map(|hbb| hbb.confidence())
+        .fold(0.0, f32::max)
+}
+
+/// Picks the rotation from per-rotation scores (in `Rotation::ALL` order):
+/// the best-scoring turn when it beats upright by `ROTATION_MARGIN`,
+/// otherwise none, so footage that is merely hard to detect is left alone
+fn pick_rotation(scores: &[f32; 4]) -> Rotation {
+    let upright = scores[0];
+    let best = Rotation::ALL
+        .into_iter()
+        .zip(scores.iter().copied())
+        .skip(1)
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    match best {
+        Some((rotation, score)) if score > 0.0 && score > upright * ROTATION_MARGIN => rotation,
+        _ => Rotation::Deg0,
+    }
+}
+
+/// Detects sideways or upside-down footage for `--auto-rotate`: runs the
+/// object model on a sample of the source's frames under each rotation and
+/// returns the one whose detections are most confident. Sample frames are
+/// extracted into `work_dir` and removed again. With `--fallback-center-crop`
+/// a model that fails to load leaves the source upright instead of failing.
+pub fn detect_rotation(args: &Args, work_dir: &str) -> Result<Rotation> {
+    let mut model = match video_processor::build_object_model(args) {
+        Ok(model) => model,
+        Err(e) if args.fallback_center_crop => {
+            eprintln!("warning: the detection model failed to load: {e:#}");
+            eprintln!(
+                "warning: ROTATION SKIPPED; leaving the source as is (--fallback-center-crop)"
+            );
+            return Ok(Rotation::Deg0);
+        }
+        Err(e) => return Err(e),
+    };
+    let object_names = args.object_names();
+    let mut scores = [0.0; 4];
+    for (index, time) in sample_times(video_sink::probe_duration(&args.source), SAMPLE_FRAMES)
+        .into_iter()
+        .enumerate()
+    {
+        let sample_path = format!("{}/orientation_sample_{}.jpg", work_dir, index);
+        audio::extract_poster(&args.source, &sample_path, time)?;
+        let frame = image::open(&sample_path)
+            .with_context(|| format!("Reading sample frame {}", sample_path))?
+            .to_rgb8();
+        fs::remove_file(&sample_path)
+            .with_context(|| format!("Removing sample frame {}", sample_path))?;
+
+        for (score, rotation) in scores.iter_mut().zip(Rotation::ALL) {
+            let rotated = usls::Image::from(rotation.apply(&frame));
+            let ys = model.forward(std::slice::from_ref(&rotated))?;
+            *score += ys
+                .first()
+                .map_or(0.0, |detection| best_confidence(detection, &object_names));
+        }
+    }
+
+    let rotation = pick_rotation(&scores);
+    video_processor_utils::status_println(format_args!(
+        "Orientation scores (0/90/180/270 degrees): {:.2}/{:.2}/{:.2}/{:.2}",
+        scores[0], scores[1], scores[2], scores[3]
+    ));
+    Ok(rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_from_str() {
+        assert_eq!("90".parse::<Rotation>(), Ok(Rotation::Deg90));
+        assert_eq!("270".parse::<Rotation>(), Ok(Rotation::Deg270));
+        assert_eq!("0".parse::<Rotation>(), Ok(Rotation::Deg0));
+        assert!("45".parse::<Rotation>().is_err());
+        assert!("-90".parse::<Rotation>().is_err());
+        assert_eq!(Rotation::Deg180.degrees(), 180);
+        assert_eq!(Rotation::Deg0.ffmpeg_filter(), None);
+        assert_eq!(Rotation::Deg90.ffmpeg_filter(), Some("transpose=clock"));
+    }
+
+    #[test]
+    fn test_rotation_apply_matches_ffmpeg_direction() {
+        // A marked top-left pixel ends up top-right after a clockwise turn
+        let mut frame = RgbImage::new(4, 2);
+        frame.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        let turned = Rotation::Deg90.apply(&frame);
+        assert_eq!(turned.dimensions(), (2, 4));
+        assert_eq!(turned.get_pixel(1, 0), &image::Rgb([255, 0, 0]));
+        let turned = Rotation::Deg270.apply(&frame);
+        assert_eq!(turned.get_pixel(0, 3), &image::Rgb([255, 0, 0]));
+        let turned = Rotation::Deg180.apply(&frame);
+        assert_eq!(turned.dimensions(), (4, 2));
+        assert_eq!(turned.get_pixel(3, 1), &image::Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn test_sample_times() {
+        assert_eq!(sample_times(Some(10.0), 5), [1.0, 3.0, 5.0, 7.0, 9.0]);
+        assert_eq!(sample_times(None, 5), [0.0]);
+        assert_eq!(sample_times(Some(0.0), 5), [0.0]);
+    }
+
+    #[test]
+    fn test_pick_rotation_needs_a_clear_margin() {
+        // Sideways faces barely detected upright, clearly after a quarter turn
+        assert_eq!(pick_rotation(&[0.8, 4.2, 0.5, 0.9]), Rotation::Deg90);
+        assert_eq!(pick_rotation(&[0.0, 0.3, 2.5, 0.0]), Rotation::Deg180);
+        // A rotation only slightly better than upright isn't trusted
+        assert_eq!(pick_rotation(&[3.0, 4.0, 0.2, 0.1]), Rotation::Deg0);
+        // Nothing detected anywhere leaves the source alone
+        assert_eq!(pick_rotation(&[0.0, 0.0, 0.0, 0.0]), Rotation::Deg0);
+    }
+}
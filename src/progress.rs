@@ -0,0 +1,82 @@
+use crate::cli::Args;
+use crate::image::CutEvent;
+use crate::video_processor_utils;
+use indicatif::ProgressBar;
+
+/// A milestone while processing a video, reported through a
+/// [`ProgressCallback`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// Processing moved on to a new stage
+    Stage(Stage),
+    /// A frame was cropped and handed to the encoder. `total` is the estimated
+    /// frame count, when the source's duration is known.
+    FrameProcessed { frame: usize, total: Option<u64> },
+    /// A scene cut was detected, sent only to a [`ProgressReporter`] that
+    /// asks for cuts
+    Cut(CutEvent),
+    /// An output file was written
+    Saved { what: String, path: String },
+}
+
+/// Stages of `VideoProcessor::process_video`, in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage {
+    /// Rendering the crops from an `--import-crops` file, skipping detection
+    Replaying { crops: usize, path: String },
+    /// Detecting, cropping, and encoding the source's frames
+    Processing { total_frames: Option<u64> },
+    /// Flushing the smoothing history and finishing the encode
+    Finalizing,
+}
+
+/// Receives progress events from `VideoProcessor::process_video`, so the
+/// pipeline can report to a GUI instead of the terminal
+pub type ProgressCallback = Box<dyn FnMut(ProgressEvent)>;
+
+/// A caller's own progress reporting for `VideoProcessor::process_video`
+pub struct ProgressReporter {
+    pub callback: ProgressCallback,
+    /// Whether `callback` wants [`ProgressEvent::Cut`]s. Scene cuts are only
+    /// detected when this is set or `OutputPaths::cuts` is.
+    pub cuts: bool,
+}
+
+/// The CLI's reporting: a frame progress bar while frames are processed and a
+/// line for each saved file. Scene cuts are not printed.
+pub fn print_progress(args: &Args) -> ProgressCallback {
+    let args = args.clone();
+    let mut bar: Option<ProgressBar> = None;
+    Box::new(move |event| match event {
+        ProgressEvent::Stage(Stage::Replaying { crops, path }) => {
+            video_processor_utils::status_println(format_args!(
+                "Replaying {} crops from: {}",
+                crops, path
+            ));
+            bar = Some(video_processor_utils::frame_progress_bar(
+                Some(crops as u64),
+                &args,
+            ));
+        }
+        ProgressEvent::Stage(Stage::Processing { total_frames }) => {
+            bar = Some(video_processor_utils::frame_progress_bar(
+                total_frames,
+                &args,
+            ));
+        }
+        ProgressEvent::Stage(Stage::Finalizing) => {
+            if let Some(bar) = bar.take() {
+                bar.finish();
+            }
+        }
+        ProgressEvent::FrameProcessed { .. } => {
+            if let Some(bar) = &bar {
+                bar.inc(1);
+            }
+        }
+        ProgressEvent::Cut(_) => {}
+        ProgressEvent::Saved { what, path } => {
+            video_processor_utils::status_println(format_args!("{} saved to: {}", what, path))
+        }
+    })
+}
@@ -6,6 +6,7 @@ use crate::video_sink::VideoSink;
 use anyhow::Result;
 
 /// Video processor that handles cropping with simple smoothing (no history)
+#[derive(Default)]
 pub struct SimpleSmoothingVideoProcessor {
     previous_crop: Option<crop::CropResult>,
 }
@@ -37,6 +38,7 @@ impl VideoProcessor for SimpleSmoothingVideoProcessor {
                 prev_crop,
                 img.width() as f32,
                 args.smooth_percentage,
+                args.similarity_metric,
             );
 
             if is_latest_crop_similar {
@@ -58,7 +60,7 @@ impl VideoProcessor for SimpleSmoothingVideoProcessor {
         self.previous_crop = Some(crop_result.clone());
 
         // Process and display the chosen crop
-        video_processor_utils::process_and_display_crop(img, &crop_result, viewer, args.headless)?;
+        video_processor_utils::process_and_display_crop(img, &crop_result, viewer, args)?;
         Ok(())
     }
 
@@ -0,0 +1,319 @@
+use usls::Hbb;
+
+/// Minimum IoU between a track's predicted box and a detection to match them
+const IOU_MATCH_THRESHOLD: f32 = 0.3;
+
+/// Frames a track survives without a matching detection before it is dropped,
+/// so brief detection dropouts (a blink, a head turn) keep the same ID
+const MAX_MISSED_FRAMES: usize = 15;
+
+/// Axis-aligned box as `[xmin, ymin, xmax, ymax]`
+type Bounds = [f32; 4];
+
+fn bounds(hbb: &Hbb) -> Bounds {
+    [hbb.xmin(), hbb.ymin(), hbb.xmax(), hbb.ymax()]
+}
+
+fn center(hbb: &Hbb) -> [f32; 2] {
+    [hbb.cx(), hbb.cy()]
+}
+
+fn iou(a: &Bounds, b: &Bounds) -> f32 {
+    let overlap_w = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+    let overlap_h = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+    let intersection = overlap_w * overlap_h;
+    let area = |r: &Bounds| (r[2] - r[0]) * (r[3] - r[1]);
+    let union = area(a) + area(b) - intersection;
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+/// One object followed across frames
+#[derive(Debug, Clone)]
+struct Track {
+    id: u64,
+    bounds: Bounds,
+    /// Per-frame motion of the box center, used to predict the next position
+    /// so objects that cross paths keep their own IDs
+    velocity: [f32; 2],
+    hits: usize,
+    misses: usize,
+}
+
+impl Track {
+    fn predicted(&self) -> Bounds {
+        let [dx, dy] = self.velocity;
+        let [x0, y0, x1, y1] = self.bounds;
+        [x0 + dx, y0 + dy, x1 + dx, y1 + dy]
+    }
+
+    fn observe(&mut self, bounds: Bounds) {
+        let center = |r: &Bounds| [(r[0] + r[2]) / 2.0, (r[1] + r[3]) / 2.0];
+        let [old_x, old_y] = center(&self.bounds);
+        let [new_x, new_y] = center(&bounds);
+        // Smooth the velocity so a single noisy box doesn't throw the prediction
+        self.velocity = [
+            (self.velocity[0] + (new_x - old_x)) / 2.0,
+            (self.velocity[1] + (new_y - old_y)) / 2.0,
+        ];
+        self.bounds = bounds;
+        self.hits += 1;
+        self.misses = 0;
+    }
+}
+
+/// A detection's identity as assigned by the tracker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackedObject {
+    /// Persistent ID, stable for as long as the object keeps being matched
+    pub id: u64,
+    /// Number of frames the object has been matched in; higher is more stable
+    pub hits: usize,
+}
+
+/// Lightweight IoU tracker that assigns persistent IDs to detections across
+/// frames and keeps a sticky "primary" object for the crop to follow
+#[derive(Debug, Default)]
+pub struct ObjectTracker {
+    tracks: Vec<Track>,
+    next_id: u64,
+    primary_id: Option<u64>,
+    /// Last position of a subject picked with `--interactive-pick`; when the
+    /// pick's track is lost, the object nearest it takes over
+    anchor: Option<[f32; 2]>,
+}
+
+impl ObjectTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches this frame's detections to existing tracks (greedily, highest
+    /// IoU with each track's predicted box first), starts new tracks for the
+    /// rest, and ages out tracks that have gone unmatched too long. Returns
+    /// one `TrackedObject` per detection, in the same order.
+    pub fn update(&mut self, objects: &[&Hbb]) -> Vec<TrackedObject> {
+        let detections: Vec<Bounds> = objects.iter().map(|o| bounds(o)).collect();
+
+        let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+        for (t, track) in self.tracks.iter().enumerate() {
+            let predicted = track.predicted();
+            for (d, detection) in detections.iter().enumerate() {
+                let overlap = iou(&predicted, detection);
+                if overlap >= IOU_MATCH_THRESHOLD {
+                    pairs.push((overlap, t, d));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut track_matched = vec![false; self.tracks.len()];
+        let mut assigned: Vec<Option<usize>> = vec![None; detections.len()];
+        for (_, t, d) in pairs {
+            if track_matched[t] || assigned[d].is_some() {
+                continue;
+            }
+            track_matched[t] = true;
+            assigned[d] = Some(t);
+            self.tracks[t].observe(detections[d]);
+        }
+
+        for (track, matched) in self.tracks.iter_mut().zip(&track_matched) {
+            if !matched {
+                track.misses += 1;
+            }
+        }
+
+        let mut tracked = Vec::with_capacity(detections.len());
+        for (d, detection) in detections.iter().enumerate() {
+            let t = match assigned[d] {
+                Some(t) => t,
+                None => {
+                    self.tracks.push(Track {
+                        id: self.next_id,
+                        bounds: *detection,
+                        velocity: [0.0, 0.0],
+                        hits: 1,
+                        misses: 0,
+                    });
+                    self.next_id += 1;
+                    self.tracks.len() - 1
+                }
+            };
+            let track = &self.tracks[t];
+            tracked.push(TrackedObject {
+                id: track.id,
+                hits: track.hits,
+            });
+        }
+
+        self.tracks
+            .retain(|track| track.misses <= MAX_MISSED_FRAMES);
+        tracked
+    }
+
+    /// Makes the detection at `index` the primary object and keeps following
+    /// it, or whatever is nearest its last position, for the rest of the clip
+    pub fn lock_onto(&mut self, objects: &[&Hbb], tracked: &[TrackedObject], index: usize) {
+        self.primary_id = Some(tracked[index].id);
+        self.anchor = Some(center(objects[index]));
+    }
+
+    /// Whether a subject was picked with `lock_onto`
+    pub fn is_locked(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    /// Index (into this frame's detections) of the object the crop should
+    /// follow: the current primary while it is still visible, otherwise the
+    /// object nearest a picked subject's last position, otherwise the most
+    /// stable object, with ties going to the larger one
+    pub fn primary_index(&mut self, objects: &[&Hbb], tracked: &[TrackedObject]) -> Option<usize> {
+        let nearest_anchor = |anchor: [f32; 2]| {
+            let distance = |object: &Hbb| {
+                let [x, y] = center(object);
+                (x - anchor[0]).hypot(y - anchor[1])
+            };
+            (0..objects.len())
+                .min_by(|&a, &b| distance(objects[a]).total_cmp(&distance(objects[b])))
+        };
+        let index = self
+            .primary_id
+            .and_then(|id| tracked.iter().position(|t| t.id == id))
+            .or_else(|| self.anchor.and_then(nearest_anchor))
+            .or_else(|| {
+                (0..tracked.len()).max_by(|&a, &b| {
+                    tracked[a]
+                        .hits
+                        .cmp(&tracked[b].hits)
+                        .then(objects[a].area().total_cmp(&objects[b].area()))
+                })
+            });
+        self.primary_id = index.map(|i| tracked[i].id);
+        if let Some(i) = index
+            && self.anchor.is_some()
+        {
+            self.anchor = Some(center(objects[i]));
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hbb(x: f32, y: f32, size: f32) -> Hbb {
+        Hbb::from_xywh(x, y, size, size)
+    }
+
+    #[test]
+    fn test_ids_persist_while_objects_move() {
+        let mut tracker = ObjectTracker::new();
+        let first = tracker.update(&[&hbb(100.0, 100.0, 80.0), &hbb(600.0, 100.0, 80.0)]);
+        assert_eq!(first[0].id, 0);
+        assert_eq!(first[1].id, 1);
+
+        // Reported in the opposite order after a small move
+        let second = tracker.update(&[&hbb(610.0, 102.0, 80.0), &hbb(105.0, 98.0, 80.0)]);
+        assert_eq!(second[0].id, 1);
+        assert_eq!(second[1].id, 0);
+        assert_eq!(second[0].hits, 2);
+    }
+
+    #[test]
+    fn test_ids_survive_crossing_paths() {
+        let mut tracker = ObjectTracker::new();
+        // A walks right, B walks left, slightly lower; they overlap mid-frame
+        let a = |t: usize| hbb(100.0 + 20.0 * t as f32, 100.0, 100.0);
+        let b = |t: usize| hbb(700.0 - 20.0 * t as f32, 130.0, 100.0);
+
+        let start = tracker.update(&[&a(0), &b(0)]);
+        let (id_a, id_b) = (start[0].id, start[1].id);
+        for t in 1..=30 {
+            // Alternate the detection order so position in the list can't help
+            let tracked = if t % 2 == 0 {
+                let tracked = tracker.update(&[&a(t), &b(t)]);
+                [tracked[0].id, tracked[1].id]
+            } else {
+                let tracked = tracker.update(&[&b(t), &a(t)]);
+                [tracked[1].id, tracked[0].id]
+            };
+            assert_eq!(tracked, [id_a, id_b], "IDs swapped at frame {t}");
+        }
+    }
+
+    #[test]
+    fn test_new_object_gets_new_id_and_lost_tracks_expire() {
+        let mut tracker = ObjectTracker::new();
+        let first = tracker.update(&[&hbb(100.0, 100.0, 80.0)]);
+        let second = tracker.update(&[&hbb(100.0, 100.0, 80.0), &hbb(800.0, 100.0, 80.0)]);
+        assert_eq!(second[0].id, first[0].id);
+        assert_eq!(second[1].id, 1);
+
+        // A brief dropout keeps the ID...
+        tracker.update(&[]);
+        assert_eq!(
+            tracker.update(&[&hbb(100.0, 100.0, 80.0)])[0].id,
+            first[0].id
+        );
+        // ...but a long absence starts a fresh track
+        for _ in 0..=MAX_MISSED_FRAMES {
+            tracker.update(&[]);
+        }
+        assert_eq!(tracker.update(&[&hbb(100.0, 100.0, 80.0)])[0].id, 2);
+    }
+
+    #[test]
+    fn test_primary_stays_locked_until_it_leaves() {
+        let mut tracker = ObjectTracker::new();
+        let speaker = hbb(100.0, 100.0, 100.0);
+        let guest = hbb(900.0, 100.0, 90.0);
+
+        // The larger object wins the first tie on stability
+        let objects = [&speaker, &guest];
+        let tracked = tracker.update(&objects);
+        assert_eq!(tracker.primary_index(&objects, &tracked), Some(0));
+
+        // The guest leaning in and growing doesn't steal the crop
+        let leaning_guest = hbb(890.0, 95.0, 110.0);
+        let objects = [&leaning_guest, &speaker];
+        let tracked = tracker.update(&objects);
+        assert_eq!(tracker.primary_index(&objects, &tracked), Some(1));
+
+        // Once the speaker is gone, the remaining object takes over
+        let objects = [&leaning_guest];
+        let tracked = tracker.update(&objects);
+        assert_eq!(tracker.primary_index(&objects, &tracked), Some(0));
+    }
+
+    #[test]
+    fn test_picked_subject_is_followed_after_its_track_is_lost() {
+        let mut tracker = ObjectTracker::new();
+        let speaker = hbb(100.0, 100.0, 100.0);
+        let guest = hbb(900.0, 100.0, 90.0);
+
+        // The user picks the smaller guest over the speaker
+        let objects = [&speaker, &guest];
+        let tracked = tracker.update(&objects);
+        tracker.lock_onto(&objects, &tracked, 1);
+        assert!(tracker.is_locked());
+        assert_eq!(tracker.primary_index(&objects, &tracked), Some(1));
+
+        // The guest's box jumps too far to match its track; the object
+        // nearest their last position still wins over the more stable speaker
+        let moved_guest = hbb(1000.0, 120.0, 90.0);
+        let objects = [&speaker, &moved_guest];
+        let tracked = tracker.update(&objects);
+        assert_ne!(tracked[1].id, 1);
+        assert_eq!(tracker.primary_index(&objects, &tracked), Some(1));
+
+        // ...and that new track is followed from then on
+        let objects = [&moved_guest, &speaker];
+        let tracked = tracker.update(&objects);
+        assert_eq!(tracker.primary_index(&objects, &tracked), Some(0));
+    }
+}
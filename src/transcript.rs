@@ -1,13 +1,68 @@
+use crate::audio;
+use crate::video_processor_utils;
 use anyhow::{Result, anyhow};
 use openai_api_rs::v1::api::OpenAIClient;
-use openai_api_rs::v1::audio::{AudioTranscriptionRequest, WHISPER_1};
+use openai_api_rs::v1::audio::{
+    AudioTranscriptionRequest, AudioTranslationRequest, TimestampGranularities, WHISPER_1,
+};
+use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
+use std::future::Future;
+use std::io::Write;
 use std::path::Path;
+use std::pin::Pin;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Caption file formats to deliver alongside the output video
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaptionFormat {
+    #[default]
+    Srt,
+    Vtt,
+    Both,
+}
+
+impl CaptionFormat {
+    pub fn includes_srt(&self) -> bool {
+        matches!(self, Self::Srt | Self::Both)
+    }
+
+    pub fn includes_vtt(&self) -> bool {
+        matches!(self, Self::Vtt | Self::Both)
+    }
+}
+
+impl FromStr for CaptionFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "srt" => Ok(Self::Srt),
+            "vtt" => Ok(Self::Vtt),
+            "both" => Ok(Self::Both),
+            _ => Err(format!(
+                "invalid caption format '{s}': expected srt, vtt, or both"
+            )),
+        }
+    }
+}
 
 pub struct TranscriptConfig {
     pub api_key: String,
     pub model: String,
+    /// ISO-639-1 language hint (e.g. "es"); `None` lets the backend auto-detect
+    pub language: Option<String>,
+    /// How many times a request that failed with a transient error is retried
+    pub retries: u32,
+    /// Delay before the first retry; each further retry doubles it
+    pub retry_delay: Duration,
+    /// Runs whisper.cpp at temperature 0 with no fallback to higher ones, so
+    /// its transcripts are repeatable. The cloud API has no such mode: even
+    /// at temperature 0 it falls back to higher temperatures on hard audio.
+    pub deterministic: bool,
 }
 
 impl Default for TranscriptConfig {
@@ -15,15 +70,78 @@ impl Default for TranscriptConfig {
         Self {
             api_key: env::var("OPENAI_API_KEY").unwrap_or_default(),
             model: WHISPER_1.to_string(),
+            language: None,
+            retries: 3,
+            retry_delay: Duration::from_secs(2),
+            deterministic: false,
         }
     }
 }
 
-pub async fn transcribe_audio(
+/// Returns true for backend errors worth retrying: timeouts, dropped
+/// connections, rate limiting, and server-side failures. Errors about the
+/// request itself (bad audio, unsupported format, auth) are not retried.
+fn is_transient_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    // Status codes only count as whole words, so request ids and sizes that
+    // happen to contain the digits aren't mistaken for them
+    let transient_status = message
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| ["429", "500", "502", "503", "504"].contains(&word));
+    transient_status
+        || [
+            "timed out",
+            "timeout",
+            "connection",
+            "error sending request",
+            "rate limit",
+            "overloaded",
+        ]
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Backoff before retry number `attempt` (1-based): `base`, then doubling
+fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+}
+
+/// Runs `request`, retrying transient failures up to `config.retries` times
+/// with exponential backoff and logging each retry
+async fn with_retries<T, F, Fut>(config: &TranscriptConfig, mut request: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < config.retries && is_transient_error(&e.to_string()) => {
+                attempt += 1;
+                let delay = backoff_delay(config.retry_delay, attempt);
+                video_processor_utils::status_println(format_args!(
+                    "{}; retrying in {:.1}s (retry {}/{})",
+                    e,
+                    delay.as_secs_f64(),
+                    attempt,
+                    config.retries
+                ));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Sends one transcription request for `audio_path` in `response_format`,
+/// optionally asking for word and segment timestamps
+async fn request_transcription(
     audio_path: &Path,
-    output_path: &Path,
     config: &TranscriptConfig,
-) -> Result<()> {
+    response_format: &str,
+    word_timestamps: bool,
+) -> Result<Vec<u8>> {
     let mut client = OpenAIClient::builder()
         .with_api_key(&config.api_key)
         .build()
@@ -33,23 +151,1083 @@ pub async fn transcribe_audio(
         audio_path.to_string_lossy().to_string(),
         config.model.clone(),
     );
-    request.response_format = Some("srt".to_string());
+    request.language = config.language.clone();
+    request.response_format = Some(response_format.to_string());
+    if word_timestamps {
+        request.timestamp_granularities = Some(vec![
+            TimestampGranularities::Word,
+            TimestampGranularities::Segment,
+        ]);
+    }
 
     let response = client
         .audio_transcription_raw(request)
         .await
         .map_err(|e| anyhow!("Failed to transcribe audio: {}", e))?;
+    Ok(response.to_vec())
+}
+
+/// Transcription backend selected with `--transcribe-backend`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TranscribeBackend {
+    /// A local whisper.cpp binary, for machines without network access
+    Local,
+    /// OpenAI's hosted Whisper API
+    #[default]
+    Cloud,
+}
+
+impl FromStr for TranscribeBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "cloud" => Ok(Self::Cloud),
+            _ => Err(format!(
+                "invalid transcribe backend '{s}': expected local or cloud"
+            )),
+        }
+    }
+}
+
+/// Future returned by `Transcriber::transcribe`, boxed so backends can be
+/// chosen at runtime as `Box<dyn Transcriber>`
+pub type TranscribeFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
+
+/// Transcribes audio into an SRT file. Implement this and return it from
+/// `transcriber_for` to plug in another speech-to-text backend
+pub trait Transcriber {
+    /// Transcribes `audio_path` and writes SRT captions to `output_path`
+    fn transcribe<'a>(
+        &'a self,
+        audio_path: &'a Path,
+        output_path: &'a Path,
+        config: &'a TranscriptConfig,
+    ) -> TranscribeFuture<'a>;
+}
+
+/// Transcribes with OpenAI's Whisper API, retrying transient failures
+pub struct CloudTranscriber;
+
+impl Transcriber for CloudTranscriber {
+    fn transcribe<'a>(
+        &'a self,
+        audio_path: &'a Path,
+        output_path: &'a Path,
+        config: &'a TranscriptConfig,
+    ) -> TranscribeFuture<'a> {
+        Box::pin(async move {
+            let response = with_retries(config, || {
+                request_transcription(audio_path, config, "srt", false)
+            })
+            .await?;
+
+            let srt_content = String::from_utf8_lossy(&response).to_string();
 
-    let srt_content = String::from_utf8_lossy(&response).to_string();
+            // Create parent directories if they don't exist
+            if let Some(parent) = output_path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| anyhow!("Failed to create output directory: {}", e))?;
+            }
 
-    // Create parent directories if they don't exist
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| anyhow!("Failed to create output directory: {}", e))?;
+            // Write the SRT content to the file
+            fs::write(output_path, srt_content)
+                .map_err(|e| anyhow!("Failed to write SRT file: {}", e))?;
+
+            Ok(())
+        })
     }
+}
 
-    // Write the SRT content to the file
-    fs::write(output_path, srt_content).map_err(|e| anyhow!("Failed to write SRT file: {}", e))?;
+/// Transcribes by running a local whisper.cpp binary (`whisper-cli`) with a
+/// ggml model, so no audio leaves the machine
+pub struct LocalTranscriber {
+    pub program: String,
+    pub model: String,
+}
+
+impl Transcriber for LocalTranscriber {
+    fn transcribe<'a>(
+        &'a self,
+        audio_path: &'a Path,
+        output_path: &'a Path,
+        config: &'a TranscriptConfig,
+    ) -> TranscribeFuture<'a> {
+        Box::pin(async move {
+            // whisper.cpp reads 16 kHz WAV, and writes `<prefix>.srt`
+            let wav_path = audio_path.with_extension("wav");
+            audio::convert_to_whisper_wav(
+                &audio_path.to_string_lossy(),
+                &wav_path.to_string_lossy(),
+            )?;
+            let prefix = output_path.with_extension("");
+            // Awaited through tokio so the run doesn't block a runtime worker
+            let output = tokio::process::Command::new(&self.program)
+                .arg("-m")
+                .arg(&self.model)
+                .arg("-f")
+                .arg(&wav_path)
+                .args(["-l", config.language.as_deref().unwrap_or("auto")])
+                .args(whisper_decoding_args(config.deterministic))
+                .args(["-osrt", "-np", "-of"])
+                .arg(&prefix)
+                .output()
+                .await
+                .map_err(|e| anyhow!("Failed to run whisper '{}': {}", self.program, e))?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "whisper '{}' failed with status {}: {}",
+                    self.program,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+
+            let written = prefix.with_extension("srt");
+            if written != output_path {
+                fs::rename(&written, output_path)
+                    .map_err(|e| anyhow!("Failed to move SRT file: {}", e))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// whisper.cpp arguments pinning decoding to temperature 0 when
+/// `deterministic`: whisper.cpp otherwise retries hard segments at higher,
+/// randomly sampled temperatures
+fn whisper_decoding_args(deterministic: bool) -> Vec<&'static str> {
+    if deterministic {
+        vec!["-tp", "0", "-nf"]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Picks the transcription backend: the cloud API, or whisper.cpp with the
+/// given ggml model
+pub fn transcriber_for(
+    backend: TranscribeBackend,
+    whisper_command: &str,
+    whisper_model: Option<&str>,
+) -> Result<Box<dyn Transcriber>> {
+    match backend {
+        TranscribeBackend::Cloud => Ok(Box::new(CloudTranscriber)),
+        TranscribeBackend::Local => {
+            let model = whisper_model
+                .ok_or_else(|| anyhow!("--transcribe-backend local requires --whisper-model"))?;
+            if !Path::new(model).is_file() {
+                return Err(anyhow!("whisper model not found: {}", model));
+            }
+            Ok(Box::new(LocalTranscriber {
+                program: whisper_command.to_string(),
+                model: model.to_string(),
+            }))
+        }
+    }
+}
+
+/// Translates caption text into a target language. Implement this and return
+/// it from `translation_for` to plug in a translation service in-process
+pub trait Translator {
+    /// Translates each cue's text, returning exactly one translation per input
+    fn translate(&self, texts: &[String], target_language: &str) -> Result<Vec<String>>;
+}
+
+/// How captions are translated to a target language
+pub enum Translation {
+    /// Whisper's translate task, which re-transcribes the audio into English
+    Whisper,
+    /// Cue-by-cue text translation that keeps the source SRT timing
+    Text(Box<dyn Translator>),
+}
+
+/// Translates cues by running an external command with the target language
+/// as its argument: the cue texts are written to its stdin as a JSON array of
+/// strings, and it must print a JSON array of the same length to stdout
+pub struct CommandTranslator {
+    pub program: String,
+}
+
+impl Translator for CommandTranslator {
+    fn translate(&self, texts: &[String], target_language: &str) -> Result<Vec<String>> {
+        let mut child = Command::new(&self.program)
+            .arg(target_language)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run translator '{}': {}", self.program, e))?;
+
+        let input = serde_json::to_vec(texts)?;
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Failed to open translator stdin"))?
+            .write_all(&input)
+            .map_err(|e| anyhow!("Failed to write to translator: {}", e))?;
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| anyhow!("Failed to read translator output: {}", e))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Translator '{}' failed with status: {}",
+                self.program,
+                output.status
+            ));
+        }
+
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| anyhow!("Translator output is not a JSON array of strings: {}", e))
+    }
+}
+
+/// Picks the translation backend for a target language: the external
+/// translator command when one is given, otherwise Whisper, which can only
+/// translate into English
+pub fn translation_for(
+    target_language: &str,
+    translator_command: Option<&str>,
+) -> Result<Translation> {
+    match (translator_command, target_language) {
+        (Some(program), _) => Ok(Translation::Text(Box::new(CommandTranslator {
+            program: program.to_string(),
+        }))),
+        (None, "en") => Ok(Translation::Whisper),
+        (None, other) => Err(anyhow!(
+            "Whisper can only translate to en; pass --translator-command to translate to '{}'",
+            other
+        )),
+    }
+}
+
+/// Translates audio straight to English SRT using Whisper's translate task
+pub async fn translate_audio_to_english(
+    audio_path: &Path,
+    output_path: &Path,
+    config: &TranscriptConfig,
+) -> Result<()> {
+    let response = with_retries(config, || async move {
+        let mut client = OpenAIClient::builder()
+            .with_api_key(&config.api_key)
+            .build()
+            .map_err(|e| anyhow!("Failed to create OpenAI client: {}", e))?;
+
+        let mut request = AudioTranslationRequest::new(
+            audio_path.to_string_lossy().to_string(),
+            config.model.clone(),
+        );
+        request.response_format = Some("srt".to_string());
+
+        client
+            .audio_translation_raw(request)
+            .await
+            .map_err(|e| anyhow!("Failed to translate audio: {}", e))
+    })
+    .await?;
+
+    fs::write(output_path, String::from_utf8_lossy(&response).to_string())
+        .map_err(|e| anyhow!("Failed to write SRT file: {}", e))?;
 
     Ok(())
 }
+
+/// Translates the text of each SRT cue, keeping cue numbers and timing intact
+pub fn translate_srt(
+    srt: &str,
+    target_language: &str,
+    translator: &dyn Translator,
+) -> Result<String> {
+    let srt = srt.replace("\r\n", "\n");
+    let cues: Vec<(Vec<&str>, String)> = srt
+        .split("\n\n")
+        .filter_map(|block| {
+            let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+            let timing_index = lines.iter().position(|l| l.contains("-->"))?;
+            let text = lines[timing_index + 1..].join("\n");
+            Some((lines[..=timing_index].to_vec(), text))
+        })
+        .collect();
+
+    let texts: Vec<String> = cues.iter().map(|(_, text)| text.clone()).collect();
+    let translated = translator.translate(&texts, target_language)?;
+    if translated.len() != texts.len() {
+        return Err(anyhow!(
+            "Translator returned {} cues for {} inputs",
+            translated.len(),
+            texts.len()
+        ));
+    }
+
+    let mut output = String::new();
+    for ((header, _), text) in cues.iter().zip(translated) {
+        for line in header {
+            output.push_str(line);
+            output.push('\n');
+        }
+        output.push_str(text.trim());
+        output.push_str("\n\n");
+    }
+    Ok(output)
+}
+
+/// Reads an SRT file, translates its cues, and writes the result to `output_path`
+pub fn translate_srt_file(
+    srt_path: &Path,
+    output_path: &Path,
+    target_language: &str,
+    translator: &dyn Translator,
+) -> Result<()> {
+    let srt =
+        fs::read_to_string(srt_path).map_err(|e| anyhow!("Failed to read SRT file: {}", e))?;
+    fs::write(
+        output_path,
+        translate_srt(&srt, target_language, translator)?,
+    )
+    .map_err(|e| anyhow!("Failed to write SRT file: {}", e))?;
+    Ok(())
+}
+
+/// Validates a transcription language given as an ISO-639-1 code (e.g. "es",
+/// "PT"), returning it lowercased
+pub fn parse_language(language: &str) -> Result<String> {
+    let code = language.trim();
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(anyhow!(
+            "invalid language '{}': expected a two-letter ISO-639-1 code, e.g. es",
+            language
+        ));
+    }
+    Ok(code.to_ascii_lowercase())
+}
+
+/// A single transcribed word with its start and end time in seconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimedWord {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// A caption line with its timing and, when every word in it was timestamped,
+/// the per-word timing used for karaoke highlighting
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptionSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    /// Empty when word timing is unavailable; callers fall back to line timing
+    pub words: Vec<TimedWord>,
+}
+
+#[derive(Deserialize)]
+struct VerboseTranscript {
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+    #[serde(default)]
+    words: Vec<VerboseWord>,
+}
+
+#[derive(Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct VerboseWord {
+    word: String,
+    start: Option<f64>,
+    end: Option<f64>,
+}
+
+/// Transcribes audio with word-level timestamps, returning caption segments
+/// whose words carry their own timing where the backend provided it
+pub async fn transcribe_audio_words(
+    audio_path: &Path,
+    config: &TranscriptConfig,
+) -> Result<Vec<CaptionSegment>> {
+    let response = with_retries(config, || {
+        request_transcription(audio_path, config, "verbose_json", true)
+    })
+    .await?;
+
+    parse_verbose_transcript(&String::from_utf8_lossy(&response))
+}
+
+/// Parses a Whisper `verbose_json` transcript into caption segments. Words are
+/// assigned to the segment they start in; a segment containing any word
+/// without timestamps keeps no words so it is captioned line by line
+pub fn parse_verbose_transcript(json: &str) -> Result<Vec<CaptionSegment>> {
+    let transcript: VerboseTranscript = serde_json::from_str(json)
+        .map_err(|e| anyhow!("Failed to parse verbose transcript: {}", e))?;
+
+    let mut segments: Vec<CaptionSegment> = transcript
+        .segments
+        .into_iter()
+        .map(|s| CaptionSegment {
+            start: s.start,
+            end: s.end,
+            text: s.text.trim().to_string(),
+            words: Vec::new(),
+        })
+        .collect();
+    let mut untimed = vec![false; segments.len()];
+
+    // Untimed words inherit the segment of the last timed word before them
+    let mut current = 0;
+    for word in transcript.words {
+        let text = word.word.trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        match (word.start, word.end) {
+            (Some(start), Some(end)) => {
+                while current + 1 < segments.len() && start >= segments[current + 1].start {
+                    current += 1;
+                }
+                if let Some(segment) = segments.get_mut(current) {
+                    segment.words.push(TimedWord { text, start, end });
+                }
+            }
+            _ => {
+                if let Some(flag) = untimed.get_mut(current) {
+                    *flag = true;
+                }
+            }
+        }
+    }
+
+    for (segment, untimed) in segments.iter_mut().zip(untimed) {
+        if untimed {
+            segment.words.clear();
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Formats seconds as an SRT timestamp (`HH:MM:SS,mmm`)
+fn srt_timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Renders caption segments as SRT, one cue per segment
+pub fn segments_to_srt(segments: &[CaptionSegment]) -> String {
+    let mut srt = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        srt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            srt_timestamp(segment.start),
+            srt_timestamp(segment.end),
+            segment.text
+        ));
+    }
+    srt
+}
+
+/// Parses an SRT timestamp (`HH:MM:SS,mmm`) into seconds
+fn parse_srt_timestamp(timestamp: &str) -> Option<f64> {
+    let (hms, millis) = timestamp.trim().split_once(',')?;
+    let mut parts = hms.split(':').map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    let millis = millis.parse::<u64>().ok()?;
+    Some((hours * 3600 + minutes * 60 + seconds) as f64 + millis as f64 / 1000.0)
+}
+
+/// Breaks `text` into lines of at most `max_chars` characters at word
+/// boundaries; a word longer than `max_chars` gets a line of its own
+fn wrap_words(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Most lines shown at once in a wrapped cue
+const MAX_CUE_LINES: usize = 2;
+
+/// Wraps each SRT cue's text at word boundaries to at most `max_chars`
+/// characters per line. A cue that needs more than two lines is split into
+/// consecutive cues of up to two lines that share its time span in proportion
+/// to their length. Cues are renumbered from 1.
+pub fn wrap_srt(srt: &str, max_chars: usize) -> String {
+    let srt = srt.replace("\r\n", "\n");
+    let mut output = String::new();
+    let mut cue_number = 0;
+
+    for block in srt.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        let Some(timing_index) = lines.iter().position(|l| l.contains("-->")) else {
+            continue;
+        };
+        let timing = lines[timing_index];
+        let wrapped = wrap_words(&lines[timing_index + 1..].join(" "), max_chars);
+
+        let span = timing.split_once("-->").and_then(|(start, end)| {
+            let end = end.split_whitespace().next()?;
+            Some((parse_srt_timestamp(start)?, parse_srt_timestamp(end)?))
+        });
+        let Some((start, end)) = span.filter(|_| wrapped.len() > MAX_CUE_LINES) else {
+            // Fits in one cue (or has timing we can't re-time): keep it whole
+            cue_number += 1;
+            output.push_str(&format!("{}\n{}\n", cue_number, timing));
+            for line in &wrapped {
+                output.push_str(line);
+                output.push('\n');
+            }
+            output.push('\n');
+            continue;
+        };
+
+        let chunks: Vec<&[String]> = wrapped.chunks(MAX_CUE_LINES).collect();
+        let length = |chunk: &[String]| -> usize { chunk.iter().map(|l| l.chars().count()).sum() };
+        let total = chunks.iter().map(|chunk| length(chunk)).sum::<usize>() as f64;
+        let mut elapsed = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let chunk_start = start + (end - start) * elapsed as f64 / total;
+            elapsed += length(chunk);
+            let chunk_end = if i + 1 == chunks.len() {
+                end
+            } else {
+                start + (end - start) * elapsed as f64 / total
+            };
+
+            cue_number += 1;
+            output.push_str(&format!(
+                "{}\n{} --> {}\n{}\n\n",
+                cue_number,
+                srt_timestamp(chunk_start),
+                srt_timestamp(chunk_end),
+                chunk.join("\n")
+            ));
+        }
+    }
+
+    output
+}
+
+/// Rewrites an SRT file with its cues wrapped to `max_chars` characters per line
+pub fn wrap_srt_file(srt_path: &Path, max_chars: usize) -> Result<()> {
+    let srt =
+        fs::read_to_string(srt_path).map_err(|e| anyhow!("Failed to read SRT file: {}", e))?;
+    fs::write(srt_path, wrap_srt(&srt, max_chars))
+        .map_err(|e| anyhow!("Failed to write SRT file: {}", e))?;
+    Ok(())
+}
+
+/// Start and end in seconds of an SRT cue's `start --> end` timing line
+fn srt_cue_span(timing: &str) -> Option<(f64, f64)> {
+    let (start, end) = timing.split_once("-->")?;
+    let end = end.split_whitespace().next()?;
+    Some((parse_srt_timestamp(start)?, parse_srt_timestamp(end)?))
+}
+
+/// Ends every cue by `content_end` seconds, dropping cues that start after
+/// it, so captions don't run on over `--pad-to-duration` padding
+pub fn clamp_srt(srt: &str, content_end: f64) -> String {
+    let srt = srt.replace("\r\n", "\n");
+    let mut output = String::new();
+    let mut cue_number = 0;
+
+    for block in srt.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        let Some(timing_index) = lines.iter().position(|l| l.contains("-->")) else {
+            continue;
+        };
+        let timing = lines[timing_index];
+        let timing = match srt_cue_span(timing) {
+            Some((start, _)) if start >= content_end => continue,
+            Some((start, end)) if end > content_end => format!(
+                "{} --> {}",
+                srt_timestamp(start),
+                srt_timestamp(content_end)
+            ),
+            _ => timing.to_string(),
+        };
+
+        cue_number += 1;
+        output.push_str(&format!("{}\n{}\n", cue_number, timing));
+        for line in &lines[timing_index + 1..] {
+            output.push_str(line);
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Rewrites an SRT file with its cues ended by `content_end` seconds
+pub fn clamp_srt_file(srt_path: &Path, content_end: f64) -> Result<()> {
+    let srt =
+        fs::read_to_string(srt_path).map_err(|e| anyhow!("Failed to read SRT file: {}", e))?;
+    fs::write(srt_path, clamp_srt(&srt, content_end))
+        .map_err(|e| anyhow!("Failed to write SRT file: {}", e))?;
+    Ok(())
+}
+
+/// Start and end in seconds of each SRT cue with readable timing, in order
+pub fn srt_cue_times(srt: &str) -> Vec<(f64, f64)> {
+    srt.lines()
+        .filter(|line| line.contains("-->"))
+        .filter_map(srt_cue_span)
+        .collect()
+}
+
+/// Prefixes each cue that `srt_cue_times` reads with an ASS `\fs` override
+/// giving it the matching size from `font_sizes`, for
+/// `--caption-dynamic-size`. ffmpeg passes the override through when it burns
+/// the SRT.
+pub fn size_srt_cues(srt: &str, font_sizes: &[u32]) -> String {
+    let mut sizes = font_sizes.iter();
+    let mut pending = None;
+    let mut output = String::new();
+    for line in srt.replace("\r\n", "\n").lines() {
+        if line.contains("-->") && srt_cue_span(line).is_some() {
+            pending = sizes.next();
+        } else if !line.trim().is_empty()
+            && let Some(size) = pending.take()
+        {
+            output.push_str(&format!("{{\\fs{size}}}"));
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Ends every segment, and word, by `content_end` seconds, dropping those
+/// that start after it
+pub fn clamp_segments(segments: Vec<CaptionSegment>, content_end: f64) -> Vec<CaptionSegment> {
+    segments
+        .into_iter()
+        .filter(|segment| segment.start < content_end)
+        .map(|mut segment| {
+            segment.end = segment.end.min(content_end);
+            segment.words.retain(|word| word.start < content_end);
+            for word in &mut segment.words {
+                word.end = word.end.min(content_end);
+            }
+            segment
+        })
+        .collect()
+}
+
+/// Serializes caption segments as the `transcript.json` array: each segment
+/// with its start/end in seconds, text, and per-word timings (empty where the
+/// backend provided none)
+pub fn segments_to_json(segments: &[CaptionSegment]) -> Result<String> {
+    serde_json::to_string_pretty(segments)
+        .map_err(|e| anyhow!("Failed to serialize transcript: {}", e))
+}
+
+/// Writes caption segments to `json_path` as JSON
+pub fn write_transcript_json(segments: &[CaptionSegment], json_path: &Path) -> Result<()> {
+    fs::write(json_path, segments_to_json(segments)?)
+        .map_err(|e| anyhow!("Failed to write {}: {}", json_path.display(), e))
+}
+
+/// Converts SRT captions to WebVTT: adds the `WEBVTT` header, switches the
+/// timestamp millisecond separator from `,` to `.`, and renumbers cues from 1
+pub fn srt_to_vtt(srt: &str) -> String {
+    let srt = srt.replace("\r\n", "\n");
+    let mut vtt = String::from("WEBVTT\n");
+    let mut cue_number = 0;
+
+    for block in srt.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        let Some(timing_index) = lines.iter().position(|l| l.contains("-->")) else {
+            continue;
+        };
+
+        cue_number += 1;
+        vtt.push_str(&format!("\n{}\n", cue_number));
+        vtt.push_str(&lines[timing_index].replace(',', "."));
+        vtt.push('\n');
+        for text in &lines[timing_index + 1..] {
+            vtt.push_str(text);
+            vtt.push('\n');
+        }
+    }
+
+    vtt
+}
+
+/// Reads an SRT file and writes it as WebVTT to `vtt_path`
+pub fn write_vtt(srt_path: &Path, vtt_path: &Path) -> Result<()> {
+    let srt =
+        fs::read_to_string(srt_path).map_err(|e| anyhow!("Failed to read SRT file: {}", e))?;
+    fs::write(vtt_path, srt_to_vtt(&srt))
+        .map_err(|e| anyhow!("Failed to write VTT file: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srt_to_vtt() {
+        let srt = "1\r\n00:00:00,000 --> 00:00:02,500\r\nHello there.\r\n\r\n\
+                   2\r\n00:00:02,500 --> 00:00:05,120\r\nGeneral Kenobi,\r\nyou are a bold one.\r\n\r\n";
+
+        let expected = "WEBVTT\n\
+                        \n\
+                        1\n\
+                        00:00:00.000 --> 00:00:02.500\n\
+                        Hello there.\n\
+                        \n\
+                        2\n\
+                        00:00:02.500 --> 00:00:05.120\n\
+                        General Kenobi,\n\
+                        you are a bold one.\n";
+
+        assert_eq!(srt_to_vtt(srt), expected);
+    }
+
+    struct ShoutingTranslator;
+
+    impl Translator for ShoutingTranslator {
+        fn translate(&self, texts: &[String], target_language: &str) -> Result<Vec<String>> {
+            Ok(texts
+                .iter()
+                .map(|t| format!("[{}] {}", target_language, t.to_uppercase()))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_translate_srt_keeps_timing() {
+        let srt = "1\r\n00:00:00,000 --> 00:00:02,500\r\nHola.\r\n\r\n\
+                   2\r\n00:00:02,500 --> 00:00:05,120\r\nQue tal,\r\namigo?\r\n\r\n";
+
+        let expected = "1\n00:00:00,000 --> 00:00:02,500\n[fr] HOLA.\n\n\
+                        2\n00:00:02,500 --> 00:00:05,120\n[fr] QUE TAL,\nAMIGO?\n\n";
+
+        assert_eq!(
+            translate_srt(srt, "fr", &ShoutingTranslator).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_translation_for() {
+        assert!(matches!(
+            translation_for("en", None),
+            Ok(Translation::Whisper)
+        ));
+        assert!(translation_for("de", None).is_err());
+        assert!(matches!(
+            translation_for("de", Some("my-translator")),
+            Ok(Translation::Text(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(parse_language("es").unwrap(), "es");
+        assert_eq!(parse_language(" PT ").unwrap(), "pt");
+        assert!(parse_language("spanish").is_err());
+        assert!(parse_language("e1").is_err());
+    }
+
+    #[test]
+    fn test_caption_format_parse() {
+        assert_eq!("VTT".parse::<CaptionFormat>().unwrap(), CaptionFormat::Vtt);
+        assert!("both".parse::<CaptionFormat>().unwrap().includes_srt());
+        assert!("both".parse::<CaptionFormat>().unwrap().includes_vtt());
+        assert!(!CaptionFormat::Srt.includes_vtt());
+        assert!("ass".parse::<CaptionFormat>().is_err());
+    }
+
+    #[test]
+    fn test_transcriber_for() {
+        assert_eq!(
+            "LOCAL".parse::<TranscribeBackend>().unwrap(),
+            TranscribeBackend::Local
+        );
+        assert!("whisper".parse::<TranscribeBackend>().is_err());
+
+        assert!(transcriber_for(TranscribeBackend::Cloud, "whisper-cli", None).is_ok());
+        // The local backend needs an existing model file
+        assert!(transcriber_for(TranscribeBackend::Local, "whisper-cli", None).is_err());
+        assert!(
+            transcriber_for(
+                TranscribeBackend::Local,
+                "whisper-cli",
+                Some("/no/such/ggml-base.bin")
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_verbose_transcript_assigns_words() {
+        let json = r#"{
+            "text": "Hello there. General Kenobi.",
+            "segments": [
+                {"start": 0.0, "end": 1.5, "text": " Hello there."},
+                {"start": 1.5, "end": 3.0, "text": " General Kenobi."}
+            ],
+            "words": [
+                {"word": "Hello", "start": 0.0, "end": 0.6},
+                {"word": "there", "start": 0.6, "end": 1.4},
+                {"word": "General", "start": 1.5, "end": 2.2},
+                {"word": "Kenobi", "start": 2.2, "end": 3.0}
+            ]
+        }"#;
+
+        let segments = parse_verbose_transcript(json).unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "Hello there.");
+        assert_eq!(segments[0].words.len(), 2);
+        assert_eq!(segments[1].words[0].text, "General");
+        assert_eq!(segments[1].words[1].start, 2.2);
+    }
+
+    #[test]
+    fn test_parse_verbose_transcript_untimed_words_fall_back() {
+        let json = r#"{
+            "segments": [
+                {"start": 0.0, "end": 1.5, "text": "Hello there."},
+                {"start": 1.5, "end": 3.0, "text": "General Kenobi."}
+            ],
+            "words": [
+                {"word": "Hello", "start": 0.0, "end": 0.6},
+                {"word": "there"},
+                {"word": "General", "start": 1.5, "end": 2.2},
+                {"word": "Kenobi", "start": 2.2, "end": 3.0}
+            ]
+        }"#;
+
+        let segments = parse_verbose_transcript(json).unwrap();
+        assert!(segments[0].words.is_empty());
+        assert_eq!(segments[1].words.len(), 2);
+    }
+
+    #[test]
+    fn test_segments_to_srt() {
+        let segments = vec![CaptionSegment {
+            start: 61.25,
+            end: 3723.5,
+            text: "Hi".to_string(),
+            words: Vec::new(),
+        }];
+        assert_eq!(
+            segments_to_srt(&segments),
+            "1\n00:01:01,250 --> 01:02:03,500\nHi\n\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap_srt_splits_long_cue() {
+        let srt = "1\n00:00:00,000 --> 00:00:06,000\nthe quick brown fox jumps over the lazy dog again and again\n\n\
+                   2\n00:00:07,000 --> 00:00:08,000\nShort one\n\n";
+        assert_eq!(
+            wrap_srt(srt, 20),
+            "1\n00:00:00,000 --> 00:00:04,000\nthe quick brown fox\njumps over the lazy\n\n\
+             2\n00:00:04,000 --> 00:00:06,000\ndog again and again\n\n\
+             3\n00:00:07,000 --> 00:00:08,000\nShort one\n\n"
+        );
+    }
+
+    #[test]
+    fn test_clamp_srt_ends_cues_with_the_content() {
+        let srt = "1\n00:00:00,000 --> 00:00:04,000\nHello\n\n\
+                   2\n00:00:09,000 --> 00:00:12,500\nrunning over\n\n\
+                   3\n00:00:12,000 --> 00:00:13,000\npast the end\n\n";
+        assert_eq!(
+            clamp_srt(srt, 10.0),
+            "1\n00:00:00,000 --> 00:00:04,000\nHello\n\n\
+             2\n00:00:09,000 --> 00:00:10,000\nrunning over\n\n"
+        );
+
+        let word = |text: &str, start, end| TimedWord {
+            text: text.to_string(),
+            start,
+            end,
+        };
+        let segments = vec![
+            CaptionSegment {
+                start: 9.0,
+                end: 10.8,
+                text: "last words".to_string(),
+                words: vec![word("last", 9.0, 9.8), word("words", 9.8, 10.8)],
+            },
+            CaptionSegment {
+                start: 10.5,
+                end: 11.0,
+                text: "gone".to_string(),
+                words: Vec::new(),
+            },
+        ];
+        let clamped = clamp_segments(segments, 10.0);
+        assert_eq!(clamped.len(), 1);
+        assert_eq!(clamped[0].end, 10.0);
+        assert_eq!(clamped[0].words[1].end, 10.0);
+    }
+
+    #[test]
+    fn test_size_srt_cues() {
+        let srt = "1\n00:00:00,000 --> 00:00:02,000\nquiet words\nsecond line\n\n\
+                   2\n00:00:02,500 --> 00:00:04,000\nLOUD WORDS\n\n";
+        assert_eq!(srt_cue_times(srt), [(0.0, 2.0), (2.5, 4.0)]);
+        assert_eq!(
+            size_srt_cues(srt, &[6, 12]),
+            "1\n00:00:00,000 --> 00:00:02,000\n{\\fs6}quiet words\nsecond line\n\n\
+             2\n00:00:02,500 --> 00:00:04,000\n{\\fs12}LOUD WORDS\n\n"
+        );
+        // Cues past the sizes given are left as they are
+        assert!(size_srt_cues(srt, &[6]).contains("\nLOUD WORDS\n"));
+    }
+
+    #[test]
+    fn test_segments_to_json() {
+        let segments = vec![
+            CaptionSegment {
+                start: 0.0,
+                end: 1.5,
+                text: "Hello there.".to_string(),
+                words: vec![
+                    TimedWord {
+                        text: "Hello".to_string(),
+                        start: 0.0,
+                        end: 0.6,
+                    },
+                    TimedWord {
+                        text: "there.".to_string(),
+                        start: 0.6,
+                        end: 1.4,
+                    },
+                ],
+            },
+            CaptionSegment {
+                start: 1.5,
+                end: 3.0,
+                text: "General Kenobi.".to_string(),
+                words: Vec::new(),
+            },
+        ];
+
+        let json: serde_json::Value =
+            serde_json::from_str(&segments_to_json(&segments).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "start": 0.0,
+                    "end": 1.5,
+                    "text": "Hello there.",
+                    "words": [
+                        {"text": "Hello", "start": 0.0, "end": 0.6},
+                        {"text": "there.", "start": 0.6, "end": 1.4}
+                    ]
+                },
+                {"start": 1.5, "end": 3.0, "text": "General Kenobi.", "words": []}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        assert!(is_transient_error(
+            "Failed to transcribe audio: error sending request: operation timed out"
+        ));
+        assert!(is_transient_error(
+            "Failed to transcribe audio: 503 Service Unavailable"
+        ));
+        assert!(is_transient_error(
+            "Failed to transcribe audio: 429 Too Many Requests"
+        ));
+        assert!(!is_transient_error(
+            "Failed to transcribe audio: 400 Bad Request: Invalid file format"
+        ));
+        assert!(!is_transient_error(
+            "Failed to transcribe audio: 401 Unauthorized"
+        ));
+        assert!(!is_transient_error(
+            "Failed to transcribe audio: 400 Bad Request: file of 15030 bytes (req_4291) is too short"
+        ));
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles() {
+        let base = Duration::from_millis(500);
+        assert_eq!(backoff_delay(base, 1), Duration::from_millis(500));
+        assert_eq!(backoff_delay(base, 2), Duration::from_secs(1));
+        assert_eq!(backoff_delay(base, 4), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_whisper_decoding_args() {
+        assert!(whisper_decoding_args(false).is_empty());
+        assert_eq!(whisper_decoding_args(true), ["-tp", "0", "-nf"]);
+    }
+
+    #[tokio::test]
+    async fn test_with_retries_only_retries_transient_errors() {
+        let config = TranscriptConfig {
+            retries: 2,
+            retry_delay: Duration::ZERO,
+            ..TranscriptConfig::default()
+        };
+
+        // Recovers once the transient failures stop
+        let mut calls = 0;
+        let result = with_retries(&config, || {
+            calls += 1;
+            let attempt = calls;
+            async move {
+                if attempt < 3 {
+                    Err(anyhow!("request timed out"))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        // Gives up after the configured number of retries
+        let mut calls = 0;
+        let result: Result<()> = with_retries(&config, || {
+            calls += 1;
+            async { Err(anyhow!("502 Bad Gateway")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        // Input errors fail immediately
+        let mut calls = 0;
+        let result: Result<()> = with_retries(&config, || {
+            calls += 1;
+            async { Err(anyhow!("400 Bad Request: Invalid file format")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
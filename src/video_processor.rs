@@ -1,45 +1,1042 @@
 use crate::cli::Args;
 use crate::config;
 use crate::crop;
+use crate::crop_log;
+use crate::error::{self, Land2PortError};
+use crate::image::{CutDetector, CutLog};
 use crate::metrics;
-use crate::video_processor_utils;
+use crate::progress::{ProgressCallback, ProgressEvent, ProgressReporter, Stage};
+use crate::tracker::ObjectTracker;
+use crate::video_processor_utils::{self, Playback, SubjectPick};
 use crate::video_sink::{self, VideoSink};
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{Receiver, SyncSender, channel, sync_channel};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 use usls::{
     Annotator, Config, DataLoader, HbbStyle, Model, ObbStyle,
     models::{DB, YOLO},
     perf_chart,
 };
 
+/// Renders the source using the per-frame crops from a `crops.json` file,
+/// skipping model inference and smoothing entirely. Errors (after decoding the
+/// whole source, so both counts are reported) if the file doesn't have exactly
+/// one crop per source frame. A `--preview-grid` contact sheet of the replayed
+/// crops is saved to `preview_path` when set.
+fn replay_crops(
+    args: &Args,
+    import_path: &str,
+    processed_video: &str,
+    preview_path: Option<&str>,
+    progress: &mut ProgressCallback,
+) -> Result<()> {
+    let crops = crop_log::load_json(import_path)?;
+    progress(ProgressEvent::Stage(Stage::Replaying {
+        crops: crops.len(),
+        path: import_path.to_string(),
+    }));
+
+    // Past the end of the crop list: keep counting so the error below can
+    // report the source's actual frame count.
+    let (viewer, frame_count) = render_crops(
+        args,
+        processed_video,
+        preview_path,
+        Some(crops.len() as u64),
+        progress,
+        |frame_index, _| crops.get(frame_index).cloned(),
+    )?;
+
+    if frame_count != crops.len() {
+        anyhow::bail!(
+            "crop count mismatch: {} has {} frames but source {} has {}",
+            import_path,
+            crops.len(),
+            args.source,
+            frame_count
+        );
+    }
+
+    finish_render(viewer, preview_path, progress)
+}
+
+/// Renders the source with a static center crop for `--fallback-center-crop`,
+/// when the detection models failed to load
+fn render_center_crop(
+    args: &Args,
+    processed_video: &str,
+    preview_path: Option<&str>,
+    progress: &mut ProgressCallback,
+) -> Result<()> {
+    let frame_rate =
+        video_processor_utils::output_frame_rate(args, video_sink::probe_fps(&args.source));
+    let total_frames = video_sink::probe_duration(&args.source)
+        .and_then(|duration| video_sink::estimate_frame_count(duration, frame_rate));
+    progress(ProgressEvent::Stage(Stage::Processing { total_frames }));
+
+    let mut center_crop = None;
+    let (viewer, _) = render_crops(
+        args,
+        processed_video,
+        preview_path,
+        total_frames,
+        progress,
+        |_, image| {
+            let crop = center_crop.get_or_insert_with(|| {
+                crop::calculate_no_subject_crop(
+                    crop::NoSubjectMode::Center,
+                    image.width() as f32,
+                    image.height() as f32,
+                    None,
+                    args.aspect_ratio,
+                )
+            });
+            Some(crop.clone())
+        },
+    )?;
+
+    finish_render(viewer, preview_path, progress)
+}
+
+/// Decodes the source and renders each frame with the crop `crop_for` gives
+/// it, without detection or smoothing; frames it has no crop for are counted
+/// but not rendered. Returns the sink, still to be finished with
+/// `finish_render`, and the number of frames decoded.
+fn render_crops(
+    args: &Args,
+    processed_video: &str,
+    preview_path: Option<&str>,
+    total_frames: Option<u64>,
+    progress: &mut ProgressCallback,
+    mut crop_for: impl FnMut(usize, &usls::Image) -> Option<crop::CropResult>,
+) -> Result<(VideoSink, usize)> {
+    let data_loader = DataLoader::new(&args.source)?.stream()?;
+    let source_fps = video_sink::probe_fps(&args.source);
+    let frame_rate = video_processor_utils::output_frame_rate(args, source_fps);
+    let mut decimator = video_processor_utils::FrameDecimator::new(source_fps, frame_rate);
+    let mut viewer = VideoSink::new(
+        processed_video.to_string(),
+        frame_rate,
+        args.encode_settings()?,
+    )
+    .with_window_scale(args.window_scale)
+    .with_output_sizes(
+        &args.output_sizes()?,
+        processed_video,
+        frame_rate,
+        args.encode_settings()?,
+    );
+    if let (Some(_), Some(interval)) = (preview_path, args.preview_grid) {
+        viewer = viewer.with_preview_grid(interval);
+    }
+
+    let mut frame_count = 0;
+    let mut frame_iter = (&data_loader).into_iter();
+    while let Some(images) = metrics::time("decode", || frame_iter.next()) {
+        metrics::inc("frames_decoded", images.len() as u64);
+        let images = decimator.select(images);
+
+        for image in images.iter() {
+            if let Some(crop_result) = crop_for(frame_count, image) {
+                video_processor_utils::process_and_display_crop(
+                    image,
+                    &crop_result,
+                    &mut viewer,
+                    args,
+                )?;
+            }
+            progress(ProgressEvent::FrameProcessed {
+                frame: frame_count,
+                total: total_frames,
+            });
+            frame_count += 1;
+        }
+    }
+    progress(ProgressEvent::Stage(Stage::Finalizing));
+    Ok((viewer, frame_count))
+}
+
+/// Finishes the encode started by `render_crops` and saves its
+/// `--preview-grid` contact sheet to `preview_path` when set
+fn finish_render(
+    mut viewer: VideoSink,
+    preview_path: Option<&str>,
+    progress: &mut ProgressCallback,
+) -> Result<()> {
+    viewer.finalize()?;
+    if let (Some(path), Some(grid)) = (preview_path, viewer.preview_grid()) {
+        grid.save(path)?;
+        progress(ProgressEvent::Saved {
+            what: "Preview grid".to_string(),
+            path: path.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Builds the object detection model for `--object` (or `--model-path`)
+pub fn build_object_model(args: &Args) -> Result<YOLO> {
+    let config = config::build_config(args)?;
+    Ok(match &args.model_path {
+        Some(model_path) => YOLO::new(config.commit()?)
+            .with_context(|| format!("Loading custom model {model_path}"))?,
+        None => YOLO::new(config.commit()?)?,
+    })
+}
+
+/// Builds the object detection model and the OCR (text detection) model
+fn build_models(args: &Args) -> Result<(YOLO, DB)> {
+    let model = build_object_model(args)?;
+
+    // build ocr model
+    let ocr_config = Config::ppocr_det_v5_mobile()
+        .with_model_dtype(usls::DType::Fp16)
+        .with_model_device(args.device.parse()?);
+    let text_model = DB::new(ocr_config.commit()?)?;
+
+    Ok((model, text_model))
+}
+
+/// Extracts the objects that drive the crop from a frame's detection result
+fn frame_objects<'a>(
+    detection: &'a usls::Y,
+    args: &Args,
+    threshold: &mut video_processor_utils::ObjectThreshold,
+) -> Vec<&'a usls::Hbb> {
+    let objects = video_processor_utils::extract_objects_above_threshold(
+        detection,
+        &args.object_names(),
+        threshold,
+    );
+    // Detections outside --roi never drive the crop
+    let objects = crop::heads_in_roi(objects, args.roi);
+    // Drop incidental faces that are tiny relative to the dominant
+    // subject (e.g. faces on a book cover) so they don't inflate the
+    // head count into a stacked layout that splits the real subject.
+    video_processor_utils::filter_small_relative_objects(objects, &args.min_area_ratio)
+}
+
+/// Runs OCR when the frame needs a graphic check, returning the text
+/// detections (if any text was found) and the fraction of the frame they
+/// cover. Whether that makes the frame a graphic depends on the frames before
+/// it, so it is decided in order by `handle_frame`.
+fn detect_text(
+    text_model: &mut DB,
+    args: &Args,
+    image: &usls::Image,
+    object_count: usize,
+) -> Result<(Option<usls::Y>, f32)> {
+    if !((object_count == 0 && args.keep_text) || args.prioritize_text) {
+        return Ok((None, 0.0));
+    }
+
+    let ys = metrics::time("ocr", || text_model.forward(&[image.clone()]))?;
+    if ys[0].hbbs.is_empty() {
+        return Ok((None, 0.0));
+    }
+
+    let coverage = video_processor_utils::graphic_coverage(
+        ys[0].hbbs.iter(),
+        image.width() as f32,
+        image.height() as f32,
+        args.text_prob_threshold,
+    );
+    Ok((Some(ys[0].clone()), coverage))
+}
+
+/// Output of the detection stage for one frame
+struct FrameDetection {
+    detection: usls::Y,
+    text: Option<usls::Y>,
+    text_coverage: f32,
+}
+
+/// The error for a frame skipped by `--detect-interval` before any detection
+/// it could reuse
+fn missing_detection(frame_index: usize) -> Land2PortError {
+    Land2PortError::Model(format!(
+        "no detection to reuse for frame {frame_index}; the object detector returned none before it"
+    ))
+}
+
+/// Runs the object and text models on a single frame
+fn detect_frame(
+    model: &mut YOLO,
+    text_model: &mut DB,
+    args: &Args,
+    image: &usls::Image,
+) -> Result<FrameDetection> {
+    let detection = metrics::time("detect", || model.forward(std::slice::from_ref(image)))
+        .map_err(|e| video_processor_utils::with_out_of_memory_hint(e, "try fewer --jobs"))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            Land2PortError::Model("the object detector returned no result".to_string())
+        })?;
+    // The OCR decision uses the fixed threshold; the adaptive one advances
+    // frame by frame in `handle_frame`
+    let mut threshold = video_processor_utils::ObjectThreshold::fixed(args.object_prob_threshold);
+    let object_count = frame_objects(&detection, args, &mut threshold).len();
+    let (text, text_coverage) = detect_text(text_model, args, image, object_count)?;
+    Ok(FrameDetection {
+        detection,
+        text,
+        text_coverage,
+    })
+}
+
+/// Per-run state used to crop, smooth, and write each detected frame
+struct FrameContext<'a> {
+    args: &'a Args,
+    viewer: VideoSink,
+    annotator: Annotator,
+    text_annotator: Annotator,
+    smooth_duration_frames: usize,
+    frame_index: usize,
+    /// Estimated frame count, when the source's duration is known
+    total_frames: Option<u64>,
+    progress: ProgressCallback,
+    tracker: ObjectTracker,
+    /// Whether `--interactive-pick` still has to ask for the subject
+    pick_pending: bool,
+    /// Crop chosen for the previous frame, for `--no-subject-mode hold-last`
+    last_crop: Option<crop::CropResult>,
+    /// Scene cuts found so far, for `--export-cuts` and the progress reporter
+    cut_log: Option<CutLog>,
+    /// Whether the preview is paused with space
+    paused: bool,
+    /// Drops decoded frames above `--max-fps`
+    decimator: video_processor_utils::FrameDecimator,
+    /// Index and confidence of the frame with the most confident detection
+    best_detection: Option<(usize, f32)>,
+    /// Turns each frame's text coverage into the graphic flag
+    graphic: video_processor_utils::GraphicHysteresis,
+    /// Object probability threshold, relaxed on empty frames with
+    /// `--adaptive-threshold`
+    object_threshold: video_processor_utils::ObjectThreshold,
+    /// Detection confidences collected for `--report-confidences`
+    confidences: Option<video_processor_utils::ConfidenceReport>,
+}
+
+impl FrameContext<'_> {
+    /// Adds a newly detected frame to the `--report-confidences` report;
+    /// frames reusing an earlier detection aren't counted again
+    fn record_confidences(&mut self, detection: &usls::Y) {
+        if let Some(report) = &mut self.confidences {
+            report.record(&detection.hbbs, &self.args.object_names());
+        }
+    }
+}
+
+/// Calculates the crop for a frame from its detections and hands it to the
+/// processor's smoothing (or straight to the sink when smoothing is off).
+/// `reused` marks a frame that repeats an earlier detection under
+/// `--detect-interval`.
+fn handle_frame<P: VideoProcessor + ?Sized>(
+    processor: &mut P,
+    ctx: &mut FrameContext,
+    image: &usls::Image,
+    detection: &usls::Y,
+    text: Option<&usls::Y>,
+    text_coverage: f32,
+    reused: bool,
+) -> Result<()> {
+    let args = ctx.args;
+    let is_graphic = ctx.graphic.update(text_coverage);
+
+    // Only the annotated (non-headless) path needs an owned image;
+    // headless borrows the DataLoader's frame to skip a full clone.
+    let mut img: Cow<usls::Image> = if !args.headless {
+        Cow::Owned(ctx.annotator.annotate(image, detection)?)
+    } else {
+        Cow::Borrowed(image)
+    };
+    if let Some(text) = text
+        && !args.headless
+    {
+        img = Cow::Owned(ctx.text_annotator.annotate(&img, text)?);
+    }
+
+    // Calculate crop areas based on the detection results. Only a new
+    // detection advances the adaptive threshold; a reused one is filtered
+    // again at the threshold it was first filtered with.
+    let objects = if reused {
+        let mut threshold =
+            video_processor_utils::ObjectThreshold::fixed(ctx.object_threshold.current());
+        frame_objects(detection, args, &mut threshold)
+    } else {
+        frame_objects(detection, args, &mut ctx.object_threshold)
+    };
+
+    // Follow objects across frames so the crop can stay locked to one subject
+    let tracked = ctx.tracker.update(&objects);
+    // With --interactive-pick, the first frame with a choice to make asks
+    // the user which subject to follow
+    if ctx.pick_pending && objects.len() > 1 {
+        ctx.pick_pending = false;
+        if let Some(index) = pick_subject(&mut ctx.viewer, image, &objects)? {
+            ctx.tracker.lock_onto(&objects, &tracked, index);
+        }
+    }
+    let primary = ctx
+        .tracker
+        .primary_index(&objects, &tracked)
+        .map(|index| objects[index]);
+    video_processor_utils::debug_println(format_args!("tracked: {:?}", tracked));
+
+    // Hold the previous crop through black frames (fades between scenes),
+    // where detection finds nothing and the fallback framing would jump
+    let held_crop = video_processor_utils::black_frame_crop(
+        image,
+        args.black_frame_threshold,
+        ctx.last_crop.as_ref(),
+    );
+    let latest_crop = metrics::time("crop_calc", || -> Result<_> {
+        Ok(if let Some(crop) = held_crop {
+            crop
+        } else if args.prioritize_text && is_graphic {
+            crop::CropResult::Resize(crop::CropArea::new(
+                0.0,
+                0.0,
+                img.width() as f32,
+                img.height() as f32,
+            ))
+        } else if let Some(roi) = args.roi
+            && objects.is_empty()
+            && !is_graphic
+            && args.no_subject_mode == crop::NoSubjectMode::Center
+        {
+            crop::calculate_roi_crop(
+                roi,
+                img.width() as f32,
+                img.height() as f32,
+                args.aspect_ratio,
+            )
+        } else if objects.is_empty() && !is_graphic {
+            crop::calculate_no_subject_crop(
+                args.no_subject_mode,
+                img.width() as f32,
+                img.height() as f32,
+                ctx.last_crop.as_ref(),
+                args.aspect_ratio,
+            )
+        } else {
+            // Narrow multiple subjects down to the one picked with
+            // --interactive-pick, or the one --focus selects
+            let focused = match primary {
+                Some(primary) if ctx.tracker.is_locked() => vec![primary],
+                _ => crop::focus_heads(&objects, args.focus, img.width() as f32),
+            };
+            // Blur fill replaces the stacked layout, so it needs stacking enabled
+            let crop = crop::calculate_crop_with_primary(
+                args.use_stack_crop || args.use_blur_fill,
+                is_graphic,
+                img.width() as f32,
+                img.height() as f32,
+                &focused,
+                primary,
+                args.aspect_ratio,
+            )?;
+            let crop = crop::pad_crop(
+                crop,
+                &focused,
+                args.crop_padding,
+                img.width() as f32,
+                img.height() as f32,
+            );
+            let crop = match args.roi {
+                Some(roi) => crop::weight_toward_roi(crop, &focused, roi, img.width() as f32),
+                None => crop,
+            };
+            if args.use_blur_fill {
+                crop::blur_fill_instead_of_stack(
+                    crop,
+                    img.width() as f32,
+                    img.height() as f32,
+                    &focused,
+                )
+            } else {
+                crop
+            }
+        })
+    })?;
+
+    // Hold the previous target through movements inside the --deadzone
+    let latest_crop = crop::apply_deadzone(
+        latest_crop,
+        ctx.last_crop.as_ref(),
+        args.deadzone,
+        img.width() as f32,
+    );
+
+    // Print debug information
+    processor.print_debug_info(&objects, &latest_crop, is_graphic);
+    ctx.last_crop = Some(latest_crop.clone());
+
+    if let Some(crop_log) = ctx.viewer.crop_log_mut() {
+        crop_log.record_detection(objects.len(), is_graphic);
+    }
+    if let Some(cut_log) = &mut ctx.cut_log
+        && let Some(cut) = cut_log.observe(image)?
+    {
+        (ctx.progress)(ProgressEvent::Cut(cut.clone()));
+    }
+    if let Some(confidence) = objects
+        .iter()
+        .filter_map(|o| o.confidence())
+        .reduce(f32::max)
+        && ctx.best_detection.is_none_or(|(_, best)| confidence > best)
+    {
+        ctx.best_detection = Some((ctx.frame_index, confidence));
+    }
+    if args.dry_run && !args.report_confidences {
+        let ids: Vec<u64> = tracked.iter().map(|t| t.id).collect();
+        println!(
+            "frame {}: {} objects detected (ids: {:?}, graphic: {})",
+            ctx.frame_index,
+            objects.len(),
+            ids,
+            is_graphic
+        );
+    }
+    (ctx.progress)(ProgressEvent::FrameProcessed {
+        frame: ctx.frame_index,
+        total: ctx.total_frames,
+    });
+    ctx.frame_index += 1;
+
+    if ctx.smooth_duration_frames > 0 {
+        processor.process_frame_with_smoothing(
+            &img,
+            &latest_crop,
+            &objects,
+            args,
+            &mut ctx.viewer,
+            ctx.smooth_duration_frames,
+        )
+    } else {
+        video_processor_utils::process_and_display_crop(&img, &latest_crop, &mut ctx.viewer, args)
+    }
+}
+
+/// Shows the full `image` with its objects numbered and waits for the user
+/// to press the number of the one to track. Returns `None` when they skip
+/// with Enter or Escape or close the window.
+fn pick_subject(
+    viewer: &mut VideoSink,
+    image: &usls::Image,
+    objects: &[&usls::Hbb],
+) -> Result<Option<usize>> {
+    // Only 1-9 can be picked with a single key
+    let mut labels = usls::Y::default();
+    labels.hbbs = objects
+        .iter()
+        .take(9)
+        .enumerate()
+        .map(|(index, object)| (*object).clone().with_name(&(index + 1).to_string()))
+        .collect();
+    let annotator = Annotator::default().with_hbb_style(
+        HbbStyle::default()
+            .with_draw_fill(false)
+            .with_thickness(4)
+            .show_confidence(false)
+            .show_id(false),
+    );
+    viewer.show(&annotator.annotate(image, &labels)?)?;
+    video_processor_utils::status_println(format_args!(
+        "Press the number of the subject to track (Enter or Escape to let the tracker choose)"
+    ));
+
+    loop {
+        if viewer.is_window_exist_and_closed() {
+            return Ok(None);
+        }
+        match video_processor_utils::subject_pick_after_key(viewer.wait_key(50), labels.hbbs.len())
+        {
+            SubjectPick::Waiting => {}
+            SubjectPick::Subject(index) => return Ok(Some(index)),
+            SubjectPick::Skip => return Ok(None),
+        }
+    }
+}
+
+/// Returns true once the user has closed the preview window or pressed Escape.
+/// Checked before each frame is cropped. Space pauses: the current frame stays
+/// on screen (nothing is decoded or re-inferred) until space resumes or the
+/// right arrow steps one frame.
+fn should_stop(viewer: &mut VideoSink, paused: &mut bool) -> bool {
+    loop {
+        if viewer.is_window_exist_and_closed() {
+            return true;
+        }
+
+        // Handle key events and delay; poll less often while paused
+        let delay = if *paused { 50 } else { 1 };
+        match video_processor_utils::playback_after_key(*paused, viewer.wait_key(delay)) {
+            Playback::Stop => return true,
+            Playback::Play => {
+                *paused = false;
+                return false;
+            }
+            Playback::Step => return false,
+            Playback::Pause => *paused = true,
+        }
+    }
+}
+
+/// Sequential pipeline: decode, detect, and crop each batch in turn on the
+/// calling thread (the encode already runs on the sink's own thread).
+fn process_frames_sequential<P: VideoProcessor + ?Sized>(
+    processor: &mut P,
+    ctx: &mut FrameContext,
+    data_loader: &DataLoader,
+    mut model: YOLO,
+    mut text_model: DB,
+) -> Result<()> {
+    let args = ctx.args;
+
+    // With --detect-interval > 1, frames between detections reuse the last
+    // detection (and its text coverage) instead of running the models.
+    let mut detect_schedule = video_processor_utils::DetectionSchedule::new(args.detect_interval);
+    let mut last_detection: Option<(usls::Y, f32)> = None;
+
+    // Drive the iterator explicitly (rather than `for images in &data_loader`)
+    // so the decode/demux time of each batch can be measured separately from
+    // detection and crop work.
+    let mut frame_iter = data_loader.into_iter();
+    'decode: while let Some(images) = metrics::time("decode", || frame_iter.next()) {
+        metrics::inc("frames_decoded", images.len() as u64);
+
+        let images = ctx.decimator.select(images);
+        if images.is_empty() {
+            continue;
+        }
+
+        let detections = if detect_schedule.should_detect(images.len()) || last_detection.is_none()
+        {
+            let detections = metrics::time("detect", || model.forward(&images)).map_err(|e| {
+                video_processor_utils::with_out_of_memory_hint(
+                    e,
+                    &format!("try a smaller --batch-size (currently {})", images.len()),
+                )
+            })?;
+            // A short result would otherwise leave frames to reuse a stale
+            // detection, or none at all
+            if detections.len() < images.len() {
+                return Err(Land2PortError::Model(format!(
+                    "the object detector returned {} results for a batch of {} frames",
+                    detections.len(),
+                    images.len()
+                ))
+                .into());
+            }
+            detections
+        } else {
+            metrics::inc("frames_detect_skipped", images.len() as u64);
+            Vec::new()
+        };
+
+        for (index, image) in images.iter().enumerate() {
+            if should_stop(&mut ctx.viewer, &mut ctx.paused) {
+                break 'decode;
+            }
+
+            if let Some(detection) = detections.get(index) {
+                let mut threshold =
+                    video_processor_utils::ObjectThreshold::fixed(args.object_prob_threshold);
+                let object_count = frame_objects(detection, args, &mut threshold).len();
+                let (text, text_coverage) =
+                    detect_text(&mut text_model, args, image, object_count)?;
+                ctx.record_confidences(detection);
+                handle_frame(
+                    processor,
+                    ctx,
+                    image,
+                    detection,
+                    text.as_ref(),
+                    text_coverage,
+                    false,
+                )?;
+                if args.detect_interval > 1 {
+                    last_detection = Some((detection.clone(), text_coverage));
+                }
+            } else {
+                let (detection, text_coverage) = last_detection
+                    .as_ref()
+                    .ok_or_else(|| missing_detection(ctx.frame_index))?;
+                handle_frame(processor, ctx, image, detection, None, *text_coverage, true)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One frame travelling through the parallel pipeline: the decoded image plus
+/// its detection, or `None` when `--detect-interval` skipped detection for it
+type DetectedFrame = (usls::Image, Option<Result<FrameDetection>>);
+
+/// The detection workers of the parallel pipeline for `--jobs > 1`
+struct DetectionPool {
+    job_tx: SyncSender<(usize, usls::Image)>,
+    result_rx: Receiver<(usize, DetectedFrame)>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl DetectionPool {
+    /// Spawns `--jobs` workers and waits for each to load its models, so a
+    /// load failure is returned before any frame is decoded
+    fn start(args: &Args) -> Result<Self> {
+        let jobs = args.jobs;
+
+        // Bounded so decode can't run far ahead of detection; results are
+        // unbounded so workers never block while the main thread is sending.
+        let (job_tx, job_rx) = sync_channel::<(usize, usls::Image)>(jobs * 2);
+        let job_rx: Arc<Mutex<Receiver<(usize, usls::Image)>>> = Arc::new(Mutex::new(job_rx));
+        let (result_tx, result_rx) = channel::<(usize, DetectedFrame)>();
+        let (ready_tx, ready_rx) = channel::<Result<()>>();
+
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                let job_rx = Arc::clone(&job_rx);
+                let result_tx = result_tx.clone();
+                let ready_tx = ready_tx.clone();
+                let args = args.clone();
+                // The models hold non-`Send` runtime state, so each worker
+                // builds and owns its own instances; only frames and results
+                // cross threads.
+                std::thread::spawn(move || {
+                    let (mut model, mut text_model) = match build_models(&args) {
+                        Ok(models) => {
+                            let _ = ready_tx.send(Ok(()));
+                            models
+                        }
+                        Err(e) => {
+                            let _ = ready_tx.send(Err(e));
+                            return;
+                        }
+                    };
+                    drop(ready_tx);
+                    loop {
+                        let job = job_rx.lock().unwrap().recv();
+                        let Ok((frame_index, image)) = job else {
+                            break;
+                        };
+                        let detection = detect_frame(&mut model, &mut text_model, &args, &image);
+                        if result_tx
+                            .send((frame_index, (image, Some(detection))))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+        // Only the workers hold senders now, so `recv` fails instead of
+        // hanging if they all exit early
+        drop(result_tx);
+        drop(ready_tx);
+
+        let pool = Self {
+            job_tx,
+            result_rx,
+            workers,
+        };
+        for _ in 0..jobs {
+            let loaded = ready_rx
+                .recv()
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("detection worker panicked")));
+            if let Err(e) = loaded {
+                let _ = pool.shutdown();
+                return Err(e);
+            }
+        }
+        Ok(pool)
+    }
+
+    /// Closes both channels, which stops the workers, including any still
+    /// working through queued frames after an early error, and waits for them
+    fn shutdown(self) -> Result<()> {
+        drop(self.job_tx);
+        drop(self.result_rx);
+        for worker in self.workers {
+            if worker.join().is_err() {
+                anyhow::bail!("detection worker panicked");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The models a run detects with: loaded once for the sequential pipeline, or
+/// by each worker of the parallel one
+enum Detector {
+    Sequential(YOLO, DB),
+    Parallel(DetectionPool),
+}
+
+/// Parallel pipeline for `--jobs > 1`: the calling thread decodes frames and
+/// feeds the pool of detection workers, then reassembles their results in
+/// frame order through a reorder buffer before cropping. Detection is
+/// per-frame and cropping/smoothing stays sequential and in order, so the
+/// output is identical to the sequential pipeline.
+fn process_frames_parallel<P: VideoProcessor + ?Sized>(
+    processor: &mut P,
+    ctx: &mut FrameContext,
+    data_loader: &DataLoader,
+    pool: DetectionPool,
+) -> Result<()> {
+    let args = ctx.args;
+
+    let mut detect_schedule = video_processor_utils::DetectionSchedule::new(args.detect_interval);
+    let mut reorder: BTreeMap<usize, DetectedFrame> = BTreeMap::new();
+    let mut last_detection: Option<(usls::Y, f32)> = None;
+    let mut next_to_send = 0;
+    let mut next_to_handle = 0;
+
+    // Handles every frame that is ready in order, returning once the next
+    // frame in sequence is still in flight. Returns true if the user stopped
+    // processing.
+    let mut drain_ready = |ctx: &mut FrameContext,
+                           reorder: &mut BTreeMap<usize, DetectedFrame>,
+                           next_to_handle: &mut usize|
+     -> Result<bool> {
+        while let Some((image, detection)) = reorder.remove(next_to_handle) {
+            if should_stop(&mut ctx.viewer, &mut ctx.paused) {
+                return Ok(true);
+            }
+            match detection {
+                Some(detection) => {
+                    let FrameDetection {
+                        detection,
+                        text,
+                        text_coverage,
+                    } = detection?;
+                    ctx.record_confidences(&detection);
+                    handle_frame(
+                        processor,
+                        ctx,
+                        &image,
+                        &detection,
+                        text.as_ref(),
+                        text_coverage,
+                        false,
+                    )?;
+                    if args.detect_interval > 1 {
+                        last_detection = Some((detection, text_coverage));
+                    }
+                }
+                None => {
+                    let (detection, text_coverage) = last_detection
+                        .as_ref()
+                        .ok_or_else(|| missing_detection(ctx.frame_index))?;
+                    handle_frame(
+                        processor,
+                        ctx,
+                        &image,
+                        detection,
+                        None,
+                        *text_coverage,
+                        true,
+                    )?;
+                }
+            }
+            *next_to_handle += 1;
+        }
+        Ok(false)
+    };
+
+    let mut frame_iter = data_loader.into_iter();
+    let result = (|| -> Result<()> {
+        while let Some(images) = metrics::time("decode", || frame_iter.next()) {
+            metrics::inc("frames_decoded", images.len() as u64);
+
+            for image in ctx.decimator.select(images) {
+                if detect_schedule.should_detect(1) {
+                    pool.job_tx
+                        .send((next_to_send, image))
+                        .context("detection workers exited early")?;
+                } else {
+                    metrics::inc("frames_detect_skipped", 1);
+                    reorder.insert(next_to_send, (image, None));
+                }
+                next_to_send += 1;
+            }
+
+            while let Ok((frame_index, frame)) = pool.result_rx.try_recv() {
+                reorder.insert(frame_index, frame);
+            }
+            if drain_ready(ctx, &mut reorder, &mut next_to_handle)? {
+                return Ok(());
+            }
+        }
+
+        // Wait for the frames still in flight
+        while next_to_handle < next_to_send {
+            let (frame_index, frame) = pool
+                .result_rx
+                .recv()
+                .context("detection workers exited early")?;
+            reorder.insert(frame_index, frame);
+            if drain_ready(ctx, &mut reorder, &mut next_to_handle)? {
+                break;
+            }
+        }
+        Ok(())
+    })();
+
+    pool.shutdown()?;
+    result
+}
+
+/// Where `VideoProcessor::process_video` writes its optional outputs besides
+/// the processed video; each one is skipped when its path is `None`
+#[derive(Debug, Clone, Default)]
+pub struct OutputPaths {
+    /// Each frame's crop decision as JSON (and to `--crop-log-csv` as CSV)
+    pub crops: Option<String>,
+    /// The detected scene cuts as JSON
+    pub cuts: Option<String>,
+    /// The annotated source frames with the chosen crop drawn on them
+    pub overlay: Option<String>,
+    /// A `--preview-grid` contact sheet of such frames as a PNG
+    pub preview: Option<String>,
+}
+
+impl OutputPaths {
+    /// The outputs the CLI flags ask for, named inside the run directory
+    pub fn from_args(args: &Args, output_dir: &str) -> Self {
+        Self {
+            crops: args
+                .export_crops
+                .then(|| format!("{}/crops.json", output_dir)),
+            cuts: args
+                .export_cuts
+                .then(|| format!("{}/cuts.json", output_dir)),
+            overlay: args
+                .debug_overlay
+                .then(|| format!("{}/debug_overlay.mp4", output_dir)),
+            preview: args
+                .preview_grid
+                .map(|_| format!("{}/preview_grid.png", output_dir)),
+        }
+    }
+
+    /// Whether scene cuts have to be detected: for `cuts`, or for a
+    /// `progress` reporter that asks for them
+    fn detects_cuts(&self, progress: Option<&ProgressReporter>) -> bool {
+        self.cuts.is_some() || progress.is_some_and(|p| p.cuts)
+    }
+}
+
 /// Base trait for video processors that handle cropping with different smoothing strategies
 pub trait VideoProcessor {
-    /// Processes a video with cropping and smoothing
-    fn process_video(&mut self, args: &Args, processed_video: &str) -> Result<()> {
-        let config = config::build_config(&args)?;
-        let mut model = YOLO::new(config.commit()?)?;
+    /// Processes a video with cropping and smoothing, writing whichever of
+    /// `outputs` are set. Each `--output-resolution` size after the first is
+    /// rendered from the same crops to a file named by
+    /// `video_sink::resolution_output_path`. Stages, frames, and saved files
+    /// are reported to `progress`, which defaults to the CLI's progress bar
+    /// and prints. Returns the time in seconds of the frame with the most
+    /// confident detection, if any, for `--poster-auto`.
+    fn process_video(
+        &mut self,
+        args: &Args,
+        processed_video: &str,
+        outputs: &OutputPaths,
+        progress: Option<ProgressReporter>,
+    ) -> error::Result<Option<f64>> {
+        let detect_cuts = outputs.detects_cuts(progress.as_ref());
+        let mut progress = match progress {
+            Some(reporter) => reporter.callback,
+            None => crate::progress::print_progress(args),
+        };
+        let crops_path = outputs.crops.as_deref();
+        let cuts_path = outputs.cuts.as_deref();
+        let overlay_path = outputs.overlay.as_deref();
+        let preview_path = outputs.preview.as_deref();
 
-        // build ocr model
-        let ocr_config = Config::ppocr_det_v5_mobile()
-            .with_model_dtype(usls::DType::Fp16)
-            .with_model_device(args.device.parse()?);
-        let mut text_model = DB::new(ocr_config.commit()?)?;
+        if let Some(import_path) = &args.import_crops {
+            replay_crops(
+                args,
+                import_path,
+                processed_video,
+                preview_path,
+                &mut progress,
+            )?;
+            return Ok(None);
+        }
+
+        // The parallel pipeline's workers load their own models, and report
+        // back before any frame is decoded, so a failure can still fall back
+        let detector = if args.jobs > 1 {
+            DetectionPool::start(args).map(Detector::Parallel)
+        } else {
+            build_models(args).map(|(model, text_model)| Detector::Sequential(model, text_model))
+        };
+        let detector = match detector {
+            Ok(detector) => detector,
+            Err(e) if args.fallback_center_crop => {
+                eprintln!("warning: the detection model failed to load: {e:#}");
+                eprintln!(
+                    "warning: DETECTION SKIPPED; rendering a static center crop (--fallback-center-crop)"
+                );
+                render_center_crop(args, processed_video, preview_path, &mut progress)?;
+                return Ok(None);
+            }
+            Err(e) => return Err(Land2PortError::Model(format!("{e:#}"))),
+        };
 
         // build dataloader
+        let batch = match &detector {
+            Detector::Sequential(model, _) => {
+                video_processor_utils::dataloader_batch(model.batch(), args.batch_size)?
+            }
+            Detector::Parallel(_) => 1,
+        };
         let data_loader = DataLoader::new(&args.source)?
-            .with_batch(model.batch() as _)
+            .with_batch(batch as _)
             .stream()?;
 
         // The DataLoader no longer exposes the source frame rate, so probe it
-        // directly. Used both for smoothing math and for output frame timing.
-        let frame_rate = video_sink::probe_fps(&args.source);
-        let smooth_duration_frames = if args.smooth_duration > 0.0 {
-            (args.smooth_duration * frame_rate as f32).round() as usize
-        } else {
-            0
-        };
+        // directly. Used both for smoothing math and for output frame timing,
+        // after capping it at --max-fps.
+        let source_fps = video_sink::probe_fps(&args.source);
+        let frame_rate = video_processor_utils::output_frame_rate(args, source_fps);
+        let smooth_duration_frames = video_processor_utils::history_size(args, frame_rate);
+        // Local files are normalized to a constant rate before we get here
+        // unless --no-normalize-fps is set; streams can't be, so their output
+        // timing may drift
+        if video_sink::probe_is_vfr(&args.source) {
+            eprintln!(
+                "warning: {} has a variable frame rate; output timing assumes a constant {frame_rate:.3} fps",
+                args.source
+            );
+        }
 
-        let mut viewer = VideoSink::new(processed_video.to_string(), frame_rate);
+        let mut viewer = VideoSink::new(
+            processed_video.to_string(),
+            frame_rate,
+            args.encode_settings()?,
+        )
+        .with_window_scale(args.window_scale)
+        .with_output_sizes(
+            &args.output_sizes()?,
+            processed_video,
+            frame_rate,
+            args.encode_settings()?,
+        );
+        if crops_path.is_some() || args.crop_log_csv.is_some() {
+            viewer = viewer.with_crop_log();
+        }
+        if let Some(path) = overlay_path {
+            viewer = viewer.with_debug_overlay(path, frame_rate, args.encode_settings()?);
+        }
+        if let (Some(_), Some(interval)) = (preview_path, args.preview_grid) {
+            viewer = viewer.with_preview_grid(interval);
+        }
 
         // build annotator
         let annotator = Annotator::default()
@@ -50,137 +1047,111 @@ pub trait VideoProcessor {
                     .with_palette(&usls::Color::palette_coco_80()),
             );
 
-        let textannotator = Annotator::default().with_hbb_style(
+        // Text boxes stay hidden unless --annotate-graphics asks for them
+        let text_annotator = Annotator::default().with_hbb_style(
             HbbStyle::default()
-                .with_visible(false)
-                .with_text_visible(false)
+                .with_visible(args.annotate_graphics)
+                .with_text_visible(args.annotate_graphics)
                 .with_thickness(1)
-                .show_confidence(false)
+                .show_confidence(args.annotate_graphics)
                 .show_id(false)
                 .show_name(false),
         );
 
-        // Common video processing logic. Drive the iterator explicitly (rather
-        // than `for images in &data_loader`) so the decode/demux time of each
-        // batch can be measured separately from detection and crop work.
-        let mut frame_iter = (&data_loader).into_iter();
-        loop {
-            let Some(images) = metrics::time("decode", || frame_iter.next()) else {
-                break;
-            };
-            metrics::inc("frames_decoded", images.len() as u64);
-
-            if viewer.is_window_exist_and_closed() {
-                break;
-            }
-
-            // Handle key events and delay
-            if let Some(key) = viewer.wait_key(1) {
-                if key == usls::Key::Escape {
-                    break;
-                }
-            }
+        let total_frames = video_sink::probe_duration(&args.source)
+            .and_then(|duration| video_sink::estimate_frame_count(duration, frame_rate));
+        progress(ProgressEvent::Stage(Stage::Processing { total_frames }));
 
-            let detections = metrics::time("detect", || model.forward(&images))?;
-
-            for (image, detection) in images.iter().zip(detections.iter()) {
-                // Only the annotated (non-headless) path needs an owned image;
-                // headless borrows the DataLoader's frame to skip a full clone.
-                let mut img: Cow<usls::Image> = if !args.headless {
-                    Cow::Owned(annotator.annotate(image, detection)?)
-                } else {
-                    Cow::Borrowed(image)
-                };
-
-                // Calculate crop areas based on the detection results
-                let objects = video_processor_utils::extract_objects_above_threshold(
-                    detection,
-                    &args.object,
-                    args.object_prob_threshold
+        let mut ctx = FrameContext {
+            args,
+            viewer,
+            annotator,
+            text_annotator,
+            smooth_duration_frames,
+            frame_index: 0,
+            total_frames,
+            progress,
+            tracker: ObjectTracker::new(),
+            pick_pending: args.interactive_pick,
+            last_crop: None,
+            paused: false,
+            decimator: video_processor_utils::FrameDecimator::new(source_fps, frame_rate),
+            best_detection: None,
+            graphic: video_processor_utils::GraphicHysteresis::new(
+                args.text_area_threshold,
+                video_processor_utils::text_area_exit_threshold(args),
+            ),
+            object_threshold: video_processor_utils::ObjectThreshold::from_args(args),
+            confidences: args
+                .report_confidences
+                .then(video_processor_utils::ConfidenceReport::new),
+            cut_log: detect_cuts.then(|| {
+                let detector = CutDetector::new(
+                    args.cut_similarity,
+                    args.cut_start,
+                    video_processor_utils::min_scene_frames(args, frame_rate),
+                    args.cut_mode,
                 );
-                // Drop incidental faces that are tiny relative to the dominant
-                // subject (e.g. faces on a book cover) so they don't inflate the
-                // head count into a stacked layout that splits the real subject.
-                let objects = video_processor_utils::filter_small_relative_objects(
-                    objects,
-                    &args.object,
-                    args.min_area_ratio,
-                );
-
-                let is_graphic =
-                    if (objects.len() == 0 && args.keep_text) || args.prioritize_text {
-                        let ys = metrics::time("ocr", || text_model.forward(&[image.clone()]))?;
-
-                        if !ys[0].hbbs.is_empty() {
-                            if !args.headless {
-                                img = Cow::Owned(textannotator.annotate(&img, &ys[0])?);
-                            }
-                            video_processor_utils::is_graphic_area_above_threshold(
-                                ys[0].hbbs.iter(),
-                                image.width() as f32,
-                                image.height() as f32,
-                                args.text_area_threshold,
-                                args.text_prob_threshold,
-                            )
-                        } else {
-                            false
-                        }
-                    } else {
-                        false
-                    };
+                CutLog::new(detector, frame_rate)
+            }),
+        };
 
-                let latest_crop = if args.prioritize_text && is_graphic {
-                    crop::CropResult::Resize(crop::CropArea::new(
-                        0.0,
-                        0.0,
-                        img.width() as f32,
-                        img.height() as f32,
-                    ))
-                } else {
-                    crop::calculate_crop(
-                        args.use_stack_crop,
-                        is_graphic,
-                        img.width() as f32,
-                        img.height() as f32,
-                        &objects,
-                    )?
-                };
-
-                // Print debug information
-                self.print_debug_info(&objects, &latest_crop, is_graphic);
-
-                if smooth_duration_frames > 0 {
-                    self.process_frame_with_smoothing(
-                        &img,
-                        &latest_crop,
-                        &objects,
-                        args,
-                        &mut viewer,
-                        smooth_duration_frames,
-                    )?;
-                } else {
-                    video_processor_utils::process_and_display_crop(
-                        &img,
-                        &latest_crop,
-                        &mut viewer,
-                        args.headless,
-                    )?;
-                }
+        match detector {
+            Detector::Sequential(model, text_model) => {
+                process_frames_sequential(self, &mut ctx, &data_loader, model, text_model)?
+            }
+            Detector::Parallel(pool) => {
+                process_frames_parallel(self, &mut ctx, &data_loader, pool)?
             }
         }
+        (ctx.progress)(ProgressEvent::Stage(Stage::Finalizing));
+
+        let mut viewer = ctx.viewer;
         self.finalize_processing(args, &mut viewer)?;
 
         // Surface an empty/unreadable source here, rather than letting main.rs
         // fail later on a missing output file with a confusing copy error.
         if viewer.frame_count() == 0 {
-            anyhow::bail!("no frames were written from source {}", args.source);
+            return Err(Land2PortError::EmptySource(args.source.clone()));
         }
 
         viewer.finalize()?;
 
+        let mut saved = |what: String, path: &str| {
+            (ctx.progress)(ProgressEvent::Saved {
+                what,
+                path: path.to_string(),
+            })
+        };
+        if let (Some(path), Some(crop_log)) = (crops_path, viewer.crop_log()) {
+            crop_log.write_json(path)?;
+            saved("Crop decisions".to_string(), path);
+        }
+        if let (Some(path), Some(crop_log)) = (&args.crop_log_csv, viewer.crop_log()) {
+            crop_log.write_csv(path)?;
+            saved("Crop log CSV".to_string(), path);
+        }
+        if let Some(path) = overlay_path {
+            saved("Debug overlay".to_string(), path);
+        }
+        if let (Some(path), Some(grid)) = (preview_path, viewer.preview_grid()) {
+            grid.save(path)?;
+            saved("Preview grid".to_string(), path);
+        }
+        if let (Some(path), Some(cut_log)) = (cuts_path, &ctx.cut_log) {
+            cut_log.write_json(path)?;
+            saved(format!("{} scene cuts", cut_log.cuts().len()), path);
+        }
+
+        if let Some(report) = &ctx.confidences {
+            println!("{}", report.format(&args.object_names()));
+        }
+
         perf_chart();
 
-        Ok(())
+        Ok(ctx
+            .best_detection
+            .map(|(frame_index, _)| frame_index as f64 / frame_rate))
     }
 
     /// Processes a single frame with smoothing logic (to be implemented by concrete processors)
@@ -210,3 +1181,115 @@ pub trait VideoProcessor {
         video_processor_utils::print_default_debug_info(objects, latest_crop, is_graphic);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crop::{CropArea, CropResult};
+    use crate::crop_log::CropLog;
+    use crate::simple_smoothing_video_processor::SimpleSmoothingVideoProcessor;
+    use std::cell::RefCell;
+    use std::process::Command;
+    use std::rc::Rc;
+
+    /// A reporter whose callback appends every event to the returned list
+    fn collecting_reporter(cuts: bool) -> (ProgressReporter, Rc<RefCell<Vec<ProgressEvent>>>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let collected = Rc::clone(&events);
+        let reporter = ProgressReporter {
+            callback: Box::new(move |event| collected.borrow_mut().push(event)),
+            cuts,
+        };
+        (reporter, events)
+    }
+
+    #[test]
+    fn test_detects_cuts_for_export_or_reporter() {
+        let outputs = OutputPaths::default();
+        assert!(!outputs.detects_cuts(None));
+        let (reporter, _) = collecting_reporter(false);
+        assert!(!outputs.detects_cuts(Some(&reporter)));
+        let (reporter, _) = collecting_reporter(true);
+        assert!(outputs.detects_cuts(Some(&reporter)));
+
+        let outputs = OutputPaths {
+            cuts: Some("cuts.json".to_string()),
+            ..OutputPaths::default()
+        };
+        assert!(outputs.detects_cuts(None));
+    }
+
+    #[test]
+    fn test_process_video_reports_replay_progress() {
+        let dir = std::env::temp_dir().join("land2port_replay_progress_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        // Five 320x240 frames at 10 fps, and a crop for each to replay
+        let status = Command::new("ffmpeg")
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc=size=320x240:rate=10:duration=0.5",
+                "-pix_fmt",
+                "yuv420p",
+                &path("source.mp4"),
+            ])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let mut log = CropLog::default();
+        for _ in 0..5 {
+            log.record_detection(1, false);
+            log.record_crop(&CropResult::Single(CropArea::new(92.0, 0.0, 135.0, 240.0)));
+        }
+        log.write_json(&path("crops.json")).unwrap();
+
+        let args = Args::from_args(
+            &["land2port"],
+            &[
+                "--source",
+                &path("source.mp4"),
+                "--import-crops",
+                &path("crops.json"),
+                "--headless",
+                "--preview-grid",
+                "2",
+            ],
+        )
+        .unwrap();
+        let outputs = OutputPaths {
+            preview: Some(path("preview_grid.png")),
+            ..OutputPaths::default()
+        };
+        let (reporter, events) = collecting_reporter(false);
+        let result = SimpleSmoothingVideoProcessor::new().process_video(
+            &args,
+            &path("processed_video.mp4"),
+            &outputs,
+            Some(reporter),
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(result.unwrap().is_none());
+
+        let mut expected = vec![ProgressEvent::Stage(Stage::Replaying {
+            crops: 5,
+            path: path("crops.json"),
+        })];
+        expected.extend((0..5).map(|frame| ProgressEvent::FrameProcessed {
+            frame,
+            total: Some(5),
+        }));
+        expected.push(ProgressEvent::Stage(Stage::Finalizing));
+        expected.push(ProgressEvent::Saved {
+            what: "Preview grid".to_string(),
+            path: path("preview_grid.png"),
+        });
+        assert_eq!(*events.borrow(), expected);
+    }
+}
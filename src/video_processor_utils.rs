@@ -1,11 +1,18 @@
+use crate::cli::Args;
 use crate::crop;
 use crate::image;
 use crate::metrics;
 use crate::video_sink::VideoSink;
 use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
 use std::env;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use usls::{Hbb, Y};
 
+/// Set by `--quiet` to silence status output
+static QUIET: AtomicBool = AtomicBool::new(false);
+
 /// Helper function to check if debug logging is enabled
 pub fn is_debug_enabled() -> bool {
     env::var("RUST_LOG")
@@ -20,19 +27,261 @@ pub fn debug_println(args: std::fmt::Arguments) {
     }
 }
 
-/// Renders a crop result and hands the finished frame to the sink. The H.264
-/// encode (and the `frames_written` count) happens on the sink's encoder
-/// thread; this function only times the CPU-bound crop render on the main thread.
+/// Silences status output for the rest of the run (`--quiet`): only errors
+/// and the final output path are printed
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether `--quiet` silenced status output
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Status print function that prints unless `--quiet` is set
+pub fn status_println(args: std::fmt::Arguments) {
+    if !is_quiet() {
+        println!("{}", args);
+    }
+}
+
+/// Builds the per-frame progress bar shown while processing a video. Hidden
+/// under `RUST_LOG=debug` and `--dry-run`, whose per-frame prints would
+/// clobber it, and under `--quiet`; a spinner when the frame count is unknown
+/// (e.g. live streams).
+pub fn frame_progress_bar(total_frames: Option<u64>, args: &Args) -> ProgressBar {
+    if is_debug_enabled() || args.dry_run || args.quiet {
+        return ProgressBar::hidden();
+    }
+
+    match total_frames {
+        Some(total) => ProgressBar::new(total).with_style(
+            ProgressStyle::with_template(
+                "{bar:40.cyan/blue} {pos}/{len} frames ({percent}%) ETA {eta}",
+            )
+            .expect("valid progress template"),
+        ),
+        None => ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("{spinner} {pos} frames ({per_sec})")
+                .expect("valid progress template"),
+        ),
+    }
+}
+
+/// Number of frames the smoothing history spans: `--history-size` when given,
+/// otherwise `--smooth-duration` seconds at the source frame rate, so the
+/// window covers the same time on 30 and 60 fps footage. 0 disables smoothing.
+pub fn history_size(args: &Args, frame_rate: f64) -> usize {
+    smoothing_window_frames(args.smooth_duration, frame_rate, args.history_size)
+}
+
+/// Minimum frames between scene cuts: `--min-scene-duration` seconds at the
+/// source frame rate (0 when the guard is disabled)
+pub fn min_scene_frames(args: &Args, frame_rate: f64) -> usize {
+    (args.min_scene_duration.max(0.0) as f64 * frame_rate).round() as usize
+}
+
+/// Frames a committed crop is held before the history smoother may switch
+/// to another target: `--min-hold-duration` seconds at the source frame rate
+pub fn min_hold_frames(args: &Args, frame_rate: f64) -> usize {
+    (args.min_hold_duration.max(0.0) as f64 * frame_rate).round() as usize
+}
+
+/// Frames the history smoother's crop transition spans:
+/// `--transition-duration` seconds at the source frame rate, or `None` to
+/// span the whole buffered history
+pub fn transition_frames(args: &Args, frame_rate: f64) -> Option<usize> {
+    args.transition_duration
+        .map(|duration| (duration.max(0.0) as f64 * frame_rate).round() as usize)
+}
+
+/// Where the history smoother glides between crops, from `--transition-mode`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TransitionMode {
+    /// Glide on every crop change, across scene cuts too
+    Always,
+    /// Glide within a scene and snap to the new crop at a cut
+    #[default]
+    WithinScene,
+    /// Snap within a scene and glide only into the crop after a cut
+    OnCut,
+}
+
+impl TransitionMode {
+    /// Whether a crop change interpolates, given whether a scene cut came
+    /// between the old crop and the new one
+    pub fn interpolates(self, across_cut: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::WithinScene => !across_cut,
+            Self::OnCut => across_cut,
+        }
+    }
+}
+
+impl FromStr for TransitionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "always" => Ok(Self::Always),
+            "within-scene" => Ok(Self::WithinScene),
+            "on-cut" => Ok(Self::OnCut),
+            _ => Err(format!(
+                "invalid transition mode '{s}': expected always, within-scene, or on-cut"
+            )),
+        }
+    }
+}
+
+/// Checks that a `--transition-duration` value is not negative
+pub fn check_transition_duration(duration: f32) -> Result<()> {
+    if !(0.0..).contains(&duration) {
+        anyhow::bail!("invalid transition duration {duration}: expected 0 or more seconds");
+    }
+    Ok(())
+}
+
+/// Buffered frames a crop change must span before the history smoother's
+/// crop selection considers it: `--min-interp-duration` seconds at the source
+/// frame rate, or `None` for a quarter of the smoothing window
+pub fn min_interp_frames(args: &Args, frame_rate: f64) -> Option<usize> {
+    args.min_interp_duration
+        .map(|duration| (duration.max(0.0) as f64 * frame_rate).round() as usize)
+}
+
+/// Checks that a `--min-interp-duration` value is not negative
+pub fn check_min_interp_duration(duration: f32) -> Result<()> {
+    if !(0.0..).contains(&duration) {
+        anyhow::bail!("invalid min interp duration {duration}: expected 0 or more seconds");
+    }
+    Ok(())
+}
+
+fn smoothing_window_frames(
+    smooth_duration: f32,
+    frame_rate: f64,
+    history_size: Option<usize>,
+) -> usize {
+    match history_size {
+        Some(frames) => frames,
+        None if smooth_duration > 0.0 => (smooth_duration * frame_rate as f32).round() as usize,
+        None => 0,
+    }
+}
+
+/// What the frame loop does after polling the preview window for a key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Playback {
+    /// Keep processing frames
+    Play,
+    /// Hold the current frame and keep polling
+    Pause,
+    /// Process the next frame, then pause again
+    Step,
+    /// Stop processing
+    Stop,
+}
+
+/// What a key press means while `--interactive-pick` waits for a subject
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubjectPick {
+    /// Keep waiting for a choice
+    Waiting,
+    /// Track the subject at this index
+    Subject(usize),
+    /// Leave the choice to the tracker
+    Skip,
+}
+
+/// Applies a key press to the `--interactive-pick` prompt over `count`
+/// numbered subjects: 1-9 picks that subject, Enter or Escape skips
+pub fn subject_pick_after_key(key: Option<usls::Key>, count: usize) -> SubjectPick {
+    use usls::Key;
+    let number = match key {
+        Some(Key::Enter | Key::Escape) => return SubjectPick::Skip,
+        Some(Key::Key1) => 1,
+        Some(Key::Key2) => 2,
+        Some(Key::Key3) => 3,
+        Some(Key::Key4) => 4,
+        Some(Key::Key5) => 5,
+        Some(Key::Key6) => 6,
+        Some(Key::Key7) => 7,
+        Some(Key::Key8) => 8,
+        Some(Key::Key9) => 9,
+        _ => return SubjectPick::Waiting,
+    };
+    if number <= count {
+        SubjectPick::Subject(number - 1)
+    } else {
+        SubjectPick::Waiting
+    }
+}
+
+/// Applies a preview key press: Escape stops, space toggles pause, and the
+/// right arrow steps one frame while paused
+pub fn playback_after_key(paused: bool, key: Option<usls::Key>) -> Playback {
+    match key {
+        Some(usls::Key::Escape) => Playback::Stop,
+        Some(usls::Key::Space) if paused => Playback::Play,
+        Some(usls::Key::Space) => Playback::Pause,
+        Some(usls::Key::Right) if paused => Playback::Step,
+        _ if paused => Playback::Pause,
+        _ => Playback::Play,
+    }
+}
+
+/// Renders a crop result and hands the finished frame to the sink, rendering
+/// it again for each extra `--output-resolution` output. The H.264 encode (and
+/// the `frames_written` count) happens on the sink's encoder thread; this
+/// function only times the CPU-bound crop render on the main thread.
 pub fn process_and_display_crop(
     img: &usls::Image,
     crop_result: &crop::CropResult,
     viewer: &mut VideoSink,
-    headless: bool,
+    args: &Args,
 ) -> Result<()> {
+    if let Some(crop_log) = viewer.crop_log_mut() {
+        crop_log.record_crop(crop_result);
+    }
+    // Recorded before the dry-run return, so a dry run can preview the framing
+    if let Some(grid) = viewer.preview_grid_mut() {
+        metrics::time("preview_grid", || grid.add_frame(img, crop_result))?;
+    }
+    if args.dry_run {
+        if !args.report_confidences {
+            println!("frame {}: crop {:?}", viewer.frame_count(), crop_result);
+        }
+        viewer.skip_frame();
+        return Ok(());
+    }
+
+    let target_width = viewer.output_width().unwrap_or(img.height() as u32);
     let cropped_img = metrics::time("crop_render", || {
-        image::create_cropped_image(img, crop_result, img.height() as u32)
+        image::create_cropped_image(
+            img,
+            crop_result,
+            target_width,
+            args.aspect_ratio,
+            args.graphic_fit,
+        )
     })?;
-    viewer.write_frame(cropped_img, headless)?;
+    for output in viewer.extra_outputs_mut() {
+        let resized = metrics::time("crop_render", || {
+            image::create_cropped_image(
+                img,
+                crop_result,
+                output.width(),
+                args.aspect_ratio,
+                args.graphic_fit,
+            )
+        })?;
+        output.write_frame(resized)?;
+    }
+    if let Some(overlay) = viewer.debug_overlay_mut() {
+        metrics::time("debug_overlay", || overlay.write_frame(img, crop_result))?;
+    }
+    viewer.write_frame(cropped_img, args.headless)?;
     Ok(())
 }
 
@@ -51,33 +300,78 @@ where
         .sum()
 }
 
-/// Determines whether the combined HBB area exceeds the configured graphic threshold
-pub fn is_graphic_area_above_threshold<'a, I>(
+/// Fraction of the frame covered by the HBBs at or above
+/// `text_prob_threshold`, or 0 for an empty frame
+pub fn graphic_coverage<'a, I>(
     hbbs: I,
     frame_width: f32,
     frame_height: f32,
-    graphic_threshold: f32,
     text_prob_threshold: f32,
-) -> bool
+) -> f32
 where
     I: IntoIterator<Item = &'a Hbb>,
 {
-    if graphic_threshold <= 0.0 {
-        return false;
-    }
-
     let frame_area = frame_width * frame_height;
     if frame_area <= 0.0 {
-        return false;
+        return 0.0;
     }
+    combined_hbb_area(hbbs, text_prob_threshold) / frame_area
+}
 
-    let total_area = combined_hbb_area(hbbs, text_prob_threshold);
-    debug_println(format_args!(
-        "total_area: {} >= frame_area * graphic_threshold: {}",
-        total_area,
-        frame_area * graphic_threshold
-    ));
-    total_area >= frame_area * graphic_threshold
+/// Decides frame by frame whether text coverage makes a frame a graphic, with
+/// hysteresis: a frame becomes a graphic once coverage reaches `enter`, and
+/// later frames stay graphics until coverage drops below `exit`. Coverage
+/// hovering between the two keeps the current state instead of flickering
+/// between the tracked crop and the full-frame resize.
+pub struct GraphicHysteresis {
+    enter: f32,
+    exit: f32,
+    active: bool,
+}
+
+impl GraphicHysteresis {
+    /// An `enter` threshold of 0 or less disables graphic detection
+    pub fn new(enter: f32, exit: f32) -> Self {
+        Self {
+            enter,
+            exit: exit.min(enter),
+            active: false,
+        }
+    }
+
+    /// Advances by one frame with its text `coverage`, returning whether the
+    /// frame is a graphic
+    pub fn update(&mut self, coverage: f32) -> bool {
+        if self.enter <= 0.0 {
+            return false;
+        }
+
+        let threshold = if self.active { self.exit } else { self.enter };
+        debug_println(format_args!(
+            "text coverage: {} >= graphic threshold: {}",
+            coverage, threshold
+        ));
+        self.active = coverage >= threshold;
+        self.active
+    }
+}
+
+/// Text coverage below which a graphic frame returns to tracking:
+/// `--text-area-exit-threshold`, or 75% of `--text-area-threshold`
+pub fn text_area_exit_threshold(args: &Args) -> f32 {
+    args.text_area_exit_threshold
+        .unwrap_or(args.text_area_threshold * 0.75)
+}
+
+/// Checks that a `--text-area-exit-threshold` value lies between 0 and the
+/// `--text-area-threshold` it releases
+pub fn check_text_area_exit_threshold(exit: f32, enter: f32) -> Result<()> {
+    if !(0.0..=enter).contains(&exit) {
+        anyhow::bail!(
+            "invalid text area exit threshold {exit}: expected between 0 and --text-area-threshold ({enter})"
+        );
+    }
+    Ok(())
 }
 
 /// Predicts the current HBB position based on the previous three frames
@@ -89,15 +383,20 @@ where
 /// * `last_frame` - The HBB from the last frame
 /// * `max_x` - Maximum x coordinate (width of frame)
 /// * `max_y` - Maximum y coordinate (height of frame)
+/// * `damping` - Scale on the acceleration term, from 0 (velocity only) to 1
+///   (full acceleration); lower values stop an abruptly halted object from
+///   being predicted to keep moving
 ///
 /// # Returns
-/// A predicted HBB for the current frame
+/// A predicted HBB for the current frame, clamped to lie entirely within the
+/// frame
 pub fn predict_current_hbb(
     three_frames_ago: &Hbb,
     two_frames_ago: &Hbb,
     last_frame: &Hbb,
     max_x: f32,
     max_y: f32,
+    damping: f32,
 ) -> Hbb {
     // Calculate velocities between consecutive frames
     let v1_x = two_frames_ago.xmin() - three_frames_ago.xmin();
@@ -109,20 +408,47 @@ pub fn predict_current_hbb(
     let ax = v2_x - v1_x;
     let ay = v2_y - v1_y;
 
-    // Predict current position using velocity + acceleration
-    // Position = last_position + velocity + 0.5 * acceleration
-    let predicted_x = last_frame.xmin() + v2_x + 0.5 * ax;
-    let predicted_y = last_frame.ymin() + v2_y + 0.5 * ay;
+    // Predict current position using velocity + damped acceleration
+    // Position = last_position + velocity + 0.5 * damping * acceleration
+    let predicted_x = last_frame.xmin() + v2_x + 0.5 * damping * ax;
+    let predicted_y = last_frame.ymin() + v2_y + 0.5 * damping * ay;
 
-    // Create a new HBB with the predicted values using center coordinates
+    // Keep the whole box inside the frame, not just its top-left corner, so
+    // a subject accelerating toward an edge can't push the crop out of bounds
+    let width = last_frame.width().min(max_x);
+    let height = last_frame.height().min(max_y);
     Hbb::from_xywh(
-        predicted_x.max(0.0).min(max_x),
-        predicted_y.max(0.0).min(max_y),
-        last_frame.width(),
-        last_frame.height(),
+        predicted_x.clamp(0.0, max_x - width),
+        predicted_y.clamp(0.0, max_y - height),
+        width,
+        height,
     )
 }
 
+/// Checks that a `--prediction-damping` value is between 0 and 1
+pub fn check_prediction_damping(damping: f32) -> Result<()> {
+    if !(0.0..=1.0).contains(&damping) {
+        anyhow::bail!("invalid prediction damping {damping}: expected 0 to 1");
+    }
+    Ok(())
+}
+
+/// Crop to hold on a black frame (`--black-frame-threshold`): the previous
+/// frame's crop, so a fade to black doesn't fall back to a default framing.
+/// `None` when the frame isn't black or there is no previous crop yet.
+pub fn black_frame_crop(
+    img: &usls::Image,
+    threshold: f64,
+    last_crop: Option<&crop::CropResult>,
+) -> Option<crop::CropResult> {
+    let last_crop = last_crop?;
+    if !metrics::time("black_check", || image::is_black_frame(img, threshold)) {
+        return None;
+    }
+    debug_println(format_args!("black frame: holding previous crop"));
+    Some(last_crop.clone())
+}
+
 /// Prints the default debug information for video processors
 pub fn print_default_debug_info(
     objects: &[&usls::Hbb],
@@ -135,33 +461,205 @@ pub fn print_default_debug_info(
     debug_println(format_args!("is_graphic: {:?}", is_graphic));
 }
 
-/// Extracts head detections above the probability threshold from YOLO detection results
+/// Whether a detection is one of `object_names` with at least
+/// `object_prob_threshold` confidence
+fn is_object_above_threshold(hbb: &Hbb, object_names: &[&str], object_prob_threshold: f32) -> bool {
+    let meets_threshold = hbb
+        .confidence()
+        .is_some_and(|confidence| confidence >= object_prob_threshold);
+    let matches_name = hbb.name().is_some_and(|name| object_names.contains(&name));
+    meets_threshold && matches_name
+}
+
+/// How far `--adaptive-threshold` lowers the object probability threshold
+/// for each frame in which no detection passes it
+const ADAPTIVE_THRESHOLD_STEP: f32 = 0.05;
+
+/// The object probability threshold detections are filtered with. With
+/// `--adaptive-threshold`, frames where nothing passes it (dim or
+/// motion-blurred ones) lower it a step at a time down to a floor, keeping
+/// the subject instead of dropping it; the first frame with a detection
+/// passing the full threshold restores it.
+pub struct ObjectThreshold {
+    base: f32,
+    floor: f32,
+    current: f32,
+}
+
+impl ObjectThreshold {
+    /// A threshold of `base` that relaxes down to `floor`
+    pub fn new(base: f32, floor: f32) -> Self {
+        Self {
+            base,
+            floor: floor.min(base),
+            current: base,
+        }
+    }
+
+    /// A threshold that stays at `base`
+    pub fn fixed(base: f32) -> Self {
+        Self::new(base, base)
+    }
+
+    /// `--object-prob-threshold`, relaxing down to
+    /// `--adaptive-threshold-floor` with `--adaptive-threshold`
+    pub fn from_args(args: &Args) -> Self {
+        if args.adaptive_threshold {
+            Self::new(args.object_prob_threshold, args.adaptive_threshold_floor)
+        } else {
+            Self::fixed(args.object_prob_threshold)
+        }
+    }
+
+    /// The threshold currently in effect
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// Advances by one frame with its detections, returning those of
+    /// `object_names` that pass the threshold
+    fn extract<'a>(&mut self, hbbs: &'a [Hbb], object_names: &[&str]) -> Vec<&'a Hbb> {
+        let above = |threshold: f32| -> Vec<&'a Hbb> {
+            hbbs.iter()
+                .filter(|hbb| is_object_above_threshold(hbb, object_names, threshold))
+                .collect()
+        };
+
+        let objects = above(self.base);
+        if !objects.is_empty() || self.floor >= self.base {
+            self.current = self.base;
+            return objects;
+        }
+        // Keep a relaxed threshold while it still finds the subject
+        if self.current < self.base {
+            let objects = above(self.current);
+            if !objects.is_empty() {
+                return objects;
+            }
+        }
+        self.current = (self.current - ADAPTIVE_THRESHOLD_STEP).max(self.floor);
+        debug_println(format_args!(
+            "no objects above threshold: relaxing to {}",
+            self.current
+        ));
+        above(self.current)
+    }
+}
+
+/// Checks that an `--adaptive-threshold-floor` value lies between 0 and the
+/// `--object-prob-threshold` it relaxes
+pub fn check_adaptive_threshold_floor(floor: f32, base: f32) -> Result<()> {
+    if !(0.0..=base).contains(&floor) {
+        anyhow::bail!(
+            "invalid adaptive threshold floor {floor}: expected between 0 and --object-prob-threshold ({base})"
+        );
+    }
+    Ok(())
+}
+
+/// Extracts detections of any of `object_names` above the probability
+/// threshold from YOLO detection results, advancing `threshold` by a frame
 pub fn extract_objects_above_threshold<'a>(
     detection: &'a Y,
-    object_name: &str,
-    object_prob_threshold: f32
+    object_names: &[&str],
+    threshold: &mut ObjectThreshold,
 ) -> Vec<&'a Hbb> {
-    detection
-        .hbbs
-        .iter()
-        .filter(|hbb| {
-            // Check confidence threshold
-            let meets_threshold = if let Some(confidence) = hbb.confidence() {
-                confidence >= object_prob_threshold
-            } else {
-                false
-            };
-
-            // Check name matching
-            let matches_name = if let Some(name) = hbb.name() {
-                name == object_name
-            } else {
-                false
-            };
-
-            meets_threshold && matches_name
+    threshold.extract(&detection.hbbs, object_names)
+}
+
+/// `--min-area-ratio`: the fraction of the largest detection's area an object
+/// must reach to be kept, either one value for every class or per class
+/// (`person=0.01,face=0.002`), with an optional bare value for the rest
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinAreaRatio {
+    default: f32,
+    per_class: Vec<(String, f32)>,
+}
+
+impl MinAreaRatio {
+    /// The ratio for classes not listed per class
+    pub const DEFAULT: f32 = 0.05;
+
+    /// The same ratio for every class
+    pub fn uniform(ratio: f32) -> Self {
+        Self {
+            default: ratio,
+            per_class: Vec::new(),
+        }
+    }
+
+    /// The ratio for a detection of class `name`, or `None` when it is exempt:
+    /// ball-type objects (`ball`, `sports ball`) unless they are listed
+    fn ratio_for(&self, name: Option<&str>) -> Option<f32> {
+        if let Some((_, ratio)) = self
+            .per_class
+            .iter()
+            .find(|(class, _)| Some(class.as_str()) == name)
+        {
+            return Some(*ratio);
+        }
+        if matches!(name, Some("ball" | "sports ball")) {
+            None
+        } else {
+            Some(self.default)
+        }
+    }
+
+    /// True when no class has a ratio above 0, so nothing is filtered
+    fn is_disabled(&self) -> bool {
+        self.default <= 0.0 && self.per_class.iter().all(|(_, ratio)| *ratio <= 0.0)
+    }
+}
+
+impl Default for MinAreaRatio {
+    fn default() -> Self {
+        Self::uniform(Self::DEFAULT)
+    }
+}
+
+impl FromStr for MinAreaRatio {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse_ratio = |value: &str| match value.trim().parse::<f32>() {
+            Ok(ratio) if ratio.is_finite() && ratio >= 0.0 => Ok(ratio),
+            _ => Err(format!(
+                "invalid min area ratio '{value}': expected a fraction of 0 or more"
+            )),
+        };
+
+        let mut default = None;
+        let mut per_class: Vec<(String, f32)> = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            match part.split_once('=') {
+                Some((class, ratio)) => {
+                    let class = class.trim();
+                    if class.is_empty() {
+                        return Err(format!("invalid min area ratio '{part}': missing a class"));
+                    }
+                    if per_class.iter().any(|(listed, _)| listed == class) {
+                        return Err(format!("min area ratio for '{class}' is given twice"));
+                    }
+                    per_class.push((class.to_string(), parse_ratio(ratio)?));
+                }
+                None if default.is_some() => {
+                    return Err(format!(
+                        "invalid min area ratio '{s}': only one value may apply to every class"
+                    ));
+                }
+                None => default = Some(parse_ratio(part)?),
+            }
+        }
+        if default.is_none() && per_class.is_empty() {
+            return Err(format!(
+                "invalid min area ratio '{s}': expected a fraction or class=fraction pairs"
+            ));
+        }
+        Ok(Self {
+            default: default.unwrap_or(Self::DEFAULT),
+            per_class,
         })
-        .collect()
+    }
 }
 
 /// Drops detections that are small *relative to the largest* detection in the
@@ -172,8 +670,8 @@ pub fn extract_objects_above_threshold<'a>(
 /// the object count and push `calculate_crop` into a stacked/multi-head layout
 /// that splits the real subject across the top and bottom of the 9:16 frame.
 ///
-/// An object is kept when its area is at least `min_area_ratio` of the largest
-/// object's area; the largest object is always kept. The ratio is on *area*, so
+/// An object is kept when its area is at least its class's `min_area_ratio` of
+/// the largest object's area; the largest object is always kept. The ratio is on *area*, so
 /// e.g. the `0.05` default keeps anything down to ~1/5 the dominant object's
 /// linear size — a genuine co-subject at similar distance (~0.9 ratio) is always
 /// kept, while a face on a book cover (a few percent) is dropped with margin.
@@ -181,37 +679,45 @@ pub fn extract_objects_above_threshold<'a>(
 /// This is scale-free (relative to the scene's own largest object), so it
 /// generalizes across resolutions and shot framings without a per-video tweak.
 ///
-/// `min_area_ratio <= 0` disables the filter. Ball-type objects (`ball`,
-/// `sports ball`) are exempt: a valid ball can be legitimately small relative to
-/// a nearer one, and the dedicated ball path selects a single ball itself.
+/// A ratio of 0 disables the filter for its classes. Ball-type objects (detected
+/// as `ball` or `sports ball`) are exempt unless given their own ratio, and
+/// never count as the largest object:
+/// a valid ball can be legitimately small relative to a nearer one or to the
+/// players around it, and the dedicated ball path selects a single ball itself.
 /// Inputs with fewer than two objects are returned as-is.
 pub fn filter_small_relative_objects<'a>(
     objects: Vec<&'a Hbb>,
-    object_name: &str,
-    min_area_ratio: f32,
+    min_area_ratio: &MinAreaRatio,
 ) -> Vec<&'a Hbb> {
-    let is_ball_type = object_name == "ball" || object_name == "sports ball";
-    if min_area_ratio <= 0.0 || is_ball_type || objects.len() < 2 {
+    if min_area_ratio.is_disabled() || objects.len() < 2 {
         return objects;
     }
+    let is_ball_type = |hbb: &Hbb| matches!(hbb.name(), Some("ball" | "sports ball"));
 
     // Use Hbb::area() to match the largest-object selection in crop.rs.
-    let largest_area = objects.iter().map(|hbb| hbb.area()).fold(0.0_f32, f32::max);
+    let largest_area = objects
+        .iter()
+        .filter(|hbb| !is_ball_type(hbb))
+        .map(|hbb| hbb.area())
+        .fold(0.0_f32, f32::max);
     if largest_area <= 0.0 {
         return objects;
     }
 
-    let area_threshold = largest_area * min_area_ratio;
     let kept: Vec<&Hbb> = objects
         .into_iter()
-        .filter(|hbb| hbb.area() >= area_threshold)
+        .filter(|hbb| {
+            min_area_ratio
+                .ratio_for(hbb.name())
+                .is_none_or(|ratio| hbb.area() >= largest_area * ratio)
+        })
         .collect();
 
     debug_println(format_args!(
-        "filter_small_relative_objects: kept {} (largest_area: {:.0}, threshold: {:.0})",
+        "filter_small_relative_objects: kept {} (largest_area: {:.0}, ratios: {:?})",
         kept.len(),
         largest_area,
-        area_threshold
+        min_area_ratio
     ));
     kept
 }
@@ -225,19 +731,28 @@ pub fn filter_small_relative_objects<'a>(
 ///
 /// # Returns
 /// A vector of CropResults that smoothly transitions from start to destination.
-/// If both inputs are Single, it performs linear interpolation on the x-coordinate only,
-/// while keeping the y, width, and height from the destination crop.
-/// If either input is not Single, it returns a vector filled with the destination CropResult.
+/// If both inputs are Single (or both BlurFill), it linearly interpolates the
+/// x, y, width, and height.
+/// Between Single and Resize, the crop rectangle expands to (or contracts from) the
+/// full frame. Every frame but the last of a Resize to Single transition stays
+/// Resize, since only the destination rectangle has the output aspect ratio and
+/// Single would stretch the others; otherwise frames use the destination variant.
+/// For any other combination, it returns a vector filled with the destination CropResult.
 pub fn interpolate_crop_results(
     start: &crop::CropResult,
     destination: &crop::CropResult,
     num_frames: usize,
 ) -> Vec<crop::CropResult> {
-    // If either crop result is not Single, return all destination crops
-    let (crop::CropResult::Single(start_crop), crop::CropResult::Single(dest_crop)) =
-        (start, destination)
-    else {
-        return vec![destination.clone(); num_frames];
+    // Only Single<->Single, BlurFill<->BlurFill and Single<->Resize transitions
+    // are animated
+    let (start_crop, dest_crop) = match (start, destination) {
+        (crop::CropResult::Single(start_crop), crop::CropResult::Single(dest_crop))
+        | (crop::CropResult::BlurFill(start_crop), crop::CropResult::BlurFill(dest_crop))
+        | (crop::CropResult::Single(start_crop), crop::CropResult::Resize(dest_crop))
+        | (crop::CropResult::Resize(start_crop), crop::CropResult::Single(dest_crop)) => {
+            (start_crop, dest_crop)
+        }
+        _ => return vec![destination.clone(); num_frames],
     };
 
     // Handle edge case of zero or one frame
@@ -255,21 +770,288 @@ pub fn interpolate_crop_results(
         .map(|i| {
             let t = i as f32 * step;
 
-            // Only interpolate x-coordinate, keep y, width, and height from destination
-            let x = start_crop.x + t * (dest_crop.x - start_crop.x);
+            let lerp = |a: f32, b: f32| a + t * (b - a);
+
+            let area = crop::CropArea::new(
+                lerp(start_crop.x, dest_crop.x),
+                lerp(start_crop.y, dest_crop.y),
+                lerp(start_crop.width, dest_crop.width),
+                lerp(start_crop.height, dest_crop.height),
+            );
 
-            crop::CropResult::Single(crop::CropArea::new(
-                x,
-                dest_crop.y,
-                dest_crop.width,
-                dest_crop.height,
-            ))
+            match (start, destination) {
+                (_, crop::CropResult::Resize(_)) => crop::CropResult::Resize(area),
+                (crop::CropResult::Resize(_), _) if i + 1 < num_frames => {
+                    crop::CropResult::Resize(area)
+                }
+                (_, crop::CropResult::BlurFill(_)) => crop::CropResult::BlurFill(area),
+                _ => crop::CropResult::Single(area),
+            }
         })
         .collect()
 }
 
+/// Dataloader batch size: `--batch-size` when given, otherwise the model's own
+/// batch size, which is also the largest batch the model accepts
+pub fn dataloader_batch(model_batch: usize, requested: Option<usize>) -> Result<usize> {
+    match requested {
+        None => Ok(model_batch),
+        Some(0) => anyhow::bail!("--batch-size must be at least 1"),
+        Some(batch) if batch > model_batch => anyhow::bail!(
+            "--batch-size {batch} exceeds the model's maximum batch size of {model_batch}"
+        ),
+        Some(batch) => Ok(batch),
+    }
+}
+
+/// Whether a detection error reports the inference runtime running out of
+/// (GPU) memory
+pub fn is_out_of_memory(err: &anyhow::Error) -> bool {
+    let message = format!("{err:#}").to_lowercase();
+    [
+        "out of memory",
+        "out_of_memory",
+        "failed to allocate",
+        "bad_alloc",
+    ]
+    .iter()
+    .any(|marker| message.contains(marker))
+}
+
+/// Adds `hint` to out-of-memory detection errors, leaving others unchanged
+pub fn with_out_of_memory_hint(err: impl Into<anyhow::Error>, hint: &str) -> anyhow::Error {
+    let err = err.into();
+    if is_out_of_memory(&err) {
+        err.context(format!("detection ran out of memory; {hint}"))
+    } else {
+        err
+    }
+}
+
+/// Width of the `--report-confidences` histogram's longest bar, in characters
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Collects the confidence of every detection of the tracked objects for
+/// `--report-confidences`, to pick `--object-prob-threshold` from the data
+#[derive(Debug, Default)]
+pub struct ConfidenceReport {
+    confidences: Vec<f32>,
+    frames: usize,
+}
+
+impl ConfidenceReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one detected frame's detections of `object_names`, whatever
+    /// their confidence
+    pub fn record(&mut self, hbbs: &[Hbb], object_names: &[&str]) {
+        self.frames += 1;
+        self.confidences.extend(
+            hbbs.iter()
+                .filter(|hbb| hbb.name().is_some_and(|name| object_names.contains(&name)))
+                .filter_map(|hbb| hbb.confidence()),
+        );
+    }
+
+    /// The confidence `percentile` (0 to 1) of the way up the recorded ones,
+    /// so a threshold there keeps the rest; `None` before any detection
+    pub fn percentile(&self, percentile: f32) -> Option<f32> {
+        let mut sorted = self.confidences.clone();
+        sorted.sort_by(f32::total_cmp);
+        let last = sorted.len().checked_sub(1)?;
+        let index = (percentile.clamp(0.0, 1.0) * last as f32).floor() as usize;
+        Some(sorted[index])
+    }
+
+    /// Detection counts in ten equal confidence bins from 0 to 1
+    fn histogram(&self) -> [usize; 10] {
+        let mut bins = [0; 10];
+        for &confidence in &self.confidences {
+            bins[((confidence.clamp(0.0, 1.0) * 10.0) as usize).min(9)] += 1;
+        }
+        bins
+    }
+
+    /// The report printed at the end of the run: a histogram of the recorded
+    /// confidences and the thresholds keeping 90%, 50% and 10% of them
+    pub fn format(&self, object_names: &[&str]) -> String {
+        let names = object_names.join(", ");
+        let (Some(p10), Some(p50), Some(p90)) = (
+            self.percentile(0.1),
+            self.percentile(0.5),
+            self.percentile(0.9),
+        ) else {
+            return format!("No {} detections in {} frames", names, self.frames);
+        };
+
+        let bins = self.histogram();
+        let largest = bins.iter().copied().max().unwrap_or(0).max(1);
+        let mut report = format!(
+            "Confidence of {} {} detections in {} frames:\n",
+            self.confidences.len(),
+            names,
+            self.frames
+        );
+        for (index, &count) in bins.iter().enumerate() {
+            let bar = "#".repeat(count * HISTOGRAM_WIDTH / largest);
+            report.push_str(&format!(
+                "  {:.1}-{:.1} | {:<width$} {}\n",
+                index as f32 / 10.0,
+                (index + 1) as f32 / 10.0,
+                bar,
+                count,
+                width = HISTOGRAM_WIDTH
+            ));
+        }
+        report.push_str(&format!(
+            "Suggested --object-prob-threshold: {p10:.2} (keeps 90%), {p50:.2} (keeps 50%), {p90:.2} (keeps 10%)"
+        ));
+        report
+    }
+}
+
+/// Decides which decoded batches run object detection for `--detect-interval`.
+///
+/// Frames are counted across batches, and a batch is detected when it contains a
+/// frame whose index is a multiple of the interval (so every batch when the
+/// interval is 0 or 1). Frames in skipped batches reuse the last detection.
+pub struct DetectionSchedule {
+    interval: usize,
+    next_frame: usize,
+}
+
+impl DetectionSchedule {
+    pub fn new(interval: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            next_frame: 0,
+        }
+    }
+
+    /// Advances past a batch of `batch_len` frames, returning whether the batch
+    /// should run detection
+    pub fn should_detect(&mut self, batch_len: usize) -> bool {
+        let start = self.next_frame;
+        self.next_frame += batch_len;
+        // First frame index at or after `start` that is due for detection
+        let next_due = start.div_ceil(self.interval) * self.interval;
+        next_due < self.next_frame
+    }
+}
+
+/// Frame rate of the processed output: the source rate, capped at
+/// `--max-fps` when given
+pub fn output_frame_rate(args: &Args, source_fps: f64) -> f64 {
+    match args.max_fps {
+        Some(max_fps) => source_fps.min(max_fps),
+        None => source_fps,
+    }
+}
+
+/// Checks that a `--max-fps` value is positive
+pub fn check_max_fps(max_fps: f64) -> Result<()> {
+    if max_fps.is_nan() || max_fps <= 0.0 {
+        anyhow::bail!("invalid max fps {max_fps}: expected more than 0");
+    }
+    Ok(())
+}
+
+/// Drops decoded frames to bring a `source_fps` video down to `output_fps`
+/// for `--max-fps`, before they reach detection.
+///
+/// A frame is kept once its timestamp reaches the next output frame's, so the
+/// kept frames play at `output_fps` over the source's duration and audio and
+/// captions stay aligned. Nothing is dropped when the output rate isn't lower.
+pub struct FrameDecimator {
+    /// Output frames per source frame, at most 1
+    ratio: f64,
+    seen: u64,
+    kept: u64,
+}
+
+impl FrameDecimator {
+    pub fn new(source_fps: f64, output_fps: f64) -> Self {
+        Self {
+            ratio: (output_fps / source_fps).min(1.0),
+            seen: 0,
+            kept: 0,
+        }
+    }
+
+    /// Advances past the next decoded frame, returning whether to keep it
+    pub fn keep(&mut self) -> bool {
+        let due = self.seen as f64 * self.ratio >= self.kept as f64 - 1e-9;
+        self.seen += 1;
+        if due {
+            self.kept += 1;
+        }
+        due
+    }
+
+    /// Keeps the frames of a decoded batch that fall on the output rate
+    pub fn select(&mut self, images: Vec<usls::Image>) -> Vec<usls::Image> {
+        let before = images.len();
+        let kept: Vec<usls::Image> = images.into_iter().filter(|_| self.keep()).collect();
+        metrics::inc("frames_dropped_max_fps", (before - kept.len()) as u64);
+        kept
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn test_black_frame_keeps_previous_crop() {
+        use super::black_frame_crop;
+        use crate::crop::{CropArea, CropResult};
+
+        let previous = CropResult::Single(CropArea::new(400.0, 0.0, 608.0, 1080.0));
+        let black = usls::Image::from(::image::RgbImage::new(1920, 1080));
+        let lit = usls::Image::from(::image::RgbImage::from_pixel(
+            1920,
+            1080,
+            ::image::Rgb([90, 90, 90]),
+        ));
+
+        assert_eq!(
+            black_frame_crop(&black, 10.0, Some(&previous)),
+            Some(previous.clone())
+        );
+        assert_eq!(black_frame_crop(&lit, 10.0, Some(&previous)), None);
+        // Nothing to hold before the first crop
+        assert_eq!(black_frame_crop(&black, 10.0, None), None);
+    }
+
+    #[test]
+    fn test_dataloader_batch() {
+        use super::dataloader_batch;
+
+        assert_eq!(dataloader_batch(8, None).unwrap(), 8);
+        assert_eq!(dataloader_batch(8, Some(2)).unwrap(), 2);
+        assert!(dataloader_batch(8, Some(16)).is_err());
+        assert!(dataloader_batch(8, Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_out_of_memory_hint() {
+        use super::{is_out_of_memory, with_out_of_memory_hint};
+
+        let oom = anyhow::anyhow!("CUDA failure 2: out of memory");
+        assert!(is_out_of_memory(&oom));
+        let hinted = with_out_of_memory_hint(oom, "lower --batch-size");
+        assert!(
+            format!("{hinted:#}").starts_with("detection ran out of memory; lower --batch-size")
+        );
+
+        let other = anyhow::anyhow!("invalid input shape");
+        assert!(!is_out_of_memory(&other));
+        assert_eq!(
+            with_out_of_memory_hint(other, "lower --batch-size").to_string(),
+            "invalid input shape"
+        );
+    }
+
     #[test]
     fn test_combined_hbb_area() {
         use super::combined_hbb_area;
@@ -289,59 +1071,485 @@ mod tests {
 
     #[test]
     fn test_filter_small_relative_objects() {
-        use super::filter_small_relative_objects;
+        use super::{MinAreaRatio, filter_small_relative_objects};
         use usls::Hbb;
 
         // One dominant face (294x410) plus two tiny book-cover faces — the exact
         // shape of the bug1.mp4 frame that split the subject.
-        let main = Hbb::from_xywh(740.0, 170.0, 294.0, 410.0).with_confidence(0.90);
-        let book1 = Hbb::from_xywh(196.0, 726.0, 66.0, 80.0).with_confidence(0.82);
-        let book2 = Hbb::from_xywh(135.0, 730.0, 45.0, 61.0).with_confidence(0.80);
+        let face = |x, y, w, h, confidence| {
+            Hbb::from_xywh(x, y, w, h)
+                .with_confidence(confidence)
+                .with_name("face")
+        };
+        let main = face(740.0, 170.0, 294.0, 410.0, 0.90);
+        let book1 = face(196.0, 726.0, 66.0, 80.0, 0.82);
+        let book2 = face(135.0, 730.0, 45.0, 61.0, 0.80);
         let objects: Vec<&Hbb> = vec![&main, &book1, &book2];
 
         // At the default ratio the two tiny faces (~2-4% of the largest) are
         // dropped, leaving only the real subject.
-        let kept = filter_small_relative_objects(objects.clone(), "face", 0.05);
+        let kept = filter_small_relative_objects(objects.clone(), &MinAreaRatio::uniform(0.05));
         assert_eq!(kept.len(), 1);
 
         // A genuine co-subject at similar size is kept (two-person stacked case).
-        let person2 = Hbb::from_xywh(314.0, 250.0, 368.0, 527.0).with_confidence(0.90);
+        let person2 = face(314.0, 250.0, 368.0, 527.0, 0.90);
         let two: Vec<&Hbb> = vec![&main, &person2];
-        assert_eq!(filter_small_relative_objects(two, "face", 0.05).len(), 2);
+        assert_eq!(
+            filter_small_relative_objects(two, &MinAreaRatio::uniform(0.05)).len(),
+            2
+        );
+
+        // Disabled (ratio 0) keeps everything.
+        assert_eq!(
+            filter_small_relative_objects(objects, &MinAreaRatio::uniform(0.0)).len(),
+            3
+        );
+
+        // Ball-type objects are exempt per object, so a far-off ball survives
+        // next to a large player while a tiny bystander doesn't.
+        let ball = |name| {
+            Hbb::from_xywh(900.0, 800.0, 20.0, 20.0)
+                .with_confidence(0.8)
+                .with_name(name)
+        };
+        let (football, sports_ball) = (ball("ball"), ball("sports ball"));
+        let player = Hbb::from_xywh(700.0, 100.0, 300.0, 700.0)
+            .with_confidence(0.9)
+            .with_name("person");
+        let bystander = Hbb::from_xywh(100.0, 500.0, 20.0, 40.0)
+            .with_confidence(0.9)
+            .with_name("person");
+        let kept = filter_small_relative_objects(
+            vec![&player, &sports_ball, &bystander, &football],
+            &MinAreaRatio::default(),
+        );
+        let kept_names: Vec<Option<&str>> = kept.iter().map(|hbb| hbb.name()).collect();
+        assert_eq!(
+            kept_names,
+            [Some("person"), Some("sports ball"), Some("ball")]
+        );
+        assert_eq!(
+            filter_small_relative_objects(vec![&football, &sports_ball], &MinAreaRatio::default())
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_min_area_ratio_from_str() {
+        use super::MinAreaRatio;
+
+        assert_eq!(
+            "0.1".parse::<MinAreaRatio>(),
+            Ok(MinAreaRatio::uniform(0.1))
+        );
+        let ratios: MinAreaRatio = "person=0.01, face=0.002".parse().unwrap();
+        assert_eq!(ratios.ratio_for(Some("person")), Some(0.01));
+        assert_eq!(ratios.ratio_for(Some("face")), Some(0.002));
+        // Unlisted classes keep the default, balls stay exempt
+        assert_eq!(ratios.ratio_for(Some("car")), Some(MinAreaRatio::DEFAULT));
+        assert_eq!(ratios.ratio_for(Some("ball")), None);
+        // A bare value sets the ratio for unlisted classes
+        let ratios: MinAreaRatio = "0.2,sports ball=0.001".parse().unwrap();
+        assert_eq!(ratios.ratio_for(Some("car")), Some(0.2));
+        assert_eq!(ratios.ratio_for(Some("sports ball")), Some(0.001));
+
+        assert!("".parse::<MinAreaRatio>().is_err());
+        assert!("face=-1".parse::<MinAreaRatio>().is_err());
+        assert!("face=0.1,face=0.2".parse::<MinAreaRatio>().is_err());
+        assert!("0.1,0.2".parse::<MinAreaRatio>().is_err());
+        assert!("=0.1".parse::<MinAreaRatio>().is_err());
+    }
+
+    #[test]
+    fn test_filter_small_relative_objects_per_class() {
+        use super::{MinAreaRatio, filter_small_relative_objects};
+        use usls::Hbb;
+
+        let object = |name, w: f32, h: f32| {
+            Hbb::from_xywh(100.0, 100.0, w, h)
+                .with_confidence(0.9)
+                .with_name(name)
+        };
+        // A nearby person, a distant one (~1.5% of the area), and a face on
+        // the distant one (~0.4%)
+        let near = object("person", 400.0, 900.0);
+        let far = object("person", 60.0, 90.0);
+        let face = object("face", 30.0, 48.0);
+        let objects: Vec<&Hbb> = vec![&near, &far, &face];
+
+        // The single default drops both small objects
+        assert_eq!(
+            filter_small_relative_objects(objects.clone(), &MinAreaRatio::default()).len(),
+            1
+        );
+        // Per class, the distant person and the face each clear their own bar
+        let ratios: MinAreaRatio = "person=0.01,face=0.002".parse().unwrap();
+        assert_eq!(
+            filter_small_relative_objects(objects.clone(), &ratios).len(),
+            3
+        );
+        // ...and a stricter face ratio drops only the face
+        let ratios: MinAreaRatio = "person=0.01,face=0.01".parse().unwrap();
+        let kept = filter_small_relative_objects(objects, &ratios);
+        let kept_names: Vec<Option<&str>> = kept.iter().map(|hbb| hbb.name()).collect();
+        assert_eq!(kept_names, [Some("person"), Some("person")]);
+    }
+
+    #[test]
+    fn test_predict_current_hbb_stays_inside_frame() {
+        use super::predict_current_hbb;
+        use usls::Hbb;
+
+        // Accelerating toward the bottom-right corner of a 1920x1080 frame
+        let three = Hbb::from_xywh(1600.0, 800.0, 200.0, 150.0);
+        let two = Hbb::from_xywh(1650.0, 850.0, 200.0, 150.0);
+        let last = Hbb::from_xywh(1720.0, 920.0, 200.0, 150.0);
+        let predicted = predict_current_hbb(&three, &two, &last, 1920.0, 1080.0, 1.0);
+        assert_eq!(predicted.width(), 200.0);
+        assert_eq!(predicted.height(), 150.0);
+        assert_eq!(predicted.xmax(), 1920.0);
+        assert_eq!(predicted.ymax(), 1080.0);
+
+        // Moving fast toward the top-left corner
+        let three = Hbb::from_xywh(200.0, 150.0, 100.0, 100.0);
+        let two = Hbb::from_xywh(100.0, 80.0, 100.0, 100.0);
+        let last = Hbb::from_xywh(10.0, 5.0, 100.0, 100.0);
+        let predicted = predict_current_hbb(&three, &two, &last, 1920.0, 1080.0, 1.0);
+        assert_eq!(predicted.xmin(), 0.0);
+        assert_eq!(predicted.ymin(), 0.0);
+
+        // A box larger than the frame is shrunk to fit
+        let big = Hbb::from_xywh(0.0, 0.0, 2000.0, 1200.0);
+        let predicted = predict_current_hbb(&big, &big, &big, 1920.0, 1080.0, 1.0);
+        assert_eq!(predicted.width(), 1920.0);
+        assert_eq!(predicted.height(), 1080.0);
+        assert_eq!(predicted.xmin(), 0.0);
+    }
+
+    #[test]
+    fn test_predict_current_hbb_damping_limits_overshoot() {
+        use super::{check_prediction_damping, predict_current_hbb};
+        use usls::Hbb;
+
+        // A ball moving right at 40 px/frame that is suddenly caught at x=540
+        let three = Hbb::from_xywh(460.0, 300.0, 20.0, 20.0);
+        let two = Hbb::from_xywh(500.0, 300.0, 20.0, 20.0);
+        let last = Hbb::from_xywh(540.0, 300.0, 20.0, 20.0);
+        let caught = Hbb::from_xywh(540.0, 300.0, 20.0, 20.0);
+        let overshoot = |damping| {
+            predict_current_hbb(&two, &last, &caught, 1920.0, 1080.0, damping).xmin() - 540.0
+        };
+
+        // Undamped, the deceleration carries the prediction back past the stop
+        assert_eq!(overshoot(1.0), -20.0);
+        assert_eq!(overshoot(0.5), -10.0);
+        assert_eq!(overshoot(0.0), 0.0);
+
+        // Steady motion is unaffected by damping
+        let steady = predict_current_hbb(&three, &two, &last, 1920.0, 1080.0, 0.5);
+        assert_eq!(steady.xmin(), 580.0);
+
+        assert!(check_prediction_damping(1.0).is_ok());
+        assert!(check_prediction_damping(0.0).is_ok());
+        assert!(check_prediction_damping(1.5).is_err());
+        assert!(check_prediction_damping(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_playback_after_key() {
+        use super::{Playback, playback_after_key};
+        use usls::Key;
+
+        assert_eq!(playback_after_key(false, None), Playback::Play);
+        assert_eq!(playback_after_key(false, Some(Key::Space)), Playback::Pause);
+        assert_eq!(playback_after_key(false, Some(Key::Right)), Playback::Play);
+        assert_eq!(playback_after_key(false, Some(Key::Escape)), Playback::Stop);
+
+        assert_eq!(playback_after_key(true, None), Playback::Pause);
+        assert_eq!(playback_after_key(true, Some(Key::Right)), Playback::Step);
+        assert_eq!(playback_after_key(true, Some(Key::Space)), Playback::Play);
+        assert_eq!(playback_after_key(true, Some(Key::Escape)), Playback::Stop);
+    }
+
+    #[test]
+    fn test_subject_pick_after_key() {
+        use super::{SubjectPick, subject_pick_after_key};
+        use usls::Key;
+
+        assert_eq!(subject_pick_after_key(None, 3), SubjectPick::Waiting);
+        assert_eq!(
+            subject_pick_after_key(Some(Key::Key1), 3),
+            SubjectPick::Subject(0)
+        );
+        assert_eq!(
+            subject_pick_after_key(Some(Key::Key3), 3),
+            SubjectPick::Subject(2)
+        );
+        // Numbers past the last subject are ignored
+        assert_eq!(
+            subject_pick_after_key(Some(Key::Key4), 3),
+            SubjectPick::Waiting
+        );
+        assert_eq!(
+            subject_pick_after_key(Some(Key::Space), 3),
+            SubjectPick::Waiting
+        );
+        assert_eq!(
+            subject_pick_after_key(Some(Key::Enter), 3),
+            SubjectPick::Skip
+        );
+        assert_eq!(
+            subject_pick_after_key(Some(Key::Escape), 3),
+            SubjectPick::Skip
+        );
+    }
+
+    #[test]
+    fn test_is_object_above_threshold_matches_any_name() {
+        use super::is_object_above_threshold;
+        use usls::Hbb;
+
+        let object = |name, confidence| {
+            Hbb::from_xywh(0.0, 0.0, 100.0, 100.0)
+                .with_confidence(confidence)
+                .with_name(name)
+        };
+        let wanted = ["person", "sports ball"];
+
+        assert!(is_object_above_threshold(
+            &object("person", 0.9),
+            &wanted,
+            0.75
+        ));
+        assert!(is_object_above_threshold(
+            &object("sports ball", 0.8),
+            &wanted,
+            0.75
+        ));
+        assert!(!is_object_above_threshold(
+            &object("car", 0.9),
+            &wanted,
+            0.75
+        ));
+        assert!(!is_object_above_threshold(
+            &object("person", 0.3),
+            &wanted,
+            0.75
+        ));
+        assert!(is_object_above_threshold(
+            &object("car", 0.9),
+            &["car"],
+            0.75
+        ));
+
+        // Unnamed or unscored detections never match
+        let unnamed = Hbb::from_xywh(0.0, 0.0, 10.0, 10.0).with_confidence(0.9);
+        assert!(!is_object_above_threshold(&unnamed, &wanted, 0.75));
+        let unscored = Hbb::from_xywh(0.0, 0.0, 10.0, 10.0).with_name("person");
+        assert!(!is_object_above_threshold(&unscored, &wanted, 0.75));
+    }
+
+    #[test]
+    fn test_transition_mode_interpolates_given_a_cut() {
+        use super::TransitionMode;
+
+        assert!(TransitionMode::Always.interpolates(false));
+        assert!(TransitionMode::Always.interpolates(true));
+        assert!(TransitionMode::WithinScene.interpolates(false));
+        assert!(!TransitionMode::WithinScene.interpolates(true));
+        assert!(!TransitionMode::OnCut.interpolates(false));
+        assert!(TransitionMode::OnCut.interpolates(true));
+
+        assert_eq!("on-cut".parse(), Ok(TransitionMode::OnCut));
+        assert_eq!("Within-Scene".parse(), Ok(TransitionMode::WithinScene));
+        assert!("never".parse::<TransitionMode>().is_err());
+    }
+
+    #[test]
+    fn test_confidence_report() {
+        use super::ConfidenceReport;
+        use usls::Hbb;
+
+        let object = |name, confidence| {
+            Hbb::from_xywh(0.0, 0.0, 100.0, 100.0)
+                .with_confidence(confidence)
+                .with_name(name)
+        };
+        let mut report = ConfidenceReport::new();
+        assert_eq!(report.percentile(0.5), None);
+        assert_eq!(
+            report.format(&["person"]),
+            "No person detections in 0 frames"
+        );
 
-        // Disabled (ratio 0) and ball-type-exempt paths keep everything.
-        assert_eq!(filter_small_relative_objects(objects.clone(), "face", 0.0).len(), 3);
-        assert_eq!(filter_small_relative_objects(objects.clone(), "ball", 0.05).len(), 3);
-        assert_eq!(filter_small_relative_objects(objects, "sports ball", 0.05).len(), 3);
+        // Other classes aren't counted; low confidences are
+        report.record(&[object("person", 0.35), object("car", 0.9)], &["person"]);
+        report.record(
+            &[object("person", 0.62), object("person", 0.95)],
+            &["person"],
+        );
+        report.record(
+            &[object("person", 0.81), object("person", 1.0)],
+            &["person"],
+        );
+        assert_eq!(report.percentile(0.0), Some(0.35));
+        assert_eq!(report.percentile(0.5), Some(0.81));
+        assert_eq!(report.percentile(0.9), Some(0.95));
+        assert_eq!(report.histogram(), [0, 0, 0, 1, 0, 0, 1, 0, 1, 2]);
+
+        let formatted = report.format(&["person"]);
+        assert!(formatted.starts_with("Confidence of 5 person detections in 3 frames:\n"));
+        assert!(formatted.contains(&format!("  0.9-1.0 | {} 2\n", "#".repeat(40))));
+        assert!(formatted.ends_with("0.35 (keeps 90%), 0.81 (keeps 50%), 0.95 (keeps 10%)"));
     }
 
     #[test]
-    fn test_is_graphic_area_above_threshold() {
-        use super::is_graphic_area_above_threshold;
+    fn test_adaptive_threshold_relaxes_and_recovers() {
+        use super::ObjectThreshold;
+        use usls::Hbb;
+
+        let object = |confidence| {
+            Hbb::from_xywh(0.0, 0.0, 100.0, 100.0)
+                .with_confidence(confidence)
+                .with_name("person")
+        };
+        let wanted = ["person"];
+        let empty: [Hbb; 0] = [];
+        let dim = [object(0.62)];
+        let bright = [object(0.9)];
+        let mut threshold = ObjectThreshold::new(0.75, 0.6);
+
+        // An empty frame relaxes the threshold a step
+        assert!(threshold.extract(&empty, &wanted).is_empty());
+        assert!((threshold.current() - 0.7).abs() < 1e-6);
+
+        // Dim frames keep relaxing it until the subject passes, then hold it
+        assert!(threshold.extract(&dim, &wanted).is_empty());
+        assert_eq!(threshold.extract(&dim, &wanted).len(), 1);
+        assert_eq!(threshold.extract(&dim, &wanted).len(), 1);
+        assert!((threshold.current() - 0.6).abs() < 1e-6);
+
+        // It never drops below the floor
+        for _ in 0..10 {
+            assert!(threshold.extract(&empty, &wanted).is_empty());
+        }
+        assert!((threshold.current() - 0.6).abs() < 1e-6);
+
+        // A detection passing the full threshold restores it
+        assert_eq!(threshold.extract(&bright, &wanted).len(), 1);
+        assert_eq!(threshold.current(), 0.75);
+        assert!(threshold.extract(&dim, &wanted).is_empty());
+
+        // Without --adaptive-threshold empty frames change nothing
+        let mut fixed = ObjectThreshold::fixed(0.75);
+        assert!(fixed.extract(&empty, &wanted).is_empty());
+        assert!(fixed.extract(&dim, &wanted).is_empty());
+        assert_eq!(fixed.current(), 0.75);
+    }
+
+    #[test]
+    fn test_graphic_coverage() {
+        use super::graphic_coverage;
         use usls::Hbb;
 
         let hbbs = vec![
             Hbb::from_xywh(0.0, 0.0, 100.0, 100.0).with_confidence(0.95),
             Hbb::from_xywh(10.0, 10.0, 20.0, 20.0).with_confidence(0.7),
         ];
-        let frame_width = 200.0;
-        let frame_height = 200.0;
-
-        assert!(is_graphic_area_above_threshold(
-            hbbs.iter(),
-            frame_width,
-            frame_height,
-            0.2,
-            0.85,
-        ));
 
-        assert!(!is_graphic_area_above_threshold(
-            hbbs.iter(),
-            frame_width,
-            frame_height,
-            0.3,
-            0.85,
-        ));
+        // Only the confident box counts: 100x100 of a 200x200 frame
+        assert_eq!(graphic_coverage(hbbs.iter(), 200.0, 200.0, 0.85), 0.25);
+        assert_eq!(graphic_coverage(hbbs.iter(), 0.0, 200.0, 0.85), 0.0);
+    }
+
+    #[test]
+    fn test_graphic_hysteresis_is_stable_inside_band() {
+        use super::GraphicHysteresis;
+
+        let mut graphic = GraphicHysteresis::new(0.01, 0.0075);
+        // Coverage oscillating around the enter threshold, without ever
+        // dropping below the exit threshold, flips to graphic once and stays
+        let coverage = [0.009, 0.0105, 0.0095, 0.0102, 0.008, 0.0099, 0.0076];
+        let states: Vec<bool> = coverage.iter().map(|&c| graphic.update(c)).collect();
+        assert_eq!(states, [false, true, true, true, true, true, true]);
+
+        // Dropping below the exit threshold releases it, and the same
+        // oscillation below the enter threshold then stays off
+        assert!(!graphic.update(0.007));
+        assert!([0.009, 0.0095, 0.008].iter().all(|&c| !graphic.update(c)));
+        assert!(graphic.update(0.01));
+    }
+
+    #[test]
+    fn test_graphic_hysteresis_disabled() {
+        use super::GraphicHysteresis;
+
+        let mut graphic = GraphicHysteresis::new(0.0, 0.0);
+        assert!(!graphic.update(1.0));
+    }
+
+    #[test]
+    fn test_smoothing_window_frames() {
+        use super::smoothing_window_frames;
+
+        // The default window scales with the frame rate
+        assert_eq!(smoothing_window_frames(1.0, 30.0, None), 30);
+        assert_eq!(smoothing_window_frames(1.0, 59.94, None), 60);
+        assert_eq!(smoothing_window_frames(0.0, 60.0, None), 0);
+
+        // An explicit history size wins over the duration
+        assert_eq!(smoothing_window_frames(1.0, 60.0, Some(12)), 12);
+    }
+
+    #[test]
+    fn test_frame_decimator() {
+        use super::FrameDecimator;
+
+        // 60 -> 30 fps keeps every other frame
+        let mut decimator = FrameDecimator::new(60.0, 30.0);
+        let kept: Vec<bool> = (0..6).map(|_| decimator.keep()).collect();
+        assert_eq!(kept, [true, false, true, false, true, false]);
+
+        // 59.94 -> 30 still keeps 30 frames per second of source
+        let mut decimator = FrameDecimator::new(59.94, 30.0);
+        let kept = (0..5994).filter(|_| decimator.keep()).count();
+        assert_eq!(kept, 3000);
+
+        // 50 -> 30 keeps 3 of every 5, spread out
+        let mut decimator = FrameDecimator::new(50.0, 30.0);
+        let kept: Vec<bool> = (0..10).map(|_| decimator.keep()).collect();
+        assert_eq!(
+            kept,
+            [
+                true, false, true, false, true, true, false, true, false, true
+            ]
+        );
+
+        // A cap at or above the source rate keeps everything
+        let mut decimator = FrameDecimator::new(30.0, 60.0);
+        assert!((0..10).all(|_| decimator.keep()));
+    }
+
+    #[test]
+    fn test_detection_schedule() {
+        use super::DetectionSchedule;
+
+        // Single-frame batches detect every third frame and reuse in between
+        let mut schedule = DetectionSchedule::new(3);
+        let detected: Vec<bool> = (0..7).map(|_| schedule.should_detect(1)).collect();
+        assert_eq!(detected, [true, false, false, true, false, false, true]);
+
+        // An interval of 0 or 1 detects every frame
+        for interval in [0, 1] {
+            let mut schedule = DetectionSchedule::new(interval);
+            assert!((0..5).all(|_| schedule.should_detect(1)));
+        }
+
+        // Multi-frame batches detect when any frame in them is due
+        let mut schedule = DetectionSchedule::new(4);
+        let detected: Vec<bool> = (0..4).map(|_| schedule.should_detect(2)).collect();
+        assert_eq!(detected, [true, false, true, false]);
     }
 
     #[test]
@@ -357,13 +1565,13 @@ mod tests {
 
         assert_eq!(result.len(), num_frames);
 
-        // Check first frame (should have start x, destination y/width/height)
+        // Check first frame (should be start)
         match &result[0] {
             CropResult::Single(crop) => {
                 assert!((crop.x - 100.0).abs() < 0.001);
-                assert!((crop.y - 300.0).abs() < 0.001); // destination y
-                assert!((crop.width - 400.0).abs() < 0.001); // destination width
-                assert!((crop.height - 500.0).abs() < 0.001); // destination height
+                assert!((crop.y - 200.0).abs() < 0.001);
+                assert!((crop.width - 300.0).abs() < 0.001);
+                assert!((crop.height - 400.0).abs() < 0.001);
             }
             _ => panic!("Expected Single crop result"),
         }
@@ -379,18 +1587,51 @@ mod tests {
             _ => panic!("Expected Single crop result"),
         }
 
-        // Check middle frame (should have halfway x, destination y/width/height)
+        // Check middle frame (should be halfway on every field)
         match &result[2] {
             CropResult::Single(crop) => {
                 assert!((crop.x - 150.0).abs() < 0.001);
-                assert!((crop.y - 300.0).abs() < 0.001); // destination y
-                assert!((crop.width - 400.0).abs() < 0.001); // destination width
-                assert!((crop.height - 500.0).abs() < 0.001); // destination height
+                assert!((crop.y - 250.0).abs() < 0.001);
+                assert!((crop.width - 350.0).abs() < 0.001);
+                assert!((crop.height - 450.0).abs() < 0.001);
             }
             _ => panic!("Expected Single crop result"),
         }
     }
 
+    #[test]
+    fn test_interpolate_crop_results_single_to_single_all_fields() {
+        use super::interpolate_crop_results;
+        use crate::crop::{CropArea, CropResult};
+
+        // x stays put while y, width, and height all change (including shrinking)
+        let start = CropResult::Single(CropArea::new(500.0, 0.0, 810.0, 1080.0));
+        let destination = CropResult::Single(CropArea::new(500.0, 120.0, 600.0, 800.0));
+        let num_frames = 4;
+
+        let result = interpolate_crop_results(&start, &destination, num_frames);
+
+        assert_eq!(result.len(), num_frames);
+
+        let expected = [
+            (0.0, 810.0, 1080.0),
+            (40.0, 740.0, 986.6667),
+            (80.0, 670.0, 893.3333),
+            (120.0, 600.0, 800.0),
+        ];
+        for (crop_result, (y, width, height)) in result.iter().zip(expected) {
+            match crop_result {
+                CropResult::Single(crop) => {
+                    assert!((crop.x - 500.0).abs() < 0.001);
+                    assert!((crop.y - y).abs() < 0.001);
+                    assert!((crop.width - width).abs() < 0.001);
+                    assert!((crop.height - height).abs() < 0.001);
+                }
+                _ => panic!("Expected Single crop result"),
+            }
+        }
+    }
+
     #[test]
     fn test_interpolate_crop_results_single_to_stacked() {
         use super::interpolate_crop_results;
@@ -455,6 +1696,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_interpolate_crop_results_single_to_resize() {
+        use super::interpolate_crop_results;
+        use crate::crop::{CropArea, CropResult};
+
+        let start = CropResult::Single(CropArea::new(555.0, 0.0, 810.0, 1080.0));
+        let destination = CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0));
+        let num_frames = 6;
+
+        let result = interpolate_crop_results(&start, &destination, num_frames);
+
+        assert_eq!(result.len(), num_frames);
+
+        // The crop should expand every frame until it fills the whole frame
+        let widths: Vec<f32> = result
+            .iter()
+            .map(|crop_result| match crop_result {
+                CropResult::Resize(crop) => crop.width,
+                _ => panic!("Expected Resize crop result"),
+            })
+            .collect();
+        assert!((widths[0] - 810.0).abs() < 0.001);
+        assert!(widths.windows(2).all(|w| w[1] > w[0]));
+        assert_eq!(result[num_frames - 1], destination);
+    }
+
+    #[test]
+    fn test_interpolate_crop_results_resize_to_single() {
+        use super::interpolate_crop_results;
+        use crate::crop::{CropArea, CropResult};
+
+        let start = CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0));
+        let destination = CropResult::Single(CropArea::new(555.0, 0.0, 810.0, 1080.0));
+        let num_frames = 6;
+
+        let result = interpolate_crop_results(&start, &destination, num_frames);
+
+        assert_eq!(result.len(), num_frames);
+
+        // The crop should contract every frame down to the tracked crop,
+        // staying Resize until it lands on it
+        let widths: Vec<f32> = result
+            .iter()
+            .map(|crop_result| match crop_result {
+                CropResult::Resize(crop) | CropResult::Single(crop) => crop.width,
+                _ => panic!("Expected Resize or Single crop result"),
+            })
+            .collect();
+        assert!((widths[0] - 1920.0).abs() < 0.001);
+        assert!(widths.windows(2).all(|w| w[1] < w[0]));
+        assert!(
+            result[..num_frames - 1]
+                .iter()
+                .all(|crop_result| matches!(crop_result, CropResult::Resize(_)))
+        );
+        assert_eq!(result[num_frames - 1], destination);
+    }
+
+    #[test]
+    fn test_interpolate_crop_results_resize_to_single_keeps_output_aspect() {
+        use super::interpolate_crop_results;
+        use crate::crop::{CropArea, CropResult};
+
+        // Single crops are stretched to the 9:16 output, so any Single in the
+        // transition must already be 9:16
+        let start = CropResult::Resize(CropArea::new(0.0, 0.0, 1920.0, 1080.0));
+        let destination = CropResult::Single(CropArea::new(656.25, 0.0, 607.5, 1080.0));
+
+        for num_frames in [2, 6, 30] {
+            let result = interpolate_crop_results(&start, &destination, num_frames);
+            for crop_result in &result {
+                if let CropResult::Single(crop) = crop_result {
+                    assert!((crop.width / crop.height - 9.0 / 16.0).abs() < 0.001);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_interpolate_crop_results_stacked_to_stacked() {
         use super::interpolate_crop_results;
@@ -574,12 +1893,11 @@ mod tests {
             let curr = &result[i];
 
             if let (CropResult::Single(prev_crop), CropResult::Single(curr_crop)) = (prev, curr) {
-                // Only X should be increasing (y, width, height stay constant)
+                // Every field should be increasing toward the destination
                 assert!(curr_crop.x >= prev_crop.x);
-                // Y, width, and height should remain constant (destination values)
-                assert!((curr_crop.y - 1000.0).abs() < 0.001); // destination y
-                assert!((curr_crop.width - 200.0).abs() < 0.001); // destination width
-                assert!((curr_crop.height - 200.0).abs() < 0.001); // destination height
+                assert!(curr_crop.y >= prev_crop.y);
+                assert!(curr_crop.width >= prev_crop.width);
+                assert!(curr_crop.height >= prev_crop.height);
             }
         }
     }
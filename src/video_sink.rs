@@ -1,12 +1,118 @@
+use crate::crop::{AspectRatio, CropResult};
+use crate::crop_log::CropLog;
+use crate::image;
 use crate::metrics;
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
 use std::sync::mpsc::{SyncSender, sync_channel};
 use std::thread::JoinHandle;
 use std::time::Instant;
-use usls::{Image, Key, Viewer};
-use video_rs::{Encoder, Frame, Time, encode::Settings};
+use usls::{Annotator, HbbStyle, Image, Key, Viewer};
+use video_rs::{Encoder, Frame, Options, Time, encode::Settings, frame::PixelFormat};
+
+/// x264 speed presets, fastest (largest files) to slowest (smallest files)
+pub const X264_PRESETS: &[&str] = &[
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+    "placebo",
+];
+
+/// Quality and rate-control options for the H.264 output encode. With nothing
+/// set, the encoder uses the `medium` preset and x264's default quality.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncodeSettings {
+    /// Constant rate factor, 0 (lossless) to 51; lower is better quality
+    pub crf: Option<u32>,
+    /// x264 speed preset, one of `X264_PRESETS`
+    pub preset: Option<String>,
+    /// Target average bitrate in bits per second, for predictable file sizes
+    pub bitrate: Option<u64>,
+}
+
+impl EncodeSettings {
+    /// Checks the values are ones x264 accepts, and that quality and bitrate
+    /// targets aren't both requested
+    pub fn validate(&self) -> Result<()> {
+        if let Some(crf) = self.crf
+            && crf > 51
+        {
+            anyhow::bail!("invalid crf {crf}: expected 0 to 51");
+        }
+        if let Some(preset) = &self.preset
+            && !X264_PRESETS.contains(&preset.as_str())
+        {
+            anyhow::bail!(
+                "invalid preset '{preset}': expected one of {}",
+                X264_PRESETS.join(", ")
+            );
+        }
+        if self.crf.is_some() && self.bitrate.is_some() {
+            anyhow::bail!("crf and video bitrate are mutually exclusive; pick one");
+        }
+        Ok(())
+    }
+
+    /// The encoder options (an ffmpeg/x264 option dictionary) for these settings
+    fn encoder_options(&self) -> HashMap<String, String> {
+        let mut options = HashMap::new();
+        options.insert(
+            "preset".to_string(),
+            self.preset.clone().unwrap_or_else(|| "medium".to_string()),
+        );
+        if let Some(crf) = self.crf {
+            options.insert("crf".to_string(), crf.to_string());
+        }
+        if let Some(bitrate) = self.bitrate {
+            options.insert("b".to_string(), bitrate.to_string());
+        }
+        options
+    }
+
+    /// The same settings as ffmpeg command-line arguments, for the H.264
+    /// re-encodes (caption burning, padding) that run outside this encoder
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-c:v".to_string(),
+            "libx264".to_string(),
+            "-preset".to_string(),
+            self.preset.clone().unwrap_or_else(|| "medium".to_string()),
+        ];
+        if let Some(crf) = self.crf {
+            args.extend(["-crf".to_string(), crf.to_string()]);
+        }
+        if let Some(bitrate) = self.bitrate {
+            args.extend(["-b:v".to_string(), bitrate.to_string()]);
+        }
+        args
+    }
+}
+
+/// Parses a bitrate such as `2500k`, `4M`, or `800000` into bits per second
+pub fn parse_bitrate(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let (digits, multiplier) = match text.chars().last() {
+        Some('k' | 'K') => (&text[..text.len() - 1], 1_000.0),
+        Some('m' | 'M') => (&text[..text.len() - 1], 1_000_000.0),
+        _ => (text, 1.0),
+    };
+    let value: f64 = digits
+        .parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite() && *value > 0.0)
+        .with_context(|| format!("invalid bitrate '{text}': expected e.g. 2500k or 4M"))?;
+    Ok((value * multiplier).round() as u64)
+}
 
 /// One encode request: the cropped frame's RGB24 bytes plus its dimensions.
 struct EncodeMsg {
@@ -36,12 +142,173 @@ pub struct VideoSink {
     tx: Option<SyncSender<EncodeMsg>>,
     handle: Option<JoinHandle<Result<()>>>,
     frame_index: usize,
+    crop_log: Option<CropLog>,
+    debug_overlay: Option<DebugOverlay>,
+    preview_grid: Option<PreviewGrid>,
+    output_width: Option<u32>,
+    extra_outputs: Vec<ExtraOutput>,
+}
+
+/// One `--output-resolution` size, parsed from `WxH`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for OutputSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("invalid output resolution '{s}': expected WxH, e.g. 1080x1920");
+        let (width, height) = s.trim().split_once('x').ok_or_else(invalid)?;
+        let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+        let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+        if width == 0 || height == 0 {
+            return Err(invalid());
+        }
+        Ok(Self { width, height })
+    }
+}
+
+impl fmt::Display for OutputSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Parses `--output-resolution`'s comma-separated sizes. Each must be even
+/// (as H.264 requires) and have the canvas's aspect ratio, since every size
+/// renders the same crops; the first is the main output.
+pub fn parse_output_sizes(text: &str, aspect_ratio: AspectRatio) -> Result<Vec<OutputSize>> {
+    let mut sizes: Vec<OutputSize> = Vec::new();
+    for part in text.split(',') {
+        let size: OutputSize = part.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+        let expected = make_even(aspect_ratio.canvas_height(size.width));
+        if size.width % 2 != 0 || size.height != expected {
+            anyhow::bail!(
+                "invalid output resolution {size}: a {aspect_ratio} canvas needs an even width \
+                 and the matching height, e.g. {}x{expected}",
+                make_even(size.width)
+            );
+        }
+        if sizes.contains(&size) {
+            anyhow::bail!("output resolution {size} is listed twice");
+        }
+        sizes.push(size);
+    }
+    Ok(sizes)
+}
+
+/// Path of the extra rendering at `size` next to `saveout`, e.g.
+/// `processed_video_720x1280.mp4` for `processed_video.mp4`
+pub fn resolution_output_path(saveout: &str, size: OutputSize) -> String {
+    let path = Path::new(saveout);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}_{}.{}", stem, size, extension.to_string_lossy()),
+        None => format!("{}_{}", stem, size),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Extra `--output-resolution` rendering of the same crops at another width
+pub struct ExtraOutput {
+    sink: Box<VideoSink>,
+    width: u32,
+}
+
+impl ExtraOutput {
+    /// Width frames are rendered at for this output
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Enqueues a frame rendered at `width` for encoding
+    pub fn write_frame(&mut self, img: Image) -> Result<()> {
+        self.sink.write_frame(img, true)
+    }
+}
+
+/// Second output for `--debug-overlay`: the full source frame, already
+/// annotated with its detections, with the committed crop drawn on top
+pub struct DebugOverlay {
+    sink: Box<VideoSink>,
+    annotator: Annotator,
+}
+
+/// The crop rectangle(s) of `crop_result` as boxes an `Annotator` can draw
+fn crop_boxes(crop_result: &CropResult) -> usls::Y {
+    let mut crop_boxes = usls::Y::default();
+    crop_boxes.hbbs = crop_result
+        .areas()
+        .into_iter()
+        .map(|area| usls::Hbb::from_xywh(area.x, area.y, area.width, area.height).with_name("crop"))
+        .collect();
+    crop_boxes
+}
+
+impl DebugOverlay {
+    /// Draws the crop rectangle(s) onto `img` and enqueues it for encoding
+    pub fn write_frame(&mut self, img: &Image, crop_result: &CropResult) -> Result<()> {
+        let frame = self.annotator.annotate(img, &crop_boxes(crop_result))?;
+        self.sink.write_frame(frame, true)
+    }
+}
+
+/// Contact sheet for `--preview-grid`: every `interval`th frame, shrunk to a
+/// tile with the committed crop drawn on it. Once it holds
+/// [`image::PREVIEW_MAX_TILES`] tiles, the interval doubles and every other
+/// tile is dropped, so the sheet stays bounded but still spans the clip.
+pub struct PreviewGrid {
+    interval: usize,
+    frame_index: usize,
+    tiles: Vec<::image::RgbImage>,
+    annotator: Annotator,
+}
+
+impl PreviewGrid {
+    /// Counts a frame, keeping it as a tile with its crop drawn on when it
+    /// falls on the interval
+    pub fn add_frame(&mut self, img: &Image, crop_result: &CropResult) -> Result<()> {
+        let frame_index = self.frame_index;
+        self.frame_index += 1;
+        if frame_index % self.interval != 0 {
+            return Ok(());
+        }
+        if self.tiles.len() >= image::PREVIEW_MAX_TILES {
+            let mut index = 0;
+            self.tiles.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.interval *= 2;
+            if frame_index % self.interval != 0 {
+                return Ok(());
+            }
+        }
+        let frame = self.annotator.annotate(img, &crop_boxes(crop_result))?;
+        self.tiles
+            .push(image::contact_sheet_tile(&frame, image::PREVIEW_TILE_WIDTH));
+        Ok(())
+    }
+
+    /// Writes the tiles collected so far to `path` as one PNG
+    pub fn save(&self, path: &str) -> Result<()> {
+        image::save_contact_sheet(&self.tiles, image::PREVIEW_GRID_COLUMNS, path)
+    }
 }
 
 impl VideoSink {
-    /// Creates a sink that encodes to `saveout` at the given frames-per-second.
-    pub fn new(saveout: impl Into<PathBuf>, fps: f64) -> Self {
-        let saveout = saveout.into();
+    /// Creates a sink that encodes to `saveout` at the given frames-per-second
+    /// using the given encoder quality settings.
+    pub fn new(saveout: impl Into<PathBuf>, fps: f64, encode: EncodeSettings) -> Self {
+        Self::spawn(saveout.into(), fps, encode, true)
+    }
+
+    /// Starts the encoder thread. `record_metrics` is off for secondary
+    /// outputs so their frames don't count toward the run's encode metrics.
+    fn spawn(saveout: PathBuf, fps: f64, encode: EncodeSettings, record_metrics: bool) -> Self {
         // Bounded so a slow encoder applies backpressure rather than letting
         // in-flight frames (each ~6 MB at 1080x1920) grow unbounded in RAM.
         let (tx, rx) = sync_channel::<EncodeMsg>(8);
@@ -56,9 +323,15 @@ impl VideoSink {
                     // The encoder is created lazily from the first frame's
                     // dimensions, mirroring how the usls `Viewer` initializes
                     // encoding.
-                    let settings = Settings::preset_h264_yuv420p(msg.w, msg.h, false);
+                    let settings = Settings::preset_h264_custom(
+                        msg.w,
+                        msg.h,
+                        PixelFormat::YUV420P,
+                        Options::from(encode.encoder_options()),
+                    );
                     encoder = Some(
-                        Encoder::new(saveout.clone(), settings).context("creating video encoder")?,
+                        Encoder::new(saveout.clone(), settings)
+                            .context("creating video encoder")?,
                     );
                 }
                 let enc = encoder.as_mut().expect("encoder initialized above");
@@ -67,10 +340,13 @@ impl VideoSink {
                 // Output frame timing is derived from a monotonic frame counter
                 // at the source fps, matching the old `Viewer::with_fps`.
                 let timestamp = Time::from_secs_f64(frame_index as f64 / fps);
-                enc.encode(&frame, timestamp).context("encoding video frame")?;
+                enc.encode(&frame, timestamp)
+                    .context("encoding video frame")?;
                 frame_index += 1;
-                metrics::record("encode_write", start.elapsed());
-                metrics::inc("frames_written", 1);
+                if record_metrics {
+                    metrics::record("encode_write", start.elapsed());
+                    metrics::inc("frames_written", 1);
+                }
             }
 
             // Sender dropped → no more frames; finalize the container (the mp4
@@ -78,7 +354,9 @@ impl VideoSink {
             if let Some(mut enc) = encoder.take() {
                 let start = Instant::now();
                 enc.finish().context("finalizing video encoder")?;
-                metrics::record("encode_finalize", start.elapsed());
+                if record_metrics {
+                    metrics::record("encode_finalize", start.elapsed());
+                }
             }
             Ok(())
         });
@@ -88,14 +366,143 @@ impl VideoSink {
             tx: Some(tx),
             handle: Some(handle),
             frame_index: 0,
+            crop_log: None,
+            debug_overlay: None,
+            preview_grid: None,
+            output_width: None,
+            extra_outputs: Vec::new(),
         }
     }
 
+    /// Sets the preview window's size relative to the frame (`--window-scale`).
+    /// Call before the first frame is written, while the window is unopened.
+    pub fn with_window_scale(mut self, scale: f32) -> Self {
+        self.viewer = Viewer::default().with_window_scale(scale);
+        self
+    }
+
+    /// Also writes a `--debug-overlay` video of the full annotated source
+    /// frames, with the crop drawn on them, to `saveout`.
+    pub fn with_debug_overlay(
+        mut self,
+        saveout: impl Into<PathBuf>,
+        fps: f64,
+        encode: EncodeSettings,
+    ) -> Self {
+        self.debug_overlay = Some(DebugOverlay {
+            sink: Box::new(VideoSink::spawn(saveout.into(), fps, encode, false)),
+            annotator: Annotator::default().with_hbb_style(
+                HbbStyle::default()
+                    .with_draw_fill(false)
+                    .with_thickness(4)
+                    .show_confidence(false)
+                    .show_id(false),
+            ),
+        });
+        self
+    }
+
+    /// The debug overlay output, if enabled.
+    pub fn debug_overlay_mut(&mut self) -> Option<&mut DebugOverlay> {
+        self.debug_overlay.as_mut()
+    }
+
+    /// Also collects every `interval`th frame, with its crop drawn on, for a
+    /// `--preview-grid` contact sheet.
+    pub fn with_preview_grid(mut self, interval: usize) -> Self {
+        self.preview_grid = Some(PreviewGrid {
+            interval: interval.max(1),
+            frame_index: 0,
+            tiles: Vec::new(),
+            // Thick lines, so the crop stays visible on the shrunken tiles
+            annotator: Annotator::default().with_hbb_style(
+                HbbStyle::default()
+                    .with_draw_fill(false)
+                    .with_thickness(12)
+                    .show_confidence(false)
+                    .show_id(false)
+                    .show_name(false),
+            ),
+        });
+        self
+    }
+
+    /// The preview grid, if enabled.
+    pub fn preview_grid_mut(&mut self) -> Option<&mut PreviewGrid> {
+        self.preview_grid.as_mut()
+    }
+
+    /// The preview grid, if enabled.
+    pub fn preview_grid(&self) -> Option<&PreviewGrid> {
+        self.preview_grid.as_ref()
+    }
+
+    /// Renders at the `--output-resolution` sizes: frames are written here at
+    /// the first size's width, and to a second sink per further size (at
+    /// `resolution_output_path(saveout, size)`), all from the same crops.
+    pub fn with_output_sizes(
+        mut self,
+        sizes: &[OutputSize],
+        saveout: &str,
+        fps: f64,
+        encode: EncodeSettings,
+    ) -> Self {
+        let Some((first, rest)) = sizes.split_first() else {
+            return self;
+        };
+        self.output_width = Some(first.width);
+        self.extra_outputs = rest
+            .iter()
+            .map(|size| ExtraOutput {
+                sink: Box::new(VideoSink::spawn(
+                    resolution_output_path(saveout, *size).into(),
+                    fps,
+                    encode.clone(),
+                    false,
+                )),
+                width: size.width,
+            })
+            .collect();
+        self
+    }
+
+    /// Width to render frames at, if `--output-resolution` set one; otherwise
+    /// the source frame's height.
+    pub fn output_width(&self) -> Option<u32> {
+        self.output_width
+    }
+
+    /// The extra `--output-resolution` outputs.
+    pub fn extra_outputs_mut(&mut self) -> &mut [ExtraOutput] {
+        &mut self.extra_outputs
+    }
+
+    /// Enables recording of per-frame crop decisions (for `--export-crops`).
+    pub fn with_crop_log(mut self) -> Self {
+        self.crop_log = Some(CropLog::default());
+        self
+    }
+
+    /// The crop decision log, if recording is enabled.
+    pub fn crop_log_mut(&mut self) -> Option<&mut CropLog> {
+        self.crop_log.as_mut()
+    }
+
+    /// The crop decision log, if recording is enabled.
+    pub fn crop_log(&self) -> Option<&CropLog> {
+        self.crop_log.as_ref()
+    }
+
     /// Polls the preview window for a key press.
     pub fn wait_key(&mut self, delay_ms: u64) -> Option<Key> {
         self.viewer.wait_key(delay_ms)
     }
 
+    /// Displays `img` in the preview window without writing it to the output.
+    pub fn show(&mut self, img: &Image) -> Result<()> {
+        self.viewer.imshow(img)
+    }
+
     /// True once the preview window has been opened and then closed by the user.
     pub fn is_window_exist_and_closed(&self) -> bool {
         self.viewer.is_window_exist_and_closed()
@@ -141,7 +548,13 @@ impl VideoSink {
         Ok(())
     }
 
-    /// Number of frames enqueued for encoding so far.
+    /// Counts a frame whose crop was chosen but deliberately not rendered
+    /// (`--dry-run`), so frame numbering and counts match a real run.
+    pub fn skip_frame(&mut self) {
+        self.frame_index += 1;
+    }
+
+    /// Number of frames enqueued for encoding (or skipped) so far.
     pub fn frame_count(&self) -> usize {
         self.frame_index
     }
@@ -150,6 +563,12 @@ impl VideoSink {
     /// queue and writes the container trailer, then joins and propagates any
     /// encode error.
     pub fn finalize(&mut self) -> Result<()> {
+        if let Some(overlay) = &mut self.debug_overlay {
+            overlay.sink.finalize()?;
+        }
+        for output in &mut self.extra_outputs {
+            output.sink.finalize()?;
+        }
         self.tx.take();
         if let Some(handle) = self.handle.take() {
             handle
@@ -180,6 +599,18 @@ impl Drop for VideoSink {
     }
 }
 
+const MAX_WINDOW_SCALE: f32 = 4.0;
+
+/// Checks that a `--window-scale` value is positive and no larger than 4
+pub fn check_window_scale(scale: f32) -> Result<()> {
+    if !(scale > 0.0 && scale <= MAX_WINDOW_SCALE) {
+        anyhow::bail!(
+            "invalid window scale {scale}: expected more than 0 and at most {MAX_WINDOW_SCALE}"
+        );
+    }
+    Ok(())
+}
+
 const DEFAULT_FPS: f64 = 30.0;
 const MIN_FPS: f64 = 1.0;
 const MAX_FPS: f64 = 240.0;
@@ -257,6 +688,76 @@ pub fn probe_fps(source: &str) -> f64 {
     }
 }
 
+/// Relative difference between a stream's nominal and average frame rates
+/// below which it still counts as constant. Constant-rate files often report
+/// slightly different values, e.g. from a dropped frame or a rounded
+/// `29.97`, and resampling them would only cost a needless re-encode.
+pub const VFR_TOLERANCE: f64 = 0.01;
+
+/// True when a stream's nominal (`r_frame_rate`) and average frame rates
+/// differ by more than `VFR_TOLERANCE` of the larger one, the signature of
+/// variable-frame-rate footage such as phone recordings.
+pub fn is_variable_frame_rate(nominal_fps: f64, average_fps: f64) -> bool {
+    (nominal_fps - average_fps).abs() > VFR_TOLERANCE * nominal_fps.max(average_fps)
+}
+
+/// Probes whether `source` has a variable frame rate via `ffprobe`. Returns
+/// `false` when the rates can't be read, treating the source as constant.
+pub fn probe_is_vfr(source: &str) -> bool {
+    let Some(output) = Command::new("ffprobe")
+        .args([
+            "-v",
+            "0",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=r_frame_rate,avg_frame_rate",
+            "-of",
+            "csv=p=0",
+        ])
+        .arg(source)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+    else {
+        return false;
+    };
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut rates = text.trim().split(',').map(parse_frame_rate);
+    match (rates.next().flatten(), rates.next().flatten()) {
+        (Some(nominal), Some(average)) => is_variable_frame_rate(nominal, average),
+        _ => false,
+    }
+}
+
+/// Estimates a source's frame count from its duration and frame rate.
+/// Returns `None` when the duration is missing or non-positive.
+pub fn estimate_frame_count(duration_secs: f64, fps: f64) -> Option<u64> {
+    (duration_secs > 0.0 && fps > 0.0).then(|| (duration_secs * fps).round() as u64)
+}
+
+/// Reads the container duration of `source` in seconds via `ffprobe`.
+/// Returns `None` without warning for sources that don't report one, such as
+/// live streams, since the duration is only used for progress reporting.
+pub fn probe_duration(source: &str) -> Option<f64> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "0",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "csv=p=0",
+        ])
+        .arg(source)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,6 +786,98 @@ mod tests {
         assert_eq!(parse_frame_rate("abc/def"), None);
     }
 
+    #[test]
+    fn test_is_variable_frame_rate() {
+        assert!(!is_variable_frame_rate(30.0, 30.0));
+        assert!(!is_variable_frame_rate(30000.0 / 1001.0, 29.97));
+        assert!(!is_variable_frame_rate(60.0, 59.9));
+        assert!(!is_variable_frame_rate(30.0, 29.75));
+        assert!(is_variable_frame_rate(30.0, 29.6));
+        // Phone footage: nominal 60 fps but averaging under 30
+        assert!(is_variable_frame_rate(60.0, 29.2));
+        assert!(is_variable_frame_rate(90000.0, 30.0));
+    }
+
+    #[test]
+    fn test_estimate_frame_count() {
+        assert_eq!(estimate_frame_count(10.0, 30.0), Some(300));
+        assert_eq!(estimate_frame_count(1.5, 29.97), Some(45));
+        assert_eq!(estimate_frame_count(0.0, 30.0), None);
+        assert_eq!(estimate_frame_count(-1.0, 30.0), None);
+    }
+
+    #[test]
+    fn test_parse_bitrate() {
+        assert_eq!(parse_bitrate("2500k").unwrap(), 2_500_000);
+        assert_eq!(parse_bitrate("4M").unwrap(), 4_000_000);
+        assert_eq!(parse_bitrate("1.5m").unwrap(), 1_500_000);
+        assert_eq!(parse_bitrate("800000").unwrap(), 800_000);
+        assert!(parse_bitrate("fast").is_err());
+        assert!(parse_bitrate("0k").is_err());
+        assert!(parse_bitrate("").is_err());
+    }
+
+    #[test]
+    fn test_encode_settings() {
+        let settings = EncodeSettings::default();
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.encoder_options()["preset"], "medium");
+        assert!(!settings.encoder_options().contains_key("crf"));
+
+        let settings = EncodeSettings {
+            crf: Some(20),
+            preset: Some("slow".to_string()),
+            bitrate: None,
+        };
+        assert!(settings.validate().is_ok());
+        let options = settings.encoder_options();
+        assert_eq!(options["crf"], "20");
+        assert_eq!(options["preset"], "slow");
+
+        let bitrate = EncodeSettings {
+            bitrate: Some(4_000_000),
+            ..Default::default()
+        };
+        assert_eq!(bitrate.encoder_options()["b"], "4000000");
+
+        assert!(
+            EncodeSettings {
+                crf: Some(52),
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            EncodeSettings {
+                preset: Some("quick".to_string()),
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            EncodeSettings {
+                crf: Some(20),
+                bitrate: Some(4_000_000),
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_check_window_scale() {
+        assert!(check_window_scale(0.5).is_ok());
+        assert!(check_window_scale(1.0).is_ok());
+        assert!(check_window_scale(4.0).is_ok());
+        assert!(check_window_scale(0.0).is_err());
+        assert!(check_window_scale(-1.0).is_err());
+        assert!(check_window_scale(4.5).is_err());
+        assert!(check_window_scale(f32::NAN).is_err());
+    }
+
     #[test]
     fn test_make_even() {
         assert_eq!(make_even(1080), 1080);
@@ -294,4 +887,101 @@ mod tests {
         assert_eq!(make_even(1), 2);
         assert_eq!(make_even(0), 2);
     }
+
+    #[test]
+    fn test_parse_output_sizes() {
+        let sizes = parse_output_sizes("1080x1920, 720x1280", AspectRatio::PORTRAIT).unwrap();
+        assert_eq!(
+            sizes,
+            [
+                OutputSize {
+                    width: 1080,
+                    height: 1920
+                },
+                OutputSize {
+                    width: 720,
+                    height: 1280
+                },
+            ]
+        );
+        assert_eq!(sizes[1].to_string(), "720x1280");
+        assert!(parse_output_sizes("1080x1350", AspectRatio::new(4.0, 5.0)).is_ok());
+
+        // Sizes must match the canvas, be even, and be listed once
+        assert!(parse_output_sizes("1080x1080", AspectRatio::PORTRAIT).is_err());
+        assert!(parse_output_sizes("721x1281", AspectRatio::PORTRAIT).is_err());
+        assert!(parse_output_sizes("720x1280,720x1280", AspectRatio::PORTRAIT).is_err());
+        assert!(parse_output_sizes("720", AspectRatio::PORTRAIT).is_err());
+        assert!(parse_output_sizes("0x0", AspectRatio::PORTRAIT).is_err());
+    }
+
+    #[test]
+    fn test_resolution_output_path() {
+        let size = OutputSize {
+            width: 720,
+            height: 1280,
+        };
+        assert_eq!(
+            resolution_output_path("/run/processed_video.mp4", size),
+            "/run/processed_video_720x1280.mp4"
+        );
+        assert_eq!(
+            resolution_output_path("out/final.webm", size),
+            "out/final_720x1280.webm"
+        );
+    }
+
+    #[test]
+    fn test_preview_grid_caps_tiles() {
+        let saveout = std::env::temp_dir().join("land2port_preview_grid_test.mp4");
+        let mut sink =
+            VideoSink::new(saveout, 30.0, EncodeSettings::default()).with_preview_grid(1);
+        let img = Image::from(::image::RgbImage::new(64, 36));
+        let crop = CropResult::Resize(crate::crop::CropArea::new(0.0, 0.0, 64.0, 36.0));
+        let grid = sink.preview_grid_mut().unwrap();
+        for _ in 0..300 {
+            grid.add_frame(&img, &crop).unwrap();
+        }
+
+        // 300 frames at interval 1 would be 300 tiles; doubling to 4 keeps
+        // frames 0, 4, ..., 296
+        let grid = sink.preview_grid().unwrap();
+        assert_eq!(grid.interval, 4);
+        assert_eq!(grid.tiles.len(), 75);
+        sink.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_output_sizes_add_a_sink_per_extra_size() {
+        let saveout = std::env::temp_dir().join("land2port_sizes_test.mp4");
+        let saveout = saveout.to_str().unwrap();
+        let sizes =
+            parse_output_sizes("1080x1920,720x1280,540x960", AspectRatio::PORTRAIT).unwrap();
+        let mut sink = VideoSink::new(saveout, 30.0, EncodeSettings::default()).with_output_sizes(
+            &sizes,
+            saveout,
+            30.0,
+            EncodeSettings::default(),
+        );
+
+        // The first size renders into this sink, the rest into their own
+        assert_eq!(sink.output_width(), Some(1080));
+        let widths: Vec<u32> = sink
+            .extra_outputs_mut()
+            .iter()
+            .map(|output| output.width())
+            .collect();
+        assert_eq!(widths, [720, 540]);
+        // No frames were written, so every encoder finalizes without a file
+        sink.finalize().unwrap();
+
+        // Without --output-resolution frames render at the source height
+        let plain = VideoSink::new(saveout, 30.0, EncodeSettings::default()).with_output_sizes(
+            &[],
+            saveout,
+            30.0,
+            EncodeSettings::default(),
+        );
+        assert_eq!(plain.output_width(), None);
+    }
 }